@@ -0,0 +1,19 @@
+/// A viewer for search results: `rg --vimgrep PATTERN | cargo run --example matches`
+use std::io::{self, Read};
+
+use bat::match_list::MatchList;
+use bat::PrettyPrinter;
+
+fn main() {
+    let mut list = String::new();
+    io::stdin().read_to_string(&mut list).unwrap();
+    let matches = MatchList::parse(&list).unwrap();
+
+    PrettyPrinter::new()
+        .header(true)
+        .grid(true)
+        .line_numbers(true)
+        .snip(true)
+        .print_matches(&matches, 2)
+        .unwrap();
+}
@@ -0,0 +1,211 @@
+//! Color-blind friendly alternatives for color-coded information: Git change markers and log
+//! levels are colored with palettes that stay distinguishable for common color-vision
+//! deficiencies, and highlighted lines and log levels are marked with glyphs in addition to
+//! their colors.
+
+use syntect::highlighting::Color;
+
+use crate::json_lines::{FormattedLine, LogLevel, Token};
+
+/// The marker in the grid border of highlighted lines
+pub(crate) const HIGHLIGHTED_LINE_MARKER: &str = "▶";
+
+/// A color-vision deficiency
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorVision {
+    /// Red-blindness
+    Protanopia,
+
+    /// Green-blindness (the most common deficiency)
+    Deuteranopia,
+
+    /// Blue-blindness
+    Tritanopia,
+}
+
+/// The colors of color-coded markers
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MarkerPalette {
+    pub added: Color,
+    pub removed: Color,
+    pub modified: Color,
+    pub error: Color,
+    pub warning: Color,
+    pub info: Color,
+    pub debug: Color,
+}
+
+const fn rgb(r: u8, g: u8, b: u8) -> Color {
+    Color { r, g, b, a: 0xff }
+}
+
+impl MarkerPalette {
+    /// A palette that is distinguishable with the given color-vision deficiency (based on the
+    /// Okabe-Ito palette)
+    pub fn for_vision(vision: ColorVision) -> Self {
+        match vision {
+            ColorVision::Protanopia | ColorVision::Deuteranopia => MarkerPalette {
+                added: rgb(0x00, 0x72, 0xb2),
+                removed: rgb(0xd5, 0x5e, 0x00),
+                modified: rgb(0xf0, 0xe4, 0x42),
+                error: rgb(0xd5, 0x5e, 0x00),
+                warning: rgb(0xf0, 0xe4, 0x42),
+                info: rgb(0x56, 0xb4, 0xe9),
+                debug: rgb(0x99, 0x99, 0x99),
+            },
+            ColorVision::Tritanopia => MarkerPalette {
+                added: rgb(0x00, 0x9e, 0x73),
+                removed: rgb(0xd5, 0x5e, 0x00),
+                modified: rgb(0xcc, 0x79, 0xa7),
+                error: rgb(0xd5, 0x5e, 0x00),
+                warning: rgb(0xcc, 0x79, 0xa7),
+                info: rgb(0x00, 0x9e, 0x73),
+                debug: rgb(0x99, 0x99, 0x99),
+            },
+        }
+    }
+
+    pub(crate) fn level(&self, level: LogLevel) -> Color {
+        match level {
+            LogLevel::Error => self.error,
+            LogLevel::Warning => self.warning,
+            LogLevel::Info => self.info,
+            LogLevel::Debug => self.debug,
+        }
+    }
+}
+
+impl Default for MarkerPalette {
+    fn default() -> Self {
+        MarkerPalette::for_vision(ColorVision::Deuteranopia)
+    }
+}
+
+fn level_glyph(level: LogLevel) -> &'static str {
+    match level {
+        LogLevel::Error => "✖ ",
+        LogLevel::Warning => "▲ ",
+        LogLevel::Info => "● ",
+        LogLevel::Debug => "· ",
+    }
+}
+
+/// Put a glyph in front of the log levels of a formatted line, so that the levels can be told
+/// apart without their colors
+pub(crate) fn mark_levels(line: FormattedLine) -> FormattedLine {
+    if !line.tokens.iter().any(|(_, token)| match token {
+        Token::Level(_) => true,
+        _ => false,
+    }) {
+        return line;
+    }
+
+    let mut marked = FormattedLine {
+        text: String::with_capacity(line.text.len() + 8),
+        tokens: Vec::with_capacity(line.tokens.len() + 2),
+    };
+    for (range, token) in line.tokens {
+        if let Token::Level(level) = token {
+            marked.push(level_glyph(level), token);
+        }
+        marked.push(&line.text[range], token);
+    }
+    marked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Simulate a color-vision deficiency (Machado et al., 2009, severity 1.0)
+    fn simulate(vision: ColorVision, color: Color) -> [f64; 3] {
+        let matrix = match vision {
+            ColorVision::Protanopia => [
+                [0.152286, 1.052583, -0.204868],
+                [0.114503, 0.786281, 0.099216],
+                [-0.003882, -0.048116, 1.051998],
+            ],
+            ColorVision::Deuteranopia => [
+                [0.367322, 0.860646, -0.227968],
+                [0.280085, 0.672501, 0.047413],
+                [-0.011820, 0.042940, 0.968881],
+            ],
+            ColorVision::Tritanopia => [
+                [1.255528, -0.076749, -0.178779],
+                [-0.078411, 0.930809, 0.147602],
+                [0.004733, 0.691367, 0.303900],
+            ],
+        };
+
+        let linear = |c: u8| {
+            let c = f64::from(c) / 255.0;
+            if c <= 0.04045 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        // Values outside of the gamut are not clipped (which doesn't matter for comparisons)
+        let srgb = |c: f64| {
+            255.0
+                * if c <= 0.003_130_8 {
+                    c * 12.92
+                } else {
+                    1.055 * c.powf(1.0 / 2.4) - 0.055
+                }
+        };
+
+        let rgb = [linear(color.r), linear(color.g), linear(color.b)];
+        let mut simulated = [0.0; 3];
+        for (channel, row) in simulated.iter_mut().zip(matrix.iter()) {
+            *channel = srgb(row.iter().zip(rgb.iter()).map(|(m, c)| m * c).sum());
+        }
+        simulated
+    }
+
+    fn assert_distinguishable(vision: ColorVision, colors: &[Color]) {
+        for (i, a) in colors.iter().enumerate() {
+            for b in &colors[i + 1..] {
+                let (a, b) = (simulate(vision, *a), simulate(vision, *b));
+                let distance = a
+                    .iter()
+                    .zip(b.iter())
+                    .map(|(a, b)| (a - b) * (a - b))
+                    .sum::<f64>()
+                    .sqrt();
+                assert!(distance > 60.0, "{:?}: {:?} vs. {:?}", vision, a, b);
+            }
+        }
+    }
+
+    #[test]
+    fn palettes_are_distinguishable() {
+        for &vision in &[
+            ColorVision::Protanopia,
+            ColorVision::Deuteranopia,
+            ColorVision::Tritanopia,
+        ] {
+            let palette = MarkerPalette::for_vision(vision);
+            assert_distinguishable(vision, &[palette.added, palette.removed, palette.modified]);
+            assert_distinguishable(
+                vision,
+                &[palette.error, palette.warning, palette.info, palette.debug],
+            );
+        }
+    }
+
+    #[test]
+    fn levels_are_marked() {
+        let mut line = FormattedLine {
+            text: "level=warn".into(),
+            tokens: vec![],
+        };
+        line.tokens.push((0..6, Token::Key));
+        line.tokens.push((6..10, Token::Level(LogLevel::Warning)));
+
+        let marked = mark_levels(line);
+        assert_eq!(marked.text, "level=▲ warn");
+        assert_eq!(marked.tokens[1].0, 6..10);
+        assert_eq!(marked.tokens[2].0, 10..14);
+    }
+}
@@ -0,0 +1,211 @@
+//! Parse (bat's own) ANSI-colored output back into styled text spans.
+
+use ansi_term::Colour::{self, Fixed, RGB};
+use ansi_term::Style;
+use console::AnsiCodeIterator;
+
+/// A piece of text together with the style that it is displayed with
+#[derive(Debug, Clone, PartialEq)]
+pub struct Span {
+    pub text: String,
+    pub style: Style,
+}
+
+/// Split a string that contains ANSI escape sequences into styled spans.
+///
+/// SGR sequences (colors, bold, italic, ...) are interpreted and attached to the following
+/// text. All other escape sequences are dropped. Neighboring pieces of text with the same style
+/// are merged into a single span. Newlines are kept as part of the text.
+pub fn parse(input: &str) -> Vec<Span> {
+    let mut spans: Vec<Span> = vec![];
+    let mut style = Style::default();
+
+    for (chunk, is_escape) in AnsiCodeIterator::new(input) {
+        if is_escape {
+            if chunk.starts_with("\x1B[") && chunk.ends_with('m') {
+                apply_sgr(&mut style, &chunk[2..chunk.len() - 1]);
+            }
+            continue;
+        }
+
+        match spans.last_mut() {
+            Some(last) if last.style == style => last.text.push_str(chunk),
+            _ => spans.push(Span {
+                text: chunk.to_owned(),
+                style,
+            }),
+        }
+    }
+
+    spans
+}
+
+/// Like `parse`, but split the result into lines. Line endings are not part of the spans.
+pub fn parse_lines(input: &str) -> Vec<Vec<Span>> {
+    let mut lines = vec![vec![]];
+
+    for span in parse(input) {
+        let mut parts = span.text.split('\n').peekable();
+        while let Some(part) = parts.next() {
+            let part = part.trim_end_matches('\r');
+            if !part.is_empty() {
+                lines.last_mut().unwrap().push(Span {
+                    text: part.to_owned(),
+                    style: span.style,
+                });
+            }
+            if parts.peek().is_some() {
+                lines.push(vec![]);
+            }
+        }
+    }
+
+    if input.ends_with('\n') {
+        lines.pop();
+    }
+
+    lines
+}
+
+fn apply_sgr(style: &mut Style, parameters: &str) {
+    let mut codes = parameters.split(';').map(|p| {
+        if p.is_empty() {
+            Some(0)
+        } else {
+            p.parse::<u8>().ok()
+        }
+    });
+
+    while let Some(code) = codes.next() {
+        match code {
+            Some(0) => *style = Style::default(),
+            Some(1) => style.is_bold = true,
+            Some(2) => style.is_dimmed = true,
+            Some(3) => style.is_italic = true,
+            Some(4) => style.is_underline = true,
+            Some(5) => style.is_blink = true,
+            Some(7) => style.is_reverse = true,
+            Some(8) => style.is_hidden = true,
+            Some(9) => style.is_strikethrough = true,
+            Some(22) => {
+                style.is_bold = false;
+                style.is_dimmed = false;
+            }
+            Some(23) => style.is_italic = false,
+            Some(24) => style.is_underline = false,
+            Some(25) => style.is_blink = false,
+            Some(27) => style.is_reverse = false,
+            Some(28) => style.is_hidden = false,
+            Some(29) => style.is_strikethrough = false,
+            Some(c @ 30..=37) => style.foreground = Some(basic_colour(c - 30)),
+            Some(38) => style.foreground = extended_colour(&mut codes),
+            Some(39) => style.foreground = None,
+            Some(c @ 40..=47) => style.background = Some(basic_colour(c - 40)),
+            Some(48) => style.background = extended_colour(&mut codes),
+            Some(49) => style.background = None,
+            Some(c @ 90..=97) => style.foreground = Some(Fixed(c - 90 + 8)),
+            Some(c @ 100..=107) => style.background = Some(Fixed(c - 100 + 8)),
+            _ => {}
+        }
+    }
+}
+
+fn basic_colour(code: u8) -> Colour {
+    match code {
+        0 => Colour::Black,
+        1 => Colour::Red,
+        2 => Colour::Green,
+        3 => Colour::Yellow,
+        4 => Colour::Blue,
+        5 => Colour::Purple,
+        6 => Colour::Cyan,
+        _ => Colour::White,
+    }
+}
+
+fn extended_colour(codes: &mut impl Iterator<Item = Option<u8>>) -> Option<Colour> {
+    match codes.next()? {
+        Some(5) => codes.next()?.map(Fixed),
+        Some(2) => {
+            let r = codes.next()??;
+            let g = codes.next()??;
+            let b = codes.next()??;
+            Some(RGB(r, g, b))
+        }
+        _ => None,
+    }
+}
+
+#[test]
+fn parse_plain_text() {
+    assert_eq!(
+        parse("hello world\n"),
+        vec![Span {
+            text: "hello world\n".into(),
+            style: Style::default()
+        }]
+    );
+    assert!(parse("").is_empty());
+}
+
+#[test]
+fn parse_round_trip() {
+    let keyword = RGB(249, 38, 114).bold();
+    let string = Fixed(186).on(Colour::Black).italic();
+    let output = format!(
+        "{} {}{}",
+        keyword.paint("fn"),
+        string.paint("\"bat\""),
+        Style::default().paint(";")
+    );
+
+    assert_eq!(
+        parse(&output),
+        vec![
+            Span {
+                text: "fn".into(),
+                style: keyword
+            },
+            Span {
+                text: " ".into(),
+                style: Style::default()
+            },
+            Span {
+                text: "\"bat\"".into(),
+                style: string
+            },
+            Span {
+                text: ";".into(),
+                style: Style::default()
+            },
+        ]
+    );
+}
+
+#[test]
+fn parse_merges_and_resets() {
+    let spans = parse("\x1B[31ma\x1B[31mb\x1B[39mc\x1B[1;92md\x1B[22;49me\x1B[Kf");
+
+    assert_eq!(spans.len(), 4);
+    assert_eq!(spans[0].text, "ab");
+    assert_eq!(spans[0].style, Colour::Red.normal());
+    assert_eq!(spans[1].text, "c");
+    assert_eq!(spans[1].style, Style::default());
+    assert_eq!(spans[2].text, "d");
+    assert_eq!(spans[2].style, Fixed(10).bold());
+    assert_eq!(spans[3].text, "ef");
+    assert_eq!(spans[3].style, Fixed(10).normal());
+}
+
+#[test]
+fn parse_lines_splits_spans() {
+    let lines = parse_lines("\x1B[32mfoo\nbar\x1B[0m\r\n\nbaz\n");
+
+    assert_eq!(lines.len(), 4);
+    assert_eq!(lines[0][0].text, "foo");
+    assert_eq!(lines[1][0].text, "bar");
+    assert_eq!(lines[1][0].style, Colour::Green.normal());
+    assert!(lines[2].is_empty());
+    assert_eq!(lines[3][0].text, "baz");
+    assert_eq!(lines[3][0].style, Style::default());
+}
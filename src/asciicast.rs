@@ -0,0 +1,126 @@
+//! Record the output of bat as an asciicast (v2) file that can be replayed with asciinema.
+//!
+//! See https://github.com/asciinema/asciinema/blob/develop/doc/asciicast-v2.md
+
+use std::env;
+use std::io::{self, Write};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+/// A writer that passes all output through and records it (with timing information) as
+/// asciicast events
+pub(crate) struct AsciicastRecorder<'a, R: Write> {
+    output: &'a mut dyn Write,
+    recording: R,
+    start: Instant,
+    deterministic: bool,
+
+    /// Bytes of an incomplete UTF-8 sequence at the end of the last write
+    incomplete: Vec<u8>,
+}
+
+impl<'a, R: Write> AsciicastRecorder<'a, R> {
+    pub(crate) fn new(
+        output: &'a mut dyn Write,
+        mut recording: R,
+        width: usize,
+        height: usize,
+        deterministic: bool,
+    ) -> io::Result<Self> {
+        // Deterministic recordings do not contain the time or the terminal type
+        let (timestamp, term) = if deterministic {
+            (0, "xterm-256color".into())
+        } else {
+            let timestamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+            let term = env::var("TERM").unwrap_or_else(|_| "xterm-256color".into());
+            (timestamp, term)
+        };
+
+        let header = serde_json::json!({
+            "version": 2,
+            "width": width,
+            "height": height,
+            "timestamp": timestamp,
+            "env": { "TERM": term },
+        });
+        writeln!(recording, "{}", header)?;
+
+        Ok(AsciicastRecorder {
+            output,
+            recording,
+            start: Instant::now(),
+            deterministic,
+            incomplete: vec![],
+        })
+    }
+
+    fn record(&mut self, buf: &[u8]) -> io::Result<()> {
+        self.incomplete.extend_from_slice(buf);
+
+        let valid_up_to = match std::str::from_utf8(&self.incomplete) {
+            Ok(_) => self.incomplete.len(),
+            // Wait for the rest of a multi-byte character
+            Err(e) if e.error_len().is_none() => e.valid_up_to(),
+            Err(_) => self.incomplete.len(),
+        };
+        if valid_up_to == 0 {
+            return Ok(());
+        }
+
+        let bytes: Vec<u8> = self.incomplete.drain(..valid_up_to).collect();
+        // The terminal translates line feeds into CR+LF, the player does not
+        let data = String::from_utf8_lossy(&bytes).replace('\n', "\r\n");
+        let time = if self.deterministic {
+            0.0
+        } else {
+            self.start.elapsed().as_micros() as f64 / 1_000_000.0
+        };
+
+        writeln!(self.recording, "{}", serde_json::json!([time, "o", data]))
+    }
+}
+
+impl<'a, R: Write> Write for AsciicastRecorder<'a, R> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.output.write(buf)?;
+        self.record(&buf[..written])?;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.output.flush()?;
+        self.recording.flush()
+    }
+}
+
+#[test]
+fn records_output_as_events() {
+    let mut output = vec![];
+    let mut recording = vec![];
+    {
+        let mut recorder =
+            AsciicastRecorder::new(&mut output, &mut recording, 80, 24, false).unwrap();
+        recorder.write_all(b"hello\n").unwrap();
+        recorder.write_all(&"wörld".as_bytes()[..2]).unwrap();
+        recorder.write_all(&"wörld".as_bytes()[2..]).unwrap();
+    }
+
+    assert_eq!(output, "hello\nwörld".as_bytes());
+
+    let recording = String::from_utf8(recording).unwrap();
+    let lines: Vec<serde_json::Value> = recording
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+
+    assert_eq!(lines.len(), 4);
+    assert_eq!(lines[0]["version"], 2);
+    assert_eq!(lines[0]["width"], 80);
+    assert_eq!(lines[0]["height"], 24);
+    assert_eq!(lines[1][1], "o");
+    assert_eq!(lines[1][2], "hello\r\n");
+    assert_eq!(lines[2][2], "w");
+    assert_eq!(lines[3][2], "örld");
+}
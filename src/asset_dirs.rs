@@ -0,0 +1,122 @@
+//! The directories of custom assets. The config directory contains the sources of custom
+//! syntaxes and themes (in its `syntaxes` and `themes` folders), the cache directory the assets
+//! that are built from them. The `bat` application uses `~/.config/bat` and `~/.cache/bat` (or
+//! the directories given by `BAT_CONFIG_DIR`, `BAT_CACHE_PATH`, `XDG_CONFIG_HOME` and
+//! `XDG_CACHE_HOME`). Applications that use `bat` as a library can keep both inside their own
+//! data directories:
+//!
+//! ```no_run
+//! use bat::asset_dirs::AssetDirs;
+//! use bat::PrettyPrinter;
+//!
+//! let dirs = AssetDirs::new("/opt/app/bat-config", "/var/cache/app/bat");
+//! dirs.build_cache(true).unwrap();
+//!
+//! PrettyPrinter::new()
+//!     .assets(dirs.load_assets().unwrap())
+//!     .input_file("src/main.rs")
+//!     .print()
+//!     .unwrap();
+//! ```
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::asset_store::{METADATA, SYNTAX_SET, THEME_SET};
+use crate::assets::HighlightingAssets;
+use crate::error::*;
+
+/// A config directory and a cache directory
+#[derive(Debug, Clone, PartialEq)]
+pub struct AssetDirs {
+    config_dir: PathBuf,
+    cache_dir: PathBuf,
+}
+
+impl AssetDirs {
+    pub fn new(config_dir: impl Into<PathBuf>, cache_dir: impl Into<PathBuf>) -> Self {
+        AssetDirs {
+            config_dir: config_dir.into(),
+            cache_dir: cache_dir.into(),
+        }
+    }
+
+    /// Use another config directory
+    pub fn with_config_dir(mut self, config_dir: impl Into<PathBuf>) -> Self {
+        self.config_dir = config_dir.into();
+        self
+    }
+
+    /// Use another cache directory
+    pub fn with_cache_dir(mut self, cache_dir: impl Into<PathBuf>) -> Self {
+        self.cache_dir = cache_dir.into();
+        self
+    }
+
+    pub fn config_dir(&self) -> &Path {
+        &self.config_dir
+    }
+
+    pub fn cache_dir(&self) -> &Path {
+        &self.cache_dir
+    }
+
+    /// Build the assets from the syntaxes and themes in the config directory (and the
+    /// integrated ones, unless `include_integrated_assets` is false) and save them to the cache
+    /// directory, like `bat cache --build` does
+    pub fn build_cache(&self, include_integrated_assets: bool) -> Result<()> {
+        HighlightingAssets::from_files(&self.config_dir, include_integrated_assets)?
+            .save_to_cache(&self.cache_dir, env!("CARGO_PKG_VERSION"))
+    }
+
+    /// Load the assets from the cache directory (see `HighlightingAssets::from_cache_dir`)
+    pub fn load_assets(&self) -> Result<HighlightingAssets> {
+        HighlightingAssets::from_cache_dir(&self.cache_dir)
+    }
+
+    /// Remove the cached assets, like `bat cache --clear` does. Files that don't exist are
+    /// skipped.
+    pub fn clear_cache(&self) -> Result<()> {
+        for name in &[THEME_SET, SYNTAX_SET, METADATA] {
+            match fs::remove_file(self.cache_dir.join(name)) {
+                Err(ref error) if error.kind() == std::io::ErrorKind::NotFound => {}
+                result => result.chain_err(|| format!("Could not remove '{}'", name))?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tempdir::TempDir;
+
+    #[test]
+    fn caches_are_built_into_the_cache_dir() {
+        let config_dir = TempDir::new("bat_asset_dirs_config").unwrap();
+        let cache_dir = TempDir::new("bat_asset_dirs_cache").unwrap();
+        let syntaxes = config_dir.path().join("syntaxes");
+        fs::create_dir(&syntaxes).unwrap();
+        fs::write(
+            syntaxes.join("Example.sublime-syntax"),
+            "%YAML 1.2\n---\nname: Example\nfile_extensions: [example]\nscope: source.example\n\
+             contexts:\n  main: []\n",
+        )
+        .unwrap();
+
+        let dirs = AssetDirs::new("unused", "unused")
+            .with_config_dir(config_dir.path())
+            .with_cache_dir(cache_dir.path());
+        dirs.build_cache(false).unwrap();
+        assert!(cache_dir.path().join(SYNTAX_SET).exists());
+
+        let assets = dirs.load_assets().unwrap();
+        assert!(assets.syntaxes().iter().any(|s| s.name == "Example"));
+
+        dirs.clear_cache().unwrap();
+        assert!(!cache_dir.path().join(SYNTAX_SET).exists());
+        dirs.clear_cache().unwrap();
+    }
+}
@@ -0,0 +1,111 @@
+//! Storage for the compiled assets (the syntax set, the theme set and their metadata), which
+//! are stored in a cache folder by default. Applications can keep them elsewhere, like in
+//! their own database or in memory, by implementing `AssetStore` (see
+//! `HighlightingAssets::from_store` and `HighlightingAssets::save_to_store`).
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::error::*;
+
+/// The name of the compiled syntax set
+pub const SYNTAX_SET: &str = "syntaxes.bin";
+
+/// The name of the compiled theme set
+pub const THEME_SET: &str = "themes.bin";
+
+/// The name of the metadata of the assets (see `AssetsMetadata`)
+pub const METADATA: &str = "metadata.yaml";
+
+/// A store for the compiled assets, which are identified by their names (`SYNTAX_SET`,
+/// `THEME_SET` and `METADATA`)
+pub trait AssetStore {
+    /// Where the assets are stored, for error messages (like the path of a folder)
+    fn location(&self) -> String;
+
+    /// The content of an asset, or `None` if the store doesn't contain it
+    fn load(&self, name: &str) -> Result<Option<Vec<u8>>>;
+
+    fn save(&mut self, name: &str, content: &[u8]) -> Result<()>;
+
+    fn contains(&self, name: &str) -> bool {
+        match self.load(name) {
+            Ok(Some(_)) => true,
+            _ => false,
+        }
+    }
+}
+
+/// The assets as files of a folder, like the cache folder of bat
+#[derive(Debug, Clone)]
+pub struct FileSystemStore {
+    dir: PathBuf,
+}
+
+impl FileSystemStore {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        FileSystemStore { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+}
+
+impl AssetStore for FileSystemStore {
+    fn location(&self) -> String {
+        self.dir.to_string_lossy().into_owned()
+    }
+
+    fn load(&self, name: &str) -> Result<Option<Vec<u8>>> {
+        let path = self.dir.join(name);
+        match fs::read(&path) {
+            Ok(content) => Ok(Some(content)),
+            Err(ref error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(error) => {
+                Err(error).chain_err(|| format!("Could not read '{}'", path.to_string_lossy()))
+            }
+        }
+    }
+
+    fn save(&mut self, name: &str, content: &[u8]) -> Result<()> {
+        let path = self.dir.join(name);
+        fs::create_dir_all(&self.dir)
+            .and_then(|_| fs::write(&path, content))
+            .chain_err(|| format!("Could not write '{}'", path.to_string_lossy()))
+    }
+
+    fn contains(&self, name: &str) -> bool {
+        self.dir.join(name).exists()
+    }
+}
+
+/// The assets in memory, for applications that don't persist them or that load them from
+/// somewhere else
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStore {
+    assets: HashMap<String, Vec<u8>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl AssetStore for MemoryStore {
+    fn location(&self) -> String {
+        "<memory>".into()
+    }
+
+    fn load(&self, name: &str) -> Result<Option<Vec<u8>>> {
+        Ok(self.assets.get(name).cloned())
+    }
+
+    fn save(&mut self, name: &str, content: &[u8]) -> Result<()> {
+        self.assets.insert(name.to_owned(), content.to_owned());
+        Ok(())
+    }
+}
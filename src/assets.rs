@@ -1,23 +1,222 @@
 use std::collections::BTreeMap;
 use std::ffi::OsStr;
-use std::fs::{self, File};
-use std::io::BufReader;
-use std::path::Path;
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
-use syntect::dumps::{dump_to_file, from_binary, from_reader};
-use syntect::highlighting::{Theme, ThemeSet};
-use syntect::parsing::{SyntaxReference, SyntaxSet, SyntaxSetBuilder};
+use once_cell::sync::Lazy;
+use syntect::dumps::{dump_binary, dump_to_file, from_binary, from_reader};
+use syntect::highlighting::{Color, Theme, ThemeSet};
+use syntect::parsing::{SyntaxDefinition, SyntaxReference, SyntaxSet, SyntaxSetBuilder};
 
+use crate::asset_store::{self, AssetStore, FileSystemStore};
 use crate::assets_metadata::AssetsMetadata;
+#[cfg(feature = "git")]
+use crate::diff;
 use crate::error::*;
-use crate::input::{InputReader, OpenedInput, OpenedInputKind};
-use crate::syntax_mapping::{MappingTarget, SyntaxMapping};
+use crate::heuristics;
+use crate::input::{Input, InputReader, OpenedInput, OpenedInputKind};
+use crate::suggestions::closest_matches;
+use crate::syntax_mapping::{ConflictResolution, MappingTarget, SyntaxMapping};
+use crate::theme_export::{self, ThemeExportFormat};
+use crate::theme_overrides::{self, ThemeOverrides};
+
+fn unknown_theme(theme_set: &ThemeSet, name: &str) -> Error {
+    let suggestions = closest_matches(name, theme_set.themes.keys().map(String::as_str));
+    ErrorKind::UnknownTheme(
+        name.to_owned(),
+        suggestions.into_iter().map(String::from).collect(),
+    )
+    .into()
+}
+
+/// Describes how bat chooses between several syntaxes that claim the same file extension
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtensionConflict {
+    pub extension: String,
+
+    /// Names of all syntaxes that claim the extension, in order of precedence
+    pub candidates: Vec<String>,
+
+    /// Name of the syntax that is used
+    pub chosen: String,
+
+    pub resolution: ConflictResolution,
+}
+
+/// The ways in which the syntax of an input is detected, see `SyntaxDetection`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DetectionMethod {
+    /// A syntax mapping (like `--map-syntax` or one of the built-in mappings) for the path
+    Mapping,
+
+    /// A syntax mapping to `MappingTarget::MapToUnknown`: the extension of the path is
+    /// ignored, only the first line is checked
+    UnknownMapping,
+
+    /// The `linguist-language` attribute of the file in its git repository (see
+    /// `SyntaxMapping::use_git_attributes`)
+    GitAttributes,
+
+    /// The whole file name, as file names like `Makefile` are registered as extensions
+    FileName,
+
+    /// The file extension
+    Extension,
+
+    /// The first line of the content (like a shebang)
+    FirstLine,
+
+    /// Plain text, if no other method found a syntax
+    Fallback,
+}
+
+/// A single step of the syntax detection
+#[derive(Debug, Clone, PartialEq)]
+pub struct DetectionStep {
+    pub method: DetectionMethod,
+
+    /// What was checked: the path, the file name, the extension or the first line
+    pub subject: String,
+
+    /// The name of the syntax that this step found
+    pub syntax: Option<String>,
+
+    /// How a conflict between several syntaxes for the extension was resolved
+    pub conflict: Option<ExtensionConflict>,
+}
+
+/// The steps that the syntax detection attempted for an input, in order, see
+/// `HighlightingAssets::explain_syntax`
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxDetection {
+    pub steps: Vec<DetectionStep>,
+
+    /// The index of the step that determined the syntax (the last step that was attempted)
+    pub winner: usize,
+}
+
+impl SyntaxDetection {
+    /// The name of the syntax that is used
+    pub fn syntax(&self) -> &str {
+        self.steps[self.winner]
+            .syntax
+            .as_ref()
+            .map_or("", String::as_str)
+    }
+}
+
+/// The default colors of a theme, for surrounding user interfaces that should match the
+/// highlighted code (see `HighlightingAssets::theme_info`). Themes that use the colors of the
+/// terminal palette (like `ansi`) encode the palette index in the red component of a color
+/// with an alpha value of 0.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ThemeInfo {
+    pub background: Option<Color>,
+    pub foreground: Option<Color>,
+
+    /// The background of the gutter (the line numbers and the Git changes)
+    pub gutter_bg: Option<Color>,
+
+    /// The background of highlighted lines (`--highlight-line`)
+    pub highlight_bg: Option<Color>,
+}
+
+/// The integrated syntax set. Deserializing it is the largest part of the startup time, so
+/// this only happens when a syntax is needed for the first time, and once per process: all
+/// assets that are loaded with `from_binary` share it.
+static INTEGRATED_SYNTAX_SET: Lazy<SyntaxSet> =
+    Lazy::new(|| from_binary(include_bytes!("../assets/syntaxes.bin")));
+
+static INTEGRATED_METADATA: Lazy<AssetsMetadata> = Lazy::new(|| {
+    AssetsMetadata::integrated(
+        INTEGRATED_SYNTAX_SET.syntaxes().len(),
+        HighlightingAssets::get_integrated_themeset().themes.len(),
+    )
+});
+
+/// The syntax set of the assets: the shared integrated one, until syntaxes are added
+#[derive(Debug, Clone)]
+enum SyntaxSetSource {
+    Integrated,
+    Owned(SyntaxSet),
+}
 
 #[derive(Debug)]
 pub struct HighlightingAssets {
-    pub(crate) syntax_set: SyntaxSet,
+    syntax_set: SyntaxSetSource,
     pub(crate) theme_set: ThemeSet,
     fallback_theme: Option<&'static str>,
+
+    /// The metadata of the assets (`None` for the integrated assets, whose metadata needs the
+    /// number of syntaxes and is therefore created on demand)
+    metadata: Option<AssetsMetadata>,
+
+    /// The cache folder that the assets have been loaded from (see `reload_if_changed`)
+    cache_source: Option<CacheSource>,
+
+    /// The syntaxes and themes that have been added at runtime, in order, which are added to
+    /// reloaded assets again
+    added: Vec<AddedAsset>,
+}
+
+/// A syntax or a theme that has been added at runtime (with `add_syntax_from_str` or
+/// `add_theme`)
+#[derive(Debug, Clone)]
+enum AddedAsset {
+    Syntax(SyntaxDefinition),
+    Theme(String, Theme),
+}
+
+/// The files of a cache folder, with their modification times when the assets were loaded
+#[derive(Debug, Clone)]
+struct CacheSource {
+    dir: PathBuf,
+    modified: Vec<Option<SystemTime>>,
+
+    /// Whether the assets were loaded with `from_cache_dir`, which falls back to the
+    /// integrated assets
+    integrated_fallback: bool,
+}
+
+impl CacheSource {
+    const FILES: &'static [&'static str] = &[
+        asset_store::SYNTAX_SET,
+        asset_store::THEME_SET,
+        asset_store::METADATA,
+    ];
+
+    fn new(dir: &Path, integrated_fallback: bool) -> Self {
+        let modified = Self::FILES
+            .iter()
+            .map(|file| {
+                fs::metadata(dir.join(file))
+                    .and_then(|metadata| metadata.modified())
+                    .ok()
+            })
+            .collect();
+        CacheSource {
+            dir: dir.to_owned(),
+            modified,
+            integrated_fallback,
+        }
+    }
+}
+
+impl Clone for HighlightingAssets {
+    fn clone(&self) -> Self {
+        HighlightingAssets {
+            syntax_set: self.syntax_set.clone(),
+            theme_set: ThemeSet {
+                themes: self.theme_set.themes.clone(),
+            },
+            fallback_theme: self.fallback_theme,
+            metadata: self.metadata.clone(),
+            cache_source: self.cache_source.clone(),
+            added: self.added.clone(),
+        }
+    }
 }
 
 impl HighlightingAssets {
@@ -43,13 +242,19 @@ impl HighlightingAssets {
                 theme_dir.to_string_lossy()
             );
         }
+        for (name, overrides) in theme_overrides::overrides_in_folder(&theme_dir)? {
+            match theme_set.themes.get_mut(&name) {
+                Some(theme) => overrides.apply(theme)?,
+                None => return Err(unknown_theme(&theme_set, &name)),
+            }
+        }
 
         let mut syntax_set_builder = if !include_integrated_assets {
             let mut builder = SyntaxSetBuilder::new();
             builder.add_plain_text_syntax();
             builder
         } else {
-            Self::get_integrated_syntaxset().into_builder()
+            INTEGRATED_SYNTAX_SET.clone().into_builder()
         };
 
         let syntax_dir = source_dir.join("syntaxes");
@@ -62,58 +267,174 @@ impl HighlightingAssets {
             );
         }
 
+        let syntax_set = syntax_set_builder.build();
+        let metadata = AssetsMetadata::new(
+            env!("CARGO_PKG_VERSION"),
+            syntax_set.syntaxes().len(),
+            theme_set.themes.len(),
+        );
+
         Ok(HighlightingAssets {
-            syntax_set: syntax_set_builder.build(),
+            syntax_set: SyntaxSetSource::Owned(syntax_set),
             theme_set,
             fallback_theme: None,
+            metadata: Some(metadata),
+            cache_source: None,
+            added: vec![],
         })
     }
 
+    /// Load the assets from a cache folder that was created with `save_to_cache`. Caches that
+    /// have been serialized in an incompatible format are rejected with an
+    /// `IncompatibleCache` error before they are deserialized.
     pub fn from_cache(cache_path: &Path) -> Result<Self> {
-        let syntax_set_path = cache_path.join("syntaxes.bin");
-        let theme_set_path = cache_path.join("themes.bin");
+        let cache_source = CacheSource::new(cache_path, false);
+        let mut assets = Self::from_store(&FileSystemStore::new(cache_path))?;
+        assets.cache_source = Some(cache_source);
+        Ok(assets)
+    }
 
-        let syntax_set_file = File::open(&syntax_set_path).chain_err(|| {
-            format!(
-                "Could not load cached syntax set '{}'",
-                syntax_set_path.to_string_lossy()
-            )
-        })?;
-        let syntax_set: SyntaxSet = from_reader(BufReader::new(syntax_set_file))
-            .chain_err(|| "Could not parse cached syntax set")?;
+    /// Load the assets from a store that they have been saved to with `save_to_store` (like
+    /// `from_cache` does for a cache folder)
+    pub fn from_store(store: &dyn AssetStore) -> Result<Self> {
+        let mut metadata = AssetsMetadata::load_from_store(store)?.unwrap_or_default();
+        metadata.ensure_format_compatible(&store.location())?;
 
-        let theme_set_file = File::open(&theme_set_path).chain_err(|| {
-            format!(
-                "Could not load cached theme set '{}'",
-                theme_set_path.to_string_lossy()
-            )
-        })?;
-        let theme_set: ThemeSet = from_reader(BufReader::new(theme_set_file))
+        let load = |name: &str, description: &str| -> Result<Vec<u8>> {
+            store.load(name)?.ok_or_else(|| {
+                format!(
+                    "Could not load cached {} from '{}'",
+                    description,
+                    store.location()
+                )
+                .into()
+            })
+        };
+
+        let syntax_set: SyntaxSet = from_reader(&load(asset_store::SYNTAX_SET, "syntax set")?[..])
+            .chain_err(|| "Could not parse cached syntax set")?;
+        let theme_set: ThemeSet = from_reader(&load(asset_store::THEME_SET, "theme set")?[..])
             .chain_err(|| "Could not parse cached theme set")?;
 
+        metadata.set_counts(syntax_set.syntaxes().len(), theme_set.themes.len());
+
         Ok(HighlightingAssets {
-            syntax_set,
+            syntax_set: SyntaxSetSource::Owned(syntax_set),
             theme_set,
             fallback_theme: None,
+            metadata: Some(metadata),
+            cache_source: None,
+            added: vec![],
         })
     }
 
-    fn get_integrated_syntaxset() -> SyntaxSet {
-        from_binary(include_bytes!("../assets/syntaxes.bin"))
+    /// Load the assets from a cache folder like the `bat` application does: caches that were
+    /// created by an incompatible version are rejected with an `IncompatibleCache` error, the
+    /// integrated assets are used if there is no (readable) cache in the folder. This allows
+    /// applications to keep their cache inside of their own data directories.
+    pub fn from_cache_dir(cache_dir: &Path) -> Result<Self> {
+        let cache_source = CacheSource::new(cache_dir, true);
+        let mut assets = Self::from_store_or_binary(&FileSystemStore::new(cache_dir))?;
+        assets.cache_source = Some(cache_source);
+        Ok(assets)
+    }
+
+    /// Load the assets from a store like `from_cache_dir` does from a cache folder: assets
+    /// that were saved by an incompatible version are rejected, the integrated assets are used
+    /// if the store doesn't contain (readable) assets.
+    pub fn from_store_or_binary(store: &dyn AssetStore) -> Result<Self> {
+        if let Some(metadata) = AssetsMetadata::load_from_store(store)? {
+            metadata.ensure_store_compatible_with(env!("CARGO_PKG_VERSION"), &store.location())?;
+        }
+
+        match Self::from_store(store) {
+            Err(error @ Error(ErrorKind::IncompatibleCache(..), _)) => Err(error),
+            result => Ok(result.unwrap_or_else(|_| Self::from_binary())),
+        }
+    }
+
+    /// Load the assets again if the files in the cache folder that they have been loaded from
+    /// (with `from_cache` or `from_cache_dir`) have changed since, for example because the
+    /// cache has been rebuilt with new custom syntaxes. Returns `None` if nothing has changed.
+    /// The fallback theme and the syntaxes and themes that have been added at runtime are
+    /// carried over to the new assets. As the current assets are left alone, assets that are
+    /// shared (in an `Arc`, for example) can be replaced by the new ones:
+    ///
+    /// ```no_run
+    /// # use std::sync::{Arc, RwLock};
+    /// # use bat::assets::HighlightingAssets;
+    /// # let shared = RwLock::new(Arc::new(HighlightingAssets::from_binary()));
+    /// let current = shared.read().unwrap().clone();
+    /// if let Some(assets) = current.reloaded_if_changed().unwrap() {
+    ///     *shared.write().unwrap() = Arc::new(assets);
+    /// }
+    /// ```
+    pub fn reloaded_if_changed(&self) -> Result<Option<Self>> {
+        let cache_source = match self.cache_source {
+            Some(ref source) => source,
+            None => return Ok(None),
+        };
+
+        let current = CacheSource::new(&cache_source.dir, cache_source.integrated_fallback);
+        if current.modified == cache_source.modified {
+            return Ok(None);
+        }
+
+        let mut assets = if current.integrated_fallback {
+            Self::from_cache_dir(&current.dir)?
+        } else {
+            Self::from_cache(&current.dir)?
+        };
+        assets.fallback_theme = self.fallback_theme;
+        assets.cache_source = Some(current);
+
+        let mut syntaxes = vec![];
+        for added in &self.added {
+            match *added {
+                AddedAsset::Syntax(ref definition) => syntaxes.push(definition.clone()),
+                AddedAsset::Theme(ref name, ref theme) => assets.add_theme(name, theme.clone()),
+            }
+        }
+        assets.add_syntax_definitions(syntaxes);
+        Ok(Some(assets))
+    }
+
+    /// Reload the assets in place if the files in their cache folder have changed (see
+    /// `reloaded_if_changed`). Returns whether the assets have been reloaded. The new assets
+    /// are loaded completely before they replace the current ones: if loading fails (a cache
+    /// that is being written, for instance), the current assets are kept, the error is
+    /// returned and the next call tries again.
+    pub fn reload_if_changed(&mut self) -> Result<bool> {
+        match self.reloaded_if_changed()? {
+            Some(assets) => {
+                *self = assets;
+                Ok(true)
+            }
+            None => Ok(false),
+        }
     }
 
     fn get_integrated_themeset() -> ThemeSet {
         from_binary(include_bytes!("../assets/themes.bin"))
     }
 
+    /// The assets that are integrated into the binary. The syntaxes are deserialized when they
+    /// are first needed (see `get_syntax_set`), so creating the assets is cheap.
     pub fn from_binary() -> Self {
-        let syntax_set = Self::get_integrated_syntaxset();
-        let theme_set = Self::get_integrated_themeset();
-
         HighlightingAssets {
-            syntax_set,
-            theme_set,
+            syntax_set: SyntaxSetSource::Integrated,
+            theme_set: Self::get_integrated_themeset(),
             fallback_theme: None,
+            metadata: None,
+            cache_source: None,
+            added: vec![],
+        }
+    }
+
+    pub(crate) fn get_syntax_set(&self) -> &SyntaxSet {
+        match self.syntax_set {
+            SyntaxSetSource::Integrated => &INTEGRATED_SYNTAX_SET,
+            SyntaxSetSource::Owned(ref syntax_set) => syntax_set,
         }
     }
 
@@ -138,7 +459,7 @@ impl HighlightingAssets {
             "Writing syntax set to {} ... ",
             syntax_set_path.to_string_lossy()
         );
-        dump_to_file(&self.syntax_set, &syntax_set_path).chain_err(|| {
+        dump_to_file(self.get_syntax_set(), &syntax_set_path).chain_err(|| {
             format!(
                 "Could not save syntax set to {}",
                 syntax_set_path.to_string_lossy()
@@ -150,24 +471,126 @@ impl HighlightingAssets {
             "Writing metadata to folder {} ... ",
             target_dir.to_string_lossy()
         );
-        AssetsMetadata::new(current_version).save_to_folder(target_dir)?;
+        AssetsMetadata::new(
+            current_version,
+            self.get_syntax_set().syntaxes().len(),
+            self.theme_set.themes.len(),
+        )
+        .save_to_folder(target_dir)?;
         println!("okay");
 
         Ok(())
     }
 
+    /// Save the assets to a store, from which they can be loaded with `from_store`
+    pub fn save_to_store(&self, store: &mut dyn AssetStore, current_version: &str) -> Result<()> {
+        store
+            .save(asset_store::THEME_SET, &dump_binary(&self.theme_set))
+            .chain_err(|| "Could not save theme set")?;
+        store
+            .save(asset_store::SYNTAX_SET, &dump_binary(self.get_syntax_set()))
+            .chain_err(|| "Could not save syntax set")?;
+        AssetsMetadata::new(
+            current_version,
+            self.get_syntax_set().syntaxes().len(),
+            self.theme_set.themes.len(),
+        )
+        .save_to_store(store)
+    }
+
+    /// Adjust a theme with overrides (like the override files in the `themes` folder, see
+    /// `theme_overrides`)
+    pub fn apply_theme_overrides(&mut self, name: &str, overrides: &ThemeOverrides) -> Result<()> {
+        match self.theme_set.themes.get_mut(name) {
+            Some(theme) => overrides.apply(theme),
+            None => Err(unknown_theme(&self.theme_set, name)),
+        }
+    }
+
+    /// Information about the assets, like the version of bat that built them
+    pub fn metadata(&self) -> &AssetsMetadata {
+        self.metadata.as_ref().unwrap_or(&INTEGRATED_METADATA)
+    }
+
     pub fn set_fallback_theme(&mut self, theme: &'static str) {
         self.fallback_theme = Some(theme);
     }
 
     pub fn syntaxes(&self) -> &[SyntaxReference] {
-        self.syntax_set.syntaxes()
+        self.get_syntax_set().syntaxes()
     }
 
     pub fn themes(&self) -> impl Iterator<Item = &str> {
         self.theme_set.themes.keys().map(|s| s.as_ref())
     }
 
+    /// Add a syntax from the contents of a `.sublime-syntax` file, with the given name (which
+    /// replaces the name in the definition). Syntaxes that are added later take precedence
+    /// over the existing ones with the same name or file extensions. Like themes, syntaxes
+    /// that are added at runtime are not part of a cache, they are added to reloaded assets
+    /// again (see `reloaded_if_changed`).
+    pub fn add_syntax_from_str(&mut self, name: &str, sublime_syntax: &str) -> Result<()> {
+        let mut definition = SyntaxDefinition::load_from_str(sublime_syntax, true, Some(name))
+            .chain_err(|| format!("Could not load syntax '{}'", name))?;
+        definition.name = name.to_owned();
+
+        self.add_syntax_definitions(vec![definition]);
+        Ok(())
+    }
+
+    fn add_syntax_definitions(&mut self, definitions: Vec<SyntaxDefinition>) {
+        if definitions.is_empty() {
+            return;
+        }
+
+        let mut builder = match std::mem::replace(&mut self.syntax_set, SyntaxSetSource::Integrated)
+        {
+            SyntaxSetSource::Integrated => INTEGRATED_SYNTAX_SET.clone().into_builder(),
+            SyntaxSetSource::Owned(syntax_set) => syntax_set.into_builder(),
+        };
+        for definition in definitions {
+            builder.add(definition.clone());
+            self.added.push(AddedAsset::Syntax(definition));
+        }
+        self.syntax_set = SyntaxSetSource::Owned(builder.build());
+    }
+
+    /// Add a theme (or replace the theme with the same name). Themes that are added at runtime
+    /// are not part of a cache, they are added to reloaded assets again (see
+    /// `reloaded_if_changed`).
+    pub fn add_theme(&mut self, name: impl Into<String>, theme: Theme) {
+        let name = name.into();
+        self.added.retain(|added| match *added {
+            AddedAsset::Theme(ref added_name, _) => *added_name != name,
+            AddedAsset::Syntax(_) => true,
+        });
+        self.added
+            .push(AddedAsset::Theme(name.clone(), theme.clone()));
+        self.theme_set.themes.insert(name, theme);
+    }
+
+    /// Add a theme from the contents of a `.tmTheme` file
+    pub fn add_theme_from_str(&mut self, name: impl Into<String>, tmtheme: &str) -> Result<()> {
+        let name = name.into();
+        let theme = ThemeSet::load_from_reader(&mut Cursor::new(tmtheme.as_bytes()))
+            .chain_err(|| format!("Could not load theme '{}'", name))?;
+        self.add_theme(name, theme);
+        Ok(())
+    }
+
+    /// Add a theme from a `.tmTheme` file. The theme is named after the file (without the
+    /// extension), like the themes in the cache. Returns the name of the theme.
+    pub fn add_theme_from_file(&mut self, path: &Path) -> Result<String> {
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .ok_or_else(|| format!("'{}' is not a theme file", path.to_string_lossy()))?;
+        let theme = ThemeSet::get_theme(path)
+            .chain_err(|| format!("Could not load theme '{}'", path.to_string_lossy()))?;
+        self.add_theme(name.clone(), theme);
+        Ok(name)
+    }
+
     pub(crate) fn get_theme(&self, theme: &str) -> &Theme {
         match self.theme_set.themes.get(theme) {
             Some(theme) => theme,
@@ -185,14 +608,70 @@ impl HighlightingAssets {
         }
     }
 
+    /// Like `get_theme`, but fails with an `UnknownTheme` error instead of falling back to
+    /// the default theme.
+    pub(crate) fn get_theme_strict(&self, theme: &str) -> Result<&Theme> {
+        if theme.is_empty() {
+            return Ok(self.get_theme(theme));
+        }
+
+        self.theme_set
+            .themes
+            .get(theme)
+            .ok_or_else(|| unknown_theme(&self.theme_set, theme))
+    }
+
+    /// The default colors of a theme
+    pub fn theme_info(&self, name: &str) -> Result<ThemeInfo> {
+        let settings = &self.get_theme_strict(name)?.settings;
+        Ok(ThemeInfo {
+            background: settings.background,
+            foreground: settings.foreground,
+            gutter_bg: settings.gutter,
+            highlight_bg: settings.line_highlight,
+        })
+    }
+
+    /// Convert a theme into the color scheme of a terminal emulator (or CSS variables), see
+    /// the `theme_export` module. Themes that use the colors of the terminal palette (like
+    /// the `ansi` themes) can not be exported.
+    pub fn export_theme(&self, name: &str, format: ThemeExportFormat) -> Result<String> {
+        let theme = self.get_theme_strict(name)?;
+        theme_export::export_theme(name, theme, format)
+    }
+
+    /// Find a syntax by its name or by one of its file extensions
+    pub(crate) fn get_syntax_by_token(&self, language: &str) -> Result<&SyntaxReference> {
+        self.get_syntax_set()
+            .find_syntax_by_token(language)
+            .ok_or_else(|| self.unknown_syntax(language))
+    }
+
+    fn unknown_syntax(&self, name: &str) -> Error {
+        let names = self
+            .syntaxes()
+            .iter()
+            .filter(|syntax| !syntax.hidden)
+            .map(|syntax| syntax.name.as_str());
+        let suggestions = closest_matches(name, names);
+
+        ErrorKind::UnknownSyntax(
+            name.to_owned(),
+            suggestions.into_iter().map(String::from).collect(),
+        )
+        .into()
+    }
+
+    /// Determine the syntax for the given input. Fails with an `UnknownSyntax` error if the
+    /// explicitly requested language (or the target of a syntax mapping) does not exist.
     pub(crate) fn get_syntax(
         &self,
         language: Option<&str>,
         input: &mut OpenedInput,
         mapping: &SyntaxMapping,
-    ) -> &SyntaxReference {
+    ) -> Result<&SyntaxReference> {
         let syntax = if let Some(language) = language {
-            self.syntax_set.find_syntax_by_token(language)
+            Some(self.get_syntax_by_token(language)?)
         } else {
             match input.kind {
                 OpenedInputKind::OrdinaryFile(ref actual_path) => {
@@ -206,50 +685,352 @@ impl HighlightingAssets {
 
                     let absolute_path = path.canonicalize().ok().unwrap_or_else(|| path.to_owned());
                     match mapping.get_syntax_for(absolute_path) {
-                        Some(MappingTarget::MapTo(syntax_name)) => {
-                            // TODO: we should probably return an error here if this syntax can not be
-                            // found. Currently, we just fall back to 'plain'.
-                            self.syntax_set.find_syntax_by_name(syntax_name)
-                        }
+                        Some(MappingTarget::MapTo(syntax_name)) => Some(
+                            self.get_syntax_set()
+                                .find_syntax_by_name(syntax_name)
+                                .ok_or_else(|| self.unknown_syntax(syntax_name))?,
+                        ),
                         Some(MappingTarget::MapToUnknown) => line_syntax,
                         None => {
-                            let file_name = path.file_name().unwrap_or_default();
-                            self.get_extension_syntax(file_name).or(line_syntax)
+                            match self.get_git_attributes_syntax(Path::new(actual_path), mapping) {
+                                Some(syntax) => Some(syntax),
+                                None => {
+                                    let file_name = path.file_name().unwrap_or_default();
+                                    self.get_extension_syntax(file_name, &mut input.reader, mapping)
+                                        .or(line_syntax)
+                                }
+                            }
                         }
                     }
                 }
                 OpenedInputKind::StdIn | OpenedInputKind::CustomReader => {
-                    if let Some(ref name) = input.metadata.user_provided_name {
-                        self.get_extension_syntax(&name)
-                            .or_else(|| self.get_first_line_syntax(&mut input.reader))
-                    } else {
-                        self.get_first_line_syntax(&mut input.reader)
-                    }
+                    let name_syntax = match input.metadata.user_provided_name {
+                        Some(ref name) => {
+                            self.get_extension_syntax(&name, &mut input.reader, mapping)
+                        }
+                        None => None,
+                    };
+                    name_syntax
+                        .or_else(|| {
+                            self.get_content_type_syntax(input.metadata.content_type.as_deref())
+                        })
+                        .or_else(|| self.get_first_line_syntax(&mut input.reader))
+                }
+                OpenedInputKind::ThemePreviewFile => {
+                    self.get_syntax_set().find_syntax_by_name("Rust")
                 }
-                OpenedInputKind::ThemePreviewFile => self.syntax_set.find_syntax_by_name("Rust"),
             }
         };
 
-        syntax.unwrap_or_else(|| self.syntax_set.find_syntax_plain_text())
+        let syntax = match syntax {
+            None if language.is_none() && mapping.detects_from_content() => {
+                self.get_content_syntax(&mut input.reader)
+            }
+            syntax => syntax,
+        };
+
+        Ok(syntax.unwrap_or_else(|| self.get_syntax_set().find_syntax_plain_text()))
+    }
+
+    /// All syntaxes that claim the given file extension, in order of precedence (the syntax
+    /// that was defined last comes first)
+    pub fn syntaxes_for_extension(&self, extension: &str) -> Vec<&SyntaxReference> {
+        self.get_syntax_set()
+            .syntaxes()
+            .iter()
+            .rev()
+            .filter(|syntax| syntax.file_extensions.iter().any(|e| e == extension))
+            .collect()
+    }
+
+    /// All file extensions that are claimed by more than one syntax, together with the names
+    /// of the candidate syntaxes (in order of precedence)
+    pub fn ambiguous_extensions(&self) -> BTreeMap<&str, Vec<&str>> {
+        let mut extensions: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for syntax in self.get_syntax_set().syntaxes().iter().rev() {
+            for extension in &syntax.file_extensions {
+                let candidates = extensions.entry(extension.as_str()).or_default();
+                if !candidates.contains(&syntax.name.as_str()) {
+                    candidates.push(&syntax.name);
+                }
+            }
+        }
+
+        extensions
+            .into_iter()
+            .filter(|(_, candidates)| candidates.len() > 1)
+            .collect()
+    }
+
+    /// Explain which syntax would be chosen for a file with the given extension and content
+    /// (only the beginning of the file is relevant). Returns `None` if no more than one syntax
+    /// claims the extension.
+    pub fn resolve_extension_conflict(
+        &self,
+        extension: &str,
+        content: &[u8],
+        mapping: &SyntaxMapping,
+    ) -> Option<ExtensionConflict> {
+        let candidates = self.syntaxes_for_extension(extension);
+        if candidates.len() < 2 {
+            return None;
+        }
+
+        let (syntax, resolution) = self.resolve_conflict(extension, &candidates, mapping, || {
+            String::from_utf8_lossy(content).into_owned()
+        });
+
+        Some(ExtensionConflict {
+            extension: extension.to_owned(),
+            candidates: candidates.iter().map(|s| s.name.clone()).collect(),
+            chosen: syntax.name.clone(),
+            resolution,
+        })
+    }
+
+    /// Explain how the syntax of a file with the given path and content is detected (the file
+    /// doesn't have to exist, only the beginning of the content is relevant). The steps are
+    /// listed in the order in which they are attempted, the last step determines the syntax.
+    /// Without a path, only the first line is checked.
+    pub fn explain_syntax(
+        &self,
+        path: Option<&Path>,
+        content: &[u8],
+        mapping: &SyntaxMapping,
+    ) -> Result<SyntaxDetection> {
+        let mut reader = Input::from_reader(Box::new(Cursor::new(content.to_vec())))
+            .open(&[][..])?
+            .reader;
+        let mut steps = vec![];
+
+        if let Some(path) = path {
+            let path = path.canonicalize().ok().unwrap_or_else(|| path.to_owned());
+            let subject = path.to_string_lossy().into_owned();
+            match mapping.get_syntax_for(&path) {
+                Some(MappingTarget::MapTo(syntax_name)) => {
+                    self.get_syntax_set()
+                        .find_syntax_by_name(syntax_name)
+                        .ok_or_else(|| self.unknown_syntax(syntax_name))?;
+                    steps.push(DetectionStep {
+                        method: DetectionMethod::Mapping,
+                        subject,
+                        syntax: Some(syntax_name.to_owned()),
+                        conflict: None,
+                    });
+                }
+                Some(MappingTarget::MapToUnknown) => steps.push(DetectionStep {
+                    method: DetectionMethod::UnknownMapping,
+                    subject,
+                    syntax: None,
+                    conflict: None,
+                }),
+                None => match self.get_git_attributes_syntax(&path, mapping) {
+                    Some(syntax) => steps.push(DetectionStep {
+                        method: DetectionMethod::GitAttributes,
+                        subject,
+                        syntax: Some(syntax.name.clone()),
+                        conflict: None,
+                    }),
+                    None => {
+                        let file_name = path
+                            .file_name()
+                            .unwrap_or_default()
+                            .to_string_lossy()
+                            .into_owned();
+                        steps.push(self.explain_extension(
+                            DetectionMethod::FileName,
+                            &file_name,
+                            &mut reader,
+                            mapping,
+                        ));
+
+                        if steps[0].syntax.is_none() {
+                            let extension = Path::new(&file_name)
+                                .extension()
+                                .and_then(|x| x.to_str())
+                                .unwrap_or_default()
+                                .to_owned();
+                            steps.push(self.explain_extension(
+                                DetectionMethod::Extension,
+                                &extension,
+                                &mut reader,
+                                mapping,
+                            ));
+                        }
+                    }
+                },
+            }
+        }
+
+        if steps.iter().all(|step| step.syntax.is_none()) {
+            let first_line = String::from_utf8_lossy(&reader.first_line).into_owned();
+            let syntax = self.get_first_line_syntax(&mut reader);
+            steps.push(DetectionStep {
+                method: DetectionMethod::FirstLine,
+                subject: first_line.trim_end().to_owned(),
+                syntax: syntax.map(|syntax| syntax.name.clone()),
+                conflict: None,
+            });
+        }
+
+        if steps.iter().all(|step| step.syntax.is_none()) {
+            steps.push(DetectionStep {
+                method: DetectionMethod::Fallback,
+                subject: String::new(),
+                syntax: Some(self.get_syntax_set().find_syntax_plain_text().name.clone()),
+                conflict: None,
+            });
+        }
+
+        Ok(SyntaxDetection {
+            winner: steps.len() - 1,
+            steps,
+        })
     }
 
-    fn get_extension_syntax(&self, file_name: &OsStr) -> Option<&SyntaxReference> {
-        self.syntax_set
-            .find_syntax_by_extension(file_name.to_str().unwrap_or_default())
+    fn explain_extension(
+        &self,
+        method: DetectionMethod,
+        extension: &str,
+        reader: &mut InputReader,
+        mapping: &SyntaxMapping,
+    ) -> DetectionStep {
+        let candidates = self.syntaxes_for_extension(extension);
+        let (syntax, conflict) = match candidates.len() {
+            0 => (None, None),
+            1 => (Some(candidates[0].name.clone()), None),
+            _ => {
+                let (syntax, resolution) =
+                    self.resolve_conflict(extension, &candidates, mapping, || {
+                        String::from_utf8_lossy(&reader.peek()).into_owned()
+                    });
+                let conflict = ExtensionConflict {
+                    extension: extension.to_owned(),
+                    candidates: candidates.iter().map(|s| s.name.clone()).collect(),
+                    chosen: syntax.name.clone(),
+                    resolution,
+                };
+                (Some(syntax.name.clone()), Some(conflict))
+            }
+        };
+
+        DetectionStep {
+            method,
+            subject: extension.to_owned(),
+            syntax,
+            conflict,
+        }
+    }
+
+    fn resolve_conflict<'s>(
+        &self,
+        extension: &str,
+        candidates: &[&'s SyntaxReference],
+        mapping: &SyntaxMapping,
+        content: impl FnOnce() -> String,
+    ) -> (&'s SyntaxReference, ConflictResolution) {
+        let names: Vec<&str> = candidates.iter().map(|s| s.name.as_str()).collect();
+        let content = if mapping.needs_content_for_conflicts() {
+            content()
+        } else {
+            String::new()
+        };
+
+        let (index, resolution) = mapping.resolve_conflict(extension, &names, &content);
+        (candidates[index], resolution)
+    }
+
+    fn find_syntax_by_extension(
+        &self,
+        extension: &str,
+        reader: &mut InputReader,
+        mapping: &SyntaxMapping,
+    ) -> Option<&SyntaxReference> {
+        let candidates = self.syntaxes_for_extension(extension);
+        match candidates.len() {
+            0 => None,
+            1 => Some(candidates[0]),
+            _ => Some(
+                self.resolve_conflict(extension, &candidates, mapping, || {
+                    String::from_utf8_lossy(&reader.peek()).into_owned()
+                })
+                .0,
+            ),
+        }
+    }
+
+    fn get_extension_syntax(
+        &self,
+        file_name: &OsStr,
+        reader: &mut InputReader,
+        mapping: &SyntaxMapping,
+    ) -> Option<&SyntaxReference> {
+        self.find_syntax_by_extension(file_name.to_str().unwrap_or_default(), reader, mapping)
             .or_else(|| {
-                self.syntax_set.find_syntax_by_extension(
+                self.find_syntax_by_extension(
                     Path::new(file_name)
                         .extension()
                         .and_then(|x| x.to_str())
                         .unwrap_or_default(),
+                    reader,
+                    mapping,
                 )
             })
     }
 
+    /// The syntax of the language that the git attributes of a file declare (see
+    /// `SyntaxMapping::use_git_attributes`). Linguist language names are mostly the names of
+    /// the syntaxes, other names (like `sh`) are looked up as tokens.
+    #[cfg(feature = "git")]
+    fn get_git_attributes_syntax(
+        &self,
+        path: &Path,
+        mapping: &SyntaxMapping,
+    ) -> Option<&SyntaxReference> {
+        if !mapping.uses_git_attributes() {
+            return None;
+        }
+        let language = diff::get_linguist_language(path)?;
+        let syntax_set = self.get_syntax_set();
+        syntax_set
+            .find_syntax_by_name(&language)
+            .or_else(|| syntax_set.find_syntax_by_token(&language))
+    }
+
+    #[cfg(not(feature = "git"))]
+    fn get_git_attributes_syntax(
+        &self,
+        _path: &Path,
+        _mapping: &SyntaxMapping,
+    ) -> Option<&SyntaxReference> {
+        None
+    }
+
+    /// The syntax of the language that is detected from the content (see
+    /// `SyntaxMapping::detect_from_content`)
+    fn get_content_syntax(&self, reader: &mut InputReader) -> Option<&SyntaxReference> {
+        let token = heuristics::detect_language(&String::from_utf8_lossy(&reader.peek()))?;
+        self.get_syntax_set().find_syntax_by_token(&token)
+    }
+
+    /// The syntax of a media type like `application/json` or `text/x-python` (see
+    /// `InputMetadata::content_type`)
+    fn get_content_type_syntax(&self, content_type: Option<&str>) -> Option<&SyntaxReference> {
+        let media_type = content_type?.split(';').next()?.trim();
+        let subtype = media_type.rsplit('/').next()?;
+        // Like `x-python` or `vnd.api+json`
+        let subtype = subtype.trim_start_matches("x-");
+        let token = subtype.rsplit('+').next()?;
+        match token {
+            "" | "plain" | "octet-stream" => None,
+            _ => self.get_syntax_set().find_syntax_by_token(token),
+        }
+    }
+
     fn get_first_line_syntax(&self, reader: &mut InputReader) -> Option<&SyntaxReference> {
         String::from_utf8(reader.first_line.clone())
             .ok()
-            .and_then(|l| self.syntax_set.find_syntax_by_first_line(&l))
+            .and_then(|l| self.get_syntax_set().find_syntax_by_first_line(&l))
     }
 }
 
@@ -293,7 +1074,8 @@ mod tests {
             let mut opened_input = input.open(dummy_stdin).unwrap();
             let syntax = self
                 .assets
-                .get_syntax(None, &mut opened_input, &self.syntax_mapping);
+                .get_syntax(None, &mut opened_input, &self.syntax_mapping)
+                .unwrap();
 
             syntax.name.clone()
         }
@@ -316,7 +1098,8 @@ mod tests {
 
             let syntax = self
                 .assets
-                .get_syntax(None, &mut opened_input, &self.syntax_mapping);
+                .get_syntax(None, &mut opened_input, &self.syntax_mapping)
+                .unwrap();
             syntax.name.clone()
         }
     }
@@ -359,6 +1142,23 @@ mod tests {
         assert_eq!(test.syntax_for_file("test.js"), "JavaScript (Babel)");
     }
 
+    #[test]
+    fn syntax_detection_content_type() {
+        let assets = HighlightingAssets::from_binary();
+        let name = |content_type: &str| {
+            assets
+                .get_content_type_syntax(Some(content_type))
+                .map(|syntax| syntax.name.clone())
+        };
+
+        assert_eq!(name("application/json"), Some("JSON".into()));
+        assert_eq!(name("application/vnd.api+json"), Some("JSON".into()));
+        assert_eq!(name("text/x-python; charset=utf-8"), Some("Python".into()));
+        assert_eq!(name("text/html"), Some("HTML".into()));
+        assert_eq!(name("text/plain"), None);
+        assert!(assets.get_content_type_syntax(None).is_none());
+    }
+
     #[test]
     fn syntax_detection_first_line() {
         let test = SyntaxDetectionTest::new();
@@ -377,6 +1177,35 @@ mod tests {
         );
     }
 
+    #[test]
+    fn syntax_detection_from_content() {
+        let mut test = SyntaxDetectionTest::new();
+        let content = "# vim: ft=ruby\nputs 1";
+        assert_eq!(
+            test.syntax_for_file_with_content("recipe", content),
+            "Plain Text"
+        );
+
+        test.syntax_mapping.detect_from_content(true);
+        assert_eq!(test.syntax_for_file_with_content("recipe", content), "Ruby");
+        assert_eq!(
+            test.syntax_for_file_with_content("data", "{\"key\": [1, 2]}"),
+            "JSON"
+        );
+        assert_eq!(
+            test.syntax_for_file_with_content("run", "#!/usr/bin/env -S deno run"),
+            "JavaScript (Babel)"
+        );
+        assert_eq!(
+            test.syntax_for_file_with_content("test.py", "# vim: ft=ruby"),
+            "Python"
+        );
+        assert_eq!(
+            test.syntax_for_file_with_content("notes", "text"),
+            "Plain Text"
+        );
+    }
+
     #[test]
     fn syntax_detection_with_custom_mapping() {
         let mut test = SyntaxDetectionTest::new();
@@ -388,6 +1217,114 @@ mod tests {
         assert_eq!(test.syntax_for_file("test.h"), "C");
     }
 
+    #[test]
+    fn syntax_detection_trace() {
+        let assets = HighlightingAssets::from_binary();
+        let mut mapping = SyntaxMapping::builtin();
+        let explain = |path: Option<&str>, content: &str, mapping: &SyntaxMapping| {
+            assets
+                .explain_syntax(path.map(Path::new), content.as_bytes(), mapping)
+                .unwrap()
+        };
+
+        let detection = explain(Some("my_script"), "#!/bin/bash\n", &mapping);
+        let methods: Vec<_> = detection.steps.iter().map(|step| step.method).collect();
+        assert_eq!(
+            methods,
+            vec![
+                DetectionMethod::FileName,
+                DetectionMethod::Extension,
+                DetectionMethod::FirstLine
+            ]
+        );
+        assert_eq!(detection.steps[2].subject, "#!/bin/bash");
+        assert_eq!(detection.syntax(), "Bourne Again Shell (bash)");
+
+        let detection = explain(Some("test.h"), "int x;\n", &mapping);
+        assert_eq!(detection.winner, 1);
+        assert_eq!(detection.steps[1].subject, "h");
+        assert!(detection.steps[1].conflict.is_some());
+
+        mapping.insert("*.h", MappingTarget::MapTo("C")).ok();
+        let detection = explain(Some("test.h"), "int x;\n", &mapping);
+        assert_eq!(detection.steps[0].method, DetectionMethod::Mapping);
+        assert_eq!(detection.syntax(), "C");
+
+        let detection = explain(None, "no syntax\n", &mapping);
+        assert_eq!(detection.steps[1].method, DetectionMethod::Fallback);
+        assert_eq!(detection.syntax(), "Plain Text");
+    }
+
+    #[test]
+    fn ambiguous_extensions() {
+        let assets = HighlightingAssets::from_binary();
+        let ambiguous = assets.ambiguous_extensions();
+
+        let candidates = &ambiguous["h"];
+        assert!(candidates.contains(&"C"));
+        assert!(candidates.contains(&"C++"));
+        assert!(!ambiguous.contains_key("rs"));
+
+        assert_eq!(assets.syntaxes_for_extension("h").len(), candidates.len());
+    }
+
+    #[test]
+    fn syntax_detection_conflict_resolution() {
+        fn detect_objective_c(_: &str, candidates: &[&str], content: &str) -> Option<usize> {
+            if content.contains("@interface") {
+                candidates.iter().position(|c| *c == "Objective-C")
+            } else {
+                None
+            }
+        }
+
+        let mut test = SyntaxDetectionTest::new();
+        test.syntax_mapping = SyntaxMapping::empty();
+        test.syntax_mapping.prefer_syntax("C");
+        assert_eq!(test.syntax_for_file("test.h"), "C");
+
+        test.syntax_mapping.add_disambiguator(detect_objective_c);
+        assert_eq!(
+            test.syntax_for_file_with_content("test.h", "@interface Foo : NSObject"),
+            "Objective-C"
+        );
+        assert_eq!(
+            test.syntax_for_file_with_content("test.h", "int foo(void);"),
+            "C"
+        );
+
+        let conflict = test
+            .assets
+            .resolve_extension_conflict("h", b"#include <stdio.h>", &test.syntax_mapping)
+            .unwrap();
+        assert_eq!(conflict.chosen, "C");
+        assert_eq!(conflict.resolution, ConflictResolution::Preference);
+        assert!(conflict.candidates.len() > 1);
+
+        assert!(test
+            .assets
+            .resolve_extension_conflict("rs", b"", &test.syntax_mapping)
+            .is_none());
+    }
+
+    #[test]
+    fn syntax_detection_builtin_heuristics() {
+        let test = SyntaxDetectionTest::new();
+
+        assert_eq!(
+            test.syntax_for_file_with_content("test.h", "#import <Foundation/Foundation.h>"),
+            "Objective-C"
+        );
+        assert_eq!(
+            test.syntax_for_file_with_content("test.h", "#include <vector>"),
+            "C++"
+        );
+        assert_eq!(
+            test.syntax_for_file_with_content("test.h", "int foo(void);"),
+            "C"
+        );
+    }
+
     #[test]
     fn syntax_detection_is_case_sensitive() {
         let mut test = SyntaxDetectionTest::new();
@@ -411,4 +1348,267 @@ mod tests {
             "Bourne Again Shell (bash)"
         );
     }
+
+    #[test]
+    fn syntax_detection_unknown_language() {
+        let test = SyntaxDetectionTest::new();
+
+        let input = Input::from_reader(Box::new(&b"x"[..]));
+        let mut opened_input = input.open(&b""[..]).unwrap();
+        let error = test
+            .assets
+            .get_syntax(Some("Rsut"), &mut opened_input, &test.syntax_mapping)
+            .unwrap_err();
+
+        match error.kind() {
+            ErrorKind::UnknownSyntax(name, suggestions) => {
+                assert_eq!(name, "Rsut");
+                assert_eq!(suggestions.first().map(String::as_str), Some("Rust"));
+            }
+            _ => panic!("unexpected error: {}", error),
+        }
+    }
+
+    #[test]
+    fn syntax_detection_unknown_mapping_target() {
+        let mut test = SyntaxDetectionTest::new();
+
+        test.syntax_mapping
+            .insert("*.rs", MappingTarget::MapTo("Does not exist"))
+            .ok();
+
+        let file_path = test.temp_dir.path().join("test.rs");
+        File::create(&file_path).unwrap();
+        let input = Input::ordinary_file(file_path.as_os_str());
+        let mut opened_input = input.open(&b""[..]).unwrap();
+
+        assert!(test
+            .assets
+            .get_syntax(None, &mut opened_input, &test.syntax_mapping)
+            .is_err());
+    }
+
+    #[test]
+    fn integrated_assets_metadata() {
+        let assets = HighlightingAssets::from_binary();
+        let metadata = assets.metadata();
+
+        assert_eq!(metadata.bat_version(), Some(env!("CARGO_PKG_VERSION")));
+        assert_eq!(metadata.syntax_count(), Some(assets.syntaxes().len()));
+        assert_eq!(metadata.theme_count(), Some(assets.themes().count()));
+        assert!(metadata.creation_time().is_none());
+        assert!(metadata.is_compatible_with(env!("CARGO_PKG_VERSION")));
+    }
+
+    #[test]
+    fn cache_dir_without_cache() {
+        let cache_dir = TempDir::new("bat_cache_dir_tests").unwrap();
+
+        let assets = HighlightingAssets::from_cache_dir(cache_dir.path()).unwrap();
+        assert_eq!(
+            assets.metadata(),
+            HighlightingAssets::from_binary().metadata()
+        );
+
+        fs::write(
+            cache_dir.path().join("metadata.yaml"),
+            "bat_version: 0.1.0\n",
+        )
+        .unwrap();
+        match HighlightingAssets::from_cache_dir(cache_dir.path()) {
+            Err(Error(ErrorKind::IncompatibleCache(..), _)) => {}
+            _ => panic!("incompatible cache was not rejected"),
+        }
+    }
+
+    #[test]
+    fn assets_are_reloaded_when_the_cache_changes() {
+        let cache_dir = TempDir::new("bat_cache_reload_tests").unwrap();
+
+        let mut assets = HighlightingAssets::from_cache_dir(cache_dir.path()).unwrap();
+        assets.set_fallback_theme("TwoDark");
+        let custom_theme = assets.get_theme("Nord").clone();
+        assets.add_theme("Custom", custom_theme);
+        assets
+            .add_syntax_from_str(
+                "Custom",
+                "%YAML 1.2\n---\nfile_extensions: [custom]\nscope: source.custom\n\
+                 contexts:\n  main: []\n",
+            )
+            .unwrap();
+        assert!(!assets.reload_if_changed().unwrap());
+
+        let mut new_assets = HighlightingAssets::from_binary();
+        new_assets.theme_set.themes.remove("Nord");
+        new_assets
+            .save_to_cache(cache_dir.path(), env!("CARGO_PKG_VERSION"))
+            .unwrap();
+
+        assert!(assets.reload_if_changed().unwrap());
+        assert!(assets.themes().all(|theme| theme != "Nord"));
+        assert!(assets.themes().any(|theme| theme == "Custom"));
+        assert!(assets
+            .syntaxes()
+            .iter()
+            .any(|syntax| syntax.name == "Custom"));
+        assert_eq!(assets.fallback_theme, Some("TwoDark"));
+        assert!(!assets.reload_if_changed().unwrap());
+
+        assert!(!HighlightingAssets::from_binary()
+            .reload_if_changed()
+            .unwrap());
+    }
+
+    #[test]
+    fn shared_assets_are_replaced_by_reloaded_ones() {
+        let cache_dir = TempDir::new("bat_cache_reloaded_tests").unwrap();
+
+        let shared =
+            std::sync::Arc::new(HighlightingAssets::from_cache_dir(cache_dir.path()).unwrap());
+        assert!(shared.reloaded_if_changed().unwrap().is_none());
+
+        let mut new_assets = HighlightingAssets::from_binary();
+        new_assets.theme_set.themes.remove("Nord");
+        new_assets
+            .save_to_cache(cache_dir.path(), env!("CARGO_PKG_VERSION"))
+            .unwrap();
+
+        let reloaded = shared.reloaded_if_changed().unwrap().unwrap();
+        assert!(reloaded.themes().all(|theme| theme != "Nord"));
+        assert!(shared.themes().any(|theme| theme == "Nord"));
+        assert!(reloaded.reloaded_if_changed().unwrap().is_none());
+    }
+
+    #[test]
+    fn assets_are_saved_to_a_store() {
+        use crate::asset_store::MemoryStore;
+
+        let mut store = MemoryStore::new();
+        assert!(HighlightingAssets::from_store(&store).is_err());
+        assert_eq!(
+            HighlightingAssets::from_store_or_binary(&store)
+                .unwrap()
+                .metadata(),
+            HighlightingAssets::from_binary().metadata()
+        );
+
+        let mut assets = HighlightingAssets::from_binary();
+        assets.theme_set.themes.remove("Nord");
+        assets
+            .save_to_store(&mut store, env!("CARGO_PKG_VERSION"))
+            .unwrap();
+
+        let loaded = HighlightingAssets::from_store_or_binary(&store).unwrap();
+        assert!(loaded.themes().all(|theme| theme != "Nord"));
+        assert_eq!(loaded.syntaxes().len(), assets.syntaxes().len());
+        assert!(loaded.metadata().creation_time().is_some());
+
+        store
+            .save(asset_store::METADATA, b"bat_version: 0.1.0\n")
+            .unwrap();
+        match HighlightingAssets::from_store_or_binary(&store) {
+            Err(Error(ErrorKind::IncompatibleCache(location, ..), _)) => {
+                assert_eq!(location, "<memory>")
+            }
+            _ => panic!("incompatible assets were not rejected"),
+        }
+    }
+
+    #[test]
+    fn integrated_syntaxes_are_shared() {
+        let assets = HighlightingAssets::from_binary();
+        let other = HighlightingAssets::from_binary();
+        assert!(std::ptr::eq(
+            assets.get_syntax_set(),
+            other.get_syntax_set()
+        ));
+
+        let mut extended = other.clone();
+        extended
+            .add_syntax_from_str("Empty", "scope: source.empty\ncontexts:\n  main: []\n")
+            .unwrap();
+        assert!(!std::ptr::eq(
+            assets.get_syntax_set(),
+            extended.get_syntax_set()
+        ));
+        assert_eq!(assets.syntaxes().len() + 1, extended.syntaxes().len());
+    }
+
+    #[test]
+    fn syntaxes_are_added_at_runtime() {
+        let mut assets = HighlightingAssets::from_binary();
+        let syntax_count = assets.syntaxes().len();
+
+        assets
+            .add_syntax_from_str(
+                "Recipe",
+                "%YAML 1.2\n\
+                 ---\n\
+                 file_extensions: [recipe]\n\
+                 scope: source.recipe\n\
+                 contexts:\n  \
+                   main:\n    \
+                     - match: '^\\w+:'\n      \
+                       scope: keyword.other.recipe\n",
+            )
+            .unwrap();
+        assert_eq!(assets.syntaxes().len(), syntax_count + 1);
+        assert_eq!(assets.get_syntax_by_token("recipe").unwrap().name, "Recipe");
+        assert!(assets.get_syntax_by_token("Rust").is_ok());
+
+        assert!(assets.add_syntax_from_str("Broken", "contexts: [").is_err());
+    }
+
+    #[test]
+    fn themes_are_added_at_runtime() {
+        let mut assets = HighlightingAssets::from_binary();
+        let tmtheme = fs::read_to_string("assets/themes/ansi-dark.tmTheme").unwrap();
+
+        assets.add_theme_from_str("Custom", &tmtheme).unwrap();
+        assert!(assets.get_theme_strict("Custom").is_ok());
+        assert!(assets.add_theme_from_str("Broken", "<plist>").is_err());
+
+        let theme_dir = TempDir::new("bat_theme_tests").unwrap();
+        let path = theme_dir.path().join("From File.tmTheme");
+        fs::write(&path, &tmtheme).unwrap();
+        assert_eq!(assets.add_theme_from_file(&path).unwrap(), "From File");
+        assert!(assets.themes().any(|theme| theme == "From File"));
+        assert!(assets
+            .add_theme_from_file(&theme_dir.path().join("missing.tmTheme"))
+            .is_err());
+    }
+
+    #[test]
+    fn theme_info() {
+        let assets = HighlightingAssets::from_binary();
+
+        let info = assets.theme_info("Monokai Extended").unwrap();
+        assert_eq!(
+            info.background,
+            Some(Color {
+                r: 0x22,
+                g: 0x22,
+                b: 0x22,
+                a: 0xff
+            })
+        );
+        assert!(info.foreground.is_some());
+        assert!(assets.theme_info("does-not-exist").is_err());
+    }
+
+    #[test]
+    fn strict_theme_lookup() {
+        let assets = HighlightingAssets::from_binary();
+
+        assert!(assets.get_theme_strict("TwoDark").is_ok());
+        assert!(assets.get_theme_strict("").is_ok());
+
+        match assets.get_theme_strict("twodark").unwrap_err().kind() {
+            ErrorKind::UnknownTheme(name, suggestions) => {
+                assert_eq!(name, "twodark");
+                assert_eq!(suggestions, &vec!["TwoDark".to_owned()]);
+            }
+            _ => panic!("unexpected error"),
+        }
+    }
 }
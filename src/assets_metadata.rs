@@ -1,38 +1,82 @@
-use std::fs::File;
 use std::path::Path;
 use std::time::SystemTime;
 
 use semver::Version;
 use serde::{Deserialize, Serialize};
 
+use crate::asset_store::{self, AssetStore, FileSystemStore};
 use crate::error::*;
 
-#[derive(Debug, PartialEq, Default, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Default, Clone, Serialize, Deserialize)]
 pub struct AssetsMetadata {
     bat_version: Option<String>,
     creation_time: Option<SystemTime>,
+    syntect_version: Option<String>,
+    syntax_count: Option<usize>,
+    theme_count: Option<usize>,
 }
 
-const FILENAME: &str = "metadata.yaml";
+/// The (minimum) version of syntect that is used to serialize the assets. Has to be kept in
+/// sync with Cargo.toml.
+const SYNTECT_VERSION: &str = "4.1.0";
 
 impl AssetsMetadata {
-    pub(crate) fn new(current_version: &str) -> AssetsMetadata {
+    pub(crate) fn new(
+        current_version: &str,
+        syntax_count: usize,
+        theme_count: usize,
+    ) -> AssetsMetadata {
         AssetsMetadata {
             bat_version: Some(current_version.to_owned()),
             creation_time: Some(SystemTime::now()),
+            syntect_version: Some(SYNTECT_VERSION.to_owned()),
+            syntax_count: Some(syntax_count),
+            theme_count: Some(theme_count),
         }
     }
 
-    pub(crate) fn save_to_folder(&self, path: &Path) -> Result<()> {
-        let file = File::create(path.join(FILENAME))?;
-        serde_yaml::to_writer(file, self)?;
+    /// Metadata for the assets that are integrated into the binary
+    pub(crate) fn integrated(syntax_count: usize, theme_count: usize) -> AssetsMetadata {
+        AssetsMetadata {
+            creation_time: None,
+            ..Self::new(env!("CARGO_PKG_VERSION"), syntax_count, theme_count)
+        }
+    }
+
+    /// The version of bat that created the assets (unknown for caches of very old versions)
+    pub fn bat_version(&self) -> Option<&str> {
+        self.bat_version.as_deref()
+    }
 
-        Ok(())
+    /// When the assets were built (unknown for the assets that are integrated into the binary)
+    pub fn creation_time(&self) -> Option<SystemTime> {
+        self.creation_time
     }
 
-    fn try_load_from_folder(path: &Path) -> Result<Self> {
-        let file = File::open(path.join(FILENAME))?;
-        Ok(serde_yaml::from_reader(file)?)
+    /// The version of syntect that was used to serialize the assets
+    pub fn syntect_version(&self) -> Option<&str> {
+        self.syntect_version.as_deref()
+    }
+
+    pub fn syntax_count(&self) -> Option<usize> {
+        self.syntax_count
+    }
+
+    pub fn theme_count(&self) -> Option<usize> {
+        self.theme_count
+    }
+
+    pub(crate) fn set_counts(&mut self, syntax_count: usize, theme_count: usize) {
+        self.syntax_count = Some(syntax_count);
+        self.theme_count = Some(theme_count);
+    }
+
+    pub(crate) fn save_to_folder(&self, path: &Path) -> Result<()> {
+        self.save_to_store(&mut FileSystemStore::new(path))
+    }
+
+    pub(crate) fn save_to_store(&self, store: &mut dyn AssetStore) -> Result<()> {
+        store.save(asset_store::METADATA, &serde_yaml::to_vec(self)?)
     }
 
     /// Load metadata about the stored cache file from the given folder.
@@ -48,22 +92,60 @@ impl AssetsMetadata {
     ///   - We do not find a metadata.yaml file and no cached assets
     ///       => no user provided assets are available, return None
     pub fn load_from_folder(path: &Path) -> Result<Option<Self>> {
-        match Self::try_load_from_folder(path) {
-            Ok(metadata) => Ok(Some(metadata)),
-            Err(e) => match e.kind() {
-                ErrorKind::SerdeYamlError(_) => Err(e),
-                _ => {
-                    if path.join("syntaxes.bin").exists() || path.join("themes.bin").exists() {
-                        Ok(Some(Self::default()))
-                    } else {
-                        Ok(None)
-                    }
-                }
-            },
+        Self::load_from_store(&FileSystemStore::new(path))
+    }
+
+    /// Load the metadata of the assets in a store (like `load_from_folder`)
+    pub fn load_from_store(store: &dyn AssetStore) -> Result<Option<Self>> {
+        match store.load(asset_store::METADATA) {
+            Ok(Some(content)) => Ok(Some(serde_yaml::from_slice(&content)?)),
+            _ if store.contains(asset_store::SYNTAX_SET)
+                || store.contains(asset_store::THEME_SET) =>
+            {
+                Ok(Some(Self::default()))
+            }
+            _ => Ok(None),
         }
     }
 
     pub fn is_compatible_with(&self, current_version: &str) -> bool {
+        self.version_incompatibility(current_version).is_none()
+            && self.format_incompatibility().is_none()
+    }
+
+    /// Fails with an `IncompatibleCache` error (which explains how to fix the cache) if the
+    /// assets in the given cache folder were built by another (minor) version of bat or can
+    /// not be deserialized by this version
+    pub fn ensure_compatible_with(&self, current_version: &str, path: &Path) -> Result<()> {
+        self.ensure_store_compatible_with(current_version, &path.to_string_lossy())
+    }
+
+    /// Like `ensure_compatible_with`, for the assets at the location of a store
+    pub(crate) fn ensure_store_compatible_with(
+        &self,
+        current_version: &str,
+        location: &str,
+    ) -> Result<()> {
+        match self.version_incompatibility(current_version) {
+            Some(reason) => Err(incompatible_cache(location, current_version, reason)),
+            None => self.ensure_format_compatible(location),
+        }
+    }
+
+    /// Fails with an `IncompatibleCache` error if the assets at the given location have been
+    /// serialized in a format that can not be deserialized by this version
+    pub(crate) fn ensure_format_compatible(&self, location: &str) -> Result<()> {
+        match self.format_incompatibility() {
+            Some(reason) => Err(incompatible_cache(
+                location,
+                env!("CARGO_PKG_VERSION"),
+                reason,
+            )),
+            None => Ok(()),
+        }
+    }
+
+    fn version_incompatibility(&self, current_version: &str) -> Option<String> {
         let current_version =
             Version::parse(current_version).expect("bat follows semantic versioning");
         let stored_version = self
@@ -71,11 +153,56 @@ impl AssetsMetadata {
             .as_ref()
             .and_then(|ver| Version::parse(ver).ok());
 
-        if let Some(stored_version) = stored_version {
-            current_version.major == stored_version.major
-                && current_version.minor == stored_version.minor
-        } else {
-            false
+        match stored_version {
+            Some(ref version)
+                if version.major == current_version.major
+                    && version.minor == current_version.minor =>
+            {
+                None
+            }
+            Some(version) => Some(format!("built by bat {}", version)),
+            None => Some("built by an unknown version of bat".into()),
+        }
+    }
+
+    fn format_incompatibility(&self) -> Option<String> {
+        let current_syntect = Version::parse(SYNTECT_VERSION).expect("valid syntect version");
+        let stored_syntect = self
+            .syntect_version
+            .as_ref()
+            .and_then(|ver| Version::parse(ver).ok());
+        match stored_syntect {
+            Some(ref syntect) if syntect.major != current_syntect.major => {
+                Some(format!("serialized with syntect {}", syntect))
+            }
+            _ => None,
+        }
+    }
+}
+
+fn incompatible_cache(location: &str, current_version: &str, reason: String) -> Error {
+    ErrorKind::IncompatibleCache(location.to_owned(), current_version.to_owned(), reason).into()
+}
+
+#[test]
+fn compatibility() {
+    let metadata = AssetsMetadata::new("0.15.0", 1, 1);
+    assert!(metadata.is_compatible_with("0.15.2"));
+    assert!(!metadata.is_compatible_with("0.16.0"));
+    assert!(!AssetsMetadata::default().is_compatible_with("0.15.0"));
+
+    let old_syntect = AssetsMetadata {
+        syntect_version: Some("3.3.0".into()),
+        ..metadata.clone()
+    };
+    let error = old_syntect
+        .ensure_compatible_with("0.15.0", Path::new("/cache"))
+        .unwrap_err();
+    match error.kind() {
+        ErrorKind::IncompatibleCache(path, _, reason) => {
+            assert_eq!(path, "/cache");
+            assert_eq!(reason, "serialized with syntect 3.3.0");
         }
+        _ => panic!("expected an IncompatibleCache error"),
     }
 }
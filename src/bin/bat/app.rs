@@ -1,37 +1,129 @@
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::env;
 use std::ffi::OsStr;
+use std::io::Cursor;
+use std::path::Path;
 use std::str::FromStr;
+use std::time::Duration;
 
 use atty::{self, Stream};
 
 use crate::{
     clap_app,
-    config::{get_args_from_config_file, get_args_from_env_var},
+    config::{config_file, get_args_from_config_file, get_args_from_env_var},
 };
 use clap::ArgMatches;
 
 use console::Term;
 
+use syntect::highlighting::Color;
+
 use bat::{
+    accessibility::{ColorVision, MarkerPalette},
     assets::HighlightingAssets,
-    config::{Config, VisibleLines},
+    card::{Card, CardAlignment, CardBorder},
+    config::{Config, MinifiedThresholds, PreviewSample, VisibleLines},
+    config_layers::{ConfigLayer, Layered, Origin, Provenance},
     error::*,
+    gutter::{GutterPattern, GutterSource},
+    history,
     input::Input,
+    layout::BlockAlignment,
+    line_processor::LineProcessors,
     line_range::{HighlightedLineRanges, LineRange, LineRanges},
-    style::{StyleComponent, StyleComponents},
-    MappingTarget, PagingMode, SyntaxMapping, WrappingMode,
+    patterns::{HighlightPatterns, PatternStyle},
+    project_config::ProjectConfig,
+    split::SplitView,
+    style::{HeaderComponent, ResponsiveLayout, StyleComponent, StyleComponents},
+    template::Template,
+    terminal_features::TerminalFeatures,
+    trace::StackTraceMode,
+    BinaryMode, BufferingMode, JsonLinesMode, LineEnding, MappingTarget, OutputEncoding,
+    PagingMode, SyntaxMapping, WrappingMode,
 };
 
-fn is_truecolor_terminal() -> bool {
-    env::var("COLORTERM")
-        .map(|colorterm| colorterm == "truecolor" || colorterm == "24bit")
-        .unwrap_or(false)
+/// Parse a color like `#1e1e1e`
+pub fn parse_hex_color(color: &str) -> Option<Color> {
+    if color.len() != 7 || !color.starts_with('#') {
+        return None;
+    }
+
+    let rgb = u32::from_str_radix(&color[1..], 16).ok()?;
+    Some(Color {
+        r: (rgb >> 16) as u8,
+        g: (rgb >> 8) as u8,
+        b: rgb as u8,
+        a: 0xff,
+    })
+}
+
+/// Guess the background color of the terminal from the COLORFGBG environment variable (like
+/// `15;0`), which contains the palette index of the background color
+/// A size in bytes, with an optional (binary) suffix: `512`, `100K`, `64M` or `1G`
+fn parse_size(size: &str) -> Option<u64> {
+    let (number, factor) = match size.chars().last()?.to_ascii_uppercase() {
+        'K' => (&size[..size.len() - 1], 1 << 10),
+        'M' => (&size[..size.len() - 1], 1 << 20),
+        'G' => (&size[..size.len() - 1], 1 << 30),
+        _ => (size, 1),
+    };
+    number.parse::<u64>().ok()?.checked_mul(factor)
+}
+
+/// The input of a FILE argument that is an HTTP(S) URL, downloading at most `max_size` bytes
+#[cfg(feature = "http")]
+fn url_input<'a>(path: &OsStr, max_size: Option<u64>) -> Option<Input<'a>> {
+    let url = path.to_str()?;
+    if url.starts_with("http://") || url.starts_with("https://") {
+        let input = Input::from_url(url);
+        Some(match max_size {
+            Some(bytes) => input.max_download_size(bytes),
+            None => input,
+        })
+    } else {
+        None
+    }
+}
+
+#[cfg(not(feature = "http"))]
+fn url_input<'a>(_path: &OsStr, _max_size: Option<u64>) -> Option<Input<'a>> {
+    None
+}
+
+fn terminal_background_from_env() -> Option<Color> {
+    let colorfgbg = env::var("COLORFGBG").ok()?;
+    let index: u8 = colorfgbg.rsplit(';').next()?.parse().ok()?;
+    let value = match index {
+        0..=6 | 8 => 0x00,
+        _ => 0xff,
+    };
+    Some(Color {
+        r: value,
+        g: value,
+        b: value,
+        a: 0xff,
+    })
+}
+
+/// The arguments of the config file (or of `BAT_OPTS`), which precede the command-line
+/// arguments
+struct ConfigArgs {
+    layer: ConfigLayer,
+
+    /// The path of the config file, or `BAT_OPTS`
+    source: String,
+
+    /// The matches of the command-line arguments alone
+    cli_matches: ArgMatches<'static>,
 }
 
 pub struct App {
     pub matches: ArgMatches<'static>,
     interactive_output: bool,
+    config_args: Option<ConfigArgs>,
+
+    /// The `.bat.toml` file of the project of the (first) input file
+    project_config: Option<ProjectConfig>,
 }
 
 impl App {
@@ -41,41 +133,123 @@ impl App {
 
         let interactive_output = atty::is(Stream::Stdout);
 
+        let (matches, config_args) = Self::matches(interactive_output)?;
+        let project_config = Self::project_config(&matches)?;
+
         Ok(App {
-            matches: Self::matches(interactive_output)?,
+            matches,
             interactive_output,
+            config_args,
+            project_config,
         })
     }
 
-    fn matches(interactive_output: bool) -> Result<ArgMatches<'static>> {
-        let args = if wild::args_os().nth(1) == Some("cache".into())
+    /// Find the project configuration, starting at the directory of the first input file (or
+    /// in the current directory). Like the config file, it is skipped with '--no-config'.
+    fn project_config(matches: &ArgMatches) -> Result<Option<ProjectConfig>> {
+        if matches.is_present("no-config") || matches.subcommand_name() == Some("cache") {
+            return Ok(None);
+        }
+
+        let current_dir = match env::current_dir() {
+            Ok(dir) => dir,
+            Err(_) => return Ok(None),
+        };
+        let start_dir = matches
+            .values_of_os("FILE")
+            .and_then(|mut files| files.find(|file| *file != "-"))
+            .map(|file| {
+                let parent = Path::new(file).parent().unwrap_or_else(|| Path::new(""));
+                current_dir.join(parent)
+            })
+            .unwrap_or_else(|| current_dir.clone());
+
+        ProjectConfig::discover(&start_dir)
+    }
+
+    fn matches(interactive_output: bool) -> Result<(ArgMatches<'static>, Option<ConfigArgs>)> {
+        if wild::args_os().nth(1) == Some("cache".into())
             || wild::args_os().any(|arg| arg == "--no-config")
         {
             // Skip the arguments in bats config file
+            let matches = clap_app::build_app(interactive_output).get_matches_from(wild::args_os());
+            return Ok((matches, None));
+        }
 
-            wild::args_os().collect::<Vec<_>>()
-        } else {
-            let mut cli_args = wild::args_os();
+        let mut cli_args = wild::args_os();
 
-            // Read arguments from bats config file
-            let mut args = get_args_from_env_var()
-                .unwrap_or_else(get_args_from_config_file)
-                .chain_err(|| "Could not parse configuration file")?;
+        // Read arguments from bats config file
+        let (args, layer, source) = match get_args_from_env_var() {
+            Some(args) => (args, ConfigLayer::Environment, "BAT_OPTS".to_owned()),
+            None => (
+                get_args_from_config_file(),
+                ConfigLayer::ConfigFile,
+                config_file().to_string_lossy().into_owned(),
+            ),
+        };
+        let mut args = args.chain_err(|| "Could not parse configuration file")?;
 
-            // Put the zero-th CLI argument (program name) first
-            args.insert(0, cli_args.next().unwrap());
+        // Put the zero-th CLI argument (program name) first
+        args.insert(0, cli_args.next().unwrap());
 
-            // .. and the rest at the end
-            cli_args.for_each(|a| args.push(a));
+        // .. and the rest at the end
+        cli_args.for_each(|a| args.push(a));
 
-            args
-        };
+        let matches = clap_app::build_app(interactive_output).get_matches_from(args);
 
-        Ok(clap_app::build_app(interactive_output).get_matches_from(args))
+        // Parsing the command-line arguments alone tells which options were given there
+        let config_args = clap_app::build_app(interactive_output)
+            .get_matches_from_safe(wild::args_os())
+            .ok()
+            .map(|cli_matches| ConfigArgs {
+                layer,
+                source,
+                cli_matches,
+            });
+        Ok((matches, config_args))
+    }
+
+    /// The origin of the value of an option: the command line, or the config file (or
+    /// `BAT_OPTS`) if the option is not given on the command line
+    fn option_origin(&self, name: &str, value: impl std::fmt::Display) -> Origin {
+        match self.config_args {
+            Some(ref args) if !args.cli_matches.is_present(name) => {
+                Origin::new(args.layer, value).with_source(args.source.as_str())
+            }
+            _ => Origin::new(ConfigLayer::Explicit, value),
+        }
+    }
+
+    /// The value of a setting from the layer with the highest precedence: the option (on
+    /// the command line or in the config file), the environment variable or the project
+    /// file. Values that can not be parsed are skipped.
+    fn layered_setting<T>(&self, option: &str, var: &str, project: Option<T>) -> Layered<T>
+    where
+        T: FromStr + std::fmt::Display,
+    {
+        let mut setting = Layered::new();
+        if let Some(value) = project {
+            let origin = Origin::new(ConfigLayer::ProjectFile, &value);
+            let origin = match self.project_config {
+                Some(ref project) => origin.with_source(project.path.to_string_lossy()),
+                None => origin,
+            };
+            setting.set(value, origin);
+        }
+        if let Some(value) = env::var(var).ok().and_then(|v| v.parse().ok()) {
+            let origin = Origin::new(ConfigLayer::Environment, &value).with_source(var);
+            setting.set(value, origin);
+        }
+        if let Some(value) = self.matches.value_of(option).and_then(|v| v.parse().ok()) {
+            let origin = self.option_origin(option, &value);
+            setting.set(value, origin);
+        }
+        setting
     }
 
     pub fn config(&self, inputs: &[Input]) -> Result<Config> {
-        let style_components = self.style_components()?;
+        let mut provenance = Provenance::default();
+        let style_components = self.style_components(&mut provenance)?;
 
         let paging_mode = match self.matches.value_of("paging") {
             Some("always") => PagingMode::Always,
@@ -102,7 +276,25 @@ impl App {
             _ => unreachable!("other values for --paging are not allowed"),
         };
 
+        // '--wrap=pager' leaves long lines to the pager like '--chop-long-lines'
+        let chop_long_lines = (self.matches.is_present("chop-long-lines")
+            || self.matches.value_of("wrap") == Some("pager"))
+            && paging_mode != PagingMode::Never;
+
         let mut syntax_mapping = SyntaxMapping::builtin();
+        syntax_mapping.detect_from_content(true);
+        syntax_mapping.use_git_attributes(self.matches.is_present("git-attributes"));
+
+        // The mappings of the project come first, so that the ones of the user take precedence
+        let project_settings = self
+            .project_config
+            .as_ref()
+            .map(|project| &project.settings);
+        if let Some(settings) = project_settings {
+            for (from, to) in &settings.mappings {
+                syntax_mapping.insert(from, MappingTarget::MapTo(to))?;
+            }
+        }
 
         if let Some(values) = self.matches.values_of("map-syntax") {
             for from_to in values {
@@ -134,8 +326,48 @@ impl App {
             }
         });
 
+        let terminal_features = TerminalFeatures::detect();
+
+        let tab_width = self
+            .layered_setting(
+                "tabs",
+                "BAT_TABS",
+                project_settings.and_then(|settings| settings.tab_width),
+            )
+            .resolve()
+            .unwrap_or_else(|| {
+                let tab_width = if style_components.plain() && paging_mode == PagingMode::Never {
+                    0
+                } else {
+                    4
+                };
+                (tab_width, Origin::new(ConfigLayer::Default, tab_width))
+            });
+        provenance.record("tabs", tab_width.1);
+
+        let theme = self
+            .layered_setting(
+                "theme",
+                "BAT_THEME",
+                project_settings.and_then(|settings| settings.theme.clone()),
+            )
+            .resolve()
+            .unwrap_or_else(|| {
+                let theme = HighlightingAssets::default_theme();
+                (theme.to_owned(), Origin::new(ConfigLayer::Default, theme))
+            });
+        provenance.record("theme", theme.1);
+
+        let pager = self.matches.value_of("pager");
+        if let Some(pager) = pager {
+            provenance.record("pager", self.option_origin("pager", pager));
+        } else if let Ok(pager) = env::var("BAT_PAGER") {
+            let origin = Origin::new(ConfigLayer::Environment, pager).with_source("BAT_PAGER");
+            provenance.record("pager", origin);
+        }
+
         Ok(Config {
-            true_color: is_truecolor_terminal(),
+            true_color: terminal_features.true_color,
             language: self.matches.value_of("language").or_else(|| {
                 if self.matches.is_present("show-all") {
                     Some("show-nonprintable")
@@ -144,10 +376,27 @@ impl App {
                 }
             }),
             show_nonprintable: self.matches.is_present("show-all"),
-            wrapping_mode: if self.interactive_output || maybe_term_width.is_some() {
+            ansi_passthrough: self.matches.is_present("ansi-passthrough"),
+            binary_mode: if self.matches.is_present("show-binary") {
+                BinaryMode::Hex
+            } else {
+                BinaryMode::Warn
+            },
+            wrapping_mode: if chop_long_lines {
+                // The pager cuts off long lines and scrolls horizontally
+                WrappingMode::NoWrapping
+            } else if self.interactive_output
+                || maybe_term_width.is_some()
+                || self.matches.is_present("card")
+            {
                 match self.matches.value_of("wrap") {
                     Some("character") => WrappingMode::Character,
+                    Some("word") => WrappingMode::Word {
+                        break_chars: WrappingMode::DEFAULT_BREAK_CHARS,
+                    },
                     Some("never") => WrappingMode::NoWrapping,
+                    // Without a pager (with one, long lines are left to the pager)
+                    Some("pager") => WrappingMode::Character,
                     Some("auto") | None => {
                         if style_components.plain() {
                             WrappingMode::NoWrapping
@@ -172,32 +421,12 @@ impl App {
             loop_through: !(self.interactive_output
                 || self.matches.value_of("color") == Some("always")
                 || self.matches.value_of("decorations") == Some("always")),
-            tab_width: self
-                .matches
-                .value_of("tabs")
-                .map(String::from)
-                .or_else(|| env::var("BAT_TABS").ok())
-                .and_then(|t| t.parse().ok())
-                .unwrap_or(
-                    if style_components.plain() && paging_mode == PagingMode::Never {
-                        0
-                    } else {
-                        4
-                    },
-                ),
-            theme: self
-                .matches
-                .value_of("theme")
-                .map(String::from)
-                .or_else(|| env::var("BAT_THEME").ok())
-                .map(|s| {
-                    if s == "default" {
-                        String::from(HighlightingAssets::default_theme())
-                    } else {
-                        s
-                    }
-                })
-                .unwrap_or_else(|| String::from(HighlightingAssets::default_theme())),
+            tab_width: tab_width.0,
+            theme: if theme.0 == "default" {
+                String::from(HighlightingAssets::default_theme())
+            } else {
+                theme.0
+            },
             visible_lines: if self.matches.is_present("diff") {
                 VisibleLines::DiffContext(
                     self.matches
@@ -215,11 +444,22 @@ impl App {
                         .unwrap_or_default(),
                 )
             },
+            focus_symbol: self.matches.value_of("focus-symbol").map(String::from),
+            preview_sample: self.preview_sample()?,
             style_components,
             syntax_mapping,
-            pager: self.matches.value_of("pager"),
+            pager,
+            chop_long_lines,
+            pager_timeout: self
+                .matches
+                .value_of("pager-timeout")
+                .and_then(|seconds| seconds.parse().ok())
+                .map(Duration::from_secs),
+            pager_per_file: self.matches.is_present("pager-per-file"),
+            sticky_header: self.matches.is_present("sticky-header"),
             use_italic_text: match self.matches.value_of("italic-text") {
                 Some("always") => true,
+                Some("auto") => terminal_features.italics,
                 _ => false,
             },
             highlighted_lines: self
@@ -230,9 +470,212 @@ impl App {
                 .map(LineRanges::from)
                 .map(|lr| HighlightedLineRanges(lr))
                 .unwrap_or_default(),
+            strict: false,
+            minified_thresholds: Some(MinifiedThresholds::default()),
+            buffering_mode: if self.matches.is_present("unbuffered") {
+                BufferingMode::Unbuffered
+            } else {
+                BufferingMode::Auto
+            },
+            line_ending: match self.matches.value_of("line-endings") {
+                Some("lf") => Some(LineEnding::Lf),
+                Some("crlf") => Some(LineEnding::CrLf),
+                _ => None,
+            },
+            output_encoding: match self.matches.value_of("output-encoding") {
+                Some("utf-16") => OutputEncoding::Utf16,
+                Some("latin-1") => OutputEncoding::Latin1,
+                _ => OutputEncoding::Utf8,
+            },
+            asciicast_file: self.matches.value_of_os("export-asciicast").map(Path::new),
+            theme_fallback: !self.matches.is_present("no-theme-fallback"),
+            max_token_length: self
+                .matches
+                .value_of("max-token-length")
+                .and_then(|n| n.parse().ok()),
+            embedded_languages: vec![],
+            responsive_layout: self.responsive_layout(maybe_term_width.is_some())?,
+            json_lines: match self.matches.value_of("json-lines") {
+                Some("json") => Some(JsonLinesMode::Json),
+                Some("flattened") => Some(JsonLinesMode::Flattened),
+                _ => None,
+            },
+            align_key_values: self.matches.is_present("align-key-values"),
+            markdown_code_blocks: self.matches.is_present("markdown-code-blocks"),
+            sandbox: false,
+            deterministic: false,
+            line_classifier: None,
+            line_class_colors: HashMap::new(),
+            line_processors: LineProcessors::default(),
+            highlighted_spans: BTreeMap::new(),
+            highlight_patterns: self.highlight_patterns()?,
+            stack_trace: if self.matches.is_present("stack-trace") {
+                Some(StackTraceMode {
+                    source_context: self
+                        .matches
+                        .value_of("source-context")
+                        .and_then(|n| n.parse().ok()),
+                })
+            } else {
+                None
+            },
+            render_diffs: self.matches.is_present("render-diffs"),
+            render_commits: self.matches.is_present("render-commits"),
+            header_template: self
+                .matches
+                .value_of("header-template")
+                .map(Template::header)
+                .transpose()?,
+            header_components: self
+                .matches
+                .value_of("header-components")
+                .map(|components| {
+                    components
+                        .split(',')
+                        .map(|component| component.trim().parse())
+                        .collect::<Result<Vec<HeaderComponent>>>()
+                })
+                .transpose()?
+                .unwrap_or_default(),
+            footer_template: self
+                .matches
+                .value_of("footer-template")
+                .map(Template::footer)
+                .transpose()?,
+            gutter_source: match self.matches.value_of("gutter") {
+                Some("byte-offset") => GutterSource::ByteOffset,
+                Some("line-number") | None => GutterSource::LineNumber,
+                Some(pattern) => GutterSource::Capture(GutterPattern::new(pattern)?),
+            },
+            line_number_template: self
+                .matches
+                .value_of("number-template")
+                .map(Template::line_number)
+                .transpose()?,
+            accessible_markers: match self.matches.value_of("color-vision") {
+                Some("protanopia") => Some(MarkerPalette::for_vision(ColorVision::Protanopia)),
+                Some("deuteranopia") => Some(MarkerPalette::for_vision(ColorVision::Deuteranopia)),
+                Some("tritanopia") => Some(MarkerPalette::for_vision(ColorVision::Tritanopia)),
+                _ => None,
+            },
+            minimum_contrast: self
+                .matches
+                .value_of("minimum-contrast")
+                .and_then(|ratio| ratio.parse().ok()),
+            terminal_background: match self.matches.value_of("terminal-background") {
+                Some(color) => parse_hex_color(color),
+                None if self.matches.is_present("minimum-contrast") => {
+                    terminal_background_from_env()
+                }
+                None => None,
+            },
+            card: self
+                .matches
+                .value_of("card")
+                .and_then(|width| width.parse().ok())
+                .map(|width| Card {
+                    width,
+                    border: match self.matches.value_of("card-border") {
+                        Some("ascii") => CardBorder::Ascii,
+                        _ => CardBorder::Rounded,
+                    },
+                    alignment: match self.matches.value_of("card-align") {
+                        Some("center") => CardAlignment::Center,
+                        _ => CardAlignment::Left,
+                    },
+                }),
+            split_view: match self.matches.values_of("split") {
+                Some(mut ranges) => Some(SplitView {
+                    left: LineRange::from(ranges.next().unwrap_or_default())?,
+                    right: LineRange::from(ranges.next().unwrap_or_default())?,
+                }),
+                None => None,
+            },
+            indent: None,
+            max_width: self
+                .matches
+                .value_of("max-width")
+                .and_then(|width| width.parse().ok()),
+            block_alignment: match self.matches.value_of("block-align") {
+                Some("center") => BlockAlignment::Center,
+                Some("right") => BlockAlignment::Right,
+                _ => BlockAlignment::Left,
+            },
+            linkify: self.matches.is_present("linkify"),
+            linkify_paths: self.matches.is_present("linkify-paths"),
+            parallelism: 1,
+            squeeze_blank_lines: if self.matches.is_present("squeeze-blank") {
+                Some(
+                    self.matches
+                        .value_of("squeeze-limit")
+                        .and_then(|limit| limit.parse().ok())
+                        .unwrap_or(1),
+                )
+            } else {
+                None
+            },
+            follow: false,
+            provenance,
         })
     }
 
+    fn highlight_patterns(&self) -> Result<HighlightPatterns> {
+        let mut patterns = HighlightPatterns::new();
+
+        for definition in self
+            .matches
+            .values_of("pattern-style")
+            .into_iter()
+            .flatten()
+        {
+            let mut parts = definition.splitn(2, '=');
+            match (parts.next(), parts.next()) {
+                (Some(name), Some(style)) if !name.is_empty() => {
+                    patterns.define_style(name, PatternStyle::parse(style)?);
+                }
+                _ => {
+                    return Err(format!(
+                        "Invalid pattern style '{}' (expected 'name=style')",
+                        definition
+                    )
+                    .into())
+                }
+            }
+        }
+
+        for pattern in self
+            .matches
+            .values_of("highlight-pattern")
+            .into_iter()
+            .flatten()
+        {
+            let mut parts = pattern.splitn(2, ':');
+            match (parts.next(), parts.next()) {
+                (Some(style), Some(regex)) => {
+                    patterns.add(style, regex)?;
+                }
+                _ => {
+                    return Err(format!(
+                        "Invalid highlight pattern '{}' (expected 'style:regex')",
+                        pattern
+                    )
+                    .into())
+                }
+            }
+        }
+
+        for pattern in self
+            .matches
+            .values_of("highlight-search")
+            .into_iter()
+            .flatten()
+        {
+            patterns.add_search(pattern)?;
+        }
+
+        Ok(patterns)
+    }
+
     pub fn inputs(&self) -> Result<Vec<Input>> {
         // verify equal length of file-names and input FILEs
         match self.matches.values_of("file-name") {
@@ -264,11 +707,26 @@ impl App {
             None => Box::new(std::iter::repeat(None)),
         };
 
+        let max_download_size = match self.matches.value_of("max-download-size") {
+            Some(size) => Some(parse_size(size).ok_or_else(|| {
+                format!(
+                    "Invalid size '{}' for '--max-download-size' (use bytes or a number \
+                     with one of the suffixes K, M or G)",
+                    size
+                )
+            })?),
+            None => None,
+        };
+
         let mut file_input = Vec::new();
         for (filepath, provided_name) in files_or_none.zip(filenames_or_none) {
             if let Some(filepath) = filepath {
                 if filepath.to_str().unwrap_or_default() == "-" {
                     file_input.push(Input::stdin().with_name(provided_name));
+                } else if let Some(input) = url_input(filepath, max_download_size) {
+                    file_input.push(input.with_name(provided_name));
+                } else if self.shows_history() {
+                    file_input.extend(self.file_versions(filepath, provided_name)?);
                 } else {
                     file_input.push(Input::ordinary_file(filepath).with_name(provided_name));
                 }
@@ -277,7 +735,90 @@ impl App {
         Ok(file_input)
     }
 
-    fn style_components(&self) -> Result<StyleComponents> {
+    fn shows_history(&self) -> bool {
+        self.matches.is_present("revision") || self.matches.is_present("history")
+    }
+
+    /// The versions of a file at the selected revisions ('--revision' or '--history')
+    fn file_versions<'a>(
+        &self,
+        path: &'a OsStr,
+        provided_name: Option<&'a OsStr>,
+    ) -> Result<Vec<Input<'a>>> {
+        let path = Path::new(path);
+        let revisions: Vec<String> = match self.matches.values_of("revision") {
+            Some(revisions) => revisions.map(str::to_owned).collect(),
+            None => {
+                let count = self
+                    .matches
+                    .value_of("history")
+                    .and_then(|n| n.parse().ok())
+                    .unwrap_or(1);
+                history::recent_revisions(path, count)?
+            }
+        };
+        let revisions: Vec<&str> = revisions.iter().map(String::as_str).collect();
+
+        Ok(history::file_versions(path, &revisions)?
+            .into_iter()
+            .map(|version| {
+                Input::from_reader(Box::new(Cursor::new(version.content)))
+                    .with_name(Some(provided_name.unwrap_or(path.as_os_str())))
+                    .at_revision(version.revision)
+            })
+            .collect())
+    }
+
+    /// Sample large files on the terminal, unless disabled with '--preview-sample=0'
+    fn preview_sample(&self) -> Result<Option<PreviewSample>> {
+        let lines = match self.matches.value_of("preview-sample") {
+            Some(lines) => lines.parse()?,
+            None if self.interactive_output => 100,
+            None => return Ok(None),
+        };
+        if lines == 0 {
+            return Ok(None);
+        }
+
+        let threshold = match self.matches.value_of("preview-threshold") {
+            Some(size) => parse_size(size).ok_or_else(|| {
+                format!(
+                    "Invalid size '{}' for '--preview-threshold' (use bytes or a \
+                     number with one of the suffixes K, M or G)",
+                    size
+                )
+            })?,
+            None => PreviewSample::DEFAULT_THRESHOLD,
+        };
+
+        Ok(Some(PreviewSample { lines, threshold }))
+    }
+
+    fn responsive_layout(&self, explicit_term_width: bool) -> Result<Option<ResponsiveLayout>> {
+        let default = ResponsiveLayout::default();
+
+        let min_content_width = match self.matches.value_of("min-content-width") {
+            Some(width) => width.parse()?,
+            // An explicitly specified terminal width is used as it is
+            None if explicit_term_width => return Ok(None),
+            None => default.min_content_width,
+        };
+
+        let priority = match self.matches.value_of("responsive-style") {
+            Some(components) => components
+                .split(',')
+                .map(StyleComponent::from_str)
+                .collect::<Result<Vec<_>>>()?,
+            None => default.priority,
+        };
+
+        Ok(Some(ResponsiveLayout {
+            min_content_width,
+            priority,
+        }))
+    }
+
+    fn style_components(&self, provenance: &mut Provenance) -> Result<StyleComponents> {
         let matches = &self.matches;
         Ok(StyleComponents(
             if matches.value_of("decorations") == Some("never") {
@@ -287,28 +828,23 @@ impl App {
             } else if matches.is_present("plain") {
                 [StyleComponent::Plain].iter().cloned().collect()
             } else {
-                let env_style_components: Option<Vec<StyleComponent>> = env::var("BAT_STYLE")
-                    .ok()
-                    .map(|style_str| {
-                        style_str
-                            .split(',')
-                            .map(|x| StyleComponent::from_str(&x))
-                            .collect::<Result<Vec<StyleComponent>>>()
-                    })
-                    .transpose()?;
-
-                matches
-                    .value_of("style")
-                    .map(|styles| {
-                        styles
-                            .split(',')
-                            .map(|style| style.parse::<StyleComponent>())
-                            .filter_map(|style| style.ok())
-                            .collect::<Vec<_>>()
-                    })
-                    .or(env_style_components)
-                    .unwrap_or_else(|| vec![StyleComponent::Full])
-                    .into_iter()
+                if let Ok(style) = env::var("BAT_STYLE") {
+                    for component in style.split(',') {
+                        StyleComponent::from_str(component)?;
+                    }
+                }
+
+                let (style, origin) = self
+                    .layered_setting::<String>("style", "BAT_STYLE", None)
+                    .resolve()
+                    .unwrap_or_else(|| {
+                        ("full".to_owned(), Origin::new(ConfigLayer::Default, "full"))
+                    });
+                provenance.record("style", origin);
+
+                style
+                    .split(',')
+                    .filter_map(|style| style.parse::<StyleComponent>().ok())
                     .map(|style| style.components(self.interactive_output))
                     .fold(HashSet::new(), |mut acc, components| {
                         acc.extend(components.iter().cloned());
@@ -1,6 +1,8 @@
 use clap::{crate_name, crate_version, App as ClapApp, AppSettings, Arg, ArgGroup, SubCommand};
 use std::path::Path;
 
+use crate::app::parse_hex_color;
+
 pub fn build_app(interactive_output: bool) -> ClapApp<'static, 'static> {
     let clap_color_setting = if interactive_output {
         AppSettings::ColoredHelp
@@ -47,6 +49,33 @@ pub fn build_app(interactive_output: bool) -> ClapApp<'static, 'static> {
                      Use '--tabs' to control the width of the tab-placeholders."
                 ),
         )
+        .arg(
+            Arg::with_name("ansi-passthrough")
+                .long("ansi-passthrough")
+                .conflicts_with("show-all")
+                .hidden_short_help(true)
+                .help("Keep the colors of input that is colored already.")
+                .long_help(
+                    "Treat input that contains ANSI escape sequences (like the colored \
+                     output of other tools) as colored already: it is not highlighted, and \
+                     its escape sequences are kept, while wrapping, line numbers and the \
+                     grid still work as usual. The escape sequences are removed if the \
+                     output is not colored.",
+                ),
+        )
+        .arg(
+            Arg::with_name("show-binary")
+                .long("show-binary")
+                .hidden_short_help(true)
+                .help("Show a hexdump of binary files.")
+                .long_help(
+                    "Show a hexdump of binary files instead of a warning: rows of 16 bytes \
+                     with their offset in the number gutter, the bytes in hexadecimal and \
+                     their printable characters. The bytes are colored by their kind (null \
+                     bytes, printable characters, whitespace, other ASCII characters and \
+                     non-ASCII bytes). Line ranges select rows.",
+                ),
+        )
         .arg(
             Arg::with_name("plain")
                 .overrides_with("plain")
@@ -93,6 +122,60 @@ pub fn build_app(interactive_output: bool) -> ClapApp<'static, 'static> {
                      '--highlight-line 40:' highlights lines 40 to the end of the file"
                 ),
         )
+        .arg(
+            Arg::with_name("highlight-pattern")
+                .long("highlight-pattern")
+                .takes_value(true)
+                .number_of_values(1)
+                .multiple(true)
+                .value_name("style:regex")
+                .hidden_short_help(true)
+                .help("Highlight the matches of a regex with a named style.")
+                .long_help(
+                    "Highlight the matches of a regular expression with a named style. The \
+                     built-in styles are 'error' (red background), 'warning' (yellow \
+                     underline), 'info' (cyan) and 'match' (yellow background); more styles \
+                     can be defined with '--pattern-style'. The option can be used multiple \
+                     times, where overlapping matches are highlighted with the pattern that \
+                     was given first. For example:\n  \
+                     '--highlight-pattern error:ERROR --highlight-pattern warning:WARN'",
+                ),
+        )
+        .arg(
+            Arg::with_name("highlight-search")
+                .long("highlight-search")
+                .takes_value(true)
+                .number_of_values(1)
+                .multiple(true)
+                .value_name("regex")
+                .hidden_short_help(true)
+                .help("Highlight the matches of a regex on top of the syntax highlighting.")
+                .long_help(
+                    "Highlight the matches of a regular expression on top of the syntax \
+                     highlighting, like 'grep --color' does. The option can be used multiple \
+                     times, where every regex gets its own background color (the styles \
+                     'match-1' to 'match-6', which can be redefined with '--pattern-style'). \
+                     For example:\n  \
+                     '--highlight-search TODO --highlight-search FIXME'",
+                ),
+        )
+        .arg(
+            Arg::with_name("pattern-style")
+                .long("pattern-style")
+                .takes_value(true)
+                .number_of_values(1)
+                .multiple(true)
+                .value_name("name=style")
+                .hidden_short_help(true)
+                .help("Define a named style for '--highlight-pattern'.")
+                .long_help(
+                    "Define (or redefine) a named style for '--highlight-pattern'. The style \
+                     is a comma-separated list of 'fg=<color>', 'bg=<color>', 'bold', \
+                     'italic' and 'underline', where colors are either '#RRGGBB' or the name \
+                     of a terminal color. For example:\n  \
+                     '--pattern-style todo=fg=#000000,bg=cyan,bold'",
+                ),
+        )
         .arg(
             Arg::with_name("file-name")
                 .long("file-name")
@@ -160,13 +243,144 @@ pub fn build_app(interactive_output: bool) -> ClapApp<'static, 'static> {
                 .overrides_with("wrap")
                 .takes_value(true)
                 .value_name("mode")
-                .possible_values(&["auto", "never", "character"])
+                .possible_values(&["auto", "never", "character", "word", "pager"])
                 .default_value("auto")
                 .hide_default_value(true)
-                .help("Specify the text-wrapping mode (*auto*, never, character).")
-                .long_help("Specify the text-wrapping mode (*auto*, never, character). \
-                           The '--terminal-width' option can be used in addition to \
-                           control the output width."),
+                .help("Specify the text-wrapping mode (*auto*, never, character, word, pager).")
+                .long_help("Specify the text-wrapping mode (*auto*, never, character, word, \
+                           pager). With 'word', long lines are wrapped after whitespace, '-' and \
+                           '/', and wrapped lines are indented like the line itself. With \
+                           'pager', long lines are passed on to the pager in full whenever the \
+                           output is paged, and 'less' is started with '--chop-long-lines' to \
+                           scroll them horizontally (like with '--chop-long-lines'). Without a \
+                           pager, 'pager' behaves like 'character'. The '--terminal-width' \
+                           option can be used in addition to control the output width."),
+        )
+        .arg(
+            Arg::with_name("max-token-length")
+                .long("max-token-length")
+                .overrides_with("max-token-length")
+                .takes_value(true)
+                .value_name("N")
+                .hidden_short_help(true)
+                .validator(
+                    |t| {
+                        t.parse::<usize>()
+                            .map_err(|_t| "must be a number")
+                            .map(|_t| ()) // Convert to Result<(), &str>
+                            .map_err(|e| e.to_string())
+                    }, // Convert to Result<(), String>
+                )
+                .help("Elide the middle of tokens longer than N characters.")
+                .long_help(
+                    "Elide the middle of tokens (runs of non-whitespace characters, like \
+                     base64 blobs) that are longer than N characters. The first and last \
+                     N/2 characters are kept, the elided part is replaced by a marker that \
+                     shows the number of elided bytes.",
+                ),
+        )
+        .arg(
+            Arg::with_name("align-key-values")
+                .long("align-key-values")
+                .hidden_short_help(true)
+                .help("Align the values of config files in a column.")
+                .long_help(
+                    "Align the values of 'key = value' (and tab-separated) lines in config \
+                     files like INI, TOML, '.properties' and git config files in a column, \
+                     with distinctly colored keys and sections.",
+                ),
+        )
+        .arg(
+            Arg::with_name("markdown-code-blocks")
+                .long("markdown-code-blocks")
+                .hidden_short_help(true)
+                .help("Highlight the code blocks of Markdown files with their languages.")
+                .long_help(
+                    "Highlight the fenced code blocks of Markdown files with the languages \
+                     of their info strings (like '```rust'), and print the text around \
+                     them with few styles (bold headings and emphasis).",
+                ),
+        )
+        .arg(
+            Arg::with_name("json-lines")
+                .long("json-lines")
+                .overrides_with("json-lines")
+                .takes_value(true)
+                .value_name("format")
+                .possible_values(&["json", "flattened"])
+                .hidden_short_help(true)
+                .help("Color lines with JSON objects on their own (json, flattened).")
+                .long_help(
+                    "Color every line that contains a JSON object on its own, like in \
+                     JSON-lines logs. With 'flattened', the fields are printed as key=value \
+                     pairs (with the keys of nested fields joined by dots). The values of \
+                     'level' and 'timestamp' fields are tinted. Possible values: json, \
+                     flattened.",
+                ),
+        )
+        .arg(
+            Arg::with_name("render-commits")
+                .long("render-commits")
+                .overrides_with("render-commits")
+                .hidden_short_help(true)
+                .help("Render the input as the output of git.")
+                .long_help(
+                    "Render the input as the output of 'git show', 'git log' or 'git diff': the \
+                     metadata and messages of commits are styled and diffs are rendered like \
+                     with '--render-diffs'. This is useful for using bat as the pager of git: \
+                     'git config --global core.pager \"bat --render-commits\"'.",
+                ),
+        )
+        .arg(
+            Arg::with_name("render-diffs")
+                .long("render-diffs")
+                .overrides_with("render-diffs")
+                .hidden_short_help(true)
+                .help("Render unified diffs structurally.")
+                .long_help(
+                    "Render unified diffs (like .patch files or the output of 'git diff') \
+                     structurally instead of highlighting them with the generic Diff syntax: \
+                     files and hunks are separated, added and removed lines are highlighted in \
+                     the language of the file and changed words are emphasized.",
+                ),
+        )
+        .arg(
+            Arg::with_name("stack-trace")
+                .long("stack-trace")
+                .overrides_with("stack-trace")
+                .hidden_short_help(true)
+                .help("Highlight the input as a stack trace.")
+                .long_help(
+                    "Highlight the input as a stack trace of a Rust panic, a Python or Java \
+                     exception or a Node.js error: the messages, functions and source \
+                     locations are highlighted instead of using a syntax. See \
+                     '--source-context' to show the source lines of the frames. For example: \
+                     'python app.py 2>&1 | bat --stack-trace --source-context 2'.",
+                ),
+        )
+        .arg(
+            Arg::with_name("source-context")
+                .long("source-context")
+                .overrides_with("source-context")
+                .takes_value(true)
+                .value_name("N")
+                .requires("stack-trace")
+                .hidden_short_help(true)
+                .validator(
+                    |t| {
+                        t.parse::<usize>()
+                            .map_err(|_t| "must be a number")
+                            .map(|_t| ()) // Convert to Result<(), &str>
+                            .map_err(|e| e.to_string())
+                    }, // Convert to Result<(), String>
+                )
+                .help("Show the source lines of stack trace frames with N lines of context.")
+                .long_help(
+                    "Show the source lines that the frames of a stack trace refer to \
+                     beneath each frame (see '--stack-trace'), with N lines of context before \
+                     and after them. The line of the frame is marked. Frames of files that \
+                     don't exist locally are skipped.",
+                ),
         )
         .arg(
             Arg::with_name("terminal-width")
@@ -193,6 +407,56 @@ pub fn build_app(interactive_output: bool) -> ClapApp<'static, 'static> {
                      as an offset to the actual terminal width. See also: '--wrap'.",
                 ),
         )
+        .arg(
+            Arg::with_name("min-content-width")
+                .long("min-content-width")
+                .overrides_with("min-content-width")
+                .takes_value(true)
+                .value_name("N")
+                .hidden_short_help(true)
+                .validator(
+                    |t| {
+                        t.parse::<usize>()
+                            .map_err(|_t| "must be a number")
+                            .map(|_t| ()) // Convert to Result<(), &str>
+                            .map_err(|e| e.to_string())
+                    }, // Convert to Result<(), String>
+                )
+                .help("Drop decorations if the code is narrower than N characters.")
+                .long_help(
+                    "Drop decorations (see '--responsive-style') as long as the code column \
+                     would be narrower than N characters (default: 20). This only happens \
+                     for an automatically detected terminal width, unless this option is \
+                     specified. A value of 0 disables the responsive behavior.",
+                ),
+        )
+        .arg(
+            Arg::with_name("responsive-style")
+                .long("responsive-style")
+                .overrides_with("responsive-style")
+                .takes_value(true)
+                .value_name("components")
+                .hidden_short_help(true)
+                .use_delimiter(false)
+                .validator(|val| {
+                    let mut invalid_vals = val.split(',').filter(|style| {
+                        !&["changes", "grid", "header", "numbers", "snip", "status", "sections"]
+                            .contains(style)
+                    });
+
+                    if let Some(invalid) = invalid_vals.next() {
+                        Err(format!("Unknown style, '{}'", invalid))
+                    } else {
+                        Ok(())
+                    }
+                })
+                .help("The order in which decorations are dropped on narrow terminals.")
+                .long_help(
+                    "Comma-separated list of style components that are dropped, one after \
+                     the other, on narrow terminals (see '--min-content-width'). Default: \
+                     'grid,numbers,header'.",
+                ),
+        )
         .arg(
             Arg::with_name("number")
                 .long("number")
@@ -220,16 +484,36 @@ pub fn build_app(interactive_output: bool) -> ClapApp<'static, 'static> {
                      Possible values: *auto*, never, always.",
                 ),
         )
+        .arg(
+            Arg::with_name("color-vision")
+                .long("color-vision")
+                .overrides_with("color-vision")
+                .takes_value(true)
+                .value_name("deficiency")
+                .possible_values(&["protanopia", "deuteranopia", "tritanopia"])
+                .hidden_short_help(true)
+                .help("Use color-blind friendly markers (protanopia, deuteranopia, tritanopia).")
+                .long_help(
+                    "Color Git change markers and log levels with a palette that stays \
+                     distinguishable with the given color-vision deficiency, and mark highlighted \
+                     lines (in the grid border) and log levels with glyphs in addition to \
+                     their colors. Possible values: protanopia, deuteranopia, tritanopia.",
+                ),
+        )
         .arg(
             Arg::with_name("italic-text")
                 .long("italic-text")
                 .takes_value(true)
                 .value_name("when")
-                .possible_values(&["always", "never"])
+                .possible_values(&["auto", "always", "never"])
                 .default_value("never")
                 .hide_default_value(true)
-                .help("Use italics in output (always, *never*)")
-                .long_help("Specify when to use ANSI sequences for italic text in the output. Possible values: always, *never*."),
+                .help("Use italics in output (auto, always, *never*)")
+                .long_help(
+                    "Specify when to use ANSI sequences for italic text in the output. The \
+                     automatic mode only uses italics if the terminal supports them (according \
+                     to its terminfo entry). Possible values: auto, always, *never*.",
+                ),
         )
         .arg(
             Arg::with_name("decorations")
@@ -282,6 +566,144 @@ pub fn build_app(interactive_output: bool) -> ClapApp<'static, 'static> {
                      Example: '--pager \"less -RF\"'.",
                 ),
         )
+        .arg(
+            Arg::with_name("pager-timeout")
+                .long("pager-timeout")
+                .overrides_with("pager-timeout")
+                .takes_value(true)
+                .value_name("seconds")
+                .hidden_short_help(true)
+                .validator(|t| {
+                    t.parse::<u64>()
+                        .map_err(|_| "must be a number of seconds")
+                        .map(|_| ())
+                        .map_err(|e| e.to_string())
+                })
+                .help("Kill the pager if it is still running after this many seconds (default: never).")
+                .long_help(
+                    "Kill the pager if it is still running after this many seconds, for \
+                     automated environments where nobody quits it. bat then exits with \
+                     an error. Without this option, the pager is never killed: bat waits \
+                     until it is quit.",
+                ),
+        )
+        .arg(
+            Arg::with_name("chop-long-lines")
+                .long("chop-long-lines")
+                .short("S")
+                .help("Let the pager cut off long lines and scroll horizontally.")
+                .long_help(
+                    "Leave long lines entirely to the pager: they are passed on without \
+                     being wrapped and 'less' is started with '--chop-long-lines', so that it \
+                     cuts them off at the width of the terminal and scrolls horizontally. \
+                     This overrides the '--wrap' option whenever the output is paged.",
+                ),
+        )
+        .arg(
+            Arg::with_name("pager-per-file")
+                .long("pager-per-file")
+                .hidden_short_help(true)
+                .help("Show every file in a pager of its own.")
+                .long_help(
+                    "Show every file in a pager of its own instead of passing all of them to \
+                     a single pager. Quitting the pager (with 'q') moves on to the next file, \
+                     'Q' quits all of them (this needs 'less' version 582 or newer, older \
+                     versions treat 'Q' like 'q').",
+                ),
+        )
+        .arg(
+            Arg::with_name("sticky-header")
+                .long("sticky-header")
+                .hidden_short_help(true)
+                .help("Keep the file header at the top of the pager.")
+                .long_help(
+                    "Keep the header of the file (with its grid lines) at the top of the \
+                     screen while scrolling in the pager. This works if the pager shows a \
+                     single file (see '--pager-per-file') and needs 'less' version 600 or \
+                     newer.",
+                ),
+        )
+        .arg(
+            Arg::with_name("revision")
+                .long("revision")
+                .takes_value(true)
+                .number_of_values(1)
+                .multiple(true)
+                .value_name("rev")
+                .conflicts_with("history")
+                .hidden_short_help(true)
+                .help("Show the files as they were in a git revision.")
+                .long_help(
+                    "Show the files as they were in a git revision (like 'HEAD~2', a tag or a \
+                     commit id), with the revision in the header. The option can be used \
+                     multiple times to show several versions of the files one after another. \
+                     Together with '--pager-per-file', every version is shown in a pager of \
+                     its own. For example:\n  \
+                     '--revision v1.0 --revision HEAD'",
+                ),
+        )
+        .arg(
+            Arg::with_name("history")
+                .long("history")
+                .takes_value(true)
+                .value_name("N")
+                .validator(|n| {
+                    n.parse::<usize>()
+                        .map_err(|_| "must be a number")
+                        .and_then(|n| if n > 0 { Ok(()) } else { Err("must be positive") })
+                        .map_err(|e| e.to_string())
+                })
+                .hidden_short_help(true)
+                .help("Show the versions of the files from the last N commits that changed them.")
+                .long_help(
+                    "Show the versions of the files from the last N commits that changed them, \
+                     starting with the oldest one (like '--revision' for each of these \
+                     commits).",
+                ),
+        )
+        .arg(
+            Arg::with_name("export-asciicast")
+                .long("export-asciicast")
+                .takes_value(true)
+                .value_name("file")
+                .hidden_short_help(true)
+                .help("Record the output as an asciicast file.")
+                .long_help(
+                    "Record the output (including its timing) as an asciicast v2 file \
+                     that can be replayed with 'asciinema play' or embedded with the \
+                     asciinema player. The output is still printed as usual.",
+                ),
+        )
+        .arg(
+            Arg::with_name("export-html")
+                .long("export-html")
+                .takes_value(true)
+                .value_name("file")
+                .hidden_short_help(true)
+                .help("Write the files as a single HTML page.")
+                .long_help(
+                    "Write the highlighted files as a single, self-contained HTML page \
+                     (with an index of the files, collapsible sections and the colors of \
+                     the theme) to the given file, instead of printing them. The title of \
+                     the page is the name of the file.",
+                ),
+        )
+        .arg(
+            Arg::with_name("serve")
+                .long("serve")
+                .takes_value(true)
+                .value_name("endpoint")
+                .hidden_short_help(true)
+                .help("Run a highlighting server on STDIN ('-') or a Unix socket.")
+                .long_help(
+                    "Run a highlighting server instead of printing files: it answers \
+                     JSON-RPC requests (one per line) with the highlighted content, so \
+                     that the syntaxes and themes are loaded only once for many requests. \
+                     The endpoint is '-' for requests on STDIN (and responses on STDOUT) \
+                     or the path of a Unix socket. Needs bat to be built with the 'serve' \
+                     feature.",
+                ),
+        )
         .arg(
             Arg::with_name("map-syntax")
                 .short("m")
@@ -299,6 +721,19 @@ pub fn build_app(interactive_output: bool) -> ClapApp<'static, 'static> {
                 )
                 .takes_value(true),
         )
+        .arg(
+            Arg::with_name("git-attributes")
+                .long("git-attributes")
+                .hidden_short_help(true)
+                .help("Use the 'linguist-language' attributes of git repositories.")
+                .long_help(
+                    "Use the 'linguist-language' attributes in the .gitattributes files of \
+                     git repositories to detect the language of a file: a file with an \
+                     attribute like '*.inc linguist-language=PHP' is highlighted with the \
+                     given syntax, unless it is mapped with '--map-syntax'. The repository \
+                     of every file is looked up, which makes printing many files slower.",
+                ),
+        )
         .arg(
             Arg::with_name("theme")
                 .long("theme")
@@ -313,12 +748,175 @@ pub fn build_app(interactive_output: bool) -> ClapApp<'static, 'static> {
                      BAT_THEME=\"...\").",
                 ),
         )
+        .arg(
+            Arg::with_name("no-theme-fallback")
+                .long("no-theme-fallback")
+                .hidden_short_help(true)
+                .help("Do not synthesize colors that the theme does not define.")
+                .long_help(
+                    "Print comments and keywords exactly as the theme defines them. By \
+                     default, colors for comments and keywords are derived from the \
+                     foreground and background colors of the theme if the theme does \
+                     not define them.",
+                ),
+        )
+        .arg(
+            Arg::with_name("minimum-contrast")
+                .long("minimum-contrast")
+                .overrides_with("minimum-contrast")
+                .takes_value(true)
+                .value_name("ratio")
+                .hidden_short_help(true)
+                .validator(|ratio| match ratio.parse::<f64>() {
+                    Ok(ratio) if (1.0..=21.0).contains(&ratio) => Ok(()),
+                    _ => Err("must be a number between 1 and 21".into()),
+                })
+                .help("Adjust theme colors with a lower contrast ratio against the background.")
+                .long_help(
+                    "Lighten (or darken) the foreground colors of the theme that have a lower \
+                     contrast ratio than the given one against the background, to prevent \
+                     unreadable output like dark blue on black. The ratio is the contrast \
+                     ratio of the WCAG, from 1 (no contrast) to 21 (black on white); 4.5 is \
+                     a good value for text. The background is the one of the theme unless it \
+                     is given with '--terminal-background' or can be detected from the \
+                     COLORFGBG environment variable.",
+                ),
+        )
+        .arg(
+            Arg::with_name("terminal-background")
+                .long("terminal-background")
+                .overrides_with("terminal-background")
+                .takes_value(true)
+                .value_name("color")
+                .requires("minimum-contrast")
+                .hidden_short_help(true)
+                .validator(|color| {
+                    parse_hex_color(&color)
+                        .map(|_| ())
+                        .ok_or_else(|| "must be a color like '#1e1e1e'".into())
+                })
+                .help("Specify the background color of the terminal (like '#1e1e1e').")
+                .long_help(
+                    "Specify the background color of the terminal (like '#1e1e1e') that \
+                     '--minimum-contrast' checks the colors of the theme against.",
+                ),
+        )
+        .arg(
+            Arg::with_name("card")
+                .long("card")
+                .overrides_with("card")
+                .takes_value(true)
+                .value_name("width")
+                .hidden_short_help(true)
+                .validator(|width| match width.parse::<usize>() {
+                    Ok(width) if width >= 5 => Ok(()),
+                    _ => Err("must be a width of at least 5 columns".into()),
+                })
+                .help("Print the output into a bordered box of the given width.")
+                .long_help(
+                    "Print the output into a bordered box (a 'card') of the given width, \
+                     independent of the width of the terminal. This is useful for embedding \
+                     highlighted snippets into other layouts and for screenshots.",
+                ),
+        )
+        .arg(
+            Arg::with_name("card-border")
+                .long("card-border")
+                .overrides_with("card-border")
+                .takes_value(true)
+                .value_name("style")
+                .possible_values(&["rounded", "ascii"])
+                .requires("card")
+                .hidden_short_help(true)
+                .help("Specify the border of the card (default: rounded)."),
+        )
+        .arg(
+            Arg::with_name("card-align")
+                .long("card-align")
+                .overrides_with("card-align")
+                .takes_value(true)
+                .value_name("alignment")
+                .possible_values(&["left", "center"])
+                .requires("card")
+                .hidden_short_help(true)
+                .help("Specify the position of the card in the terminal (default: left)."),
+        )
+        .arg(
+            Arg::with_name("max-width")
+                .long("max-width")
+                .overrides_with("max-width")
+                .takes_value(true)
+                .value_name("width")
+                .validator(|width| match width.parse::<usize>() {
+                    Ok(width) if width > 0 => Ok(()),
+                    _ => Err("must be a positive width".into()),
+                })
+                .help("Limit the width of the output.")
+                .long_help(
+                    "Limit the width of the output (including the decorations) to the given \
+                     number of columns. On wider terminals, the output is positioned with \
+                     '--block-align'. This is useful on very wide terminals and for \
+                     presentations.",
+                ),
+        )
+        .arg(
+            Arg::with_name("squeeze-blank")
+                .long("squeeze-blank")
+                .short("s")
+                .help("Squeeze consecutive empty lines.")
+                .long_help(
+                    "Squeeze consecutive empty lines into a single empty line (or into the \
+                     number of empty lines of '--squeeze-limit').",
+                ),
+        )
+        .arg(
+            Arg::with_name("squeeze-limit")
+                .long("squeeze-limit")
+                .takes_value(true)
+                .value_name("N")
+                .requires("squeeze-blank")
+                .validator(|limit| {
+                    limit
+                        .parse::<usize>()
+                        .map(|_| ())
+                        .map_err(|_| "must be a number of lines".into())
+                })
+                .hidden_short_help(true)
+                .help("Set the maximum number of consecutive empty lines of '--squeeze-blank'."),
+        )
+        .arg(
+            Arg::with_name("block-align")
+                .long("block-align")
+                .overrides_with("block-align")
+                .takes_value(true)
+                .value_name("alignment")
+                .possible_values(&["left", "center", "right"])
+                .requires("max-width")
+                .hidden_short_help(true)
+                .help("Specify the position of the output in wider terminals (default: left)."),
+        )
         .arg(
             Arg::with_name("list-themes")
                 .long("list-themes")
                 .help("Display all supported highlighting themes.")
                 .long_help("Display a list of supported themes for syntax highlighting."),
         )
+        .arg(
+            Arg::with_name("export-theme")
+                .long("export-theme")
+                .takes_value(true)
+                .value_name("format")
+                .possible_values(&["alacritty", "wezterm", "kitty", "css"])
+                .conflicts_with("list-themes")
+                .hidden_short_help(true)
+                .help("Print the colors of the theme for a terminal emulator or CSS.")
+                .long_help(
+                    "Print the colors of the theme that is selected with '--theme' in the \
+                     given format: as a color scheme for Alacritty, WezTerm or Kitty, or as \
+                     CSS variables. The palette colors of the terminal are derived from the \
+                     colors of the theme.",
+                ),
+        )
         .arg(
             Arg::with_name("style")
                 .long("style")
@@ -334,7 +932,8 @@ pub fn build_app(interactive_output: bool) -> ClapApp<'static, 'static> {
                 .validator(|val| {
                     let mut invalid_vals = val.split(',').filter(|style| {
                         !&[
-                            "auto", "full", "plain", "changes", "header", "grid", "numbers", "snip"
+                            "auto", "full", "plain", "changes", "header", "grid", "numbers", "snip",
+                            "status", "sections"
                         ]
                             .contains(style)
                     });
@@ -347,7 +946,8 @@ pub fn build_app(interactive_output: bool) -> ClapApp<'static, 'static> {
                 })
                 .help(
                     "Comma-separated list of style elements to display \
-                     (*auto*, full, plain, changes, header, grid, numbers, snip).",
+                     (*auto*, full, plain, changes, header, grid, numbers, snip, status, \
+                     sections).",
                 )
                 .long_help(
                     "Configure which elements (line numbers, file headers, grid \
@@ -357,7 +957,124 @@ pub fn build_app(interactive_output: bool) -> ClapApp<'static, 'static> {
                      pre-defined style ('full'). To set a default style, add the \
                      '--style=\"..\"' option to the configuration file or export the \
                      BAT_STYLE environment variable (e.g.: export BAT_STYLE=\"..\"). \
-                     Possible values: *auto*, full, plain, changes, header, grid, numbers, snip.",
+                     The 'status' component (not part of 'full') adds a status line with the \
+                     file name, language, line count and printed range below each file when \
+                     the output is not paged. The 'sections' component (not part of 'full') \
+                     prints a subtle separator line before top-level declarations like \
+                     functions, classes or headings. \
+                     Possible values: *auto*, full, plain, changes, header, grid, numbers, snip, status, \
+                     sections.",
+                ),
+        )
+        .arg(
+            Arg::with_name("header-template")
+                .long("header-template")
+                .overrides_with("header-template")
+                .takes_value(true)
+                .value_name("template")
+                .hidden_short_help(true)
+                .help("Customize the header of files, e.g. '{path} • {language} • {size}'.")
+                .long_help(
+                    "Replace the file name in the header by the given template. Fields in \
+                     braces are filled in: {path}, {name} (the file name without its \
+                     directory), {language}, {size}, {modified}, \
+                     {permissions}, {mode} (like '<BINARY>') and \
+                     {revision} (see '--revision'). Use '{{' and '}}' for literal braces and '\\e' for an escape character (to add \
+                     colors). Example: --header-template='{path} • {language} • {size}'.",
+                ),
+        )
+        .arg(
+            Arg::with_name("header-components")
+                .long("header-components")
+                .overrides_with("header-components")
+                .takes_value(true)
+                .value_name("components")
+                .hidden_short_help(true)
+                .help("Choose the information in the header, e.g. 'filename,size,modified'.")
+                .long_help(
+                    "Choose the information about a file in its header, as a comma-separated \
+                     list of: filename, size, modified (the time of the last modification, \
+                     in UTC) and permissions. Example: --header-components=filename,size",
+                ),
+        )
+        .arg(
+            Arg::with_name("footer-template")
+                .long("footer-template")
+                .overrides_with("footer-template")
+                .takes_value(true)
+                .value_name("template")
+                .hidden_short_help(true)
+                .help("Print a footer after each file, e.g. '{lines} lines'.")
+                .long_help(
+                    "Print a footer line after the contents of each file. The template can \
+                     use the fields of '--header-template' as well as {lines} (the number \
+                     of lines) and {range} (the range of printed lines).",
+                ),
+        )
+        .arg(
+            Arg::with_name("number-template")
+                .long("number-template")
+                .overrides_with("number-template")
+                .takes_value(true)
+                .value_name("template")
+                .hidden_short_help(true)
+                .help("Customize the line numbers, e.g. '{number}:'.")
+                .long_help(
+                    "Customize the line numbers in the gutter with a template. The {number} \
+                     field is replaced by the (padded) line number. The width of the gutter \
+                     is determined by the template (escape sequences don't count).",
+                ),
+        )
+        .arg(
+            Arg::with_name("gutter")
+                .long("gutter")
+                .overrides_with("gutter")
+                .takes_value(true)
+                .value_name("source")
+                .hidden_short_help(true)
+                .help("Show byte offsets or extracted values instead of line numbers.")
+                .long_help(
+                    "Specify what is shown in the number gutter: 'line-number' (default), \
+                     'byte-offset' (the offset of the first byte of each line) or a regular \
+                     expression that extracts a value from every line, like the timestamp of \
+                     a log message: the first capture group (or the whole match) is shown. \
+                     The gutter is as wide as the value in the first line, lines without a \
+                     match have an empty gutter. \
+                     Example: --gutter='^\\[([^\\]]+)\\]'",
+                ),
+        )
+        .arg(
+            Arg::with_name("linkify")
+                .long("linkify")
+                .help("Turn URLs into clickable hyperlinks.")
+                .long_help(
+                    "Turn the URLs in the content into hyperlinks (OSC 8 escape sequences), \
+                     which terminals that support them make clickable. The visible text \
+                     doesn't change.",
+                ),
+        )
+        .arg(
+            Arg::with_name("linkify-paths")
+                .long("linkify-paths")
+                .requires("linkify")
+                .hidden_short_help(true)
+                .help("Also turn paths of existing files into hyperlinks.")
+                .long_help(
+                    "Also turn the paths of existing files into hyperlinks with '--linkify': \
+                     absolute paths and paths that start with './' or '../'.",
+                ),
+        )
+        .arg(
+            Arg::with_name("repo")
+                .long("repo")
+                .takes_value(true)
+                .value_name("dir")
+                .help("Print an index of a directory, followed by its files.")
+                .long_help(
+                    "Print an index of the given directory (a tree of its files with their \
+                     sizes and languages), followed by the files. If files are given as \
+                     arguments, only these files are printed after the index. Hidden files \
+                     and files that are ignored by git are skipped.",
                 ),
         )
         .arg(
@@ -368,15 +1085,97 @@ pub fn build_app(interactive_output: bool) -> ClapApp<'static, 'static> {
                 .takes_value(true)
                 .number_of_values(1)
                 .value_name("N:M")
+                .allow_hyphen_values(true)
                 .conflicts_with("diff")
                 .help("Only print the lines from N to M.")
                 .long_help(
-                    "Only print the specified range of lines for each file. \
+                    "Only print the specified range of lines for each file. Negative \
+                     numbers count from the end of the file (-1 is the last line). \
                      For example:\n  \
                      '--line-range 30:40' prints lines 30 to 40\n  \
                      '--line-range :40' prints lines 1 to 40\n  \
                      '--line-range 40:' prints lines 40 to the end of the file\n  \
-                     '--line-range 40' only prints line 40",
+                     '--line-range 40' only prints line 40\n  \
+                     '--line-range -50:' prints the last 50 lines\n  \
+                     '--line-range 10:-6' prints lines 10 to the end, except for the last 5",
+                ),
+        )
+        .arg(
+            Arg::with_name("focus-symbol")
+                .long("focus-symbol")
+                .takes_value(true)
+                .value_name("name")
+                .conflicts_with_all(&["line-range", "diff"])
+                .help("Only print the definition of a symbol.")
+                .long_help(
+                    "Only print the definition of the symbol with the given name (like a \
+                     function, a struct or a class), including the comments and attributes \
+                     above it. The definition is located via the syntax highlighting, for \
+                     example: '--focus-symbol main' prints the main function.",
+                ),
+        )
+        .arg(
+            Arg::with_name("preview-sample")
+                .long("preview-sample")
+                .overrides_with("preview-sample")
+                .takes_value(true)
+                .value_name("N")
+                .validator(|n| {
+                    n.parse::<usize>()
+                        .map_err(|_| "must be a number")
+                        .map(|_| ()) // Convert to Result<(), &str>
+                        .map_err(|e| e.to_string())
+                })
+                .help("Only show the first and last N lines of large files.")
+                .long_help(
+                    "Only show the first and the last N lines of large files (see \
+                     '--preview-threshold'), with a marker that tells how many lines were \
+                     left out in between. This is enabled with N=100 when printing to a \
+                     terminal, use '--preview-sample=0' to always show all lines. Files are \
+                     not sampled if a line range is given.",
+                ),
+        )
+        .arg(
+            Arg::with_name("max-download-size")
+                .long("max-download-size")
+                .overrides_with("max-download-size")
+                .takes_value(true)
+                .value_name("size")
+                .hidden_short_help(true)
+                .help("Abort downloads of URLs that are larger than this size.")
+                .long_help(
+                    "Abort the download of a FILE argument that is an HTTP(S) URL once it \
+                     exceeds this size, in bytes or with one of the suffixes K, M or G. \
+                     'curl' is killed then and bat exits with an error. By default, the \
+                     size of downloads is not limited.",
+                ),
+        )
+        .arg(
+            Arg::with_name("preview-threshold")
+                .long("preview-threshold")
+                .overrides_with("preview-threshold")
+                .takes_value(true)
+                .value_name("size")
+                .hidden_short_help(true)
+                .help("The size from which on files are sampled (default: 64M).")
+                .long_help(
+                    "The size from which on files are sampled with '--preview-sample', in \
+                     bytes or with one of the suffixes K, M or G (default: 64M).",
+                ),
+        )
+        .arg(
+            Arg::with_name("split")
+                .long("split")
+                .takes_value(true)
+                .number_of_values(2)
+                .value_names(&["N:M", "N:M"])
+                .conflicts_with_all(&["line-range", "diff"])
+                .hidden_short_help(true)
+                .help("Print two ranges of lines side by side.")
+                .long_help(
+                    "Print two ranges of lines of each file side by side, for example a \
+                     function definition and its call site:\n  \
+                     '--split 10:20 85:90' prints lines 10 to 20 next to lines 85 to 90",
                 ),
         )
         .arg(
@@ -393,9 +1192,39 @@ pub fn build_app(interactive_output: bool) -> ClapApp<'static, 'static> {
                 .long("unbuffered")
                 .hidden_short_help(true)
                 .long_help(
-                    "This option exists for POSIX-compliance reasons ('u' is for \
-                     'unbuffered'). The output is always unbuffered - this option \
-                     is simply ignored.",
+                    "Write the output immediately instead of buffering it. By default, \
+                     the output is line-buffered when reading from STDIN and \
+                     block-buffered when printing files.",
+                ),
+        )
+        .arg(
+            Arg::with_name("line-endings")
+                .long("line-endings")
+                .overrides_with("line-endings")
+                .takes_value(true)
+                .value_name("style")
+                .possible_values(&["lf", "crlf"])
+                .hidden_short_help(true)
+                .help("Convert all line endings of the output to LF or CRLF.")
+                .long_help(
+                    "Convert all line endings of the output to LF ('lf') or CRLF ('crlf'). \
+                     By default, the line endings of the input are kept.",
+                ),
+        )
+        .arg(
+            Arg::with_name("output-encoding")
+                .long("output-encoding")
+                .overrides_with("output-encoding")
+                .takes_value(true)
+                .value_name("encoding")
+                .possible_values(&["utf-8", "utf-16", "latin-1"])
+                .hidden_short_help(true)
+                .help("Specify the character encoding of the output (default: utf-8).")
+                .long_help(
+                    "Specify the character encoding of the output (*utf-8*, utf-16, \
+                     latin-1), for example for piping into legacy tools. UTF-16 is written \
+                     in little endian with a byte order mark. Characters that can not be \
+                     represented in Latin-1 are replaced by '?'.",
                 ),
         )
         .arg(
@@ -412,6 +1241,14 @@ pub fn build_app(interactive_output: bool) -> ClapApp<'static, 'static> {
                 .hidden(true)
                 .help("Show path to the configuration file."),
         )
+        .arg(
+            Arg::with_name("describe-config")
+                .long("describe-config")
+                .conflicts_with("list-languages")
+                .conflicts_with("list-themes")
+                .hidden(true)
+                .help("Show the settings of the config file, the environment and the command line."),
+        )
         .arg(
             Arg::with_name("generate-config-file")
                 .long("generate-config-file")
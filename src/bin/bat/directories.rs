@@ -3,9 +3,12 @@ use std::path::{Path, PathBuf};
 
 use lazy_static::lazy_static;
 
+use bat::asset_dirs::AssetDirs;
+
 /// Wrapper for 'dirs' that treats MacOS more like Linux, by following the XDG specification.
 /// This means that the `XDG_CACHE_HOME` and `XDG_CONFIG_HOME` environment variables are
-/// checked first. The fallback directories are `~/.cache/bat` and `~/.config/bat`, respectively.
+/// checked first (after `BAT_CONFIG_DIR` and `BAT_CACHE_PATH`). The fallback directories are
+/// `~/.cache/bat` and `~/.config/bat`, respectively.
 pub struct BatProjectDirs {
     cache_dir: PathBuf,
     config_dir: PathBuf,
@@ -14,6 +17,20 @@ pub struct BatProjectDirs {
 impl BatProjectDirs {
     fn new() -> Option<BatProjectDirs> {
         let cache_dir = BatProjectDirs::get_cache_dir()?;
+        let config_dir = BatProjectDirs::get_config_dir()?;
+
+        Some(BatProjectDirs {
+            cache_dir,
+            config_dir,
+        })
+    }
+
+    fn get_config_dir() -> Option<PathBuf> {
+        // on all OS prefer BAT_CONFIG_DIR if set
+        let config_dir_op = env::var_os("BAT_CONFIG_DIR").map(PathBuf::from);
+        if config_dir_op.is_some() {
+            return config_dir_op;
+        }
 
         #[cfg(target_os = "macos")]
         let config_dir_op = env::var_os("XDG_CONFIG_HOME")
@@ -24,12 +41,7 @@ impl BatProjectDirs {
         #[cfg(not(target_os = "macos"))]
         let config_dir_op = dirs::config_dir();
 
-        let config_dir = config_dir_op.map(|d| d.join("bat"))?;
-
-        Some(BatProjectDirs {
-            cache_dir,
-            config_dir,
-        })
+        config_dir_op.map(|d| d.join("bat"))
     }
 
     fn get_cache_dir() -> Option<PathBuf> {
@@ -58,6 +70,10 @@ impl BatProjectDirs {
     pub fn config_dir(&self) -> &Path {
         &self.config_dir
     }
+
+    pub fn asset_dirs(&self) -> AssetDirs {
+        AssetDirs::new(&self.config_dir, &self.cache_dir)
+    }
 }
 
 lazy_static! {
@@ -9,12 +9,13 @@ mod directories;
 
 use std::collections::HashSet;
 use std::ffi::OsStr;
+use std::fs::File;
 use std::io;
-use std::io::Write;
+use std::io::{BufWriter, Cursor, Write};
 use std::path::Path;
 use std::process;
 
-use ansi_term::Colour::Green;
+use ansi_term::Colour::{Green, Yellow};
 use ansi_term::Style;
 
 use crate::{
@@ -22,16 +23,18 @@ use crate::{
     config::{config_file, generate_config_file},
 };
 use assets::{assets_from_cache_or_binary, cache_dir, clear_assets, config_dir};
-use clap::crate_version;
 use directories::PROJECT_DIRS;
 
 use bat::{
+    asset_dirs::AssetDirs,
     assets::HighlightingAssets,
     config::Config,
     controller::Controller,
     error::*,
     input::Input,
+    repository::RepositoryIndex,
     style::{StyleComponent, StyleComponents},
+    theme_export::ThemeExportFormat,
 };
 
 fn run_cache_subcommand(matches: &clap::ArgMatches) -> Result<()> {
@@ -47,8 +50,7 @@ fn run_cache_subcommand(matches: &clap::ArgMatches) -> Result<()> {
 
         let blank = matches.is_present("blank");
 
-        let assets = HighlightingAssets::from_files(source_dir, !blank)?;
-        assets.save_to_cache(target_dir, crate_version!())?;
+        AssetDirs::new(source_dir, target_dir).build_cache(!blank)?;
     } else if matches.is_present("clear") {
         clear_assets();
     }
@@ -150,12 +152,99 @@ pub fn list_themes(cfg: &Config) -> Result<()> {
     Ok(())
 }
 
+pub fn export_theme(cfg: &Config, format: &str) -> Result<()> {
+    let assets = assets_from_cache_or_binary()?;
+    let format = ThemeExportFormat::from_name(format)
+        .ok_or_else(|| Error::from(format!("Unknown theme export format '{}'", format)))?;
+
+    write!(io::stdout(), "{}", assets.export_theme(&cfg.theme, format)?)?;
+    Ok(())
+}
+
 fn run_controller(inputs: Vec<Input>, config: &Config) -> Result<bool> {
     let assets = assets_from_cache_or_binary()?;
-    let controller = Controller::new(&config, &assets);
+    run_controller_with_assets(inputs, config, &assets)
+}
+
+fn run_controller_with_assets(
+    inputs: Vec<Input>,
+    config: &Config,
+    assets: &HighlightingAssets,
+) -> Result<bool> {
+    for warning in config.validate()? {
+        eprintln!("{}: {}", Yellow.paint("[bat warning]"), warning);
+    }
+
+    let controller = Controller::new(&config, assets);
     controller.run(inputs)
 }
 
+/// Write the inputs as an HTML page to the given file (see `Controller::write_html_report`)
+fn run_html_report(inputs: Vec<Input>, config: &Config, path: &Path) -> Result<bool> {
+    let assets = assets_from_cache_or_binary()?;
+    for warning in config.validate()? {
+        eprintln!("{}: {}", Yellow.paint("[bat warning]"), warning);
+    }
+
+    let title = path.file_stem().unwrap_or_default().to_string_lossy();
+    let mut file = BufWriter::new(
+        File::create(path)
+            .chain_err(|| format!("Could not create '{}'", path.to_string_lossy()))?,
+    );
+    Controller::new(config, &assets).write_html_report(inputs, &title, &mut file)
+}
+
+/// Run the highlighting server on STDIN or on a Unix socket (see `bat::serve`)
+#[cfg(feature = "serve")]
+fn run_server(endpoint: &OsStr, config: &Config) -> Result<bool> {
+    use std::sync::Arc;
+
+    let assets = Arc::new(assets_from_cache_or_binary()?);
+    let mut server = bat::serve::Server::new(assets);
+    server.theme(config.theme.as_str());
+
+    if endpoint == "-" {
+        server.serve(io::BufReader::new(io::stdin()), io::stdout())?;
+        return Ok(true);
+    }
+
+    #[cfg(unix)]
+    {
+        server.serve_unix_socket(Path::new(endpoint))?;
+        Ok(true)
+    }
+    #[cfg(not(unix))]
+    {
+        Err("Unix sockets are not supported on this platform, use '--serve -'".into())
+    }
+}
+
+#[cfg(not(feature = "serve"))]
+fn run_server(_endpoint: &OsStr, _config: &Config) -> Result<bool> {
+    Err("bat was built without support for the highlighting server (feature 'serve')".into())
+}
+
+/// Print the index of a directory, followed by the given files (or all files of the index)
+fn run_repository(root: &Path, files: Vec<Input>, config: &Config) -> Result<bool> {
+    let assets = assets_from_cache_or_binary()?;
+    let index = RepositoryIndex::scan(root, &assets, &config.syntax_mapping)?;
+
+    let name = format!("Index of {}", root.to_string_lossy());
+    let mut inputs = vec![Input::from_reader(Box::new(Cursor::new(index.render())))
+        .with_name(Some(OsStr::new(&name)))];
+    if files.is_empty() {
+        inputs.extend(
+            index
+                .files()
+                .map(|path| Input::ordinary_file(path.as_os_str())),
+        );
+    } else {
+        inputs.extend(files);
+    }
+
+    run_controller_with_assets(inputs, config, &assets)
+}
+
 /// Returns `Err(..)` upon fatal errors. Otherwise, returns `Ok(true)` on full success and
 /// `Ok(false)` if any intermediate errors occurred (were printed).
 fn run() -> Result<bool> {
@@ -186,9 +275,15 @@ fn run() -> Result<bool> {
             } else if app.matches.is_present("list-themes") {
                 list_themes(&config)?;
                 Ok(true)
+            } else if let Some(format) = app.matches.value_of("export-theme") {
+                export_theme(&config, format)?;
+                Ok(true)
             } else if app.matches.is_present("config-file") {
                 println!("{}", config_file().to_string_lossy());
                 Ok(true)
+            } else if app.matches.is_present("describe-config") {
+                write!(io::stdout(), "{}", config.describe())?;
+                Ok(true)
             } else if app.matches.is_present("generate-config-file") {
                 generate_config_file()?;
                 Ok(true)
@@ -198,6 +293,17 @@ fn run() -> Result<bool> {
             } else if app.matches.is_present("cache-dir") {
                 writeln!(io::stdout(), "{}", cache_dir())?;
                 Ok(true)
+            } else if let Some(endpoint) = app.matches.value_of_os("serve") {
+                run_server(endpoint, &config)
+            } else if let Some(path) = app.matches.value_of_os("export-html") {
+                run_html_report(inputs, &config, Path::new(path))
+            } else if let Some(root) = app.matches.value_of_os("repo") {
+                let files = if app.matches.is_present("FILE") {
+                    inputs
+                } else {
+                    vec![]
+                };
+                run_repository(Path::new(root), files, &config)
             } else {
                 run_controller(inputs, &config)
             }
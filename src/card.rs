@@ -0,0 +1,189 @@
+//! Output in a fixed-width "card": a bordered box around all of the output, which is
+//! independent of the width of the terminal (for embedding highlighted snippets into larger
+//! layouts or for screenshots).
+
+use std::io::{self, Write};
+
+use console::measure_text_width;
+
+use crate::template::truncate;
+
+/// The characters of the border of a card
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CardBorder {
+    /// Box-drawing characters with rounded corners
+    Rounded,
+
+    /// ASCII characters only (`+`, `-` and `|`)
+    Ascii,
+}
+
+/// The position of a card within the terminal
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CardAlignment {
+    Left,
+    Center,
+}
+
+/// A fixed-width box around the output
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Card {
+    /// The width of the card in terminal columns, including the border
+    pub width: usize,
+    pub border: CardBorder,
+    pub alignment: CardAlignment,
+}
+
+/// The smallest width of a card (the border and the padding take four columns)
+pub(crate) const MIN_CARD_WIDTH: usize = 5;
+
+impl Card {
+    /// A left-aligned card with a rounded border
+    pub fn new(width: usize) -> Self {
+        Card {
+            width,
+            border: CardBorder::Rounded,
+            alignment: CardAlignment::Left,
+        }
+    }
+
+    /// The width of the contents of the card
+    pub(crate) fn inner_width(&self) -> usize {
+        self.width.saturating_sub(4)
+    }
+
+    /// The number of columns in front of the card on a terminal of the given width
+    pub(crate) fn margin(&self, term_width: usize) -> usize {
+        match self.alignment {
+            CardAlignment::Left => 0,
+            CardAlignment::Center => term_width.saturating_sub(self.width) / 2,
+        }
+    }
+
+    /// The characters of the corners (top left, top right, bottom left, bottom right) and of
+    /// the horizontal and vertical lines
+    fn characters(&self) -> ([&'static str; 4], &'static str, &'static str) {
+        match self.border {
+            CardBorder::Rounded => (["╭", "╮", "╰", "╯"], "─", "│"),
+            CardBorder::Ascii => (["+", "+", "+", "+"], "-", "|"),
+        }
+    }
+}
+
+/// A writer that puts all output (line by line) into a card. The bottom border is written by
+/// `finish`.
+pub(crate) struct CardWriter<'a> {
+    output: &'a mut dyn Write,
+    card: Card,
+    margin: String,
+
+    /// The part of the current line that has been written so far
+    line: Vec<u8>,
+}
+
+impl<'a> CardWriter<'a> {
+    pub(crate) fn new(
+        output: &'a mut dyn Write,
+        card: Card,
+        term_width: usize,
+    ) -> io::Result<Self> {
+        let mut writer = CardWriter {
+            output,
+            card,
+            margin: " ".repeat(card.margin(term_width)),
+            line: vec![],
+        };
+        writer.write_border(0, 1)?;
+        Ok(writer)
+    }
+
+    fn write_border(&mut self, left: usize, right: usize) -> io::Result<()> {
+        let (corners, horizontal, _) = self.card.characters();
+        writeln!(
+            self.output,
+            "{}{}{}{}",
+            self.margin,
+            corners[left],
+            horizontal.repeat(self.card.width.saturating_sub(2)),
+            corners[right]
+        )
+    }
+
+    fn write_line(&mut self) -> io::Result<()> {
+        let (_, _, vertical) = self.card.characters();
+        let line = String::from_utf8_lossy(&self.line);
+        let content = truncate(line.trim_end_matches('\r'), self.card.inner_width());
+        let padding = self
+            .card
+            .inner_width()
+            .saturating_sub(measure_text_width(&content));
+        let reset = if content.contains('\x1b') {
+            "\x1b[0m"
+        } else {
+            ""
+        };
+
+        writeln!(
+            self.output,
+            "{}{} {}{}{} {}",
+            self.margin,
+            vertical,
+            content,
+            reset,
+            " ".repeat(padding),
+            vertical
+        )?;
+        self.line.clear();
+        Ok(())
+    }
+
+    /// Write the last (incomplete) line and the bottom border
+    pub(crate) fn finish(&mut self) -> io::Result<()> {
+        if !self.line.is_empty() {
+            self.write_line()?;
+        }
+        self.write_border(2, 3)?;
+        self.output.flush()
+    }
+}
+
+impl<'a> Write for CardWriter<'a> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for &byte in buf {
+            if byte == b'\n' {
+                self.write_line()?;
+            } else {
+                self.line.push(byte);
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.output.flush()
+    }
+}
+
+#[test]
+fn output_is_put_into_a_card() {
+    let mut output = vec![];
+    {
+        let card = Card {
+            width: 12,
+            border: CardBorder::Ascii,
+            alignment: CardAlignment::Center,
+        };
+        let mut writer = CardWriter::new(&mut output, card, 16).unwrap();
+        write!(writer, "fn main\n\x1b[1mlong line\x1b[0m\r\n}}").unwrap();
+        writer.finish().unwrap();
+    }
+
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "  +----------+\n  \
+         | fn main  |\n  \
+         | \x1b[1mlong lin\x1b[0m\x1b[0m |\n  \
+         | }        |\n  \
+         +----------+\n"
+    );
+}
@@ -1,8 +1,32 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use syntect::highlighting::Color;
+
+use crate::accessibility::MarkerPalette;
+use crate::card::{Card, MIN_CARD_WIDTH};
+use crate::config_layers::Provenance;
+use crate::embedded::EmbeddedLanguage;
+use crate::error::*;
+use crate::gutter::GutterSource;
+use crate::hexdump::BinaryMode;
+use crate::json_lines::JsonLinesMode;
+use crate::layout::BlockAlignment;
+use crate::line_processor::LineProcessors;
 use crate::line_range::{HighlightedLineRanges, LineRanges};
+use crate::output::{BufferingMode, LineEnding, OutputEncoding};
 #[cfg(feature = "paging")]
 use crate::paging::PagingMode;
-use crate::style::StyleComponents;
+use crate::patterns::HighlightPatterns;
+use crate::split::{self, SplitView};
+use crate::style::{HeaderComponent, ResponsiveLayout, StyleComponents};
 use crate::syntax_mapping::SyntaxMapping;
+use crate::template::Template;
+use crate::trace::StackTraceMode;
 use crate::wrapping::WrappingMode;
 
 #[derive(Debug, Clone)]
@@ -31,6 +55,102 @@ impl Default for VisibleLines {
     }
 }
 
+/// Assigns a class (like "failure") to a line, given its line number and its content (without
+/// the line ending). Lines of a class are printed with the background color that is configured
+/// for the class in `Config::line_class_colors`. The printer may be shared between threads when
+/// inputs are printed in parallel, so classifiers with state need to synchronize it.
+#[derive(Clone)]
+pub struct LineClassifier(Arc<dyn Fn(usize, &str) -> Option<String> + Send + Sync>);
+
+impl LineClassifier {
+    pub fn new(classify: impl Fn(usize, &str) -> Option<String> + Send + Sync + 'static) -> Self {
+        LineClassifier(Arc::new(classify))
+    }
+
+    pub(crate) fn classify(&self, line_number: usize, line: &str) -> Option<String> {
+        (self.0)(line_number, line)
+    }
+}
+
+impl fmt::Debug for LineClassifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "LineClassifier")
+    }
+}
+
+/// The terminal size that is used in deterministic mode (unless a width is configured)
+pub const DETERMINISTIC_TERM_WIDTH: usize = 80;
+pub const DETERMINISTIC_TERM_HEIGHT: usize = 24;
+
+/// Thresholds for detecting minified or generated input. Such input is printed without syntax
+/// highlighting, since highlighting extremely long lines can take a very long time.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MinifiedThresholds {
+    /// Maximum average length of the lines at the beginning of the input (in bytes)
+    pub average_line_length: usize,
+
+    /// Maximum length of the first line (in bytes)
+    pub first_line_length: usize,
+}
+
+impl Default for MinifiedThresholds {
+    fn default() -> Self {
+        MinifiedThresholds {
+            average_line_length: 500,
+            first_line_length: 10_000,
+        }
+    }
+}
+
+impl MinifiedThresholds {
+    /// Whether an input with the given file name and beginning (first line plus whatever
+    /// follows) looks like it has been minified or generated
+    pub fn is_minified(&self, file_name: &str, first_line: &[u8], beginning: &[u8]) -> bool {
+        let file_name = Path::new(file_name)
+            .file_name()
+            .map(|f| f.to_string_lossy())
+            .unwrap_or_default();
+        if file_name.contains(".min.") {
+            return true;
+        }
+
+        if first_line.len() > self.first_line_length {
+            return true;
+        }
+
+        let text = String::from_utf8_lossy(beginning);
+        if text.contains("sourceMappingURL=") {
+            return true;
+        }
+
+        let lines = text.lines().count().max(1);
+        beginning.len() / lines > self.average_line_length
+    }
+}
+
+/// Show only the first and the last lines of large files (with a snip marker that tells how
+/// many lines were omitted in between), so that printing one by accident stays fast
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PreviewSample {
+    /// The number of lines at the beginning and at the end of the file
+    pub lines: usize,
+
+    /// The size (in bytes) from which on a file is sampled
+    pub threshold: u64,
+}
+
+impl PreviewSample {
+    /// The default threshold for sampling files (64 MiB)
+    pub const DEFAULT_THRESHOLD: u64 = 64 * 1024 * 1024;
+
+    pub fn new(lines: usize) -> Self {
+        PreviewSample {
+            lines,
+            threshold: Self::DEFAULT_THRESHOLD,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct Config<'a> {
     /// The explicitly configured language, if any
@@ -39,6 +159,15 @@ pub struct Config<'a> {
     /// Whether or not to show/replace non-printable characters like space, tab and newline.
     pub show_nonprintable: bool,
 
+    /// Keep the ANSI escape sequences of input that is colored already (like the output of
+    /// other tools): the input is not highlighted, but its escape sequences are passed through
+    /// and don't count towards the width of the lines when they are wrapped, padded or tabs
+    /// are expanded. Without colored output, the escape sequences are removed.
+    pub ansi_passthrough: bool,
+
+    /// How binary content is printed (unless non-printable characters are shown)
+    pub binary_mode: BinaryMode,
+
     /// The character width of the terminal
     pub term_width: usize,
 
@@ -68,6 +197,25 @@ pub struct Config<'a> {
     /// Specifies which lines should be printed
     pub visible_lines: VisibleLines,
 
+    /// Only print the definition of the symbol with this name (like a function, a struct or a
+    /// Markdown heading), including the comments and attributes above it. The definition is
+    /// located via the scopes of the syntax.
+    pub focus_symbol: Option<String>,
+
+    /// Align the values of `key = value` (and tab-separated) lines in config files like INI,
+    /// TOML or `.properties` files in a column, with distinctly colored keys and sections. Can
+    /// be overridden per input with `Input::align_key_values`.
+    pub align_key_values: bool,
+
+    /// Highlight the fenced code blocks of Markdown files with the languages of their info
+    /// strings (like ```` ```rust ````), and print the prose around them with few styles
+    pub markdown_code_blocks: bool,
+
+    /// Show only the beginning and the end of large files (if all lines are visible). The
+    /// omitted lines are not highlighted, so the last lines are highlighted as if they
+    /// followed the first ones.
+    pub preview_sample: Option<PreviewSample>,
+
     /// The syntax highlighting theme
     pub theme: String,
 
@@ -77,11 +225,268 @@ pub struct Config<'a> {
     /// Command to start the pager
     pub pager: Option<&'a str>,
 
+    /// Leave horizontal scrolling to the pager: `less` is started with `--chop-long-lines` and
+    /// long lines are passed on without being wrapped (use with `WrappingMode::NoWrapping`)
+    pub chop_long_lines: bool,
+
+    /// Kill the pager if it is still running this long after it was started, for automated
+    /// environments where nobody quits it. Printing then fails with
+    /// `ErrorKind::SubprocessTimeout`.
+    pub pager_timeout: Option<Duration>,
+
+    /// Show every input in a pager of its own (if there are several): quitting the pager moves
+    /// on to the next input, the `Q` key in `less` quits all of them
+    pub pager_per_file: bool,
+
+    /// Keep the header of the input (with its grid lines) at the top of the screen while
+    /// scrolling in the pager, if the pager shows a single input. This needs `less` 600 or
+    /// newer (for `--header`).
+    pub sticky_header: bool,
+
     /// Whether or not to use ANSI italics
     pub use_italic_text: bool,
 
     /// Ranges of lines which should be highlighted with a special background color
     pub highlighted_lines: HighlightedLineRanges,
+
+    /// Whether or not unknown languages and themes are reported as errors (instead of
+    /// falling back to plain text and the default theme)
+    pub strict: bool,
+
+    /// Print input without syntax highlighting if it looks minified or generated. `None`
+    /// disables the detection. Inputs with an explicitly configured language are always
+    /// highlighted.
+    pub minified_thresholds: Option<MinifiedThresholds>,
+
+    /// How the output is buffered
+    pub buffering_mode: BufferingMode,
+
+    /// Convert all line endings of the output (default: keep the line endings of the input)
+    pub line_ending: Option<LineEnding>,
+
+    /// The character encoding of the output
+    pub output_encoding: OutputEncoding,
+
+    /// Record the output as an asciicast (v2) file with the given path
+    pub asciicast_file: Option<&'a Path>,
+
+    /// Whether or not to synthesize colors for comments and keywords (derived from the base
+    /// colors of the theme) if the theme does not define them
+    pub theme_fallback: bool,
+
+    /// Elide the middle of tokens (runs of non-whitespace characters, like base64 blobs) that
+    /// are longer than the given number of characters
+    pub max_token_length: Option<usize>,
+
+    /// Regions of the input that are highlighted with the syntax of another language
+    pub embedded_languages: Vec<EmbeddedLanguage>,
+
+    /// Color every line that contains a JSON object on its own, as JSON or as flattened
+    /// `key=value` pairs
+    pub json_lines: Option<JsonLinesMode>,
+
+    /// Drop style components on narrow terminals instead of squeezing the code column
+    pub responsive_layout: Option<ResponsiveLayout>,
+
+    /// Refuse to use any feature that writes to the filesystem or spawns subprocesses (the
+    /// pager, the Git integration and asciicast recordings)
+    pub sandbox: bool,
+
+    /// Produce the same output on every machine, for snapshot tests: the pager and the Git
+    /// integration are not used, headers do not contain the modification time of files and
+    /// recordings do not contain the time or the terminal type
+    pub deterministic: bool,
+
+    /// Tint lines by custom logic, see `LineClassifier`
+    pub line_classifier: Option<LineClassifier>,
+
+    /// The background colors of the line classes
+    pub line_class_colors: HashMap<String, Color>,
+
+    /// Custom transformations of the lines before the decorations are added, see
+    /// `LineProcessor`
+    pub line_processors: LineProcessors,
+
+    /// Byte ranges within lines (given by their line number) that are highlighted like search
+    /// results. The ranges of a line have to be sorted and must not overlap.
+    pub highlighted_spans: BTreeMap<usize, Vec<Range<usize>>>,
+
+    /// Regular expressions whose matches are highlighted with named styles, in priority order
+    pub highlight_patterns: HighlightPatterns,
+
+    /// Treat the input as a stack trace: its messages and frames are highlighted (instead of
+    /// using a syntax) and the referenced source lines are optionally inlined
+    pub stack_trace: Option<StackTraceMode>,
+
+    /// Render unified diffs (inputs with the `Diff` syntax) structurally: files and hunks are
+    /// separated, added and removed lines are highlighted in the language of the file and
+    /// changed words are emphasized
+    pub render_diffs: bool,
+
+    /// Render the input as the output of `git show`, `git log` or `git diff` (independent of
+    /// the syntax): the metadata and messages of commits are styled and diffs are rendered
+    /// like with `render_diffs`
+    pub render_commits: bool,
+
+    /// A template for the header of a file (instead of the file name)
+    pub header_template: Option<Template>,
+
+    /// The information about a file in its header, in this order (only the file name if
+    /// empty). Information that is not available (like the size of STDIN) is left out.
+    pub header_components: Vec<HeaderComponent>,
+
+    /// A template for a footer that is printed after the contents of a file
+    pub footer_template: Option<Template>,
+
+    /// What is shown in the number gutter: line numbers (default), byte offsets or values that
+    /// are extracted from the lines
+    pub gutter_source: GutterSource,
+
+    /// A template for the line numbers in the gutter
+    pub line_number_template: Option<Template>,
+
+    /// Color Git change markers and log levels with a color-blind friendly palette, and mark
+    /// highlighted lines and log levels with glyphs in addition to their colors
+    pub accessible_markers: Option<MarkerPalette>,
+
+    /// Lighten (or darken) the foreground colors of the theme that have a lower contrast ratio
+    /// (from 1 to 21, see the WCAG) than this against the background
+    pub minimum_contrast: Option<f64>,
+
+    /// The background color of the terminal that `minimum_contrast` is checked against
+    /// (instead of the background of the theme)
+    pub terminal_background: Option<Color>,
+
+    /// Put the output into a bordered box of a fixed width. The contents of the card are
+    /// printed as if the terminal had the width of the inside of the card.
+    pub card: Option<Card>,
+
+    /// Print two line ranges of each input side by side (instead of `visible_lines`)
+    pub split_view: Option<SplitView>,
+
+    /// A prefix for every line of the output (like `> ` for a quoted block). The output is
+    /// printed as if the terminal was narrower by the width of the prefix.
+    pub indent: Option<String>,
+
+    /// The largest width of the output (including the decorations and the indentation). The
+    /// output on wider terminals is positioned according to `block_alignment`.
+    pub max_width: Option<usize>,
+
+    /// The position of the output if the terminal is wider than `max_width`
+    pub block_alignment: BlockAlignment,
+
+    /// Turn the URLs in the content into hyperlinks (with OSC 8 escape sequences), without
+    /// changing the visible text. Lines with elided tokens are printed without links.
+    pub linkify: bool,
+
+    /// Also link paths of existing files (absolute ones and ones that start with `./` or
+    /// `../`), if `linkify` is enabled
+    pub linkify_paths: bool,
+
+    /// The number of files that are highlighted at the same time, on threads of their own (0
+    /// or 1: one after another). Their output is still written in the order of the inputs.
+    pub parallelism: usize,
+
+    /// Collapse runs of empty lines to at most this number of empty lines (like `cat -s` with
+    /// `Some(1)`)
+    pub squeeze_blank_lines: Option<usize>,
+
+    /// Keep reading files after their end (like `tail -f`): lines that are appended to a file
+    /// are highlighted and printed as they are written, so printing only finishes when the
+    /// file is removed. Files in split views and diffs, UTF-16LE files and binary files that
+    /// are printed as hex dumps are not followed.
+    pub follow: bool,
+
+    /// The layers that set the settings (see `describe`)
+    pub provenance: Provenance,
+}
+
+impl<'a> Config<'a> {
+    /// Check the configuration for contradictory settings before printing. Settings that make
+    /// printing impossible are reported as an error, settings that probably lead to unexpected
+    /// output are returned as warnings.
+    pub fn validate(&self) -> Result<Vec<String>> {
+        let invalid = |reason: &str| Err(ErrorKind::InvalidConfig(reason.into()).into());
+
+        match self.card {
+            Some(card) if card.width < MIN_CARD_WIDTH => {
+                return invalid("the card is narrower than five columns");
+            }
+            _ => {}
+        }
+        if self.max_width == Some(0) {
+            return invalid("the maximum width is 0");
+        }
+        if self.indent.is_some() && self.term_width == 0 {
+            return invalid("the indentation is as wide as the terminal");
+        }
+        if self.term_width == 0 && !self.loop_through {
+            return invalid("the terminal width is 0");
+        }
+        if self.split_view.is_some() && split::pane_width(self.term_width) == 0 {
+            return invalid("the terminal is too narrow for a split view");
+        }
+        if self.highlighted_lines.0.is_relative() {
+            return invalid("highlighted lines can not be counted from the end of the input");
+        }
+        if self.max_token_length == Some(0) {
+            return invalid("the maximum token length is 0");
+        }
+        match self.minimum_contrast {
+            Some(ratio) if !(1.0..=21.0).contains(&ratio) => {
+                return invalid("the minimum contrast ratio has to be between 1 and 21");
+            }
+            _ => {}
+        }
+
+        let mut warnings = vec![];
+
+        let visible_ranges = match self.visible_lines {
+            VisibleLines::Ranges(ref visible) => Some(visible),
+            #[cfg(feature = "git")]
+            VisibleLines::DiffContext(_) => None,
+        };
+        if let Some(visible) = visible_ranges {
+            for highlighted in self.highlighted_lines.0.ranges() {
+                if !visible
+                    .ranges()
+                    .iter()
+                    .any(|range| range.overlaps(highlighted))
+                {
+                    warnings.push(format!(
+                        "The highlighted lines {} are outside of the printed lines",
+                        highlighted.describe()
+                    ));
+                }
+            }
+        }
+
+        #[cfg(not(feature = "paging"))]
+        {
+            if self.pager.is_some() {
+                warnings.push(
+                    "A pager is configured, but bat was built without support for paging".into(),
+                );
+            }
+        }
+
+        if self.terminal_background.is_some() && self.minimum_contrast.is_none() {
+            warnings.push(
+                "A terminal background is configured, but no minimum contrast to check".into(),
+            );
+        }
+
+        Ok(warnings)
+    }
+
+    /// The settings that were set by a layer (like the config file or an environment
+    /// variable), one per line with their values and where they came from
+    pub fn describe(&self) -> String {
+        self.provenance
+            .iter()
+            .map(|(name, origin)| format!("{} = {} ({})\n", name, origin.value, origin))
+            .collect()
+    }
 }
 
 #[test]
@@ -100,3 +505,45 @@ fn default_config_should_highlight_no_lines() {
         RangeCheckResult::InRange
     );
 }
+
+#[test]
+fn validation_reports_contradictions() {
+    use crate::line_range::LineRange;
+
+    let config = Config {
+        term_width: 80,
+        visible_lines: VisibleLines::Ranges(LineRanges::from(vec![LineRange::new(1, 10)])),
+        highlighted_lines: HighlightedLineRanges(LineRanges::from(vec![
+            LineRange::new(5, 5),
+            LineRange::from("20:").unwrap(),
+        ])),
+        ..Default::default()
+    };
+    assert_eq!(
+        config.validate().unwrap(),
+        vec!["The highlighted lines 20: are outside of the printed lines".to_owned()]
+    );
+
+    let config = Config {
+        term_width: 0,
+        ..Default::default()
+    };
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn minified_detection() {
+    let thresholds = MinifiedThresholds {
+        average_line_length: 20,
+        first_line_length: 50,
+    };
+    let short = b"fn main() {\n    println!();\n}\n";
+
+    assert!(!thresholds.is_minified("main.rs", b"fn main() {\n", short));
+    assert!(thresholds.is_minified("/path/to/jquery.min.js", b"fn main() {\n", short));
+    assert!(thresholds.is_minified("main.js", &[b'x'; 51], &[b'x'; 51]));
+    assert!(thresholds.is_minified("main.js", b"a\n", b"a\n//# sourceMappingURL=main.js.map\n"));
+
+    let long_lines = [&[b'x'; 30][..], b"\n", &[b'x'; 30][..], b"\n"].concat();
+    assert!(thresholds.is_minified("main.js", &long_lines[..31], &long_lines));
+}
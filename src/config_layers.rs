@@ -0,0 +1,169 @@
+//! The layers that settings come from, and which setting was set by which layer (see
+//! `Config::provenance`). Layers with a higher precedence override the lower ones, regardless
+//! of the order in which they are applied:
+//!
+//! defaults < project file (`.bat.toml`) < config file < environment < command line / builder
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// A source of settings, in the order of precedence
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ConfigLayer {
+    Default,
+    ProjectFile,
+    ConfigFile,
+    Environment,
+
+    /// Set explicitly, with a command-line option or a method of the `PrettyPrinter`
+    Explicit,
+}
+
+impl fmt::Display for ConfigLayer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ConfigLayer::Default => "default",
+            ConfigLayer::ProjectFile => "project file",
+            ConfigLayer::ConfigFile => "config file",
+            ConfigLayer::Environment => "environment",
+            ConfigLayer::Explicit => "explicit",
+        })
+    }
+}
+
+/// Where the value of a setting came from
+#[derive(Debug, Clone, PartialEq)]
+pub struct Origin {
+    pub layer: ConfigLayer,
+
+    /// The variable or file of the layer, like `BAT_THEME`
+    pub source: Option<String>,
+
+    /// The value, as it would be written in the layer
+    pub value: String,
+}
+
+impl Origin {
+    pub fn new(layer: ConfigLayer, value: impl fmt::Display) -> Self {
+        Origin {
+            layer,
+            source: None,
+            value: value.to_string(),
+        }
+    }
+
+    pub fn with_source(mut self, source: impl Into<String>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.source {
+            Some(ref source) => write!(f, "{}: {}", self.layer, source),
+            None => write!(f, "{}", self.layer),
+        }
+    }
+}
+
+/// The value of a setting from the layer with the highest precedence. Of the values of the
+/// same layer, the last one wins (like with repeated command-line options).
+#[derive(Debug, Clone)]
+pub struct Layered<T> {
+    value: Option<(T, Origin)>,
+}
+
+impl<T> Default for Layered<T> {
+    fn default() -> Self {
+        Layered { value: None }
+    }
+}
+
+impl<T> Layered<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Offer a value of a layer, which is kept if no layer with a higher precedence has set
+    /// the setting already
+    pub fn set(&mut self, value: T, origin: Origin) -> &mut Self {
+        let overrides = match self.value {
+            Some((_, ref current)) => origin.layer >= current.layer,
+            None => true,
+        };
+        if overrides {
+            self.value = Some((value, origin));
+        }
+        self
+    }
+
+    /// The value with the highest precedence, if any layer has set the setting
+    pub fn resolve(self) -> Option<(T, Origin)> {
+        self.value
+    }
+}
+
+/// The origins of the settings of a `Config`, by the names of the settings (like the
+/// command-line options). Settings that are not listed have their default values.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Provenance {
+    settings: BTreeMap<String, Origin>,
+}
+
+impl Provenance {
+    /// Record the origin of a setting, replacing the previous one
+    pub fn record(&mut self, name: &str, origin: Origin) {
+        self.settings.insert(name.to_owned(), origin);
+    }
+
+    /// The origin of a setting, if it was recorded
+    pub fn origin(&self, name: &str) -> Option<&Origin> {
+        self.settings.get(name)
+    }
+
+    /// Whether a value of the given layer takes precedence over the recorded one
+    pub fn is_overridden_by(&self, name: &str, layer: ConfigLayer) -> bool {
+        match self.origin(name) {
+            Some(origin) => layer >= origin.layer,
+            None => true,
+        }
+    }
+
+    /// The settings with their origins, ordered by name
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Origin)> {
+        self.settings
+            .iter()
+            .map(|(name, origin)| (name.as_str(), origin))
+    }
+}
+
+#[test]
+fn higher_layers_take_precedence() {
+    let mut theme = Layered::new();
+    theme
+        .set("Nord", Origin::new(ConfigLayer::Explicit, "Nord"))
+        .set(
+            "TwoDark",
+            Origin::new(ConfigLayer::Environment, "TwoDark").with_source("BAT_THEME"),
+        )
+        .set("ansi", Origin::new(ConfigLayer::Explicit, "ansi"));
+    let (value, origin) = theme.resolve().unwrap();
+    assert_eq!(value, "ansi");
+    assert_eq!(origin.to_string(), "explicit");
+
+    let mut tabs = Layered::new();
+    tabs.set(8, Origin::new(ConfigLayer::ConfigFile, 8)).set(
+        2,
+        Origin::new(ConfigLayer::Environment, 2).with_source("BAT_TABS"),
+    );
+    let (value, origin) = tabs.resolve().unwrap();
+    assert_eq!(value, 2);
+    assert_eq!(origin.to_string(), "environment: BAT_TABS");
+    assert!(Layered::<usize>::new().resolve().is_none());
+
+    let mut provenance = Provenance::default();
+    provenance.record("theme", Origin::new(ConfigLayer::Explicit, "Nord"));
+    assert!(!provenance.is_overridden_by("theme", ConfigLayer::Environment));
+    assert!(provenance.is_overridden_by("tabs", ConfigLayer::Environment));
+}
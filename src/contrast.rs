@@ -0,0 +1,149 @@
+//! Enforcement of a minimum contrast between the foreground colors of a theme and the
+//! background of the terminal, based on the contrast ratio of the WCAG (which ranges from 1 for
+//! equal colors to 21 for black on white).
+
+use syntect::highlighting::{Color, Theme};
+
+const BLACK: Color = Color {
+    r: 0x00,
+    g: 0x00,
+    b: 0x00,
+    a: 0xff,
+};
+
+const WHITE: Color = Color {
+    r: 0xff,
+    g: 0xff,
+    b: 0xff,
+    a: 0xff,
+};
+
+/// Whether the color is an RGB color (as opposed to a color of the terminal palette, whose
+/// actual color isn't known)
+fn is_rgb(color: Color) -> bool {
+    color.a != 0x00 && color.a != 0x0f
+}
+
+fn relative_luminance(color: Color) -> f64 {
+    let channel = |c: u8| {
+        let c = f64::from(c) / 255.0;
+        if c <= 0.039_28 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    0.2126 * channel(color.r) + 0.7152 * channel(color.g) + 0.0722 * channel(color.b)
+}
+
+/// The contrast ratio of two colors
+pub(crate) fn contrast_ratio(a: Color, b: Color) -> f64 {
+    let (a, b) = (relative_luminance(a), relative_luminance(b));
+    let (lighter, darker) = if a > b { (a, b) } else { (b, a) };
+    (lighter + 0.05) / (darker + 0.05)
+}
+
+fn mix(from: Color, to: Color, amount: f64) -> Color {
+    let mix = |from: u8, to: u8| {
+        (f64::from(from) + (f64::from(to) - f64::from(from)) * amount).round() as u8
+    };
+    Color {
+        r: mix(from.r, to.r),
+        g: mix(from.g, to.g),
+        b: mix(from.b, to.b),
+        a: from.a,
+    }
+}
+
+/// Lighten (or darken, on light backgrounds) the foreground color as little as possible such
+/// that it has (at least) the given contrast ratio against the background. If the ratio can't
+/// be reached at all, the result is white (or black).
+pub(crate) fn with_minimum_contrast(foreground: Color, background: Color, ratio: f64) -> Color {
+    if !is_rgb(foreground) || contrast_ratio(foreground, background) >= ratio {
+        return foreground;
+    }
+
+    let target = if contrast_ratio(WHITE, background) >= contrast_ratio(BLACK, background) {
+        WHITE
+    } else {
+        BLACK
+    };
+
+    // The contrast grows monotonically while mixing in the target color
+    let (mut low, mut high) = (0.0, 1.0);
+    for _ in 0..16 {
+        let amount = (low + high) / 2.0;
+        if contrast_ratio(mix(foreground, target, amount), background) >= ratio {
+            high = amount;
+        } else {
+            low = amount;
+        }
+    }
+    mix(foreground, target, high)
+}
+
+/// Adjust all foreground colors of the theme that have a lower contrast ratio than `ratio`
+/// against the background. The background of the theme is used if no `background` is given.
+/// Themes without an RGB background are returned as they are.
+pub(crate) fn with_theme_contrast(theme: &Theme, background: Option<Color>, ratio: f64) -> Theme {
+    let mut theme = theme.clone();
+    let background = match background.or(theme.settings.background) {
+        Some(background) if is_rgb(background) => background,
+        _ => return theme,
+    };
+
+    let adjust = |color: &mut Option<Color>| {
+        if let Some(ref mut color) = color {
+            *color = with_minimum_contrast(*color, background, ratio);
+        }
+    };
+    adjust(&mut theme.settings.foreground);
+    adjust(&mut theme.settings.gutter_foreground);
+    for item in theme.scopes.iter_mut() {
+        adjust(&mut item.style.foreground);
+    }
+
+    theme
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rgb(r: u8, g: u8, b: u8) -> Color {
+        Color { r, g, b, a: 0xff }
+    }
+
+    #[test]
+    fn contrast_ratios() {
+        assert!((contrast_ratio(BLACK, WHITE) - 21.0).abs() < 0.01);
+        assert!((contrast_ratio(WHITE, WHITE) - 1.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn low_contrast_colors_are_adjusted() {
+        let dark_blue = rgb(0x00, 0x00, 0x80);
+
+        let adjusted = with_minimum_contrast(dark_blue, BLACK, 4.5);
+        assert!(contrast_ratio(adjusted, BLACK) >= 4.5);
+        assert!(contrast_ratio(adjusted, BLACK) < 4.7);
+        assert!(adjusted.b > adjusted.r);
+
+        // Colors with enough contrast (and palette colors) are kept
+        assert_eq!(with_minimum_contrast(dark_blue, WHITE, 4.5), dark_blue);
+        let palette_color = Color {
+            r: 4,
+            g: 0,
+            b: 0,
+            a: 0,
+        };
+        assert_eq!(
+            with_minimum_contrast(palette_color, BLACK, 4.5),
+            palette_color
+        );
+
+        // Dark on light
+        let light_yellow = rgb(0xff, 0xff, 0xa0);
+        assert!(contrast_ratio(with_minimum_contrast(light_yellow, WHITE, 3.0), WHITE) >= 3.0);
+    }
+}
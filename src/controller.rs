@@ -1,27 +1,170 @@
-use std::io::{self, Write};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, VecDeque};
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::panic;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
 
+use ansi_term::Colour::Fixed;
+
+use console::{measure_text_width, Term};
+use content_inspector::ContentType;
+use syntect::highlighting::Theme;
+
+use crate::asciicast::AsciicastRecorder;
 use crate::assets::HighlightingAssets;
-use crate::config::{Config, VisibleLines};
+use crate::card::CardWriter;
+use crate::config::{Config, VisibleLines, DETERMINISTIC_TERM_HEIGHT};
+use crate::contrast;
 #[cfg(feature = "git")]
 use crate::diff::{get_git_diff, LineChanges};
 use crate::error::*;
-use crate::input::{Input, InputReader, OpenedInput};
-#[cfg(feature = "git")]
-use crate::line_range::LineRange;
-use crate::line_range::{LineRanges, RangeCheckResult};
-use crate::output::OutputType;
+use crate::html_report::{self, ReportSection};
+use crate::input::{Input, InputKind, InputMetadata, InputReader, OpenedInput, OpenedInputKind};
+use crate::line_diff::{self, DiffRow};
+use crate::line_range::{LineRange, LineRanges, RangeCheckResult};
+use crate::output::{
+    BufferedOutput, BufferingMode, ConvertedOutput, IndentedOutput, OutputEncoding, OutputType,
+    DEFAULT_BLOCK_SIZE,
+};
+#[cfg(feature = "paging")]
+use crate::output::{PagerHints, QUIT_ALL_EXIT_CODE};
 #[cfg(feature = "paging")]
 use crate::paging::PagingMode;
-use crate::printer::{InteractivePrinter, Printer, SimplePrinter};
+use crate::pipeline::{Decorator, Highlighter, OutputFormat, Renderer};
+use crate::printer::{Colors, InteractivePrinter, Printer, SimplePrinter, DEFAULT_GUTTER_COLOR};
+use crate::split::{self, SplitView};
+#[cfg(feature = "structured-diff")]
+use crate::structured_diff;
+use crate::style::{StyleComponent, StyleComponents};
+use crate::suggestions::closest_matches;
+use crate::symbols::Symbols;
+use crate::theme_fallback::with_fallback_styles;
+use crate::warning::{Warning, WarningKind};
+use crate::wrapping::WrappingMode;
+
+/// How often followed files are checked for appended lines (see `Config::follow`)
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The outcome of printing a single input, as returned by `Controller::run_each`
+#[derive(Debug)]
+pub struct InputResult {
+    /// The name of the input (like in the header)
+    pub name: String,
+
+    /// The name of the syntax that the input was highlighted with
+    pub language: Option<String>,
+
+    /// The number of lines that were printed
+    pub lines: usize,
+
+    /// The error that occurred while printing the input
+    pub error: Option<Error>,
+
+    /// The anomalies that didn't stop the input from being printed
+    pub warnings: Vec<Warning>,
+}
+
+impl InputResult {
+    pub fn success(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// The warnings of both panes of a view, without the ones that both have (like an unknown
+/// theme)
+fn merge_warnings(mut warnings: Vec<Warning>, other: Vec<Warning>) -> Vec<Warning> {
+    for warning in other {
+        if !warnings.contains(&warning) {
+            warnings.push(warning);
+        }
+    }
+    warnings
+}
+
+/// Read all lines of an input
+fn read_lines(input: Input) -> Result<Vec<Vec<u8>>> {
+    let mut opened_input = input.open(io::stdin().lock())?;
+    let mut lines = vec![];
+    let mut line_buffer = vec![];
+    while opened_input.reader.read_line(&mut line_buffer)? {
+        lines.push(std::mem::take(&mut line_buffer));
+    }
+    Ok(lines)
+}
+
+/// The size of an input, as determined by `Controller::measure`
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct InputSize {
+    /// The number of lines in the input
+    pub lines: usize,
+
+    /// The number of terminal rows that the visible lines occupy when they are printed with
+    /// the configured wrapping mode and terminal width (not including the header, grid
+    /// lines, snip markers and section separators)
+    pub display_rows: usize,
+}
 
 pub struct Controller<'a> {
-    config: &'a Config<'a>,
+    config: Cow<'a, Config<'a>>,
     assets: &'a HighlightingAssets,
+
+    /// The width of the terminal without the indentation and the margin (which differs from
+    /// the terminal width of the configuration when the output is put into a card)
+    term_width: usize,
+
+    /// The number of columns in front of the output (see `Config::max_width`)
+    margin: usize,
 }
 
 impl<'b> Controller<'b> {
     pub fn new<'a>(config: &'a Config, assets: &'a HighlightingAssets) -> Controller<'a> {
-        Controller { config, assets }
+        let (term_width, margin) = match config.max_width {
+            Some(max_width) if max_width < config.term_width => (
+                max_width,
+                config.block_alignment.margin(config.term_width, max_width),
+            ),
+            _ => (config.term_width, 0),
+        };
+        let term_width = match config.indent {
+            Some(ref prefix) => term_width.saturating_sub(measure_text_width(prefix)),
+            None => term_width,
+        };
+
+        // The contents of a card are printed as if the terminal was as wide as the card
+        let config = match config.card {
+            Some(card) => Cow::Owned(Config {
+                term_width: card.inner_width(),
+                ..config.clone()
+            }),
+            None if term_width != config.term_width => Cow::Owned(Config {
+                term_width,
+                ..config.clone()
+            }),
+            None => Cow::Borrowed(config),
+        };
+
+        let style_components = config
+            .responsive_layout
+            .as_ref()
+            .map(|layout| layout.apply(&config.style_components, config.term_width));
+        let config = match style_components {
+            Some(style_components) => Cow::Owned(Config {
+                style_components,
+                ..config.into_owned()
+            }),
+            None => config,
+        };
+
+        Controller {
+            config,
+            assets,
+            term_width,
+            margin,
+        }
     }
 
     pub fn run(&self, inputs: Vec<Input>) -> Result<bool> {
@@ -33,16 +176,40 @@ impl<'b> Controller<'b> {
         inputs: Vec<Input>,
         handle_error: impl Fn(&Error, &mut dyn Write),
     ) -> Result<bool> {
-        let mut output_type;
+        let results = self.run_each(inputs, handle_error)?;
+        Ok(results.iter().all(InputResult::success))
+    }
+
+    /// Print the inputs and return the outcome of every single one of them. Errors that
+    /// concern a single input are passed to `handle_error` and returned in its result, only
+    /// errors that stop the whole run (like an invalid configuration) are returned directly.
+    pub fn run_each(
+        &self,
+        inputs: Vec<Input>,
+        handle_error: impl Fn(&Error, &mut dyn Write),
+    ) -> Result<Vec<InputResult>> {
+        self.run_each_with_writer(inputs, None, handle_error)
+    }
+
+    /// Like `run`, but write the output to the given writer instead of the terminal. No pager
+    /// is started. Error messages are printed to STDERR.
+    pub fn run_with_writer(&self, inputs: Vec<Input>, writer: &mut dyn Write) -> Result<bool> {
+        let results = self.run_each_with_writer(inputs, Some(writer), default_error_handler)?;
+        Ok(results.iter().all(InputResult::success))
+    }
+
+    /// The pager or STDOUT
+    #[cfg_attr(not(feature = "paging"), allow(unused_variables))]
+    fn output_type(&self, inputs: &[Input], quit_all_key: bool) -> Result<OutputType> {
+        let output_type;
 
         #[cfg(feature = "paging")]
         {
-            use crate::input::InputKind;
-            use std::path::Path;
-
             // Do not launch the pager if NONE of the input files exist
             let mut paging_mode = self.config.paging_mode;
-            if self.config.paging_mode != PagingMode::Never {
+            if self.config.deterministic {
+                paging_mode = PagingMode::Never;
+            } else if self.config.paging_mode != PagingMode::Never {
                 let call_pager = inputs.iter().any(|ref input| {
                     if let InputKind::OrdinaryFile(ref path) = input.kind {
                         Path::new(path).exists()
@@ -54,7 +221,19 @@ impl<'b> Controller<'b> {
                     paging_mode = PagingMode::Never;
                 }
             }
-            output_type = OutputType::from_mode(paging_mode, self.config.pager)?;
+            // Tell the pager about a content width that doesn't match the terminal (e.g. from
+            // '--terminal-width'), so that its line wrapping agrees with ours
+            let indent_width = self.config.indent.as_deref().map_or(0, measure_text_width);
+            let columns = Some(self.margin + indent_width + self.term_width)
+                .filter(|width| *width != Term::stdout().size().1 as usize);
+            let hints = PagerHints {
+                columns,
+                chop_long_lines: self.config.chop_long_lines,
+                quit_all_key,
+                timeout: self.config.pager_timeout,
+                header_rows: self.sticky_header_rows(inputs),
+            };
+            output_type = OutputType::from_mode(paging_mode, self.config.pager, hints)?;
         }
 
         #[cfg(not(feature = "paging"))]
@@ -62,85 +241,969 @@ impl<'b> Controller<'b> {
             output_type = OutputType::stdout();
         }
 
-        let attached_to_pager = output_type.is_pager();
-        let writer = output_type.handle()?;
-        let mut no_errors: bool = true;
+        Ok(output_type)
+    }
+
+    /// The writer for the output: the given one or the handle of the output type
+    fn handle<'w, 'o: 'w>(
+        writer: Option<&'w mut (dyn Write + 'o)>,
+        output_type: &'w mut Option<OutputType>,
+    ) -> Result<&'w mut dyn Write> {
+        match (writer, output_type) {
+            (Some(writer), _) => Ok(writer),
+            (None, Some(output_type)) => output_type.handle(),
+            (None, None) => unreachable!("an output type is created if there is no writer"),
+        }
+    }
+
+    pub(crate) fn run_each_with_writer(
+        &self,
+        inputs: Vec<Input>,
+        writer: Option<&mut dyn Write>,
+        handle_error: impl Fn(&Error, &mut dyn Write),
+    ) -> Result<Vec<InputResult>> {
+        self.config.validate()?;
+
+        if self.config.sandbox {
+            self.ensure_sandboxed(&inputs)?;
+        }
+
+        if self.config.strict {
+            self.assets.get_theme_strict(&self.config.theme)?;
+            if let Some(language) = self.config.language {
+                self.assets.get_syntax_by_token(language)?;
+            }
+        }
+
+        #[cfg(feature = "paging")]
+        {
+            if writer.is_none() && self.pages_per_input(&inputs) {
+                return self.run_with_pager_per_input(inputs, &handle_error);
+            }
+        }
+
+        let mut output_type = match writer {
+            Some(_) => None,
+            None => Some(self.output_type(&inputs, false)?),
+        };
+        let attached_to_pager = output_type.iter().any(OutputType::is_pager);
+        let handle = Self::handle(writer, &mut output_type)?;
+        let results = self.print_inputs(inputs, handle, attached_to_pager, &handle_error);
+
+        #[cfg(feature = "paging")]
+        {
+            if let Some(ref mut output_type) = output_type {
+                output_type.wait();
+                if let Some(error) = output_type.timeout_error() {
+                    return Err(error);
+                }
+            }
+        }
+        results
+    }
+
+    /// The number of rows of the header of the only input, which the pager keeps at the top
+    /// of the screen (see `Config::sticky_header`)
+    #[cfg(feature = "paging")]
+    fn sticky_header_rows(&self, inputs: &[Input]) -> Option<usize> {
+        let components = &self.config.style_components;
+        if !self.config.sticky_header
+            || inputs.len() != 1
+            || !components.header()
+            || self.config.loop_through
+            || self.config.card.is_some()
+        {
+            return None;
+        }
+
+        // The header line, between two grid lines
+        Some(if components.grid() { 3 } else { 1 })
+    }
+
+    /// Whether every input is shown in a pager of its own (see `Config::pager_per_file`)
+    #[cfg(feature = "paging")]
+    fn pages_per_input(&self, inputs: &[Input]) -> bool {
+        self.config.pager_per_file
+            && inputs.len() > 1
+            && self.config.paging_mode != PagingMode::Never
+            && !self.config.deterministic
+            && self.config.asciicast_file.is_none()
+    }
+
+    /// Start a new pager for every input. Quitting the pager moves on to the next input, the
+    /// `Q` key (in `less`) quits all of them. Inputs that were skipped this way have no result.
+    #[cfg(feature = "paging")]
+    fn run_with_pager_per_input(
+        &self,
+        inputs: Vec<Input>,
+        handle_error: &impl Fn(&Error, &mut dyn Write),
+    ) -> Result<Vec<InputResult>> {
+        let mut results = Vec::with_capacity(inputs.len());
+
+        for input in inputs.into_iter() {
+            let mut output_type = self.output_type(std::slice::from_ref(&input), true)?;
+            let attached_to_pager = output_type.is_pager();
+            let outcome = self.print_inputs(
+                vec![input],
+                output_type.handle()?,
+                attached_to_pager,
+                handle_error,
+            );
+            let exit_code = output_type.wait();
+            if let Some(error) = output_type.timeout_error() {
+                return Err(error);
+            }
+
+            match outcome {
+                Ok(input_results) => results.extend(input_results),
+                // The pager was closed before all of the input was written
+                Err(Error(ErrorKind::Io(ref error), _))
+                    if error.kind() == io::ErrorKind::BrokenPipe => {}
+                Err(error) => return Err(error),
+            }
+            if exit_code == Some(QUIT_ALL_EXIT_CODE) {
+                break;
+            }
+        }
+
+        Ok(results)
+    }
+
+    /// Print the inputs to the given handle (through all of the configured output layers)
+    fn print_inputs(
+        &self,
+        inputs: Vec<Input>,
+        handle: &mut dyn Write,
+        attached_to_pager: bool,
+        handle_error: &impl Fn(&Error, &mut dyn Write),
+    ) -> Result<Vec<InputResult>> {
+        let mut converted_output;
+        let handle: &mut dyn Write = if self.config.line_ending.is_some()
+            || self.config.output_encoding != OutputEncoding::Utf8
+        {
+            converted_output =
+                ConvertedOutput::new(handle, self.config.line_ending, self.config.output_encoding);
+            &mut converted_output
+        } else {
+            handle
+        };
+        let mut recorder;
+        let handle: &mut dyn Write = match self.config.asciicast_file {
+            Some(path) => {
+                let file = File::create(path)
+                    .map_err(|e| format!("'{}': {}", path.to_string_lossy(), e))?;
+                let height = if self.config.deterministic {
+                    DETERMINISTIC_TERM_HEIGHT
+                } else {
+                    Term::stdout().size().0 as usize
+                };
+                recorder = AsciicastRecorder::new(
+                    handle,
+                    BufWriter::new(file),
+                    self.config.term_width,
+                    height,
+                    self.config.deterministic,
+                )?;
+                &mut recorder
+            }
+            None => handle,
+        };
+        let prefix = format!(
+            "{}{}",
+            " ".repeat(self.margin),
+            self.config.indent.as_deref().unwrap_or_default()
+        );
+        let mut indented_output;
+        let handle: &mut dyn Write = if prefix.is_empty() {
+            handle
+        } else {
+            indented_output = IndentedOutput::new(handle, &prefix);
+            &mut indented_output
+        };
+        let mut card_writer = None;
+        let handle: &mut dyn Write = match self.config.card {
+            Some(card) => {
+                card_writer.get_or_insert(CardWriter::new(handle, card, self.term_width)?)
+            }
+            None => handle,
+        };
+        let mut output = BufferedOutput::new(handle);
+        let mut results = Vec::with_capacity(inputs.len());
 
         let stderr = io::stderr();
         let print_error = |error: &Error, write: &mut dyn Write| {
             if attached_to_pager {
                 handle_error(error, write);
             } else {
+                // Keep the order of regular output and error messages
+                let _ = write.flush();
                 handle_error(error, &mut stderr.lock());
             }
         };
 
-        for input in inputs.into_iter() {
-            match input.open(io::stdin().lock()) {
-                Err(error) => {
-                    print_error(&error, writer);
-                    no_errors = false;
+        let mut inputs = inputs.into_iter().peekable();
+        while let Some(mut input) = inputs.next() {
+            if self.prints_concurrently(&input) {
+                let mut files = vec![input];
+                while inputs
+                    .peek()
+                    .map_or(false, |input| self.prints_concurrently(input))
+                {
+                    files.extend(inputs.next());
                 }
-                Ok(mut opened_input) => {
-                    #[cfg(feature = "git")]
-                    let line_changes = if self.config.visible_lines.diff_mode()
-                        || (!self.config.loop_through && self.config.style_components.changes())
-                    {
-                        match opened_input.kind {
-                            crate::input::OpenedInputKind::OrdinaryFile(ref path) => {
-                                let diff = get_git_diff(path);
-
-                                // Skip files without Git modifications
-                                if self.config.visible_lines.diff_mode()
-                                    && diff
-                                        .as_ref()
-                                        .map(|changes| changes.is_empty())
-                                        .unwrap_or(false)
-                                {
-                                    continue;
-                                }
-
-                                diff
-                            }
-                            _ if self.config.visible_lines.diff_mode() => {
-                                // Skip non-file inputs in diff mode
-                                continue;
+                results.extend(self.print_concurrently(
+                    files,
+                    &mut output,
+                    attached_to_pager,
+                    &print_error,
+                )?);
+                continue;
+            }
+
+            if let Some(old) = input.diff_base.take() {
+                #[cfg(feature = "structured-diff")]
+                if input.metadata.structured_diff {
+                    results.push(self.print_structured_diff(
+                        *old,
+                        input,
+                        &mut output,
+                        attached_to_pager,
+                        &print_error,
+                    )?);
+                    continue;
+                }
+
+                results.push(self.print_side_by_side_diff(
+                    *old,
+                    input,
+                    &mut output,
+                    attached_to_pager,
+                    &print_error,
+                )?);
+                continue;
+            }
+
+            results.push(match self.config.split_view {
+                Some(ref split_view) => self.print_split_view(
+                    input,
+                    split_view,
+                    &mut output,
+                    attached_to_pager,
+                    &print_error,
+                )?,
+                None => self.print_input(input, &mut output, attached_to_pager, &print_error)?,
+            });
+        }
+
+        output.flush()?;
+        drop(output);
+        if let Some(ref mut card_writer) = card_writer {
+            card_writer.finish()?;
+        }
+
+        Ok(results)
+    }
+
+    /// Whether the input is printed on a thread of its own (see `Config::parallelism`): only
+    /// files are, which are printed like any other input
+    fn prints_concurrently(&self, input: &Input) -> bool {
+        self.config.parallelism > 1
+            && !self.config.follow
+            && self.config.split_view.is_none()
+            && input.diff_base.is_none()
+            && match input.kind {
+                InputKind::OrdinaryFile(_) => true,
+                _ => false,
+            }
+    }
+
+    /// Print files on `Config::parallelism` threads, which take the files from a shared queue
+    /// and print each of them into a buffer. The buffers are written in the order of the
+    /// files (as soon as all files before have been written), followed by the error message
+    /// of the file (if any).
+    fn print_concurrently(
+        &self,
+        files: Vec<Input>,
+        output: &mut BufferedOutput,
+        attached_to_pager: bool,
+        print_error: &dyn Fn(&Error, &mut dyn Write),
+    ) -> Result<Vec<InputResult>> {
+        let count = files.len();
+        let (queue, jobs) = crossbeam_channel::unbounded();
+        for (index, input) in files.into_iter().enumerate() {
+            // Inputs can not be sent to other threads (they may contain a reader), files are
+            // opened again from their path
+            match input.kind {
+                InputKind::OrdinaryFile(path) => queue.send((index, path, input.metadata)).ok(),
+                _ => unreachable!("only files are printed concurrently"),
+            };
+        }
+        drop(queue);
+
+        crossbeam_utils::thread::scope(|scope| {
+            let (done, printed) = crossbeam_channel::unbounded();
+            for _ in 0..self.config.parallelism.min(count) {
+                let jobs = jobs.clone();
+                let done = done.clone();
+                scope.spawn(move |_| {
+                    for (index, path, metadata) in jobs {
+                        let printed = self.print_into_buffer(path, metadata, attached_to_pager);
+                        // The receiver is gone if a file could not be printed
+                        if done.send((index, printed)).is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+            drop(done);
+
+            // Files that are finished before the files in front of them wait here
+            let mut finished = BTreeMap::new();
+            let mut results = Vec::with_capacity(count);
+            for (index, printed) in printed {
+                finished.insert(index, printed);
+                while let Some(printed) = finished.remove(&results.len()) {
+                    let (result, buffer) = printed?;
+                    output.write_all(&buffer)?;
+                    if let Some(ref error) = result.error {
+                        print_error(error, output);
+                    }
+                    results.push(result);
+                }
+            }
+            Ok(results)
+        })
+        .unwrap_or_else(|panic| panic::resume_unwind(panic))
+    }
+
+    /// Print a file into a buffer, on a thread of its own (see `print_concurrently`)
+    fn print_into_buffer(
+        &self,
+        path: OsString,
+        metadata: InputMetadata,
+        attached_to_pager: bool,
+    ) -> Result<(InputResult, Vec<u8>)> {
+        let input = Input {
+            kind: InputKind::OrdinaryFile(path),
+            metadata,
+            diff_base: None,
+        };
+        let mut buffer = vec![];
+        let mut file_output = BufferedOutput::new(&mut buffer);
+        let result = self.print_input(input, &mut file_output, attached_to_pager, &|_, _| {})?;
+        file_output.flush()?;
+        drop(file_output);
+        Ok((result, buffer))
+    }
+
+    /// Print a single input. Errors are reported with `print_error` (and returned in the
+    /// result).
+    fn print_input(
+        &self,
+        input: Input,
+        output: &mut BufferedOutput,
+        attached_to_pager: bool,
+        print_error: &dyn Fn(&Error, &mut dyn Write),
+    ) -> Result<InputResult> {
+        let mut result = InputResult {
+            name: input.name(),
+            language: None,
+            lines: 0,
+            error: None,
+            warnings: vec![],
+        };
+        let fail = |error: Error, output: &mut BufferedOutput| {
+            print_error(&error, output);
+            Some(error)
+        };
+
+        match input.open(io::stdin().lock()) {
+            Err(error) => result.error = fail(error, output),
+            Ok(mut opened_input) => {
+                output.set_mode(self.buffering_mode(&opened_input))?;
+
+                #[cfg(feature = "git")]
+                let line_changes = if !self.config.deterministic
+                    && (self.config.visible_lines.diff_mode()
+                        || (!self.config.loop_through && self.config.style_components.changes()))
+                {
+                    match opened_input.kind {
+                        OpenedInputKind::OrdinaryFile(ref path) => {
+                            let diff = get_git_diff(path);
+
+                            // Skip files without Git modifications
+                            if self.config.visible_lines.diff_mode()
+                                && diff
+                                    .as_ref()
+                                    .map(|changes| changes.is_empty())
+                                    .unwrap_or(false)
+                            {
+                                return Ok(result);
                             }
-                            _ => None,
+
+                            diff
                         }
-                    } else {
-                        None
-                    };
+                        _ if self.config.visible_lines.diff_mode() => {
+                            // Skip non-file inputs in diff mode
+                            return Ok(result);
+                        }
+                        _ => None,
+                    }
+                } else {
+                    None
+                };
 
-                    let mut printer: Box<dyn Printer> = if self.config.loop_through {
-                        Box::new(SimplePrinter::new())
-                    } else {
-                        Box::new(InteractivePrinter::new(
-                            &self.config,
-                            &self.assets,
-                            &mut opened_input,
-                            #[cfg(feature = "git")]
-                            &line_changes,
-                        ))
+                // The status line would end up as the last line of the pager output
+                // and is therefore only shown when writing to the terminal directly.
+                let show_status_line = !self.config.loop_through
+                    && !attached_to_pager
+                    && self.config.style_components.status_line();
+
+                let theme;
+                let mut printer: Box<dyn Printer> = if self.config.loop_through {
+                    Box::new(SimplePrinter::new())
+                } else {
+                    theme = match self.theme() {
+                        Ok(theme) => theme,
+                        Err(error) => {
+                            result.error = fail(error, output);
+                            return Ok(result);
+                        }
                     };
 
-                    let result = self.print_file(
-                        &mut *printer,
-                        writer,
+                    match InteractivePrinter::new(
+                        &self.config,
+                        &self.assets,
+                        &theme,
                         &mut opened_input,
                         #[cfg(feature = "git")]
                         &line_changes,
-                    );
+                    ) {
+                        Ok(mut printer) => {
+                            printer.show_status_line = show_status_line;
+                            Box::new(printer)
+                        }
+                        Err(error) => {
+                            result.error = fail(error, output);
+                            return Ok(result);
+                        }
+                    }
+                };
+                let language = printer.language().map(String::from);
+                if let Some(warning) = self.theme_warning() {
+                    result.warnings.push(warning);
+                }
+
+                match self.print_file(
+                    &mut *printer,
+                    output,
+                    &mut opened_input,
+                    show_status_line,
+                    #[cfg(feature = "git")]
+                    &line_changes,
+                ) {
+                    Ok(lines) => result.lines = lines,
+                    Err(error) => result.error = fail(error, output),
+                }
+                result.language = language;
+                result.warnings.extend(printer.take_warnings());
+            }
+        }
+
+        Ok(result)
+    }
 
-                    if let Err(error) = result {
-                        print_error(&error, writer);
-                        no_errors = false;
+    /// Print two line ranges of an input side by side (see `Config::split_view`). The panes
+    /// are printed like separate inputs with half of the terminal width.
+    fn print_split_view(
+        &self,
+        mut input: Input,
+        split_view: &SplitView,
+        output: &mut BufferedOutput,
+        attached_to_pager: bool,
+        print_error: &dyn Fn(&Error, &mut dyn Write),
+    ) -> Result<InputResult> {
+        let name = input.name();
+        let right_input = match input.try_clone() {
+            Ok(right_input) => right_input,
+            Err(error) => {
+                print_error(&error, output);
+                return Ok(InputResult {
+                    name,
+                    language: None,
+                    lines: 0,
+                    error: Some(error),
+                    warnings: vec![],
+                });
+            }
+        };
+
+        let pane_width = split::pane_width(self.config.term_width);
+        let print_pane = |input: Input, range: &LineRange| -> Result<(InputResult, String)> {
+            let pane = Controller {
+                config: Cow::Owned(Config {
+                    visible_lines: VisibleLines::Ranges(LineRanges::from(vec![range.clone()])),
+                    term_width: pane_width,
+                    split_view: None,
+                    ..self.config.as_ref().clone()
+                }),
+                assets: self.assets,
+                term_width: pane_width,
+                margin: 0,
+            };
+
+            let mut buffer = vec![];
+            let mut pane_output = BufferedOutput::new(&mut buffer);
+            let result =
+                pane.print_input(input, &mut pane_output, attached_to_pager, print_error)?;
+            pane_output.flush()?;
+            drop(pane_output);
+            Ok((result, String::from_utf8_lossy(&buffer).into_owned()))
+        };
+        let (left_result, left) = print_pane(input, &split_view.left)?;
+        let (right_result, right) = print_pane(right_input, &split_view.right)?;
+
+        let separator = if self.config.colored_output {
+            Fixed(DEFAULT_GUTTER_COLOR)
+                .paint(split::SEPARATOR)
+                .to_string()
+        } else {
+            split::SEPARATOR.to_owned()
+        };
+        let lines = split::side_by_side(&left, &right, pane_width, &separator);
+        output.write_all(lines.as_bytes())?;
+
+        let warnings = merge_warnings(left_result.warnings, right_result.warnings);
+        Ok(InputResult {
+            lines: left_result.lines + right_result.lines,
+            error: left_result.error.or(right_result.error),
+            warnings,
+            ..left_result
+        })
+    }
+
+    /// Print the changes between the parsed data of two JSON or YAML inputs, highlighted as a
+    /// diff
+    #[cfg(feature = "structured-diff")]
+    fn print_structured_diff(
+        &self,
+        old: Input,
+        new: Input,
+        output: &mut BufferedOutput,
+        attached_to_pager: bool,
+        print_error: &dyn Fn(&Error, &mut dyn Write),
+    ) -> Result<InputResult> {
+        let (old_name, name) = (old.name(), new.name());
+        let parse = |input: Input, name: &str| {
+            read_lines(input).and_then(|lines| structured_diff::parse(name, &lines.concat()))
+        };
+        let changes = parse(old, &old_name)
+            .and_then(|old| Ok(structured_diff::diff(&old, &parse(new, &name)?)));
+        let changes = match changes {
+            Ok(changes) => changes,
+            Err(error) => {
+                print_error(&error, output);
+                return Ok(InputResult {
+                    name,
+                    language: None,
+                    lines: 0,
+                    error: Some(error),
+                    warnings: vec![],
+                });
+            }
+        };
+
+        let diff = Controller {
+            config: Cow::Owned(Config {
+                language: Some("Diff"),
+                ..self.config.as_ref().clone()
+            }),
+            assets: self.assets,
+            term_width: self.term_width,
+            margin: self.margin,
+        };
+        let rendered = structured_diff::render(&changes);
+        let input =
+            Input::from_reader(Box::new(io::Cursor::new(rendered))).with_name(Some(name.as_ref()));
+        diff.print_input(input, output, attached_to_pager, print_error)
+    }
+
+    /// Print the old and the new version of an input next to each other, with the lines
+    /// aligned by a line diff and markers for removed (`-`), added (`+`) and changed (`~`)
+    /// lines. Both versions are highlighted in full, long lines are cut off.
+    fn print_side_by_side_diff(
+        &self,
+        mut old: Input,
+        mut new: Input,
+        output: &mut BufferedOutput,
+        attached_to_pager: bool,
+        print_error: &dyn Fn(&Error, &mut dyn Write),
+    ) -> Result<InputResult> {
+        let name = new.name();
+        let contents = old
+            .try_clone()
+            .and_then(read_lines)
+            .and_then(|old_lines| Ok((old_lines, read_lines(new.try_clone()?)?)));
+        let (old_lines, new_lines) = match contents {
+            Ok(contents) => contents,
+            Err(error) => {
+                print_error(&error, output);
+                return Ok(InputResult {
+                    name,
+                    language: None,
+                    lines: 0,
+                    error: Some(error),
+                    warnings: vec![],
+                });
+            }
+        };
+
+        // Each pane has a column for the markers
+        let pane_width = split::pane_width(self.config.term_width);
+        let content_width = pane_width.saturating_sub(2);
+        let style_components = if self.config.style_components.numbers() {
+            StyleComponents::new(&[StyleComponent::LineNumbers])
+        } else {
+            StyleComponents::new(&[])
+        };
+        let print_pane = |input: Input| -> Result<(InputResult, Vec<String>)> {
+            let pane = Controller {
+                config: Cow::Owned(Config {
+                    visible_lines: VisibleLines::default(),
+                    style_components: style_components.clone(),
+                    wrapping_mode: WrappingMode::Chop,
+                    term_width: content_width,
+                    split_view: None,
+                    preview_sample: None,
+                    ..self.config.as_ref().clone()
+                }),
+                assets: self.assets,
+                term_width: content_width,
+                margin: 0,
+            };
+
+            let mut buffer = vec![];
+            let mut pane_output = BufferedOutput::new(&mut buffer);
+            let result =
+                pane.print_input(input, &mut pane_output, attached_to_pager, print_error)?;
+            pane_output.flush()?;
+            drop(pane_output);
+            let rows = String::from_utf8_lossy(&buffer)
+                .lines()
+                .map(String::from)
+                .collect();
+            Ok((result, rows))
+        };
+        let (old_result, old_rows) = print_pane(old)?;
+        let (new_result, new_rows) = print_pane(new)?;
+
+        let theme = self.theme()?;
+        let colors = if self.config.colored_output {
+            Colors::colored(
+                &theme,
+                self.config.true_color,
+                self.config.accessible_markers.as_ref(),
+            )
+        } else {
+            Colors::plain()
+        };
+        let row = |rows: &[String], index: usize, marker: &str, style: ansi_term::Style| {
+            let content = rows.get(index).map_or("", String::as_str);
+            format!("{} {}\n", style.paint(marker), content)
+        };
+
+        let mut left = String::new();
+        let mut right = String::new();
+        if self.config.style_components.header() {
+            left.push_str(&format!("{}\n", colors.filename.paint(old_result.name)));
+            right.push_str(&format!(
+                "{}\n",
+                colors.filename.paint(new_result.name.clone())
+            ));
+        }
+        for diff_row in line_diff::diff_rows(&old_lines, &new_lines) {
+            let (old_row, new_row) = match diff_row {
+                DiffRow::Unchanged(o, n) => (
+                    row(&old_rows, o, " ", colors.grid),
+                    row(&new_rows, n, " ", colors.grid),
+                ),
+                DiffRow::Removed(o) => (row(&old_rows, o, "-", colors.git_removed), "\n".into()),
+                DiffRow::Added(n) => ("\n".into(), row(&new_rows, n, "+", colors.git_added)),
+                DiffRow::Changed(o, n) => (
+                    row(&old_rows, o, "~", colors.git_modified),
+                    row(&new_rows, n, "~", colors.git_modified),
+                ),
+            };
+            left.push_str(&old_row);
+            right.push_str(&new_row);
+        }
+
+        let separator = colors.grid.paint(split::SEPARATOR).to_string();
+        let lines = split::side_by_side(&left, &right, pane_width, &separator);
+        output.write_all(lines.as_bytes())?;
+
+        let warnings = merge_warnings(old_result.warnings, new_result.warnings);
+        Ok(InputResult {
+            lines: new_lines.len(),
+            error: old_result.error.or(new_result.error),
+            warnings,
+            ..new_result
+        })
+    }
+
+    /// The configured theme, with synthesized styles for scopes that it does not style (unless
+    /// disabled) and foreground colors adjusted to the minimum contrast (if configured)
+    fn theme(&self) -> Result<Cow<'b, Theme>> {
+        let theme = if self.config.strict {
+            self.assets.get_theme_strict(&self.config.theme)?
+        } else {
+            self.assets.get_theme(&self.config.theme)
+        };
+
+        let theme = if self.config.theme_fallback {
+            with_fallback_styles(theme)
+        } else {
+            Cow::Borrowed(theme)
+        };
+
+        Ok(match self.config.minimum_contrast {
+            Some(ratio) => Cow::Owned(contrast::with_theme_contrast(
+                &theme,
+                self.config.terminal_background,
+                ratio,
+            )),
+            None => theme,
+        })
+    }
+
+    /// A warning if the configured theme is unknown and the default theme is used instead
+    fn theme_warning(&self) -> Option<Warning> {
+        let theme = &self.config.theme;
+        if self.config.loop_through
+            || theme.is_empty()
+            || self.assets.get_theme_strict(theme).is_ok()
+        {
+            return None;
+        }
+        Some(Warning::new(WarningKind::ThemeFallback(theme.clone())))
+    }
+
+    /// Fail if the configuration requires a feature that is not available in sandbox mode
+    fn ensure_sandboxed(&self, inputs: &[Input]) -> Result<()> {
+        let violation = |feature: &str| Err(ErrorKind::SandboxViolation(feature.into()).into());
+
+        #[cfg(feature = "paging")]
+        {
+            if self.config.paging_mode != PagingMode::Never {
+                return violation("Paging");
+            }
+        }
+
+        if self.config.asciicast_file.is_some() {
+            return violation("Recording an asciicast");
+        }
+
+        if inputs.iter().any(Input::is_url) {
+            return violation("Downloading inputs from URLs");
+        }
+
+        #[cfg(feature = "git")]
+        {
+            if self.config.visible_lines.diff_mode()
+                || (!self.config.loop_through && self.config.style_components.changes())
+            {
+                return violation("The Git integration");
+            }
+        }
+
+        Ok(())
+    }
+
+    fn buffering_mode(&self, input: &OpenedInput) -> BufferingMode {
+        match self.config.buffering_mode {
+            BufferingMode::Auto => match input.kind {
+                OpenedInputKind::OrdinaryFile(_) | OpenedInputKind::ThemePreviewFile => {
+                    BufferingMode::Block(DEFAULT_BLOCK_SIZE)
+                }
+                OpenedInputKind::StdIn | OpenedInputKind::CustomReader => BufferingMode::Line,
+            },
+            mode => mode,
+        }
+    }
+
+    /// Determine the size of an input without printing (or highlighting) it
+    pub fn measure(&self, input: Input) -> Result<InputSize> {
+        if self.config.sandbox {
+            self.ensure_sandboxed(std::slice::from_ref(&input))?;
+        }
+
+        let mut opened_input = input.open(io::stdin().lock())?;
+
+        #[cfg(feature = "git")]
+        let line_changes = match opened_input.kind {
+            OpenedInputKind::OrdinaryFile(ref path)
+                if self.config.visible_lines.diff_mode() && !self.config.deterministic =>
+            {
+                get_git_diff(path)
+            }
+            _ => None,
+        };
+
+        let theme = self.theme()?;
+        let printer = InteractivePrinter::new(
+            &self.config,
+            self.assets,
+            &theme,
+            &mut opened_input,
+            #[cfg(feature = "git")]
+            &line_changes,
+        )?;
+        let mut line_ranges = match self.config.focus_symbol {
+            Some(ref name) => self.focus_ranges(&mut opened_input, name)?,
+            None => self.line_ranges(
+                #[cfg(feature = "git")]
+                &line_changes,
+            ),
+        };
+        if line_ranges.is_relative() {
+            line_ranges = line_ranges.resolve(opened_input.count_lines()?);
+        }
+
+        let mut size = InputSize::default();
+        let mut line_buffer = Vec::new();
+        while opened_input.reader.read_line(&mut line_buffer)? {
+            size.lines += 1;
+            if line_ranges.check(size.lines) == RangeCheckResult::InRange {
+                size.display_rows += printer.display_rows(&line_buffer)?;
+            }
+            line_buffer.clear();
+        }
+
+        Ok(size)
+    }
+
+    /// Write the inputs as a self-contained HTML page (see `PrettyPrinter::print_html_report`)
+    /// instead of printing them. The inputs are highlighted completely, with line numbers (if
+    /// enabled) and the highlighted lines of the config. Errors that concern a single input are
+    /// printed to STDERR and the input is left out. Returns whether all inputs are included.
+    pub fn write_html_report(
+        &self,
+        inputs: Vec<Input>,
+        title: &str,
+        writer: &mut dyn Write,
+    ) -> Result<bool> {
+        if self.config.sandbox {
+            self.ensure_sandboxed(&inputs)?;
+        }
+
+        let theme = self.theme()?;
+        let mut sections = vec![];
+        let mut success = true;
+        for input in inputs {
+            match self.report_section(input, &theme, sections.len()) {
+                Ok(section) => sections.push(section),
+                Err(error) => {
+                    default_error_handler(&error, &mut io::stderr().lock());
+                    success = false;
+                }
+            }
+        }
+
+        html_report::write_report(writer, title, &theme, self.config.tab_width, &sections)?;
+        Ok(success)
+    }
+
+    /// The section of the HTML report with the given index for an input
+    fn report_section(&self, input: Input, theme: &Theme, index: usize) -> Result<ReportSection> {
+        let mut opened_input = input.open(io::stdin().lock())?;
+        let syntax = self.assets.get_syntax(
+            self.config.language,
+            &mut opened_input,
+            &self.config.syntax_mapping,
+        )?;
+        let binary = opened_input.reader.content_type == Some(ContentType::BINARY);
+
+        let mut lines = vec![];
+        let mut line_buffer = Vec::new();
+        while opened_input.reader.read_line(&mut line_buffer)? {
+            lines.push(String::from_utf8_lossy(&line_buffer).into_owned());
+            line_buffer.clear();
+        }
+
+        let html = if binary {
+            None
+        } else {
+            let mut highlighter =
+                Highlighter::with_syntax(self.assets.get_syntax_set(), syntax, theme);
+            let mut decorator = Decorator::new(&self.config.style_components);
+            decorator.max_line_number(lines.len());
+            let mut renderer = Renderer::new(OutputFormat::Html);
+            renderer
+                .line_anchors(Some(&format!("{}-L", html_report::section_id(index))))
+                .highlighted_lines(self.config.highlighted_lines.clone());
+
+            let mut html = vec![];
+            for (index, line) in lines.iter().enumerate() {
+                let spans = highlighter.highlight_line(line);
+                let gutter = decorator.gutter(index + 1, false);
+                renderer.write_numbered_line(&mut html, index + 1, &gutter, &spans)?;
+            }
+            renderer.finish(&mut html)?;
+            Some(String::from_utf8_lossy(&html).into_owned())
+        };
+
+        Ok(ReportSection {
+            name: opened_input.description().name,
+            language: syntax.name.clone(),
+            lines: lines.len(),
+            html,
+        })
+    }
+
+    fn line_ranges(
+        &self,
+        #[cfg(feature = "git")] line_changes: &Option<LineChanges>,
+    ) -> LineRanges {
+        match self.config.visible_lines {
+            VisibleLines::Ranges(ref line_ranges) => line_ranges.clone(),
+            #[cfg(feature = "git")]
+            VisibleLines::DiffContext(context) => {
+                let mut line_ranges: Vec<LineRange> = vec![];
+
+                if let Some(line_changes) = line_changes {
+                    for line in line_changes.keys() {
+                        let line = *line as usize;
+                        line_ranges
+                            .push(LineRange::new(line.saturating_sub(context), line + context));
                     }
                 }
+
+                LineRanges::from(line_ranges)
             }
         }
+    }
+
+    /// The lines of the definition of `Config::focus_symbol` in the input
+    fn focus_ranges(&self, input: &mut OpenedInput, name: &str) -> Result<LineRanges> {
+        let content = input.peek_content()?;
+        let text = String::from_utf8_lossy(&content);
+        let syntax =
+            self.assets
+                .get_syntax(self.config.language, input, &self.config.syntax_mapping)?;
+        let symbols = Symbols::parse(self.assets.get_syntax_set(), syntax, &text);
 
-        Ok(no_errors)
+        match symbols.definition(name) {
+            Some((first, last)) => Ok(LineRanges::from(vec![LineRange::new(first, last)])),
+            None => {
+                let suggestions = closest_matches(name, symbols.names());
+                Err(ErrorKind::UnknownSymbol(
+                    name.to_owned(),
+                    input.description().full,
+                    suggestions.into_iter().map(String::from).collect(),
+                )
+                .into())
+            }
+        }
     }
 
     fn print_file<'a>(
@@ -148,35 +1211,121 @@ impl<'b> Controller<'b> {
         printer: &mut dyn Printer,
         writer: &mut dyn Write,
         input: &mut OpenedInput,
+        count_all_lines: bool,
         #[cfg(feature = "git")] line_changes: &Option<LineChanges>,
-    ) -> Result<()> {
-        if !input.reader.first_line.is_empty() || self.config.style_components.header() {
+    ) -> Result<usize> {
+        // Empty files are followed as well, since lines may still be appended to them
+        let follow = match input.kind {
+            OpenedInputKind::OrdinaryFile(ref path)
+                if self.config.follow && input.reader.has_newline_terminated_lines() =>
+            {
+                Some(PathBuf::from(path))
+            }
+            _ => None,
+        };
+        let has_content = !input.reader.first_line.is_empty() || follow.is_some();
+        let focus = match self.config.focus_symbol {
+            Some(ref name) if has_content => Some(self.focus_ranges(input, name)?),
+            _ => None,
+        };
+
+        if has_content || self.config.style_components.header() {
             printer.print_header(writer, input)?;
         }
 
-        if !input.reader.first_line.is_empty() {
-            let line_ranges = match self.config.visible_lines {
-                VisibleLines::Ranges(ref line_ranges) => line_ranges.clone(),
-                #[cfg(feature = "git")]
-                VisibleLines::DiffContext(context) => {
-                    let mut line_ranges: Vec<LineRange> = vec![];
-
-                    if let Some(line_changes) = line_changes {
-                        for line in line_changes.keys() {
-                            let line = *line as usize;
-                            line_ranges.push(LineRange::new(line - context, line + context));
-                        }
-                    }
+        let mut lines = 0;
+        if has_content {
+            let mut line_ranges = match focus {
+                Some(line_ranges) => line_ranges,
+                None => self.line_ranges(
+                    #[cfg(feature = "git")]
+                    line_changes,
+                ),
+            };
+            if line_ranges.is_relative() {
+                line_ranges = line_ranges.resolve(input.count_lines()?);
+            }
 
-                    LineRanges::from(line_ranges)
+            lines = match self.preview_sample(input, &line_ranges) {
+                Some(sample_lines) if follow.is_none() => {
+                    self.print_file_sample(printer, writer, &mut input.reader, sample_lines)?
                 }
+                _ => self.print_file_ranges(
+                    printer,
+                    writer,
+                    &mut input.reader,
+                    &line_ranges,
+                    count_all_lines,
+                    follow.as_ref().map(PathBuf::as_path),
+                )?,
             };
-
-            self.print_file_ranges(printer, writer, &mut input.reader, &line_ranges)?;
         }
         printer.print_footer(writer, input)?;
 
-        Ok(())
+        Ok(lines)
+    }
+
+    /// The number of lines at the beginning and at the end of a sampled file (see
+    /// `Config::preview_sample`), if the input is sampled
+    fn preview_sample(&self, input: &OpenedInput, line_ranges: &LineRanges) -> Option<usize> {
+        let sample = self.config.preview_sample?;
+        if sample.lines == 0 || self.config.loop_through || !line_ranges.is_all() {
+            return None;
+        }
+
+        match input.kind {
+            OpenedInputKind::OrdinaryFile(ref path) => fs::metadata(path)
+                .ok()
+                .filter(|metadata| metadata.len() >= sample.threshold)
+                .map(|_| sample.lines),
+            _ => None,
+        }
+    }
+
+    /// Print the first and the last lines of the input, with a snip marker in between. The
+    /// lines in between are not highlighted.
+    fn print_file_sample(
+        &self,
+        printer: &mut dyn Printer,
+        writer: &mut dyn Write,
+        reader: &mut InputReader,
+        lines: usize,
+    ) -> Result<usize> {
+        let mut line_buffer = Vec::new();
+        let mut line_number: usize = 1;
+
+        while line_number <= lines && reader.read_line(&mut line_buffer)? {
+            printer.print_line(false, writer, line_number, &line_buffer)?;
+            line_number += 1;
+            line_buffer.clear();
+        }
+
+        let head = line_number - 1;
+        let mut total_lines = head;
+
+        // Only keep the last lines (and reuse their buffers)
+        let mut tail: VecDeque<Vec<u8>> = VecDeque::with_capacity(lines);
+        while reader.read_line(&mut line_buffer)? {
+            total_lines += 1;
+            let mut recycled = if tail.len() == lines {
+                tail.pop_front().unwrap_or_default()
+            } else {
+                Vec::new()
+            };
+            recycled.clear();
+            tail.push_back(std::mem::replace(&mut line_buffer, recycled));
+        }
+
+        let omitted = total_lines - head - tail.len();
+        if omitted > 0 {
+            printer.print_omitted(writer, omitted)?;
+        }
+        let first_tail_line = total_lines - tail.len() + 1;
+        for (index, line) in tail.iter().enumerate() {
+            printer.print_line(false, writer, first_tail_line + index, line)?;
+        }
+
+        Ok(head + tail.len())
     }
 
     fn print_file_ranges(
@@ -185,14 +1334,29 @@ impl<'b> Controller<'b> {
         writer: &mut dyn Write,
         reader: &mut InputReader,
         line_ranges: &LineRanges,
-    ) -> Result<()> {
+        count_all_lines: bool,
+        follow: Option<&Path>,
+    ) -> Result<usize> {
+        let mut printed_lines = 0;
         let mut line_buffer = Vec::new();
         let mut line_number: usize = 1;
 
         let mut first_range: bool = true;
         let mut mid_range: bool = false;
+        let mut blank_lines: usize = 0;
+
+        while Self::read_line(reader, writer, &mut line_buffer, follow)? {
+            let squeezed = match self.config.squeeze_blank_lines {
+                Some(max) if line_buffer == b"\n" || line_buffer == b"\r\n" => {
+                    blank_lines += 1;
+                    blank_lines > max
+                }
+                _ => {
+                    blank_lines = 0;
+                    false
+                }
+            };
 
-        while reader.read_line(&mut line_buffer)? {
             match line_ranges.check(line_number) {
                 RangeCheckResult::BeforeOrBetweenRanges => {
                     // Call the printer in case we need to call the syntax highlighter
@@ -201,6 +1365,11 @@ impl<'b> Controller<'b> {
                     mid_range = false;
                 }
 
+                RangeCheckResult::InRange if squeezed => {
+                    // Highlight the line, but don't print it
+                    printer.print_line(true, writer, line_number, &line_buffer)?;
+                }
+
                 RangeCheckResult::InRange => {
                     if self.config.style_components.snip() {
                         if first_range {
@@ -213,6 +1382,11 @@ impl<'b> Controller<'b> {
                     }
 
                     printer.print_line(false, writer, line_number, &line_buffer)?;
+                    printed_lines += 1;
+                }
+                RangeCheckResult::AfterLastRange if count_all_lines => {
+                    // Keep feeding the printer so that it knows the total number of lines.
+                    printer.print_line(true, writer, line_number, &line_buffer)?;
                 }
                 RangeCheckResult::AfterLastRange => {
                     break;
@@ -222,6 +1396,35 @@ impl<'b> Controller<'b> {
             line_number += 1;
             line_buffer.clear();
         }
-        Ok(())
+        Ok(printed_lines)
+    }
+
+    /// Read the next line of the input. When following the file at the given path (see
+    /// `Config::follow`), the end of the input is only reached when the file is removed: until
+    /// then, the output is flushed and the input is polled until a complete line has been
+    /// appended.
+    fn read_line(
+        reader: &mut InputReader,
+        writer: &mut dyn Write,
+        line_buffer: &mut Vec<u8>,
+        follow: Option<&Path>,
+    ) -> Result<bool> {
+        loop {
+            let read = reader.read_line(line_buffer)?;
+            let path = match follow {
+                Some(path) => path,
+                None => return Ok(read),
+            };
+            if line_buffer.ends_with(b"\n") {
+                return Ok(true);
+            }
+            if !path.exists() {
+                return Ok(!line_buffer.is_empty());
+            }
+
+            // Keep the partial line in the buffer, the rest of it is appended to it
+            writer.flush()?;
+            thread::sleep(FOLLOW_INTERVAL);
+        }
     }
 }
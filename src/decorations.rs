@@ -1,7 +1,10 @@
+use crate::accessibility::HIGHLIGHTED_LINE_MARKER;
 #[cfg(feature = "git")]
 use crate::diff::LineChange;
 use crate::printer::{Colors, InteractivePrinter};
+use crate::template::Template;
 use ansi_term::Style;
+use console::measure_text_width;
 
 #[derive(Debug, Clone)]
 pub(crate) struct DecorationText {
@@ -21,18 +24,36 @@ pub(crate) trait Decoration {
 
 pub(crate) struct LineNumberDecoration {
     color: Style,
+    template: Option<Template>,
     cached_wrap: DecorationText,
-    cached_wrap_invalid_at: usize,
+
+    /// The line number from which on line numbers are wider than the gutter (`None` if the
+    /// gutter shows other values, see `GutterSource`)
+    cached_wrap_invalid_at: Option<usize>,
 }
 
 impl LineNumberDecoration {
-    pub(crate) fn new(colors: &Colors) -> Self {
+    /// A gutter for line numbers, or for the values of the printer with the given width (see
+    /// `InteractivePrinter::gutter_value`)
+    pub(crate) fn new(
+        colors: &Colors,
+        template: Option<&Template>,
+        value_width: Option<usize>,
+    ) -> Self {
+        let number_width = value_width.unwrap_or(4);
+        let width = template.map_or(number_width, |template| {
+            template.line_number_width(number_width)
+        });
         LineNumberDecoration {
             color: colors.line_number,
-            cached_wrap_invalid_at: 10000,
+            template: template.cloned(),
+            cached_wrap_invalid_at: match value_width {
+                None => Some(10000),
+                Some(_) => None,
+            },
             cached_wrap: DecorationText {
-                text: colors.line_number.paint(" ".repeat(4)).to_string(),
-                width: 4,
+                text: colors.line_number.paint(" ".repeat(width)).to_string(),
+                width,
             },
         }
     }
@@ -43,10 +64,14 @@ impl Decoration for LineNumberDecoration {
         &self,
         line_number: usize,
         continuation: bool,
-        _printer: &InteractivePrinter,
+        printer: &InteractivePrinter,
     ) -> DecorationText {
         if continuation {
-            if line_number > self.cached_wrap_invalid_at {
+            if self
+                .cached_wrap_invalid_at
+                .iter()
+                .any(|invalid_at| line_number > *invalid_at)
+            {
                 let new_width = self.cached_wrap.width + 1;
                 return DecorationText {
                     text: self.color.paint(" ".repeat(new_width)).to_string(),
@@ -56,16 +81,28 @@ impl Decoration for LineNumberDecoration {
 
             self.cached_wrap.clone()
         } else {
-            let plain: String = format!("{:4}", line_number);
-            DecorationText {
-                width: plain.len(),
-                text: self.color.paint(plain).to_string(),
+            let plain: String = match printer.gutter_value {
+                Some(ref value) => value.clone(),
+                None => format!("{:4}", line_number),
+            };
+            match self.template {
+                Some(ref template) => {
+                    let text = template.render(|_| plain.clone());
+                    DecorationText {
+                        width: measure_text_width(&text),
+                        text: self.color.paint(text).to_string(),
+                    }
+                }
+                None => DecorationText {
+                    width: measure_text_width(&plain),
+                    text: self.color.paint(plain).to_string(),
+                },
             }
         }
     }
 
     fn width(&self) -> usize {
-        4
+        self.cached_wrap.width
     }
 }
 
@@ -129,6 +166,7 @@ impl Decoration for LineChangesDecoration {
 
 pub(crate) struct GridBorderDecoration {
     cached: DecorationText,
+    cached_highlighted: DecorationText,
 }
 
 impl GridBorderDecoration {
@@ -138,6 +176,10 @@ impl GridBorderDecoration {
                 text: colors.grid.paint("│").to_string(),
                 width: 1,
             },
+            cached_highlighted: DecorationText {
+                text: colors.grid.paint(HIGHLIGHTED_LINE_MARKER).to_string(),
+                width: 1,
+            },
         }
     }
 }
@@ -145,11 +187,15 @@ impl GridBorderDecoration {
 impl Decoration for GridBorderDecoration {
     fn generate(
         &self,
-        _line_number: usize,
-        _continuation: bool,
-        _printer: &InteractivePrinter,
+        line_number: usize,
+        continuation: bool,
+        printer: &InteractivePrinter,
     ) -> DecorationText {
-        self.cached.clone()
+        if !continuation && printer.marks_highlighted_line(line_number) {
+            self.cached_highlighted.clone()
+        } else {
+            self.cached.clone()
+        }
     }
 
     fn width(&self) -> usize {
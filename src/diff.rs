@@ -5,7 +5,7 @@ use std::ffi::OsStr;
 use std::fs;
 use std::path::Path;
 
-use git2::{DiffOptions, IntoCString, Repository};
+use git2::{AttrCheckFlags, DiffOptions, IntoCString, Repository};
 
 #[derive(Copy, Clone, Debug)]
 pub enum LineChange {
@@ -82,3 +82,27 @@ pub fn get_git_diff(filename: &OsStr) -> Option<LineChanges> {
 
     Some(line_changes)
 }
+
+/// The language of a file from the `linguist-language` attribute of the `.gitattributes`
+/// files of its repository (like `*.inc linguist-language=PHP`)
+pub fn get_linguist_language(filename: &Path) -> Option<String> {
+    let repo = Repository::discover(filename).ok()?;
+
+    let repo_path_absolute = fs::canonicalize(repo.workdir()?).ok()?;
+    let filepath_absolute = fs::canonicalize(filename).ok()?;
+    let filepath_relative_to_repo = filepath_absolute.strip_prefix(&repo_path_absolute).ok()?;
+
+    let language = repo
+        .get_attr(
+            filepath_relative_to_repo,
+            "linguist-language",
+            AttrCheckFlags::default(),
+        )
+        .ok()??;
+    // libgit2 represents attributes that are set without a value (or unset) by markers
+    if language.starts_with("[internal]__") {
+        None
+    } else {
+        Some(language.to_owned())
+    }
+}
@@ -0,0 +1,119 @@
+//! User-configured regions whose content is highlighted with a different syntax, like SQL
+//! queries in `sql!(...)` macros or in `<<SQL` heredocs.
+
+#[derive(Debug, Clone, PartialEq)]
+enum Delimiters {
+    Between { start: String, end: String },
+    Heredoc { marker: String },
+}
+
+/// A rule that describes where a language is embedded in other files (see
+/// `PrettyPrinter::embedded_language`). The content of such regions is highlighted with the
+/// syntax of the embedded language, everything else (including the delimiters) with the
+/// syntax of the file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmbeddedLanguage {
+    delimiters: Delimiters,
+    language: String,
+}
+
+impl EmbeddedLanguage {
+    /// Highlight the text between `start` and the next occurrence of `end` (which can be on
+    /// a later line) as `language`. For example, `between("sql!(", ")", "sql")`.
+    pub fn between(
+        start: impl Into<String>,
+        end: impl Into<String>,
+        language: impl Into<String>,
+    ) -> Self {
+        EmbeddedLanguage {
+            delimiters: Delimiters::Between {
+                start: start.into(),
+                end: end.into(),
+            },
+            language: language.into(),
+        }
+    }
+
+    /// Highlight the content of heredocs with the given marker as `language`. For example,
+    /// `heredoc("SQL", "sql")` applies to `<<SQL`, `<<-SQL`, `<<~SQL` and `<<'SQL'`.
+    pub fn heredoc(marker: impl Into<String>, language: impl Into<String>) -> Self {
+        EmbeddedLanguage {
+            delimiters: Delimiters::Heredoc {
+                marker: marker.into(),
+            },
+            language: language.into(),
+        }
+    }
+
+    /// The name or file extension of the embedded language
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    /// Find the beginning of a region in `text`. Returns the position of the start delimiter
+    /// and the position where the content starts.
+    pub(crate) fn find_start(&self, text: &str) -> Option<(usize, usize)> {
+        match self.delimiters {
+            Delimiters::Between { ref start, .. } if !start.is_empty() => text
+                .find(start.as_str())
+                .map(|position| (position, position + start.len())),
+            Delimiters::Between { .. } => None,
+            Delimiters::Heredoc { ref marker } => {
+                let mut search_from = 0;
+                while let Some(position) = text[search_from..].find("<<") {
+                    let position = search_from + position;
+                    search_from = position + 2;
+
+                    let rest = text[search_from..].trim_start_matches(&['-', '~'][..]);
+                    let rest = rest.trim_start_matches(&['\'', '"'][..]);
+                    let is_marker = rest.starts_with(marker.as_str())
+                        && !rest[marker.len()..]
+                            .chars()
+                            .next()
+                            .map_or(false, |c| c.is_alphanumeric() || c == '_');
+                    if is_marker {
+                        // The content starts on the next line
+                        return Some((position, text.len()));
+                    }
+                }
+                None
+            }
+        }
+    }
+
+    /// Find the end of the content of a region in `text`. `line_start` is true if `text`
+    /// is a complete line. Returns the position where the content ends and the position
+    /// after the end delimiter.
+    pub(crate) fn find_end(&self, text: &str, line_start: bool) -> Option<(usize, usize)> {
+        match self.delimiters {
+            Delimiters::Between { ref end, .. } => text
+                .find(end.as_str())
+                .map(|position| (position, position + end.len())),
+            Delimiters::Heredoc { ref marker } => {
+                if line_start && text.trim() == marker {
+                    Some((0, text.len()))
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn find_delimiters() {
+    let macro_rule = EmbeddedLanguage::between("sql!(", ")", "sql");
+    assert_eq!(
+        macro_rule.find_start("let q = sql!(SELECT 1);"),
+        Some((8, 13))
+    );
+    assert_eq!(macro_rule.find_end("SELECT 1);", false), Some((8, 9)));
+    assert_eq!(macro_rule.find_start("let q = 1;"), None);
+
+    let heredoc = EmbeddedLanguage::heredoc("SQL", "sql");
+    assert_eq!(heredoc.find_start("psql <<SQL\n"), Some((5, 11)));
+    assert_eq!(heredoc.find_start("psql <<-'SQL'"), Some((5, 13)));
+    assert_eq!(heredoc.find_start("cat <<SQLITE"), None);
+    assert_eq!(heredoc.find_end("  SQL\n", true), Some((0, 6)));
+    assert_eq!(heredoc.find_end("SELECT 1; -- SQL\n", true), None);
+}
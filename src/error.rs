@@ -10,6 +10,66 @@ error_chain! {
         GlobParsingError(::globset::Error);
         SerdeYamlError(::serde_yaml::Error);
     }
+
+    errors {
+        UnknownSyntax(name: String, suggestions: Vec<String>) {
+            description("unknown syntax")
+            display("Unknown syntax '{}'{}", name, did_you_mean(suggestions))
+        }
+        UnknownTheme(name: String, suggestions: Vec<String>) {
+            description("unknown theme")
+            display("Unknown theme '{}'{}", name, did_you_mean(suggestions))
+        }
+        IncompatibleCache(path: String, current_version: String, reason: String) {
+            description("incompatible cache")
+            display(
+                "The binary caches for the user-customized syntaxes and themes in '{}' are not \
+                 compatible with this version of bat ({}, the cache was {}). To solve this, \
+                 either rebuild the cache (bat cache --build) or remove the custom \
+                 syntaxes/themes (bat cache --clear).\n\
+                 For more information, see:\n\n  \
+                 https://github.com/sharkdp/bat#adding-new-syntaxes--language-definitions",
+                path,
+                current_version,
+                reason
+            )
+        }
+        UnknownSymbol(name: String, input: String, suggestions: Vec<String>) {
+            description("unknown symbol")
+            display("No definition of '{}' in {}{}", name, input, did_you_mean(suggestions))
+        }
+        UnknownProfile(name: String, suggestions: Vec<String>) {
+            description("unknown profile")
+            display("Unknown profile '{}'{}", name, did_you_mean(suggestions))
+        }
+        InvalidConfig(reason: String) {
+            description("invalid configuration")
+            display("Invalid configuration: {}", reason)
+        }
+        SubprocessTimeout(command: String, timeout: ::std::time::Duration) {
+            description("subprocess timed out")
+            display("'{}' was killed since it was still running after {:?}", command, timeout)
+        }
+        SandboxViolation(feature: String) {
+            description("feature is not available in sandbox mode")
+            display("{} is not available in sandbox mode", feature)
+        }
+    }
+}
+
+fn did_you_mean(suggestions: &[String]) -> String {
+    if suggestions.is_empty() {
+        String::new()
+    } else {
+        format!(
+            ". Did you mean {}?",
+            suggestions
+                .iter()
+                .map(|s| format!("'{}'", s))
+                .collect::<Vec<_>>()
+                .join(" or ")
+        )
+    }
 }
 
 pub fn default_error_handler(error: &Error, output: &mut dyn Write) {
@@ -0,0 +1,104 @@
+//! The contents of the number gutter: the line number, the byte offset of the line or a value
+//! that is extracted from the line with a regular expression (like the timestamp of a log
+//! message).
+
+use console::measure_text_width;
+use syntect::parsing::{Regex, Region};
+
+use crate::error::*;
+use crate::template::truncate;
+
+/// What is shown in the number gutter (the `numbers` style component)
+#[derive(Debug, Clone)]
+pub enum GutterSource {
+    LineNumber,
+
+    /// The offset of the first byte of the line within the input
+    ByteOffset,
+
+    /// A value that is extracted from every line
+    Capture(GutterPattern),
+}
+
+impl Default for GutterSource {
+    fn default() -> Self {
+        GutterSource::LineNumber
+    }
+}
+
+/// A regular expression that extracts the gutter value from a line: the first capture group
+/// or (if there is none) the whole match. Lines without a match have an empty gutter.
+#[derive(Debug, Clone)]
+pub struct GutterPattern {
+    regex: Regex,
+}
+
+impl GutterPattern {
+    pub fn new(pattern: &str) -> Result<Self> {
+        if let Some(error) = Regex::try_compile(pattern) {
+            return Err(format!("Invalid gutter pattern '{}': {}", pattern, error).into());
+        }
+        Ok(GutterPattern {
+            regex: Regex::new(pattern.to_owned()),
+        })
+    }
+
+    fn extract<'l>(&self, line: &'l str) -> Option<&'l str> {
+        let mut region = Region::new();
+        if !self.regex.search(line, 0, line.len(), Some(&mut region)) {
+            return None;
+        }
+        let (start, end) = region.pos(1).or_else(|| region.pos(0))?;
+        Some(&line[start..end])
+    }
+}
+
+impl GutterSource {
+    /// The width of the gutter. The width of an extracted value is the width of the value in
+    /// the first line of the input.
+    pub(crate) fn width(&self, first_line: &str) -> usize {
+        match self {
+            GutterSource::LineNumber => 4,
+            GutterSource::ByteOffset => 8,
+            GutterSource::Capture(pattern) => pattern
+                .extract(first_line.trim_end_matches(&['\n', '\r'][..]))
+                .map_or(0, measure_text_width)
+                .max(4),
+        }
+    }
+
+    /// The gutter value of a line. Extracted values are cut to the width of the gutter (line
+    /// numbers and byte offsets make the gutter wider instead).
+    pub(crate) fn value(
+        &self,
+        line_number: usize,
+        byte_offset: usize,
+        line: &str,
+        width: usize,
+    ) -> String {
+        match self {
+            GutterSource::LineNumber => format!("{:w$}", line_number, w = width),
+            GutterSource::ByteOffset => format!("{:w$}", byte_offset, w = width),
+            GutterSource::Capture(pattern) => {
+                let line = line.trim_end_matches(&['\n', '\r'][..]);
+                let value = truncate(pattern.extract(line).unwrap_or(""), width);
+                let padding = width.saturating_sub(measure_text_width(&value));
+                format!("{}{}", value, " ".repeat(padding))
+            }
+        }
+    }
+}
+
+#[test]
+fn gutter_values() {
+    let timestamp = GutterSource::Capture(GutterPattern::new(r"^\[([^\]]+)\]").unwrap());
+    let first_line = "[12:00:01] started\n";
+    assert_eq!(timestamp.width(first_line), 8);
+    assert_eq!(timestamp.value(1, 0, first_line, 8), "12:00:01");
+    assert_eq!(timestamp.value(2, 19, "  continued\n", 8), "        ");
+    assert_eq!(timestamp.value(3, 31, "[12:00:01.5] done\n", 8), "12:00:01");
+
+    assert_eq!(GutterSource::ByteOffset.value(2, 19, "", 8), "      19");
+    assert_eq!(GutterSource::LineNumber.value(12, 19, "", 4), "  12");
+    assert!(GutterPattern::new("(").is_err());
+}
@@ -0,0 +1,359 @@
+//! Content-based heuristics to choose between several syntaxes that claim the same file
+//! extension, and to detect the language of files without a known extension.
+
+/// The built-in disambiguator for `.h`, `.m`, `.pl` and `.v` files (see `Disambiguator`)
+pub(crate) fn disambiguate(extension: &str, candidates: &[&str], content: &str) -> Option<usize> {
+    if content.trim().is_empty() {
+        return None;
+    }
+
+    let syntax = match extension {
+        "h" => detect_header(content),
+        "m" => detect_m(content),
+        "pl" => detect_pl(content),
+        "v" => detect_v(content),
+        _ => None,
+    }?;
+
+    candidates.iter().position(|candidate| *candidate == syntax)
+}
+
+fn lines(content: &str) -> impl Iterator<Item = &str> {
+    content.lines().map(str::trim)
+}
+
+fn is_objective_c(content: &str) -> bool {
+    const DIRECTIVES: &[&str] = &[
+        "@interface",
+        "@implementation",
+        "@protocol",
+        "@class",
+        "@property",
+        "@end",
+        "#import",
+    ];
+
+    lines(content).any(|line| DIRECTIVES.iter().any(|d| line.starts_with(d)))
+}
+
+fn is_cpp(content: &str) -> bool {
+    const HEADERS: &[&str] = &[
+        "<cstdint>",
+        "<cstdlib>",
+        "<string>",
+        "<vector>",
+        "<map>",
+        "<memory>",
+        "<iostream>",
+        "<algorithm>",
+        "<unordered_map>",
+    ];
+    const PREFIXES: &[&str] = &[
+        "template <",
+        "template<",
+        "namespace ",
+        "using namespace ",
+        "class ",
+        "public:",
+        "private:",
+        "protected:",
+    ];
+
+    content.contains("std::")
+        || lines(content).any(|line| {
+            PREFIXES.iter().any(|p| line.starts_with(p))
+                || (line.starts_with("#include") && HEADERS.iter().any(|h| line.ends_with(h)))
+        })
+}
+
+/// C, C++, Objective-C or Objective-C++. Plain headers without any of the typical C++ or
+/// Objective-C constructs are classified as C.
+fn detect_header(content: &str) -> Option<&'static str> {
+    Some(match (is_objective_c(content), is_cpp(content)) {
+        (true, true) => "Objective-C++",
+        (true, false) => "Objective-C",
+        (false, true) => "C++",
+        (false, false) => "C",
+    })
+}
+
+/// Objective-C or MATLAB
+fn detect_m(content: &str) -> Option<&'static str> {
+    if is_objective_c(content) || lines(content).any(|line| line.starts_with("#include")) {
+        return Some("Objective-C");
+    }
+
+    let is_matlab = lines(content)
+        .any(|line| line.starts_with('%') || line.starts_with("function ") || line == "end");
+    if is_matlab {
+        Some("MATLAB")
+    } else {
+        None
+    }
+}
+
+/// Perl or Prolog
+fn detect_pl(content: &str) -> Option<&'static str> {
+    const PERL: &[&str] = &[
+        "use strict",
+        "use warnings",
+        "my $",
+        "sub ",
+        "package ",
+        "#!",
+    ];
+
+    if lines(content).any(|line| PERL.iter().any(|p| line.starts_with(p))) {
+        return Some("Perl");
+    }
+
+    let is_prolog = lines(content)
+        .any(|line| line.starts_with(":-") || (line.contains(":-") && line.ends_with('.')));
+    if is_prolog {
+        Some("Prolog")
+    } else {
+        None
+    }
+}
+
+/// Verilog or Coq
+fn detect_v(content: &str) -> Option<&'static str> {
+    const VERILOG: &[&str] = &["module ", "endmodule", "always @", "assign ", "`timescale"];
+    const COQ: &[&str] = &[
+        "Require ",
+        "From ",
+        "Theorem ",
+        "Lemma ",
+        "Definition ",
+        "Fixpoint ",
+        "Inductive ",
+        "Proof.",
+        "Qed.",
+    ];
+
+    if lines(content).any(|line| COQ.iter().any(|p| line.starts_with(p))) {
+        Some("Coq")
+    } else if lines(content).any(|line| VERILOG.iter().any(|p| line.starts_with(p))) {
+        Some("Verilog")
+    } else {
+        None
+    }
+}
+
+/// Aliases of language names in modelines and of interpreters in shebang lines, and the
+/// tokens of their syntaxes (see `SyntaxSet::find_syntax_by_token`)
+const LANGUAGE_ALIASES: &[(&str, &str)] = &[
+    ("c++", "cpp"),
+    ("shell-script", "sh"),
+    ("zsh", "sh"),
+    ("dash", "sh"),
+    ("ksh", "sh"),
+    ("node", "js"),
+    ("nodejs", "js"),
+    ("deno", "js"),
+    ("bun", "js"),
+    ("javascript", "js"),
+    ("typescript", "ts"),
+    ("python", "py"),
+    ("pypy", "py"),
+    ("ruby", "rb"),
+    ("perl", "pl"),
+    ("php", "php"),
+    ("lua", "lua"),
+    ("make", "Makefile"),
+    ("tclsh", "tcl"),
+];
+
+fn language_token(name: &str) -> String {
+    let name = name.trim().to_lowercase();
+    LANGUAGE_ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == name)
+        .map_or(name.clone(), |(_, token)| (*token).to_owned())
+}
+
+/// The language of a modeline like `-*- mode: python -*-` (Emacs) or `vim: ft=python`
+fn modeline_language(line: &str) -> Option<String> {
+    if let Some(start) = line.find("-*-") {
+        let rest = &line[start + 3..];
+        let variables = &rest[..rest.find("-*-")?];
+        let mode = if variables.contains(':') {
+            variables.split(';').find_map(|variable| {
+                let mut parts = variable.splitn(2, ':');
+                match (parts.next()?.trim(), parts.next()) {
+                    ("mode", Some(mode)) => Some(mode),
+                    _ => None,
+                }
+            })?
+        } else {
+            variables
+        };
+        return Some(language_token(mode));
+    }
+
+    let start = ["vim:", "vi:", "ex:"]
+        .iter()
+        .filter_map(|marker| line.find(marker).map(|index| index + marker.len()))
+        .min()?;
+    line[start..]
+        .split(|c: char| c == ':' || c.is_whitespace())
+        .find_map(|option| {
+            let mut parts = option.splitn(2, '=');
+            match (parts.next()?, parts.next()) {
+                ("ft", Some(value)) | ("filetype", Some(value)) | ("syntax", Some(value))
+                    if !value.is_empty() =>
+                {
+                    Some(language_token(value))
+                }
+                _ => None,
+            }
+        })
+}
+
+/// The interpreter of a shebang line like `#!/usr/bin/env -S python3 -u`, without its
+/// version (`python3.11` is `python`)
+fn shebang_language(line: &str) -> Option<String> {
+    if !line.starts_with("#!") {
+        return None;
+    }
+    let mut words = line[2..].split_whitespace();
+    let mut interpreter = words.next()?.rsplit('/').next()?;
+    if interpreter == "env" {
+        interpreter = words.find(|word| !word.starts_with('-') && !word.contains('='))?;
+    }
+    let name = interpreter.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    if name.is_empty() {
+        None
+    } else {
+        Some(language_token(name))
+    }
+}
+
+/// The language of content that has distinctive contents, like XML declarations or JSON
+fn distinctive_language(content: &str) -> Option<&'static str> {
+    let start = content.trim_start();
+    let lowercase: String = start.chars().take(15).collect::<String>().to_lowercase();
+    if lowercase.starts_with("<?xml") {
+        Some("xml")
+    } else if lowercase.starts_with("<!doctype html") || lowercase.starts_with("<html") {
+        Some("html")
+    } else if start.starts_with("diff --git ")
+        || (start.starts_with("--- ") && content.contains("\n+++ "))
+    {
+        Some("diff")
+    } else if is_json(start) {
+        Some("json")
+    } else {
+        None
+    }
+}
+
+/// Whether the beginning of the content looks like a JSON object or array
+fn is_json(start: &str) -> bool {
+    let mut chars = start.chars();
+    let first = match chars.next() {
+        Some(c @ '{') | Some(c @ '[') => c,
+        _ => return false,
+    };
+    let next = chars
+        .find(|c| !c.is_whitespace())
+        .unwrap_or(if first == '{' { '}' } else { ']' });
+    match first {
+        '{' => next == '"' || next == '}',
+        _ => next == '{' || next == '[' || next == '"' || next == ']' || next.is_ascii_digit(),
+    }
+}
+
+/// The token of the language of the content (a syntax name or a file extension), for files
+/// without a known extension: from a modeline in the first or last lines, the interpreter in
+/// the shebang line or distinctive contents
+pub(crate) fn detect_language(content: &str) -> Option<String> {
+    let lines: Vec<&str> = content.lines().collect();
+    let modeline = lines
+        .iter()
+        .take(2)
+        .chain(lines.iter().rev().take(5))
+        .find_map(|line| modeline_language(line));
+
+    modeline
+        .or_else(|| lines.first().and_then(|line| shebang_language(line)))
+        .or_else(|| distinctive_language(content).map(str::to_owned))
+}
+
+#[test]
+fn disambiguate_headers() {
+    let candidates = ["Objective-C", "Objective-C++", "C", "C++"];
+    let detect = |content| disambiguate("h", &candidates, content).map(|i| candidates[i]);
+
+    assert_eq!(detect(""), None);
+    assert_eq!(detect("int foo(void);\n"), Some("C"));
+    assert_eq!(detect("#include <vector>\n"), Some("C++"));
+    assert_eq!(detect("namespace bat {\n}\n"), Some("C++"));
+    assert_eq!(
+        detect("#import <Foundation/Foundation.h>\n"),
+        Some("Objective-C")
+    );
+    assert_eq!(
+        detect("@interface Foo : NSObject\n  std::string name;\n@end\n"),
+        Some("Objective-C++")
+    );
+}
+
+#[test]
+fn disambiguate_other_extensions() {
+    let candidates = ["MATLAB", "Objective-C", "Prolog", "Perl", "Coq", "Verilog"];
+    let detect = |ext, content| disambiguate(ext, &candidates, content).map(|i| candidates[i]);
+
+    assert_eq!(detect("m", "% comment\nx = 1;\n"), Some("MATLAB"));
+    assert_eq!(
+        detect("m", "@implementation Foo\n@end\n"),
+        Some("Objective-C")
+    );
+    assert_eq!(detect("m", "x = 1;\n"), None);
+
+    assert_eq!(detect("pl", "use strict;\nmy $x = 1;\n"), Some("Perl"));
+    assert_eq!(
+        detect("pl", "parent(X, Y) :- father(X, Y).\n"),
+        Some("Prolog")
+    );
+
+    assert_eq!(
+        detect("v", "module counter(input clk);\nendmodule\n"),
+        Some("Verilog")
+    );
+    assert_eq!(
+        detect("v", "Theorem foo : True.\nProof.\nQed.\n"),
+        Some("Coq")
+    );
+
+    assert_eq!(disambiguate("v", &["Verilog"], "Lemma x : True."), None);
+}
+
+#[test]
+fn detect_languages_from_content() {
+    let detect = |content| detect_language(content);
+
+    assert_eq!(
+        detect("# -*- mode: ruby; coding: utf-8 -*-\n"),
+        Some("rb".into())
+    );
+    assert_eq!(detect("/* -*- C++ -*- */\n"), Some("cpp".into()));
+    assert_eq!(
+        detect("x = 1\n# vim: set ft=python ts=4 :\n"),
+        Some("py".into())
+    );
+    assert_eq!(
+        detect("#!/usr/bin/env -S node --harmony\n"),
+        Some("js".into())
+    );
+    assert_eq!(detect("#!/usr/bin/python3.11 -u\n"), Some("py".into()));
+    assert_eq!(
+        detect("<?xml version=\"1.0\"?>\n<a/>\n"),
+        Some("xml".into())
+    );
+    assert_eq!(detect("<!DOCTYPE html>\n"), Some("html".into()));
+    assert_eq!(detect("{\n  \"key\": 1\n}\n"), Some("json".into()));
+    assert_eq!(detect("[1, 2]\n"), Some("json".into()));
+    assert_eq!(detect("[section]\nkey = value\n"), None);
+    assert_eq!(detect("plain text\n"), None);
+}
@@ -0,0 +1,157 @@
+//! Hexdumps of binary content: rows of 16 bytes with the offset of the row in the number
+//! gutter, the bytes in hexadecimal and their printable characters next to them. The bytes
+//! are colored by their kind (null bytes, printable characters, whitespace, ...).
+
+use std::ops::Range;
+
+use syntect::highlighting::{Color, Style};
+
+/// How binary content is printed to the terminal
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinaryMode {
+    /// Print a warning instead of the content
+    Warn,
+
+    /// Print a hexdump of the content
+    Hex,
+}
+
+impl Default for BinaryMode {
+    fn default() -> Self {
+        BinaryMode::Warn
+    }
+}
+
+/// The number of bytes in a row of a hexdump
+pub(crate) const BYTES_PER_ROW: usize = 16;
+
+/// The width of the offset of a row
+pub(crate) const OFFSET_WIDTH: usize = 8;
+
+/// A color of the terminal palette (see `terminal::to_ansi_color`)
+const fn ansi(index: u8) -> Color {
+    Color {
+        r: index,
+        g: 0x00,
+        b: 0x00,
+        a: 0x00,
+    }
+}
+
+fn byte_color(byte: u8) -> Color {
+    match byte {
+        0x00 => ansi(8),
+        b'\t' | b'\n' | b'\r' | b' ' => ansi(2),
+        0x21..=0x7e => ansi(6),
+        0x01..=0x7f => ansi(5),
+        _ => ansi(3),
+    }
+}
+
+/// A row of a hexdump, with the colors of its bytes
+pub(crate) struct HexRow {
+    pub(crate) text: String,
+    colored: Vec<(Range<usize>, Color)>,
+}
+
+impl HexRow {
+    /// Format a row of up to `BYTES_PER_ROW` bytes. The offset is only part of the row if it
+    /// is not shown in the number gutter.
+    pub(crate) fn new(bytes: &[u8], offset: Option<usize>) -> Self {
+        let mut row = HexRow {
+            text: String::new(),
+            colored: vec![],
+        };
+        if let Some(offset) = offset {
+            row.text
+                .push_str(&format!("{:0w$x}  ", offset, w = OFFSET_WIDTH));
+        }
+
+        for index in 0..BYTES_PER_ROW {
+            if index > 0 {
+                row.text.push(' ');
+            }
+            if index == BYTES_PER_ROW / 2 {
+                row.text.push(' ');
+            }
+            match bytes.get(index) {
+                Some(&byte) => row.push(&format!("{:02x}", byte), byte),
+                None => row.text.push_str("  "),
+            }
+        }
+
+        row.text.push_str("  |");
+        for &byte in bytes {
+            let character = if byte == b' ' || byte.is_ascii_graphic() {
+                byte as char
+            } else {
+                '.'
+            };
+            row.push(&character.to_string(), byte);
+        }
+        row.text.push('|');
+
+        row
+    }
+
+    fn push(&mut self, text: &str, byte: u8) {
+        let start = self.text.len();
+        self.text.push_str(text);
+        self.colored
+            .push((start..self.text.len(), byte_color(byte)));
+    }
+
+    /// The parts of the row with their style. Bytes are printed with their color, everything
+    /// else with the given style.
+    pub(crate) fn regions(&self, style: Style) -> Vec<(Style, &str)> {
+        let mut regions = vec![];
+        let mut position = 0;
+        for (range, color) in &self.colored {
+            if range.start > position {
+                regions.push((style, &self.text[position..range.start]));
+            }
+            let byte_style = Style {
+                foreground: *color,
+                ..style
+            };
+            regions.push((byte_style, &self.text[range.clone()]));
+            position = range.end;
+        }
+        if position < self.text.len() {
+            regions.push((style, &self.text[position..]));
+        }
+        regions
+    }
+}
+
+#[test]
+fn hex_rows() {
+    let row = HexRow::new(
+        b"\x7fELF\x02\x01\x01\x00\x00\x00\x00\x00\x00\x00\x00\x00",
+        None,
+    );
+    assert_eq!(
+        row.text,
+        "7f 45 4c 46 02 01 01 00  00 00 00 00 00 00 00 00  |.ELF............|"
+    );
+
+    let row = HexRow::new(b"a b\n", Some(0x10));
+    assert_eq!(
+        row.text,
+        "00000010  61 20 62 0a                                       |a b.|"
+    );
+    let regions = row.regions(Style::default());
+    let text: String = regions.iter().map(|(_, text)| *text).collect();
+    assert_eq!(text, row.text);
+    assert_eq!(
+        regions[1],
+        (
+            Style {
+                foreground: ansi(6),
+                ..Style::default()
+            },
+            "61"
+        )
+    );
+    assert_eq!(regions[3].0.foreground, ansi(2));
+}
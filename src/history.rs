@@ -0,0 +1,142 @@
+//! Old versions of a file from the git history, to see how a file evolved. The versions are
+//! printed one after another (or in a pager each, see `Config::pager_per_file`), with the
+//! revision in the header.
+#![cfg(feature = "git")]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use git2::{Commit, Oid, Repository, Sort};
+
+use crate::error::*;
+
+/// The content of a file at some revision
+#[derive(Debug, Clone)]
+pub struct FileVersion {
+    /// The short id and the summary of the commit, like `1a2b3c4 Add caching`
+    pub revision: String,
+    pub content: Vec<u8>,
+}
+
+/// The repository of a file and the path of the file within the repository. The file doesn't
+/// need to exist anymore, as long as its directory does.
+fn open_repository(path: &Path) -> Result<(Repository, PathBuf)> {
+    let not_in_a_repository = || -> Error {
+        format!(
+            "'{}' is not within a git repository",
+            path.to_string_lossy()
+        )
+        .into()
+    };
+
+    let absolute = fs::canonicalize(path).or_else(|_| {
+        let directory = match path.parent() {
+            Some(directory) if !directory.as_os_str().is_empty() => directory,
+            _ => Path::new("."),
+        };
+        fs::canonicalize(directory)
+            .map(|directory| directory.join(path.file_name().unwrap_or_default()))
+    })?;
+
+    let repo = Repository::discover(absolute.parent().unwrap_or(&absolute))
+        .map_err(|_| not_in_a_repository())?;
+    let workdir = repo
+        .workdir()
+        .map(fs::canonicalize)
+        .ok_or_else(not_in_a_repository)??;
+    let relative = absolute
+        .strip_prefix(&workdir)
+        .map_err(|_| not_in_a_repository())?
+        .to_owned();
+
+    Ok((repo, relative))
+}
+
+/// The id of the file in the tree of the commit (if the file is part of it)
+fn file_id(commit: &Commit, path: &Path) -> Option<Oid> {
+    commit
+        .tree()
+        .ok()?
+        .get_path(path)
+        .ok()
+        .map(|entry| entry.id())
+}
+
+fn git_error(error: git2::Error) -> Error {
+    error.message().to_owned().into()
+}
+
+/// The contents of a file at the given revisions (like `HEAD~2`, a tag or a commit id)
+pub fn file_versions(path: &Path, revisions: &[&str]) -> Result<Vec<FileVersion>> {
+    let (repo, relative) = open_repository(path)?;
+
+    revisions
+        .iter()
+        .map(|revision| {
+            let commit = repo
+                .revparse_single(revision)
+                .and_then(|object| object.peel_to_commit())
+                .map_err(|error| -> Error {
+                    format!("Unknown revision '{}': {}", revision, error.message()).into()
+                })?;
+            let id = file_id(&commit, &relative).ok_or_else(|| -> Error {
+                format!(
+                    "'{}' does not exist in revision '{}'",
+                    relative.to_string_lossy(),
+                    revision
+                )
+                .into()
+            })?;
+            let blob = repo.find_blob(id).map_err(git_error)?;
+
+            let short_id = commit
+                .as_object()
+                .short_id()
+                .map_err(git_error)?
+                .as_str()
+                .unwrap_or_default()
+                .to_owned();
+            Ok(FileVersion {
+                revision: format!("{} {}", short_id, commit.summary().unwrap_or_default()),
+                content: blob.content().to_owned(),
+            })
+        })
+        .collect()
+}
+
+/// The ids of the last `count` commits that changed the file (starting with the oldest one)
+pub fn recent_revisions(path: &Path, count: usize) -> Result<Vec<String>> {
+    let (repo, relative) = open_repository(path)?;
+
+    let mut walk = repo.revwalk().map_err(git_error)?;
+    walk.set_sorting(Sort::TIME).map_err(git_error)?;
+    walk.push_head().map_err(git_error)?;
+
+    let mut revisions = vec![];
+    for id in walk {
+        if revisions.len() == count {
+            break;
+        }
+        let commit = repo
+            .find_commit(id.map_err(git_error)?)
+            .map_err(git_error)?;
+        let current = file_id(&commit, &relative);
+        let previous = commit
+            .parent(0)
+            .ok()
+            .and_then(|parent| file_id(&parent, &relative));
+        if current.is_some() && current != previous {
+            revisions.push(commit.id().to_string());
+        }
+    }
+
+    if revisions.is_empty() {
+        return Err(format!(
+            "'{}' is not part of the git history",
+            relative.to_string_lossy()
+        )
+        .into());
+    }
+    revisions.reverse();
+    Ok(revisions)
+}
@@ -0,0 +1,192 @@
+//! A self-contained HTML page with many highlighted inputs (see
+//! `PrettyPrinter::print_html_report`): a sidebar with an index of the inputs and one
+//! collapsible section per input, with the lines in `OutputFormat::Html`. The colors of the
+//! theme are inlined into the stylesheet of the page, so that it can be shared as one file.
+
+use std::io::Write;
+
+use syntect::highlighting::{Color, Theme};
+
+use crate::error::*;
+use crate::pipeline::{hex_color, xml_escape};
+use crate::printer::DEFAULT_GUTTER_COLOR;
+
+/// The highlighted lines of an input
+pub(crate) struct ReportSection {
+    pub(crate) name: String,
+    pub(crate) language: String,
+    pub(crate) lines: usize,
+
+    /// The lines in `OutputFormat::Html`, or `None` for binary content
+    pub(crate) html: Option<String>,
+}
+
+/// The id of a section, which is also the prefix of the ids of its lines (`file-1-L12`)
+pub(crate) fn section_id(index: usize) -> String {
+    format!("file-{}", index + 1)
+}
+
+/// The colors of the page, taken from the theme
+struct ReportColors {
+    foreground: String,
+    background: String,
+    gutter: String,
+    highlight: String,
+}
+
+impl ReportColors {
+    fn new(theme: &Theme) -> Self {
+        let settings = &theme.settings;
+        let color = |color: Option<Color>, default: &str| match color {
+            Some(color) => hex_color(color),
+            None => default.to_owned(),
+        };
+        ReportColors {
+            foreground: color(settings.foreground, "#000000"),
+            background: color(settings.background, "#ffffff"),
+            gutter: hex_color(settings.gutter_foreground.unwrap_or(Color {
+                r: DEFAULT_GUTTER_COLOR,
+                g: 0,
+                b: 0,
+                a: 0,
+            })),
+            highlight: color(settings.line_highlight, "rgba(128, 128, 128, 0.25)"),
+        }
+    }
+}
+
+fn stylesheet(colors: &ReportColors, tab_width: usize) -> String {
+    format!(
+        "body {{ margin: 0; display: flex; font-family: sans-serif; \
+         color: {fg}; background: {bg}; }}\n\
+         nav {{ position: sticky; top: 0; height: 100vh; overflow-y: auto; flex: none; \
+         max-width: 20em; padding: 1em; box-sizing: border-box; \
+         border-right: 1px solid {gutter}; }}\n\
+         nav h1 {{ font-size: 1.2em; margin-top: 0; }}\n\
+         nav ul {{ list-style: none; margin: 0; padding: 0; }}\n\
+         nav li {{ margin: 0.3em 0; word-break: break-all; }}\n\
+         a {{ color: inherit; }}\n\
+         main {{ flex: 1; min-width: 0; padding: 1em; }}\n\
+         details {{ margin-bottom: 1em; }}\n\
+         summary {{ cursor: pointer; font-weight: bold; padding: 0.3em 0; \
+         border-bottom: 1px solid {gutter}; }}\n\
+         .meta {{ font-weight: normal; opacity: 0.7; }}\n\
+         pre {{ margin: 0.5em 0; overflow-x: auto; tab-size: {tab_width}; }}\n\
+         pre .gutter {{ color: {gutter}; text-decoration: none; user-select: none; }}\n\
+         pre .highlighted {{ background: {highlight}; }}\n",
+        fg = colors.foreground,
+        bg = colors.background,
+        gutter = colors.gutter,
+        highlight = colors.highlight,
+        tab_width = tab_width,
+    )
+}
+
+/// The language and the number of lines of a section
+fn meta(section: &ReportSection) -> String {
+    let lines = match section.lines {
+        1 => "1 line".to_owned(),
+        lines => format!("{} lines", lines),
+    };
+    xml_escape(&format!("{}, {}", section.language, lines))
+}
+
+/// Write the page with the given sections
+pub(crate) fn write_report(
+    out: &mut dyn Write,
+    title: &str,
+    theme: &Theme,
+    tab_width: usize,
+    sections: &[ReportSection],
+) -> Result<()> {
+    let title = xml_escape(title);
+    writeln!(out, "<!DOCTYPE html>")?;
+    writeln!(out, "<html>\n<head>\n<meta charset=\"utf-8\">")?;
+    writeln!(out, "<title>{}</title>", title)?;
+    writeln!(
+        out,
+        "<style>\n{}</style>",
+        stylesheet(&ReportColors::new(theme), tab_width)
+    )?;
+    writeln!(out, "</head>\n<body>")?;
+
+    writeln!(out, "<nav>\n<h1>{}</h1>\n<ul>", title)?;
+    for (index, section) in sections.iter().enumerate() {
+        writeln!(
+            out,
+            "<li><a href=\"#{}\">{}</a> <span class=\"meta\">{}</span></li>",
+            section_id(index),
+            xml_escape(&section.name),
+            meta(section)
+        )?;
+    }
+    writeln!(out, "</ul>\n</nav>")?;
+
+    writeln!(out, "<main>")?;
+    for (index, section) in sections.iter().enumerate() {
+        writeln!(
+            out,
+            "<details id=\"{}\" open>\n<summary>{} <span class=\"meta\">{}</span></summary>",
+            section_id(index),
+            xml_escape(&section.name),
+            meta(section)
+        )?;
+        match section.html {
+            Some(ref html) => writeln!(out, "<pre>{}</pre>", html)?,
+            None => writeln!(out, "<p class=\"meta\">Binary content</p>")?,
+        }
+        writeln!(out, "</details>")?;
+    }
+    writeln!(out, "</main>\n</body>\n</html>")?;
+
+    out.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assets::HighlightingAssets;
+
+    #[test]
+    fn pages_have_an_index_and_sections() {
+        let assets = HighlightingAssets::from_binary();
+        let sections = vec![
+            ReportSection {
+                name: "a<b>.rs".into(),
+                language: "Rust".into(),
+                lines: 1,
+                html: Some("<span class=\"line\">x</span>\n".into()),
+            },
+            ReportSection {
+                name: "image.png".into(),
+                language: "Plain Text".into(),
+                lines: 3,
+                html: None,
+            },
+        ];
+
+        let mut output = vec![];
+        write_report(
+            &mut output,
+            "Report",
+            assets.get_theme("Monokai Extended"),
+            4,
+            &sections,
+        )
+        .unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(output.contains("background: #222222;"));
+        assert!(output.contains("tab-size: 4;"));
+        assert!(output.contains(
+            "<li><a href=\"#file-1\">a&lt;b&gt;.rs</a> <span class=\"meta\">Rust, 1 line</span></li>"
+        ));
+        assert!(output.contains(
+            "<details id=\"file-2\" open>\n<summary>image.png <span class=\"meta\">Plain Text, \
+             3 lines</span></summary>\n<p class=\"meta\">Binary content</p>"
+        ));
+        assert!(output.contains("<pre><span class=\"line\">x</span>\n</pre>"));
+        assert!(output.ends_with("</main>\n</body>\n</html>\n"));
+    }
+}
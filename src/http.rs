@@ -0,0 +1,228 @@
+//! Downloading inputs from URLs (see `Input::from_url`). The resources are downloaded with
+//! `curl`, which has to be installed, and streamed into the printer while they arrive.
+
+use std::io::{self, BufRead, BufReader, Read};
+use std::process::{Child, ChildStdout, Command, Stdio};
+
+use crate::error::*;
+
+/// A resource that is being downloaded
+pub(crate) struct Download {
+    /// The media type of the resource (without parameters like the charset)
+    pub(crate) content_type: Option<String>,
+
+    pub(crate) body: Body,
+}
+
+/// The content of a resource. Reading fails at the end of the content if `curl` failed.
+pub(crate) struct Body {
+    child: Child,
+    stdout: BufReader<ChildStdout>,
+
+    /// The number of bytes that may still be read before the download is aborted
+    remaining: Option<u64>,
+}
+
+impl Read for Body {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        // Read one byte more than allowed to tell whether the limit is exceeded
+        let length = match self.remaining {
+            Some(remaining) => remaining.saturating_add(1).min(buf.len() as u64) as usize,
+            None => buf.len(),
+        };
+        let size = self.stdout.read(&mut buf[..length])?;
+        if let Some(remaining) = self.remaining {
+            if size as u64 > remaining {
+                return Err(self.abort());
+            }
+            self.remaining = Some(remaining - size as u64);
+        }
+
+        if size == 0 && !buf.is_empty() {
+            let status = self.child.wait()?;
+            if !status.success() {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    format!("the download was interrupted (curl: {})", status),
+                ));
+            }
+        }
+        Ok(size)
+    }
+}
+
+impl Body {
+    /// Stop `curl` once the size limit is exceeded
+    fn abort(&mut self) -> io::Error {
+        self.child.kill().ok();
+        self.child.wait().ok();
+        io::Error::new(
+            io::ErrorKind::Other,
+            "the download was aborted since it exceeds the size limit",
+        )
+    }
+}
+
+impl Drop for Body {
+    fn drop(&mut self) {
+        self.child.kill().ok();
+        self.child.wait().ok();
+    }
+}
+
+/// Whether the input path is a URL that can be downloaded
+pub(crate) fn is_url(path: &str) -> bool {
+    path.starts_with("http://") || path.starts_with("https://")
+}
+
+/// The URL without its query and fragment, which is used as the name of the input (so that
+/// the syntax is detected from the file name in the path, if there is one)
+pub(crate) fn input_name(url: &str) -> &str {
+    match url.find(&['?', '#'][..]) {
+        Some(index) => &url[..index],
+        None => url,
+    }
+}
+
+/// Start downloading a resource. Fails if `curl` can not be started or if the server does
+/// not respond successfully. Reading the body fails once more than `max_size` bytes (if
+/// given) have been read, and `curl` is killed.
+pub(crate) fn download(url: &str, max_size: Option<u64>) -> Result<Download> {
+    if !is_url(url) {
+        return Err(format!("'{}' is not an HTTP(S) URL", url).into());
+    }
+
+    let mut child = Command::new("curl")
+        .args(&["--silent", "--show-error", "--fail", "--location"])
+        // The headers of all responses (including redirects) precede the content
+        .args(&["--dump-header", "-", "--", url])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Could not run 'curl' to download '{}': {}", url, e))?;
+    let mut stdout = BufReader::new(child.stdout.take().expect("piped stdout"));
+
+    match read_headers(&mut stdout)? {
+        Some(content_type) => Ok(Download {
+            content_type,
+            body: Body {
+                child,
+                stdout,
+                remaining: max_size,
+            },
+        }),
+        None => {
+            let mut message = String::new();
+            if let Some(mut stderr) = child.stderr.take() {
+                stderr.read_to_string(&mut message).ok();
+            }
+            child.wait()?;
+            Err(format!("Could not download '{}': {}", url, message.trim()).into())
+        }
+    }
+}
+
+/// Skip the headers of the responses up to the final one, and return its content type.
+/// Returns `None` if there is no successful final response.
+fn read_headers(reader: &mut impl BufRead) -> Result<Option<Option<String>>> {
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        let status = line
+            .split_whitespace()
+            .nth(1)
+            .unwrap_or_default()
+            .to_owned();
+
+        let mut content_type = None;
+        loop {
+            line.clear();
+            if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+                break;
+            }
+            let mut header = line.splitn(2, ':');
+            let name = header.next().unwrap_or_default();
+            if name.eq_ignore_ascii_case("content-type") {
+                content_type = header
+                    .next()
+                    .and_then(|value| value.split(';').next())
+                    .map(|value| value.trim().to_ascii_lowercase())
+                    .filter(|value| !value.is_empty());
+            }
+        }
+
+        // Informational responses and redirects are followed by other responses
+        if status.starts_with('2') {
+            return Ok(Some(content_type));
+        } else if !status.starts_with('1') && !status.starts_with('3') {
+            return Ok(None);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn headers_of_redirects_are_skipped() {
+        let response = "HTTP/1.1 301 Moved Permanently\r\n\
+                        Location: /file\r\n\
+                        Content-Type: text/html\r\n\
+                        \r\n\
+                        HTTP/1.1 200 OK\r\n\
+                        content-type: application/json; charset=utf-8\r\n\
+                        \r\n\
+                        {}\n";
+        let mut reader = response.as_bytes();
+
+        let content_type = read_headers(&mut reader).unwrap();
+        assert_eq!(content_type, Some(Some("application/json".to_owned())));
+        assert_eq!(reader, b"{}\n");
+
+        assert_eq!(read_headers(&mut "".as_bytes()).unwrap(), None);
+        let not_found = "HTTP/2 404\r\n\r\n";
+        assert_eq!(read_headers(&mut not_found.as_bytes()).unwrap(), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn downloads_are_aborted_above_their_size_limit() {
+        let body = |max_size| {
+            let mut child = Command::new("printf")
+                .arg("0123456789")
+                .stdout(Stdio::piped())
+                .spawn()
+                .unwrap();
+            let stdout = BufReader::new(child.stdout.take().unwrap());
+            Body {
+                child,
+                stdout,
+                remaining: max_size,
+            }
+        };
+
+        let mut content = String::new();
+        body(Some(10)).read_to_string(&mut content).unwrap();
+        assert_eq!(content, "0123456789");
+
+        let error = body(Some(9))
+            .read_to_string(&mut String::new())
+            .unwrap_err();
+        assert!(error.to_string().contains("size limit"));
+    }
+
+    #[test]
+    fn names_of_urls() {
+        assert!(is_url("https://example.com/main.rs"));
+        assert!(!is_url("main.rs"));
+        assert_eq!(
+            input_name("https://example.com/main.rs?raw=true#L1"),
+            "https://example.com/main.rs"
+        );
+    }
+}
@@ -0,0 +1,308 @@
+//! Highlighting of single diff hunks, for tools that show changes hunk by hunk (like
+//! interactive stagers or code review interfaces). A `HunkRenderer` is created once and then
+//! renders every hunk with full syntax highlighting and with the changed words emphasized.
+//!
+//! ```
+//! use bat::assets::HighlightingAssets;
+//! use bat::hunk::{Hunk, HunkRenderer};
+//!
+//! let assets = HighlightingAssets::from_binary();
+//! let renderer = HunkRenderer::new(&assets, "rs", "Monokai Extended").unwrap();
+//!
+//! let hunk = Hunk {
+//!     header: "@@ -1,2 +1,2 @@",
+//!     old_lines: &["fn main() {", "    let x = 1;"],
+//!     new_lines: &["fn main() {", "    let x = 2;"],
+//! };
+//! let rendered = renderer.render(&hunk);
+//! assert_eq!(
+//!     console::strip_ansi_codes(&rendered),
+//!     "@@ -1,2 +1,2 @@\n fn main() {\n-    let x = 1;\n+    let x = 2;\n"
+//! );
+//! ```
+
+use std::ops::Range;
+
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{Color, FontStyle, Style, Theme};
+use syntect::parsing::{SyntaxReference, SyntaxSet};
+
+use crate::assets::HighlightingAssets;
+use crate::error::*;
+use crate::line_diff::{diff_rows, DiffRow};
+use crate::patch::word_diff;
+use crate::printer::{ADDED_BACKGROUND, ADDED_EMPHASIS, REMOVED_BACKGROUND, REMOVED_EMPHASIS};
+use crate::regions::restyle_ranges;
+use crate::terminal::as_terminal_escaped;
+
+/// A hunk of a diff: the lines of the old and of the new version (without line endings),
+/// including the context lines that both have in common
+#[derive(Debug, Clone, Copy)]
+pub struct Hunk<'b> {
+    /// The header of the hunk, like `@@ -1,4 +1,5 @@ fn main()` (empty for none)
+    pub header: &'b str,
+    pub old_lines: &'b [&'b str],
+    pub new_lines: &'b [&'b str],
+}
+
+/// The kind of a line of a rendered hunk
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HunkLineKind {
+    Header,
+    Context,
+    Removed,
+    Added,
+}
+
+/// A line of a rendered hunk, as the styled regions of its text (without the `-`/`+` marker
+/// and the line ending)
+#[derive(Debug, Clone, PartialEq)]
+pub struct HunkLine {
+    pub kind: HunkLineKind,
+    pub regions: Vec<(Style, String)>,
+
+    /// The background of the whole line (for removed and added lines)
+    pub background: Option<Color>,
+}
+
+/// Renders diff hunks with the syntax highlighting of a language. The assets, the syntax and
+/// the theme are looked up once and shared by all hunks.
+pub struct HunkRenderer<'a> {
+    syntax_set: &'a SyntaxSet,
+    syntax: &'a SyntaxReference,
+    theme: &'a Theme,
+    true_color: bool,
+    italics: bool,
+}
+
+impl<'a> HunkRenderer<'a> {
+    /// Create a renderer for the given language (name or file extension) and theme.
+    /// Fails if the language is unknown. Unknown themes fall back to the default theme.
+    pub fn new(assets: &'a HighlightingAssets, language: &str, theme: &str) -> Result<Self> {
+        Ok(HunkRenderer {
+            syntax_set: assets.get_syntax_set(),
+            syntax: assets.get_syntax_by_token(language)?,
+            theme: assets.get_theme(theme),
+            true_color: true,
+            italics: false,
+        })
+    }
+
+    /// Whether or not to output 24bit colors (default: true)
+    pub fn true_color(&mut self, yes: bool) -> &mut Self {
+        self.true_color = yes;
+        self
+    }
+
+    /// Whether or not to use ANSI italics (default: off)
+    pub fn use_italics(&mut self, yes: bool) -> &mut Self {
+        self.italics = yes;
+        self
+    }
+
+    /// The lines of a hunk with their styles: the header, then the context lines and the
+    /// blocks of changes, where the removed lines come before the added lines. The words
+    /// that differ between a removed line and the added line that replaces it are emphasized.
+    pub fn highlight(&self, hunk: &Hunk) -> Vec<HunkLine> {
+        // Both versions are highlighted on their own, so that a change (like an opened
+        // string) only affects the highlighting of its own version
+        let old = self.highlight_lines(hunk.old_lines);
+        let new = self.highlight_lines(hunk.new_lines);
+
+        let mut lines = vec![];
+        if !hunk.header.is_empty() {
+            let style = Style {
+                font_style: FontStyle::BOLD,
+                ..self.default_style()
+            };
+            lines.push(HunkLine {
+                kind: HunkLineKind::Header,
+                regions: vec![(style, hunk.header.to_owned())],
+                background: None,
+            });
+        }
+
+        let mut removed: Vec<HunkLine> = vec![];
+        let mut added: Vec<HunkLine> = vec![];
+        let changed = |regions: &[(Style, &str)], ranges: &[Range<usize>], kind| {
+            let (background, emphasis) = match kind {
+                HunkLineKind::Removed => (REMOVED_BACKGROUND, REMOVED_EMPHASIS),
+                _ => (ADDED_BACKGROUND, ADDED_EMPHASIS),
+            };
+            let regions = restyle_ranges(regions, ranges, |mut style| {
+                style.background = emphasis;
+                style
+            });
+            HunkLine {
+                kind,
+                regions: owned(&regions),
+                background: Some(background),
+            }
+        };
+
+        for row in diff_rows(hunk.old_lines, hunk.new_lines) {
+            match row {
+                DiffRow::Unchanged(_, index) => {
+                    lines.append(&mut removed);
+                    lines.append(&mut added);
+                    lines.push(HunkLine {
+                        kind: HunkLineKind::Context,
+                        regions: owned(&regions(hunk.new_lines[index], &new[index])),
+                        background: None,
+                    });
+                }
+                DiffRow::Removed(index) => removed.push(changed(
+                    &regions(hunk.old_lines[index], &old[index]),
+                    &[],
+                    HunkLineKind::Removed,
+                )),
+                DiffRow::Added(index) => added.push(changed(
+                    &regions(hunk.new_lines[index], &new[index]),
+                    &[],
+                    HunkLineKind::Added,
+                )),
+                DiffRow::Changed(old_index, new_index) => {
+                    let (old_line, new_line) =
+                        (hunk.old_lines[old_index], hunk.new_lines[new_index]);
+                    let (old_ranges, new_ranges) = word_diff(old_line, new_line);
+                    removed.push(changed(
+                        &regions(old_line, &old[old_index]),
+                        &old_ranges,
+                        HunkLineKind::Removed,
+                    ));
+                    added.push(changed(
+                        &regions(new_line, &new[new_index]),
+                        &new_ranges,
+                        HunkLineKind::Added,
+                    ));
+                }
+            }
+        }
+        lines.append(&mut removed);
+        lines.append(&mut added);
+
+        lines
+    }
+
+    /// The hunk as ANSI-escaped text, with a `-`, `+` or space in front of every line
+    pub fn render(&self, hunk: &Hunk) -> String {
+        let mut output = String::new();
+        for line in self.highlight(hunk) {
+            let marker = match line.kind {
+                HunkLineKind::Header => "",
+                HunkLineKind::Context => " ",
+                HunkLineKind::Removed => "-",
+                HunkLineKind::Added => "+",
+            };
+            let marker_style = match line.kind {
+                HunkLineKind::Removed | HunkLineKind::Added => Style {
+                    font_style: FontStyle::BOLD,
+                    ..self.default_style()
+                },
+                _ => self.default_style(),
+            };
+
+            output += &self.escaped(marker_style, marker, line.background);
+            for (style, text) in &line.regions {
+                output += &self.escaped(*style, text, line.background);
+            }
+            output.push('\n');
+        }
+        output
+    }
+
+    /// The styles of the regions of the lines of one version
+    fn highlight_lines(&self, lines: &[&str]) -> Vec<Vec<(Style, usize)>> {
+        let mut highlighter = HighlightLines::new(self.syntax, self.theme);
+        lines
+            .iter()
+            .map(|line| {
+                let line = format!("{}\n", line.trim_end_matches(&['\r', '\n'][..]));
+                highlighter
+                    .highlight(&line, self.syntax_set)
+                    .into_iter()
+                    .map(|(style, region)| (style, region.len()))
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn default_style(&self) -> Style {
+        Style {
+            foreground: self.theme.settings.foreground.unwrap_or(Color::WHITE),
+            background: self.theme.settings.background.unwrap_or(Color::BLACK),
+            font_style: FontStyle::empty(),
+        }
+    }
+
+    fn escaped(&self, style: Style, text: &str, background: Option<Color>) -> String {
+        as_terminal_escaped(style, text, self.true_color, true, self.italics, background)
+            .to_string()
+    }
+}
+
+/// The regions of a line for the styles and lengths of its highlighted regions (without the
+/// line ending)
+fn regions<'b>(line: &'b str, styles: &[(Style, usize)]) -> Vec<(Style, &'b str)> {
+    let line = line.trim_end_matches(&['\r', '\n'][..]);
+    let mut offset = 0;
+    let mut regions = vec![];
+    for &(style, length) in styles {
+        let end = (offset + length).min(line.len());
+        if end > offset {
+            regions.push((style, &line[offset..end]));
+        }
+        offset += length;
+    }
+    regions
+}
+
+fn owned(regions: &[(Style, &str)]) -> Vec<(Style, String)> {
+    regions
+        .iter()
+        .filter(|(_, text)| !text.is_empty())
+        .map(|&(style, text)| (style, text.to_owned()))
+        .collect()
+}
+
+#[test]
+fn hunks_are_highlighted() {
+    let assets = HighlightingAssets::from_binary();
+    let renderer = HunkRenderer::new(&assets, "rs", "Monokai Extended").unwrap();
+
+    let hunk = Hunk {
+        header: "@@ -1,4 +1,5 @@",
+        old_lines: &["fn main() {", "    let x = 1;", "    old();", "}"],
+        new_lines: &[
+            "fn main() {",
+            "    let x = 2;",
+            "    new();",
+            "    added();",
+            "}",
+        ],
+    };
+    let lines = renderer.highlight(&hunk);
+    let kinds: Vec<HunkLineKind> = lines.iter().map(|line| line.kind).collect();
+    use HunkLineKind::*;
+    assert_eq!(
+        kinds,
+        vec![Header, Context, Removed, Removed, Added, Added, Added, Context]
+    );
+
+    // Only the changed number is emphasized
+    let emphasized: Vec<&str> = lines[2]
+        .regions
+        .iter()
+        .filter(|(style, _)| style.background == REMOVED_EMPHASIS)
+        .map(|(_, text)| text.as_str())
+        .collect();
+    assert_eq!(emphasized, vec!["1"]);
+    assert_eq!(lines[6].background, Some(ADDED_BACKGROUND));
+
+    assert_eq!(
+        console::strip_ansi_codes(&renderer.render(&hunk)),
+        "@@ -1,4 +1,5 @@\n fn main() {\n-    let x = 1;\n-    old();\n\
+         +    let x = 2;\n+    new();\n+    added();\n }\n"
+    );
+    assert!(HunkRenderer::new(&assets, "no-such-language", "").is_err());
+}
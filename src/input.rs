@@ -5,6 +5,8 @@ use std::io::{self, BufRead, BufReader, Read};
 use content_inspector::{self, ContentType};
 
 use crate::error::*;
+#[cfg(feature = "http")]
+use crate::http;
 
 const THEME_PREVIEW_FILE: &[u8] = include_bytes!("../assets/theme_preview.rs");
 
@@ -20,16 +22,39 @@ pub(crate) enum InputKind<'a> {
     StdIn,
     ThemePreviewFile,
     CustomReader(Box<dyn Read + 'a>),
+    #[cfg(feature = "http")]
+    Url(String),
 }
 
 #[derive(Clone, Default)]
 pub(crate) struct InputMetadata {
     pub(crate) user_provided_name: Option<OsString>,
+
+    /// The revision that the content is from, for old versions of a file
+    pub(crate) revision: Option<String>,
+
+    /// Compare the input with its diff base as parsed data (see `Input::compared_structurally`)
+    #[cfg(feature = "structured-diff")]
+    pub(crate) structured_diff: bool,
+
+    /// Whether the values of the input are aligned like in a config file, regardless of
+    /// `Config::align_key_values` (see `Input::align_key_values`)
+    pub(crate) align_key_values: Option<bool>,
+
+    /// The media type of the content, if it is known (like for downloaded inputs)
+    pub(crate) content_type: Option<String>,
+
+    /// The number of bytes after which a download is aborted (see `Input::max_download_size`)
+    #[cfg(feature = "http")]
+    pub(crate) max_download_size: Option<u64>,
 }
 
 pub struct Input<'a> {
     pub(crate) kind: InputKind<'a>,
     pub(crate) metadata: InputMetadata,
+
+    /// The old version of the input, if the input is printed as a side-by-side diff
+    pub(crate) diff_base: Option<Box<Input<'a>>>,
 }
 
 pub(crate) enum OpenedInputKind {
@@ -50,6 +75,7 @@ impl<'a> Input<'a> {
         Input {
             kind: InputKind::OrdinaryFile(path.to_os_string()),
             metadata: InputMetadata::default(),
+            diff_base: None,
         }
     }
 
@@ -57,6 +83,7 @@ impl<'a> Input<'a> {
         Input {
             kind: InputKind::StdIn,
             metadata: InputMetadata::default(),
+            diff_base: None,
         }
     }
 
@@ -64,6 +91,7 @@ impl<'a> Input<'a> {
         Input {
             kind: InputKind::ThemePreviewFile,
             metadata: InputMetadata::default(),
+            diff_base: None,
         }
     }
 
@@ -71,6 +99,53 @@ impl<'a> Input<'a> {
         Input {
             kind: InputKind::CustomReader(reader),
             metadata: InputMetadata::default(),
+            diff_base: None,
+        }
+    }
+
+    /// Download the input from an HTTP(S) URL with `curl` when it is printed. The syntax is
+    /// detected from the file name in the path of the URL or from the content type of the
+    /// response.
+    #[cfg(feature = "http")]
+    pub fn from_url(url: impl Into<String>) -> Self {
+        Input {
+            kind: InputKind::Url(url.into()),
+            metadata: InputMetadata::default(),
+            diff_base: None,
+        }
+    }
+
+    /// Abort the download of this input (see `from_url`) once more than the given number of
+    /// bytes have been received (`curl` is killed then). Printing the input fails in that case,
+    /// other inputs are not affected.
+    #[cfg(feature = "http")]
+    pub fn max_download_size(mut self, bytes: u64) -> Self {
+        self.metadata.max_download_size = Some(bytes);
+        self
+    }
+
+    /// The name of the input, like in the header (see `OpenedInput::description`)
+    pub(crate) fn name(&self) -> String {
+        if let Some(ref name) = self.metadata.user_provided_name {
+            return name.to_string_lossy().into_owned();
+        }
+
+        match self.kind {
+            InputKind::OrdinaryFile(ref path) => path.to_string_lossy().into_owned(),
+            InputKind::StdIn => "STDIN".into(),
+            InputKind::ThemePreviewFile => "".into(),
+            InputKind::CustomReader(_) => "READER".into(),
+            #[cfg(feature = "http")]
+            InputKind::Url(ref url) => http::input_name(url).into(),
+        }
+    }
+
+    /// Whether the input is downloaded from a URL
+    pub(crate) fn is_url(&self) -> bool {
+        match self.kind {
+            #[cfg(feature = "http")]
+            InputKind::Url(_) => true,
+            _ => false,
         }
     }
 
@@ -87,6 +162,61 @@ impl<'a> Input<'a> {
         self
     }
 
+    /// Mark the content as an old version of the file, which is shown in the header (see
+    /// `history::file_versions`)
+    pub fn at_revision(mut self, revision: impl Into<String>) -> Self {
+        self.metadata.revision = Some(revision.into());
+        self
+    }
+
+    /// Align the values of `key = value` lines in this input (or not), regardless of its syntax
+    /// and of `Config::align_key_values`
+    pub fn align_key_values(mut self, yes: bool) -> Self {
+        self.metadata.align_key_values = Some(yes);
+        self
+    }
+
+    /// Print this input as a side-by-side diff against its old version
+    pub fn compared_with(mut self, old: Input<'a>) -> Self {
+        self.diff_base = Some(Box::new(old));
+        self
+    }
+
+    /// Print the changes of the data of this JSON or YAML input against its old version,
+    /// instead of the content (see `structured_diff`)
+    #[cfg(feature = "structured-diff")]
+    pub fn compared_structurally(mut self, old: Input<'a>) -> Self {
+        self.diff_base = Some(Box::new(old));
+        self.metadata.structured_diff = true;
+        self
+    }
+
+    /// Create a copy of this input that can be opened independently. STDIN and custom readers
+    /// are read into memory for this purpose.
+    pub(crate) fn try_clone(&mut self) -> Result<Input<'a>> {
+        let kind = match self.kind {
+            InputKind::OrdinaryFile(ref path) => InputKind::OrdinaryFile(path.clone()),
+            InputKind::ThemePreviewFile => InputKind::ThemePreviewFile,
+            #[cfg(feature = "http")]
+            InputKind::Url(ref url) => InputKind::Url(url.clone()),
+            InputKind::StdIn | InputKind::CustomReader(_) => {
+                let mut content = vec![];
+                match self.kind {
+                    InputKind::CustomReader(ref mut reader) => reader.read_to_end(&mut content)?,
+                    _ => io::stdin().lock().read_to_end(&mut content)?,
+                };
+                self.kind = InputKind::CustomReader(Box::new(io::Cursor::new(content.clone())));
+                InputKind::CustomReader(Box::new(io::Cursor::new(content)))
+            }
+        };
+
+        Ok(Input {
+            kind,
+            metadata: self.metadata.clone(),
+            diff_base: None,
+        })
+    }
+
     pub(crate) fn open<R: BufRead + 'a>(self, stdin: R) -> Result<OpenedInput<'a>> {
         match self.kind {
             InputKind::StdIn => Ok(OpenedInput {
@@ -116,11 +246,67 @@ impl<'a> Input<'a> {
                 metadata: self.metadata,
                 reader: InputReader::new(BufReader::new(reader)),
             }),
+            #[cfg(feature = "http")]
+            InputKind::Url(url) => {
+                let download = http::download(&url, self.metadata.max_download_size)?;
+                let mut metadata = self.metadata;
+                if metadata.user_provided_name.is_none() {
+                    metadata.user_provided_name = Some(http::input_name(&url).into());
+                }
+                metadata.content_type = download.content_type;
+
+                Ok(OpenedInput {
+                    kind: OpenedInputKind::CustomReader,
+                    metadata,
+                    reader: InputReader::new(BufReader::new(download.body)),
+                })
+            }
         }
     }
 }
 
 impl<'a> OpenedInput<'a> {
+    /// A second reader for the whole input, which leaves the input itself unconsumed. Files are
+    /// opened a second time for this purpose, other inputs are read into memory.
+    fn second_reader(&mut self) -> Result<InputReader<'static>> {
+        let mut reader = match self.kind {
+            OpenedInputKind::OrdinaryFile(ref path) => InputReader::new(BufReader::new(
+                File::open(path).map_err(|e| format!("'{}': {}", path.to_string_lossy(), e))?,
+            )),
+            _ => {
+                let mut rest = vec![];
+                self.reader.inner.read_to_end(&mut rest)?;
+                let content = [&self.reader.first_line[..], &rest[..]].concat();
+                self.reader.inner = Box::new(io::Cursor::new(rest));
+                InputReader::new(io::Cursor::new(content))
+            }
+        };
+        reader.row_width = self.reader.row_width;
+        Ok(reader)
+    }
+
+    /// The number of lines (or rows of bytes) of the input, without consuming them
+    pub(crate) fn count_lines(&mut self) -> Result<usize> {
+        let mut counter = self.second_reader()?;
+
+        let mut lines = 0;
+        let mut line_buffer = vec![];
+        while counter.read_line(&mut line_buffer)? {
+            lines += 1;
+            line_buffer.clear();
+        }
+        Ok(lines)
+    }
+
+    /// The whole content of the input, without consuming it
+    pub(crate) fn peek_content(&mut self) -> Result<Vec<u8>> {
+        let mut reader = self.second_reader()?;
+
+        let mut content = vec![];
+        while reader.read_line(&mut content)? {}
+        Ok(content)
+    }
+
     pub fn description(&self) -> InputDescription {
         if let Some(ref name) = self.metadata.user_provided_name {
             InputDescription {
@@ -159,6 +345,9 @@ pub(crate) struct InputReader<'a> {
     inner: Box<dyn BufRead + 'a>,
     pub(crate) first_line: Vec<u8>,
     pub(crate) content_type: Option<ContentType>,
+
+    /// Read rows of this many bytes instead of lines, for hexdumps of binary content
+    pub(crate) row_width: Option<usize>,
 }
 
 impl<'a> InputReader<'a> {
@@ -180,10 +369,36 @@ impl<'a> InputReader<'a> {
             inner: Box::new(reader),
             first_line,
             content_type,
+            row_width: None,
+        }
+    }
+
+    /// The beginning of the content (the first line and everything that is currently
+    /// buffered), without consuming it.
+    pub(crate) fn peek(&mut self) -> Vec<u8> {
+        let mut content = self.first_line.clone();
+        if let Ok(buffer) = self.inner.fill_buf() {
+            content.extend_from_slice(buffer);
         }
+        content
+    }
+
+    /// Whether every line that is read ends with a newline character. Rows of bytes (see
+    /// `row_width`) don't, and neither do UTF-16LE lines (`\n\0`).
+    pub(crate) fn has_newline_terminated_lines(&self) -> bool {
+        self.row_width.is_none() && self.content_type != Some(ContentType::UTF_16LE)
     }
 
     pub(crate) fn read_line(&mut self, buf: &mut Vec<u8>) -> io::Result<bool> {
+        if let Some(width) = self.row_width {
+            let start = buf.len();
+            let from_first_line = width.min(self.first_line.len());
+            buf.extend(self.first_line.drain(..from_first_line));
+            let rest = (width - from_first_line) as u64;
+            Read::by_ref(&mut self.inner).take(rest).read_to_end(buf)?;
+            return Ok(buf.len() > start);
+        }
+
         if self.first_line.is_empty() {
             let res = self.inner.read_until(b'\n', buf).map(|size| size > 0)?;
 
@@ -256,3 +471,49 @@ fn utf16le() {
     assert_eq!(false, res.unwrap());
     assert!(buffer.is_empty());
 }
+
+#[test]
+fn peek_does_not_consume() {
+    let content = b"first\nsecond\nthird\n";
+    let mut reader = InputReader::new(&content[..]);
+
+    assert_eq!(&reader.peek()[..], &content[..]);
+
+    let mut buffer = vec![];
+    reader.read_line(&mut buffer).unwrap();
+    assert_eq!(b"first\n", &buffer[..]);
+    buffer.clear();
+    reader.read_line(&mut buffer).unwrap();
+    assert_eq!(b"second\n", &buffer[..]);
+}
+
+#[test]
+fn lines_are_counted_without_consuming_them() {
+    let mut input = Input::from_reader(Box::new(&b"first\nsecond\nthird"[..]))
+        .open(io::empty())
+        .unwrap();
+    assert_eq!(input.count_lines().unwrap(), 3);
+
+    let mut buffer = vec![];
+    let mut lines = 0;
+    while input.reader.read_line(&mut buffer).unwrap() {
+        lines += 1;
+    }
+    assert_eq!(lines, 3);
+    assert_eq!(buffer, b"first\nsecond\nthird");
+}
+
+#[test]
+fn rows_of_bytes() {
+    let content = b"ab\ncdefg";
+    let mut reader = InputReader::new(&content[..]);
+    reader.row_width = Some(4);
+
+    let mut rows = vec![];
+    let mut buffer = vec![];
+    while reader.read_line(&mut buffer).unwrap() {
+        rows.push(buffer.clone());
+        buffer.clear();
+    }
+    assert_eq!(rows, vec![b"ab\nc".to_vec(), b"defg".to_vec()]);
+}
@@ -0,0 +1,523 @@
+//! Semantic formatting of JSON-lines logs: every line that contains a JSON object is colored
+//! on its own (independently of the lines before it), optionally flattened to `key=value`
+//! pairs. The values of well-known fields like `level` and `timestamp` are tinted.
+
+use std::ops::Range;
+
+use syntect::highlighting::{Color, FontStyle, Highlighter, Style, Theme};
+use syntect::parsing::Scope;
+
+/// How lines with JSON objects are formatted
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JsonLinesMode {
+    /// Keep the JSON as it is
+    Json,
+
+    /// Print the fields as `key=value` pairs. Keys of nested fields are joined with dots.
+    Flattened,
+}
+
+const LEVEL_KEYS: &[&str] = &["level", "lvl", "severity", "loglevel"];
+const TIMESTAMP_KEYS: &[&str] = &["timestamp", "time", "ts", "@timestamp", "datetime"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum LogLevel {
+    Error,
+    Warning,
+    Info,
+    Debug,
+}
+
+impl LogLevel {
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "fatal" | "critical" | "crit" | "error" | "err" | "panic" => Some(LogLevel::Error),
+            "warning" | "warn" => Some(LogLevel::Warning),
+            "info" | "notice" => Some(LogLevel::Info),
+            "debug" | "trace" => Some(LogLevel::Debug),
+            _ => None,
+        }
+    }
+}
+
+/// The semantic role of a part of a formatted line
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Token {
+    Punctuation,
+    Key,
+    String,
+    Number,
+    Literal,
+    Timestamp,
+    Level(LogLevel),
+    Function,
+    Keyword,
+    Comment,
+}
+
+/// A line with the byte ranges of its tokens. The ranges cover the whole text.
+#[derive(Debug, PartialEq)]
+pub(crate) struct FormattedLine {
+    pub(crate) text: String,
+    pub(crate) tokens: Vec<(Range<usize>, Token)>,
+}
+
+impl FormattedLine {
+    pub(crate) fn push(&mut self, part: &str, token: Token) {
+        let start = self.text.len();
+        self.text.push_str(part);
+        self.tokens.push((start..self.text.len(), token));
+    }
+}
+
+/// The styles of the tokens, taken from the theme
+pub(crate) struct TokenStyles {
+    punctuation: Style,
+    key: Style,
+    string: Style,
+    number: Style,
+    literal: Style,
+    timestamp: Style,
+    error: Style,
+    warning: Style,
+    info: Style,
+    debug: Style,
+    function: Style,
+    keyword: Style,
+    comment: Style,
+}
+
+impl TokenStyles {
+    pub(crate) fn new(theme: &Theme) -> Self {
+        let highlighter = Highlighter::new(theme);
+        let style = |scope: &str| {
+            let scopes: Vec<Scope> = scope
+                .split_whitespace()
+                .map(|scope| Scope::new(scope).expect("valid scope"))
+                .collect();
+            highlighter.style_for_stack(&scopes)
+        };
+        let bold = |mut style: Style| {
+            style.font_style |= FontStyle::BOLD;
+            style
+        };
+
+        TokenStyles {
+            punctuation: style("source.json"),
+            key: style("source.json meta.mapping.key.json support.type.property-name.json"),
+            string: style("source.json string.quoted.double.json"),
+            number: style("source.json constant.numeric.json"),
+            literal: style("source.json constant.language.json"),
+            timestamp: style("comment"),
+            error: bold(style("markup.deleted")),
+            warning: bold(style("markup.changed")),
+            info: bold(style("markup.inserted")),
+            debug: style("comment"),
+            function: style("entity.name.function"),
+            keyword: style("keyword"),
+            comment: style("comment"),
+        }
+    }
+
+    /// Color the log levels with the given colors (instead of the colors of the theme)
+    pub(crate) fn recolor_levels(&mut self, color: impl Fn(LogLevel) -> Color) {
+        self.error.foreground = color(LogLevel::Error);
+        self.warning.foreground = color(LogLevel::Warning);
+        self.info.foreground = color(LogLevel::Info);
+        self.debug.foreground = color(LogLevel::Debug);
+    }
+
+    pub(crate) fn get(&self, token: Token) -> Style {
+        match token {
+            Token::Punctuation => self.punctuation,
+            Token::Key => self.key,
+            Token::String => self.string,
+            Token::Number => self.number,
+            Token::Literal => self.literal,
+            Token::Timestamp => self.timestamp,
+            Token::Level(LogLevel::Error) => self.error,
+            Token::Level(LogLevel::Warning) => self.warning,
+            Token::Level(LogLevel::Info) => self.info,
+            Token::Level(LogLevel::Debug) => self.debug,
+            Token::Function => self.function,
+            Token::Keyword => self.keyword,
+            Token::Comment => self.comment,
+        }
+    }
+}
+
+#[derive(Debug)]
+enum NodeKind {
+    Object(Vec<(Range<usize>, Node)>),
+    Array(Vec<Node>),
+    String,
+    Number,
+    Literal,
+}
+
+#[derive(Debug)]
+struct Node {
+    range: Range<usize>,
+    kind: NodeKind,
+}
+
+/// A minimal (validating) JSON parser that keeps the positions of all values
+struct Parser<'a> {
+    text: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn skip_whitespace(&mut self) {
+        while let Some(b' ') | Some(b'\t') | Some(b'\r') | Some(b'\n') = self.peek() {
+            self.position += 1;
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.get(self.position).cloned()
+    }
+
+    fn expect(&mut self, byte: u8) -> Option<()> {
+        self.skip_whitespace();
+        if self.peek() == Some(byte) {
+            self.position += 1;
+            Some(())
+        } else {
+            None
+        }
+    }
+
+    fn value(&mut self) -> Option<Node> {
+        self.skip_whitespace();
+        let start = self.position;
+        let kind = match self.peek()? {
+            b'{' => self.object()?,
+            b'[' => self.array()?,
+            b'"' => {
+                self.string()?;
+                NodeKind::String
+            }
+            b'-' | b'0'..=b'9' => {
+                while let Some(b'-') | Some(b'+') | Some(b'.') | Some(b'e') | Some(b'E')
+                | Some(b'0'..=b'9') = self.peek()
+                {
+                    self.position += 1;
+                }
+                NodeKind::Number
+            }
+            _ => {
+                let literal = ["true", "false", "null"]
+                    .iter()
+                    .find(|literal| self.text[start..].starts_with(literal.as_bytes()))?;
+                self.position += literal.len();
+                NodeKind::Literal
+            }
+        };
+
+        Some(Node {
+            range: start..self.position,
+            kind,
+        })
+    }
+
+    fn string(&mut self) -> Option<Range<usize>> {
+        self.skip_whitespace();
+        let start = self.position;
+        if self.peek() != Some(b'"') {
+            return None;
+        }
+        self.position += 1;
+
+        loop {
+            match self.peek()? {
+                b'"' => break,
+                b'\\' => self.position += 2,
+                _ => self.position += 1,
+            }
+        }
+        self.position += 1;
+
+        Some(start..self.position)
+    }
+
+    fn object(&mut self) -> Option<NodeKind> {
+        self.expect(b'{')?;
+        let mut fields = vec![];
+        if self.expect(b'}').is_some() {
+            return Some(NodeKind::Object(fields));
+        }
+
+        loop {
+            let key = self.string()?;
+            self.expect(b':')?;
+            fields.push((key, self.value()?));
+
+            if self.expect(b',').is_none() {
+                self.expect(b'}')?;
+                return Some(NodeKind::Object(fields));
+            }
+        }
+    }
+
+    fn array(&mut self) -> Option<NodeKind> {
+        self.expect(b'[')?;
+        let mut elements = vec![];
+        if self.expect(b']').is_some() {
+            return Some(NodeKind::Array(elements));
+        }
+
+        loop {
+            elements.push(self.value()?);
+
+            if self.expect(b',').is_none() {
+                self.expect(b']')?;
+                return Some(NodeKind::Array(elements));
+            }
+        }
+    }
+}
+
+/// Parse a line that consists of a single JSON object
+fn parse_object(line: &str) -> Option<Node> {
+    if !line.trim_start().starts_with('{') {
+        return None;
+    }
+
+    let mut parser = Parser {
+        text: line.as_bytes(),
+        position: 0,
+    };
+    let node = parser.value()?;
+    parser.skip_whitespace();
+
+    if parser.position == line.len() {
+        Some(node)
+    } else {
+        None
+    }
+}
+
+/// The content of a JSON string (without the quotes)
+fn unquote(raw: &str) -> &str {
+    &raw[1..raw.len() - 1]
+}
+
+/// The token of a scalar value of a top-level field
+fn value_token(key: Option<&str>, node: &Node, text: &str) -> Token {
+    let key = key.map(|key| key.to_ascii_lowercase());
+    let key = key.as_deref();
+
+    let is_level = key.map_or(false, |key| LEVEL_KEYS.contains(&key));
+    let is_timestamp = key.map_or(false, |key| TIMESTAMP_KEYS.contains(&key));
+
+    match node.kind {
+        NodeKind::String if is_level => {
+            match LogLevel::from_name(unquote(&text[node.range.clone()])) {
+                Some(level) => Token::Level(level),
+                None => Token::String,
+            }
+        }
+        NodeKind::String | NodeKind::Number if is_timestamp => Token::Timestamp,
+        NodeKind::String => Token::String,
+        NodeKind::Number => Token::Number,
+        _ => Token::Literal,
+    }
+}
+
+/// Collect the tokens of a value (in the original text). Only the fields of the top-level
+/// object are tinted.
+fn json_tokens(node: &Node, top_level: bool, text: &str, tokens: &mut Vec<(Range<usize>, Token)>) {
+    match node.kind {
+        NodeKind::Object(ref fields) => {
+            for (key, value) in fields {
+                tokens.push((key.clone(), Token::Key));
+                match value.kind {
+                    NodeKind::Object(_) | NodeKind::Array(_) => {
+                        json_tokens(value, false, text, tokens)
+                    }
+                    _ => {
+                        let key = Some(unquote(&text[key.clone()])).filter(|_| top_level);
+                        tokens.push((value.range.clone(), value_token(key, value, text)));
+                    }
+                }
+            }
+        }
+        NodeKind::Array(ref elements) => {
+            for element in elements {
+                json_tokens(element, false, text, tokens);
+            }
+        }
+        _ => tokens.push((node.range.clone(), value_token(None, node, text))),
+    }
+}
+
+/// Append `key=value` pairs for all scalar values (and empty objects and arrays)
+fn flatten(node: &Node, path: &str, text: &str, line: &mut FormattedLine) {
+    let join = |key: &str| {
+        if path.is_empty() {
+            key.to_owned()
+        } else {
+            format!("{}.{}", path, key)
+        }
+    };
+
+    match node.kind {
+        NodeKind::Object(ref fields) if !fields.is_empty() => {
+            for (key, value) in fields {
+                flatten(value, &join(unquote(&text[key.clone()])), text, line);
+            }
+        }
+        NodeKind::Array(ref elements) if !elements.is_empty() => {
+            for (index, element) in elements.iter().enumerate() {
+                flatten(element, &join(&index.to_string()), text, line);
+            }
+        }
+        _ => {
+            if !line.text.is_empty() {
+                line.push(" ", Token::Punctuation);
+            }
+            line.push(path, Token::Key);
+            line.push("=", Token::Punctuation);
+
+            let raw = &text[node.range.clone()];
+            let top_level_key = Some(path).filter(|path| !path.contains('.'));
+            let token = match node.kind {
+                NodeKind::Object(_) | NodeKind::Array(_) => Token::Punctuation,
+                _ => value_token(top_level_key, node, text),
+            };
+
+            // Strings are only quoted if they would be ambiguous otherwise
+            let is_string = match node.kind {
+                NodeKind::String => true,
+                _ => false,
+            };
+            let needs_quotes = is_string
+                && unquote(raw)
+                    .chars()
+                    .any(|c| c.is_whitespace() || c == '=' || c == '"' || c == '\\')
+                || raw == "\"\"";
+            let value = if is_string && !needs_quotes {
+                unquote(raw)
+            } else {
+                raw
+            };
+            line.push(value, token);
+        }
+    }
+}
+
+/// Format a line if it consists of a JSON object. Other lines are printed as usual.
+pub(crate) fn format_line(line: &str, mode: JsonLinesMode) -> Option<FormattedLine> {
+    let content = line.trim_end_matches(&['\r', '\n'][..]);
+    let line_ending = &line[content.len()..];
+    let node = parse_object(content)?;
+
+    let mut formatted = match mode {
+        JsonLinesMode::Json => {
+            let mut tokens = vec![];
+            json_tokens(&node, true, content, &mut tokens);
+            FormattedLine {
+                text: content.to_owned(),
+                tokens: cover(content.len(), tokens),
+            }
+        }
+        JsonLinesMode::Flattened => {
+            let mut formatted = FormattedLine {
+                text: String::with_capacity(content.len()),
+                tokens: vec![],
+            };
+            flatten(&node, "", content, &mut formatted);
+            formatted
+        }
+    };
+
+    if !line_ending.is_empty() {
+        formatted.push(line_ending, Token::Punctuation);
+    }
+
+    Some(formatted)
+}
+
+/// Fill the gaps between the (sorted) tokens with punctuation
+pub(crate) fn cover(
+    length: usize,
+    tokens: Vec<(Range<usize>, Token)>,
+) -> Vec<(Range<usize>, Token)> {
+    let mut result = Vec::with_capacity(2 * tokens.len() + 1);
+    let mut position = 0;
+    for (range, token) in tokens {
+        if range.start > position {
+            result.push((position..range.start, Token::Punctuation));
+        }
+        position = range.end;
+        result.push((range, token));
+    }
+    if position < length {
+        result.push((position..length, Token::Punctuation));
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parts(line: &FormattedLine) -> Vec<(&str, Token)> {
+        line.tokens
+            .iter()
+            .map(|(range, token)| (&line.text[range.clone()], *token))
+            .collect()
+    }
+
+    #[test]
+    fn only_json_objects_are_formatted() {
+        assert!(format_line("plain text\n", JsonLinesMode::Json).is_none());
+        assert!(format_line("[1, 2]\n", JsonLinesMode::Json).is_none());
+        assert!(format_line("{\"a\": 1\n", JsonLinesMode::Json).is_none());
+        assert!(format_line("{\"a\": 1} trailing\n", JsonLinesMode::Json).is_none());
+        assert!(format_line("  {}\n", JsonLinesMode::Json).is_some());
+    }
+
+    #[test]
+    fn json_lines_keep_their_text() {
+        let line = "{\"level\": \"warn\", \"ts\": 17, \"data\": {\"level\": \"x\"}}\n";
+        let formatted = format_line(line, JsonLinesMode::Json).unwrap();
+
+        assert_eq!(formatted.text, line);
+        assert_eq!(
+            parts(&formatted),
+            vec![
+                ("{", Token::Punctuation),
+                ("\"level\"", Token::Key),
+                (": ", Token::Punctuation),
+                ("\"warn\"", Token::Level(LogLevel::Warning)),
+                (", ", Token::Punctuation),
+                ("\"ts\"", Token::Key),
+                (": ", Token::Punctuation),
+                ("17", Token::Timestamp),
+                (", ", Token::Punctuation),
+                ("\"data\"", Token::Key),
+                (": {", Token::Punctuation),
+                ("\"level\"", Token::Key),
+                (": ", Token::Punctuation),
+                ("\"x\"", Token::String),
+                ("}}", Token::Punctuation),
+                ("\n", Token::Punctuation),
+            ]
+        );
+    }
+
+    #[test]
+    fn flattened_json_lines() {
+        let line = "{\"level\":\"ERROR\",\"msg\":\"disk full\",\"ctx\":{\"ids\":[1,2],\"ok\":false,\"tags\":[]}}";
+        let formatted = format_line(line, JsonLinesMode::Flattened).unwrap();
+
+        assert_eq!(
+            formatted.text,
+            "level=ERROR msg=\"disk full\" ctx.ids.0=1 ctx.ids.1=2 ctx.ok=false ctx.tags=[]"
+        );
+        assert_eq!(formatted.tokens[2].1, Token::Level(LogLevel::Error));
+        assert_eq!(formatted.tokens[6].1, Token::String);
+    }
+}
@@ -0,0 +1,213 @@
+//! Aligned formatting of simple config files with `key = value` (or tab-separated) lines,
+//! like INI, TOML or `.properties` files: the values of all keys start in the same column,
+//! and keys, section headers, values and comments are colored distinctly.
+
+use unicode_width::UnicodeWidthStr;
+
+use crate::json_lines::{FormattedLine, Token};
+
+/// Keys that are longer than this are not taken into account for the column of the values
+const MAX_KEY_WIDTH: usize = 32;
+
+/// The syntaxes of the files that are aligned by default (see `Config::align_key_values`)
+pub(crate) const KEY_VALUE_SYNTAXES: &[&str] = &["INI", "Java Properties", "TOML", "Git Config"];
+
+/// A line of a config file
+#[derive(Debug, PartialEq)]
+enum ConfigLine<'a> {
+    Section(&'a str),
+    Comment(&'a str),
+    KeyValue {
+        indentation: &'a str,
+        key: &'a str,
+        separator: Separator,
+        value: &'a str,
+    },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Separator {
+    Equals,
+    Tab,
+}
+
+fn parse_line(content: &str) -> Option<ConfigLine<'_>> {
+    let trimmed = content.trim();
+    if trimmed.starts_with('#') || trimmed.starts_with(';') {
+        return Some(ConfigLine::Comment(content));
+    }
+    if trimmed.starts_with('[') && trimmed.ends_with(']') {
+        return Some(ConfigLine::Section(content));
+    }
+
+    let indentation = &content[..content.len() - content.trim_start().len()];
+    let rest = &content[indentation.len()..];
+    let (index, separator) = match (rest.find('='), rest.find('\t')) {
+        // Tabs in front of the `=` are just padding
+        (Some(equals), Some(tab)) if tab < equals && !rest[tab..equals].trim().is_empty() => {
+            (tab, Separator::Tab)
+        }
+        (Some(equals), _) => (equals, Separator::Equals),
+        (None, Some(tab)) => (tab, Separator::Tab),
+        (None, None) => return None,
+    };
+
+    let key = rest[..index].trim_end();
+    let value = rest[index + 1..].trim();
+    if key.is_empty() || (key.starts_with('"') && !key.ends_with('"')) {
+        return None;
+    }
+
+    Some(ConfigLine::KeyValue {
+        indentation,
+        key,
+        separator,
+        value,
+    })
+}
+
+/// The column of the values of a config file. Since tabs are expanded later on, the
+/// indentation of the keys is not taken into account (only keys with the same indentation are
+/// aligned with each other).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct KeyValueLayout {
+    key_width: usize,
+}
+
+impl KeyValueLayout {
+    /// The layout of the given content, if it has `key = value` lines
+    pub(crate) fn measure(text: &str) -> Option<Self> {
+        text.lines()
+            .filter_map(|line| match parse_line(line)? {
+                ConfigLine::KeyValue { key, .. } => Some(key.width()),
+                _ => None,
+            })
+            .filter(|width| *width <= MAX_KEY_WIDTH)
+            .max()
+            .map(|key_width| KeyValueLayout { key_width })
+    }
+}
+
+/// The token of a value
+fn value_token(value: &str) -> Token {
+    let quoted = value.len() >= 2
+        && (value.starts_with('"') && value.ends_with('"')
+            || value.starts_with('\'') && value.ends_with('\''));
+
+    if quoted {
+        Token::String
+    } else if value.parse::<f64>().is_ok() {
+        Token::Number
+    } else {
+        match value.to_ascii_lowercase().as_str() {
+            "true" | "false" | "yes" | "no" | "on" | "off" => Token::Literal,
+            _ => Token::String,
+        }
+    }
+}
+
+/// Format a line of a config file with the values aligned in a column. Other lines (like
+/// continuation lines) are printed as usual.
+pub(crate) fn format_line(line: &str, layout: KeyValueLayout) -> Option<FormattedLine> {
+    let content = line.trim_end_matches(&['\r', '\n'][..]);
+    let line_ending = &line[content.len()..];
+
+    let mut formatted = FormattedLine {
+        text: String::with_capacity(content.len() + layout.key_width),
+        tokens: vec![],
+    };
+    match parse_line(content)? {
+        ConfigLine::Section(section) => formatted.push(section, Token::Keyword),
+        ConfigLine::Comment(comment) => formatted.push(comment, Token::Comment),
+        ConfigLine::KeyValue {
+            indentation,
+            key,
+            separator,
+            value,
+        } => {
+            if !indentation.is_empty() {
+                formatted.push(indentation, Token::Punctuation);
+            }
+            formatted.push(key, Token::Key);
+
+            let padding = layout.key_width.saturating_sub(key.width());
+            let separator = match separator {
+                Separator::Equals => " = ",
+                Separator::Tab => "  ",
+            };
+            formatted.push(
+                &format!("{}{}", " ".repeat(padding), separator),
+                Token::Punctuation,
+            );
+            if !value.is_empty() {
+                formatted.push(value, value_token(value));
+            }
+        }
+    }
+
+    if !line_ending.is_empty() {
+        formatted.push(line_ending, Token::Punctuation);
+    }
+
+    Some(formatted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parts(line: &FormattedLine) -> Vec<(&str, Token)> {
+        line.tokens
+            .iter()
+            .map(|(range, token)| (&line.text[range.clone()], *token))
+            .collect()
+    }
+
+    #[test]
+    fn values_are_aligned() {
+        let config = "[core]\n\tname = bat\n\teditor\t= vim  \n# comment\nbranch\tmain\n";
+        let layout = KeyValueLayout::measure(config).unwrap();
+        let text: Vec<String> = config
+            .lines()
+            .map(|line| format_line(line, layout).unwrap().text)
+            .collect();
+
+        assert_eq!(
+            text,
+            vec![
+                "[core]",
+                "\tname   = bat",
+                "\teditor = vim",
+                "# comment",
+                "branch  main",
+            ]
+        );
+    }
+
+    #[test]
+    fn tokens_of_lines() {
+        let layout = KeyValueLayout { key_width: 4 };
+
+        let line = format_line("on = true\n", layout).unwrap();
+        assert_eq!(
+            parts(&line),
+            vec![
+                ("on", Token::Key),
+                ("   = ", Token::Punctuation),
+                ("true", Token::Literal),
+                ("\n", Token::Punctuation),
+            ]
+        );
+        assert_eq!(
+            format_line("n=1.5", layout).unwrap().tokens[2].1,
+            Token::Number
+        );
+        assert_eq!(
+            format_line("[a.b]", layout).unwrap().tokens[0].1,
+            Token::Keyword
+        );
+
+        assert!(format_line("  continued", layout).is_none());
+        assert!(KeyValueLayout::measure("no keys\n").is_none());
+    }
+}
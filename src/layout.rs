@@ -0,0 +1,152 @@
+//! The geometry of the output, for tools that align their own output with the lines printed
+//! by bat (for example diagnostics below a line of code).
+
+use std::borrow::Cow;
+
+use crate::config::Config;
+use crate::style::StyleComponents;
+use crate::template::Template;
+
+/// The position of the output within a terminal that is wider than `Config::max_width`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlockAlignment {
+    Left,
+    Center,
+    Right,
+}
+
+impl Default for BlockAlignment {
+    fn default() -> Self {
+        BlockAlignment::Left
+    }
+}
+
+impl BlockAlignment {
+    /// The number of columns in front of output of the given width
+    pub(crate) fn margin(self, term_width: usize, width: usize) -> usize {
+        let space = term_width.saturating_sub(width);
+        match self {
+            BlockAlignment::Left => 0,
+            BlockAlignment::Center => space / 2,
+            BlockAlignment::Right => space,
+        }
+    }
+}
+
+/// Minimum width of the line number column (in characters)
+const LINE_NUMBER_WIDTH: usize = 4;
+
+/// The widths of the decorations (line numbers, VCS markers and the grid border) of a line
+fn decoration_widths(
+    components: &StyleComponents,
+    max_line_number: usize,
+    number_template: Option<&Template>,
+) -> Vec<usize> {
+    let mut widths = vec![];
+
+    if components.numbers() {
+        let number_width = LINE_NUMBER_WIDTH.max(max_line_number.to_string().len());
+        widths.push(number_template.map_or(number_width, |template| {
+            template.line_number_width(number_width)
+        }));
+    }
+
+    #[cfg(feature = "git")]
+    {
+        if components.changes() {
+            widths.push(1);
+        }
+    }
+
+    if components.grid() && !widths.is_empty() {
+        widths.push(1);
+    }
+
+    widths
+}
+
+/// The number of columns in front of the code, including the spaces after each decoration.
+/// The panel is not shown at all if the terminal is too small (i.e. can't fit 5 characters
+/// next to it).
+pub(crate) fn panel_width(
+    components: &StyleComponents,
+    term_width: usize,
+    max_line_number: usize,
+    number_template: Option<&Template>,
+) -> usize {
+    let widths = decoration_widths(components, max_line_number, number_template);
+    let width = widths.len() + widths.iter().sum::<usize>();
+
+    if term_width < width + 5 {
+        0
+    } else {
+        width
+    }
+}
+
+/// The number of columns in front of the code of every line (line numbers, VCS modification
+/// markers, the grid border and the spaces between them) that is printed with the given
+/// configuration. `max_line_number` is the largest line number that is printed, since line
+/// numbers with more than four digits widen the gutter.
+pub fn gutter_width(config: &Config, max_line_number: usize) -> usize {
+    if config.loop_through {
+        return 0;
+    }
+
+    let components = match config.responsive_layout {
+        Some(ref layout) => Cow::Owned(layout.apply(&config.style_components, config.term_width)),
+        None => Cow::Borrowed(&config.style_components),
+    };
+
+    panel_width(
+        &components,
+        config.term_width,
+        max_line_number,
+        config.line_number_template.as_ref(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::style::StyleComponent;
+
+    fn config(components: &[StyleComponent], term_width: usize) -> Config<'static> {
+        Config {
+            style_components: StyleComponents::new(components),
+            term_width,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn margins_of_aligned_blocks() {
+        assert_eq!(BlockAlignment::Left.margin(100, 80), 0);
+        assert_eq!(BlockAlignment::Center.margin(100, 79), 10);
+        assert_eq!(BlockAlignment::Right.margin(100, 80), 20);
+        assert_eq!(BlockAlignment::Right.margin(60, 80), 0);
+    }
+
+    #[test]
+    fn gutter_width_of_decorations() {
+        let numbers_and_grid = [StyleComponent::LineNumbers, StyleComponent::Grid];
+
+        assert_eq!(gutter_width(&config(&[], 80), 100), 0);
+        assert_eq!(gutter_width(&config(&[StyleComponent::Grid], 80), 100), 0);
+        assert_eq!(
+            gutter_width(&config(&[StyleComponent::LineNumbers], 80), 100),
+            5
+        );
+        assert_eq!(gutter_width(&config(&numbers_and_grid, 80), 100), 7);
+        assert_eq!(gutter_width(&config(&numbers_and_grid, 80), 123_456), 9);
+
+        // Not enough space for the panel
+        assert_eq!(gutter_width(&config(&numbers_and_grid, 11), 100), 0);
+
+        let templated = Config {
+            line_number_template: Some(Template::line_number("L{number}:").unwrap()),
+            ..config(&numbers_and_grid, 80)
+        };
+        assert_eq!(gutter_width(&templated, 100), 9);
+    }
+}
@@ -19,29 +19,81 @@
 //!     .unwrap();
 //! ```
 
+pub mod accessibility;
+pub mod ansi;
+mod asciicast;
+pub mod asset_dirs;
+pub mod asset_store;
 pub mod assets;
 pub mod assets_metadata;
+pub mod card;
 pub mod config;
+pub mod config_layers;
+mod contrast;
 pub mod controller;
 mod decorations;
 mod diff;
+mod embedded;
 pub mod error;
+pub mod gutter;
+mod heuristics;
+mod hexdump;
+pub mod history;
+mod html_report;
+#[cfg(feature = "http")]
+mod http;
+pub mod hunk;
 pub mod input;
+mod json_lines;
+mod key_values;
+pub mod layout;
 mod less;
+mod line_diff;
+pub mod line_processor;
 pub mod line_range;
+mod links;
+mod long_tokens;
+mod markdown;
+pub mod match_list;
 mod output;
 #[cfg(feature = "paging")]
 pub(crate) mod paging;
+mod patch;
+pub mod patterns;
+pub mod pipeline;
 mod preprocessor;
 mod pretty_printer;
 pub(crate) mod printer;
+pub mod project_config;
+mod regions;
+pub mod repository;
+mod result_cache;
+#[cfg(feature = "serve")]
+pub mod serve;
+pub mod snippet;
+pub mod split;
+pub mod structured_diff;
 pub mod style;
+pub mod suggestions;
+mod symbols;
 pub(crate) mod syntax_mapping;
+pub mod template;
 mod terminal;
+pub mod terminal_features;
+pub mod theme_export;
+mod theme_fallback;
+pub mod theme_overrides;
+pub mod trace;
+pub mod warning;
 pub(crate) mod wrapping;
 
-pub use pretty_printer::PrettyPrinter;
-pub use syntax_mapping::{MappingTarget, SyntaxMapping};
+pub use controller::{InputResult, InputSize};
+pub use embedded::EmbeddedLanguage;
+pub use hexdump::BinaryMode;
+pub use json_lines::JsonLinesMode;
+pub use output::{BufferingMode, LineEnding, OutputEncoding};
+pub use pretty_printer::{PrettyPrinter, PrettyPrinterSnapshot};
+pub use syntax_mapping::{ConflictResolution, Disambiguator, MappingTarget, SyntaxMapping};
 pub use wrapping::WrappingMode;
 
 #[cfg(feature = "paging")]
@@ -0,0 +1,120 @@
+//! A line diff of two inputs, as the rows of a side-by-side view.
+
+use std::ops::Range;
+
+/// A row of a side-by-side diff, with the (zero-based) indices of the old and new lines
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum DiffRow {
+    Unchanged(usize, usize),
+    Removed(usize),
+    Added(usize),
+
+    /// A removed line next to the added line that replaces it
+    Changed(usize, usize),
+}
+
+/// The largest number of cells of the table for the longest common subsequence. Larger
+/// changes are shown as replaced blocks of lines.
+const MAX_TABLE_SIZE: usize = 4_000_000;
+
+/// The rows of a side-by-side diff of the lines. Removed lines that are directly followed by
+/// added lines are paired up as changed lines.
+pub(crate) fn diff_rows<T: PartialEq>(old: &[T], new: &[T]) -> Vec<DiffRow> {
+    let prefix = old.iter().zip(new).take_while(|(o, n)| o == n).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(o, n)| o == n)
+        .count();
+    let old_middle = &old[prefix..old.len() - suffix];
+    let new_middle = &new[prefix..new.len() - suffix];
+
+    let (old_end, new_end) = (old.len() - suffix, new.len() - suffix);
+
+    let mut rows: Vec<DiffRow> = (0..prefix).map(|i| DiffRow::Unchanged(i, i)).collect();
+    let (mut old_next, mut new_next) = (prefix, prefix);
+    for (old_index, new_index) in common_subsequence(old_middle, new_middle)
+        .into_iter()
+        .map(|(o, n)| (o + prefix, n + prefix))
+        .chain(std::iter::once((old_end, new_end)))
+    {
+        push_changes(&mut rows, old_next..old_index, new_next..new_index);
+        if old_index < old_end {
+            rows.push(DiffRow::Unchanged(old_index, new_index));
+        }
+        old_next = old_index + 1;
+        new_next = new_index + 1;
+    }
+
+    rows.extend((0..suffix).map(|i| DiffRow::Unchanged(old_end + i, new_end + i)));
+    rows
+}
+
+/// The rows for a block of removed and added lines
+fn push_changes(rows: &mut Vec<DiffRow>, removed: Range<usize>, added: Range<usize>) {
+    let pairs = removed.len().min(added.len());
+    rows.extend((0..pairs).map(|i| DiffRow::Changed(removed.start + i, added.start + i)));
+    rows.extend((removed.start + pairs..removed.end).map(DiffRow::Removed));
+    rows.extend((added.start + pairs..added.end).map(DiffRow::Added));
+}
+
+/// The index pairs of a longest common subsequence of the lines (none if the table would be
+/// too large)
+fn common_subsequence<T: PartialEq>(old: &[T], new: &[T]) -> Vec<(usize, usize)> {
+    if old.is_empty() || new.is_empty() || (old.len() + 1) * (new.len() + 1) > MAX_TABLE_SIZE {
+        return vec![];
+    }
+
+    // lengths[i][j]: the length of the longest common subsequence of old[i..] and new[j..]
+    let width = new.len() + 1;
+    let mut lengths = vec![0u32; (old.len() + 1) * width];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lengths[i * width + j] = if old[i] == new[j] {
+                lengths[(i + 1) * width + j + 1] + 1
+            } else {
+                lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut pairs = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            pairs.push((i, j));
+            i += 1;
+            j += 1;
+        } else if lengths[(i + 1) * width + j] >= lengths[i * width + j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs
+}
+
+#[test]
+fn rows_of_a_diff() {
+    use DiffRow::*;
+
+    let old = ["a", "b", "c", "d", "e"];
+    let new = ["a", "c", "x", "y", "e", "f"];
+    assert_eq!(
+        diff_rows(&old, &new),
+        vec![
+            Unchanged(0, 0),
+            Removed(1),
+            Unchanged(2, 1),
+            Changed(3, 2),
+            Added(3),
+            Unchanged(4, 4),
+            Added(5)
+        ]
+    );
+
+    assert_eq!(diff_rows(&old, &old).len(), 5);
+    assert_eq!(diff_rows::<&str>(&[], &["a"]), vec![Added(0)]);
+    assert_eq!(diff_rows(&["a"], &[]), vec![Removed(0)]);
+}
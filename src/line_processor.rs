@@ -0,0 +1,81 @@
+//! Custom transformations of the printed lines (see `PrettyPrinter::line_processor`), like
+//! redacting secrets or adding inline annotations. The processors get the styled segments of
+//! every line after it was highlighted and before the decorations (line numbers, the grid)
+//! are added, so they can change the text as well as the styles.
+
+use std::fmt;
+use std::sync::Arc;
+
+use syntect::highlighting::Style;
+
+/// A part of a line with its style. The last segment of a line ends with the line ending (if
+/// the line has one).
+pub type StyledSegment = (Style, String);
+
+/// Transforms the segments of a line. The printer may be shared between threads when inputs
+/// are printed in parallel, so processors with state need to synchronize it.
+pub trait LineProcessor: Send + Sync {
+    fn process(&self, line_number: usize, segments: Vec<StyledSegment>) -> Vec<StyledSegment>;
+}
+
+impl<F> LineProcessor for F
+where
+    F: Fn(usize, Vec<StyledSegment>) -> Vec<StyledSegment> + Send + Sync,
+{
+    fn process(&self, line_number: usize, segments: Vec<StyledSegment>) -> Vec<StyledSegment> {
+        self(line_number, segments)
+    }
+}
+
+/// A chain of processors, which are called in the order in which they were added
+#[derive(Clone, Default)]
+pub struct LineProcessors(Vec<Arc<dyn LineProcessor>>);
+
+impl LineProcessors {
+    pub fn push(&mut self, processor: Arc<dyn LineProcessor>) {
+        self.0.push(processor);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Pass the regions of a line through all processors
+    pub(crate) fn process(
+        &self,
+        line_number: usize,
+        regions: &[(Style, &str)],
+    ) -> Vec<StyledSegment> {
+        let segments = regions
+            .iter()
+            .map(|(style, text)| (*style, (*text).to_owned()))
+            .collect();
+        self.0.iter().fold(segments, |segments, processor| {
+            processor.process(line_number, segments)
+        })
+    }
+}
+
+impl fmt::Debug for LineProcessors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "LineProcessors({})", self.0.len())
+    }
+}
+
+#[test]
+fn processors_are_chained_in_order() {
+    let mut processors = LineProcessors::default();
+    processors.push(Arc::new(|_, mut segments: Vec<StyledSegment>| {
+        segments[0].1 = segments[0].1.replace("secret", "******");
+        segments
+    }));
+    processors.push(Arc::new(|line_number, mut segments: Vec<StyledSegment>| {
+        segments.push((Style::default(), format!(" # line {}", line_number)));
+        segments
+    }));
+
+    let segments = processors.process(3, &[(Style::default(), "token = secret")]);
+    let text: Vec<&str> = segments.iter().map(|(_, text)| text.as_str()).collect();
+    assert_eq!(text, vec!["token = ******", " # line 3"]);
+    assert_eq!(format!("{:?}", processors), "LineProcessors(2)");
+}
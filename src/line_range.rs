@@ -1,9 +1,17 @@
 use crate::error::*;
 
+/// A range of lines. Its bounds are line numbers or count from the end of the input (`-1` is
+/// the last line), like in `-50:` for the last fifty lines. Ranges that are relative to the
+/// end contain no lines until they are resolved with the number of lines of the input (see
+/// `LineRanges::resolve`).
 #[derive(Debug, Clone)]
 pub struct LineRange {
     lower: usize,
     upper: usize,
+
+    /// Whether the bounds count from the end of the input (starting with 1 for the last line)
+    lower_from_end: bool,
+    upper_from_end: bool,
 }
 
 impl Default for LineRange {
@@ -11,6 +19,8 @@ impl Default for LineRange {
         LineRange {
             lower: usize::min_value(),
             upper: usize::max_value(),
+            lower_from_end: false,
+            upper_from_end: false,
         }
     }
 }
@@ -20,6 +30,27 @@ impl LineRange {
         LineRange {
             lower: from,
             upper: to,
+            ..LineRange::default()
+        }
+    }
+
+    /// The last lines of the input (like `-N:`)
+    pub fn last(lines: usize) -> Self {
+        LineRange {
+            lower: lines,
+            lower_from_end: true,
+            ..LineRange::default()
+        }
+    }
+
+    /// The lines from the given line to the end of the input, without the given number of
+    /// lines at the end (like `N:-M` with `M = lines + 1`)
+    pub fn excluding_last(from: usize, lines: usize) -> Self {
+        LineRange {
+            lower: from,
+            upper: lines + 1,
+            upper_from_end: true,
+            ..LineRange::default()
         }
     }
 
@@ -31,23 +62,34 @@ impl LineRange {
         let mut new_range = LineRange::default();
 
         if range_raw.bytes().next().ok_or("Empty line range")? == b':' {
-            new_range.upper = range_raw[1..].parse()?;
+            let (upper, from_end) = Self::parse_bound(&range_raw[1..])?;
+            new_range.upper = upper;
+            new_range.upper_from_end = from_end;
             return Ok(new_range);
         } else if range_raw.bytes().last().ok_or("Empty line range")? == b':' {
-            new_range.lower = range_raw[..range_raw.len() - 1].parse()?;
+            let (lower, from_end) = Self::parse_bound(&range_raw[..range_raw.len() - 1])?;
+            new_range.lower = lower;
+            new_range.lower_from_end = from_end;
             return Ok(new_range);
         }
 
         let line_numbers: Vec<&str> = range_raw.split(':').collect();
         match line_numbers.len() {
             1 => {
-                new_range.lower = line_numbers[0].parse()?;
-                new_range.upper = new_range.lower;
+                let (line, from_end) = Self::parse_bound(line_numbers[0])?;
+                new_range.lower = line;
+                new_range.upper = line;
+                new_range.lower_from_end = from_end;
+                new_range.upper_from_end = from_end;
                 Ok(new_range)
             }
             2 => {
-                new_range.lower = line_numbers[0].parse()?;
-                new_range.upper = line_numbers[1].parse()?;
+                let (lower, lower_from_end) = Self::parse_bound(line_numbers[0])?;
+                let (upper, upper_from_end) = Self::parse_bound(line_numbers[1])?;
+                new_range.lower = lower;
+                new_range.upper = upper;
+                new_range.lower_from_end = lower_from_end;
+                new_range.upper_from_end = upper_from_end;
                 Ok(new_range)
             }
             _ => Err(
@@ -57,8 +99,68 @@ impl LineRange {
         }
     }
 
+    /// A line number, or the number of a line counted from the end (`-N`)
+    fn parse_bound(bound: &str) -> Result<(usize, bool)> {
+        if bound.starts_with('-') {
+            return match bound[1..].parse()? {
+                0 => Err("Lines from the end of the file are counted from -1".into()),
+                line => Ok((line, true)),
+            };
+        }
+        Ok((bound.parse()?, false))
+    }
+
+    /// Whether one of the bounds counts from the end of the input
+    pub(crate) fn is_relative(&self) -> bool {
+        self.lower_from_end || self.upper_from_end
+    }
+
+    /// The range with line numbers instead of bounds that count from the end, for an input
+    /// with the given number of lines
+    fn resolve(&self, lines: usize) -> LineRange {
+        let line_number = |bound: usize, from_end: bool| {
+            if from_end {
+                (lines + 1).saturating_sub(bound)
+            } else {
+                bound
+            }
+        };
+        LineRange::new(
+            line_number(self.lower, self.lower_from_end),
+            line_number(self.upper, self.upper_from_end),
+        )
+    }
+
     pub(crate) fn is_inside(&self, line: usize) -> bool {
-        line >= self.lower && line <= self.upper
+        !self.is_relative() && line >= self.lower && line <= self.upper
+    }
+
+    /// Whether the two ranges have any lines in common (which is assumed for ranges that are
+    /// relative to the end)
+    pub(crate) fn overlaps(&self, other: &LineRange) -> bool {
+        self.is_relative()
+            || other.is_relative()
+            || (self.lower <= other.upper && other.lower <= self.upper)
+    }
+
+    /// The range in the syntax of `--line-range`
+    pub(crate) fn describe(&self) -> String {
+        let unbounded = LineRange::default();
+        let bound = |line: usize, from_end: bool| {
+            if from_end {
+                format!("-{}", line)
+            } else {
+                line.to_string()
+            }
+        };
+        let lower = bound(self.lower, self.lower_from_end);
+        let upper = bound(self.upper, self.upper_from_end);
+        match (self.lower, self.upper) {
+            _ if lower == upper => lower,
+            (_, line) if line == unbounded.upper && !self.upper_from_end => format!("{}:", lower),
+            (line, _) if line == unbounded.lower && !self.lower_from_end => format!(":{}", upper),
+            _ => format!("{}:{}", lower, upper),
+        }
     }
 }
 
@@ -90,6 +192,26 @@ fn test_parse_single() {
     assert_eq!(40, range.upper);
 }
 
+#[test]
+fn test_parse_from_end() {
+    let range = LineRange::from("-50:").expect("Shouldn't fail on test!");
+    assert_eq!(50, range.lower);
+    assert!(range.lower_from_end);
+    assert_eq!(usize::max_value(), range.upper);
+    assert_eq!(range.describe(), "-50:");
+
+    let range = LineRange::from("10:-6").expect("Shouldn't fail on test!");
+    assert_eq!(10, range.lower);
+    assert!(!range.lower_from_end);
+    assert_eq!(6, range.upper);
+    assert!(range.upper_from_end);
+    assert_eq!(range.describe(), "10:-6");
+
+    assert_eq!(LineRange::from("-1").unwrap().describe(), "-1");
+    assert_eq!(LineRange::from(":-2").unwrap().describe(), ":-2");
+    assert!(LineRange::from("-0:").is_err());
+}
+
 #[test]
 fn test_parse_fail() {
     let range = LineRange::from("40:50:80");
@@ -130,7 +252,13 @@ impl LineRanges {
     pub fn from(ranges: Vec<LineRange>) -> LineRanges {
         let largest_upper_bound = ranges
             .iter()
-            .map(|r| r.upper)
+            .map(|r| {
+                if r.upper_from_end {
+                    usize::max_value()
+                } else {
+                    r.upper
+                }
+            })
             .max()
             .unwrap_or(usize::max_value());
         LineRanges {
@@ -139,6 +267,30 @@ impl LineRanges {
         }
     }
 
+    pub(crate) fn ranges(&self) -> &[LineRange] {
+        &self.ranges
+    }
+
+    /// Whether every line is included in one of the ranges
+    pub(crate) fn is_all(&self) -> bool {
+        let unbounded = LineRange::default().upper;
+        self.ranges
+            .iter()
+            .any(|r| !r.is_relative() && r.lower <= 1 && r.upper == unbounded)
+    }
+
+    /// Whether one of the ranges counts from the end of the input, so that the ranges have to
+    /// be resolved with the number of lines of the input before they are checked
+    pub(crate) fn is_relative(&self) -> bool {
+        self.ranges.iter().any(LineRange::is_relative)
+    }
+
+    /// The ranges with line numbers instead of bounds that count from the end, for an input
+    /// with the given number of lines
+    pub(crate) fn resolve(&self, lines: usize) -> LineRanges {
+        LineRanges::from(self.ranges.iter().map(|r| r.resolve(lines)).collect())
+    }
+
     pub(crate) fn check(&self, line: usize) -> RangeCheckResult {
         if self.ranges.iter().any(|r| r.is_inside(line)) {
             RangeCheckResult::InRange
@@ -212,6 +364,29 @@ fn test_ranges_open_high() {
     assert_eq!(RangeCheckResult::InRange, ranges.check(9));
 }
 
+#[test]
+fn test_ranges_from_end() {
+    let ranges = ranges(&["-3:", "2:4"]);
+    assert!(ranges.is_relative());
+    assert_eq!(RangeCheckResult::BeforeOrBetweenRanges, ranges.check(5));
+
+    let resolved = ranges.resolve(10);
+    assert!(!resolved.is_relative());
+    assert_eq!(RangeCheckResult::BeforeOrBetweenRanges, resolved.check(1));
+    assert_eq!(RangeCheckResult::InRange, resolved.check(3));
+    assert_eq!(RangeCheckResult::BeforeOrBetweenRanges, resolved.check(7));
+    assert_eq!(RangeCheckResult::InRange, resolved.check(8));
+    assert_eq!(RangeCheckResult::InRange, resolved.check(10));
+
+    let resolved = LineRanges::from(vec![LineRange::excluding_last(3, 5)]).resolve(10);
+    assert_eq!(RangeCheckResult::InRange, resolved.check(5));
+    assert_eq!(RangeCheckResult::AfterLastRange, resolved.check(6));
+
+    // More lines from the end than the input has
+    let resolved = LineRanges::from(vec![LineRange::last(50)]).resolve(10);
+    assert_eq!(RangeCheckResult::InRange, resolved.check(1));
+}
+
 #[test]
 fn test_ranges_all() {
     let ranges = LineRanges::all();
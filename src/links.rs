@@ -0,0 +1,151 @@
+//! Hyperlinks for the URLs (and optionally the file paths) in the content, as OSC 8 escape
+//! sequences (see `Config::linkify`). Terminals that support them make the links clickable,
+//! the visible text doesn't change.
+
+use std::fmt;
+use std::fs;
+use std::ops::Range;
+
+/// The URL schemes that are linked
+const SCHEMES: &[&str] = &["https://", "http://", "ftp://", "file://"];
+
+/// A link in a line
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Link {
+    /// The byte range of the text of the link
+    pub(crate) range: Range<usize>,
+    pub(crate) target: String,
+}
+
+fn is_link_char(c: char) -> bool {
+    !c.is_whitespace() && !c.is_control() && !"<>\"'`\\^{}|".contains(c)
+}
+
+/// The end of a link that starts at `start`: the first character that can't be part of a
+/// link, without trailing punctuation and closing brackets that are not opened within the
+/// link (like in `(see https://example.com)`).
+fn link_end(line: &str, start: usize) -> usize {
+    let mut end = line[start..]
+        .find(|c: char| !is_link_char(c))
+        .map_or(line.len(), |index| start + index);
+
+    let unbalanced = |text: &str, open: char, close: char| {
+        text.matches(open).count() < text.matches(close).count()
+    };
+    loop {
+        let text = &line[start..end];
+        match text.chars().next_back() {
+            Some('.') | Some(',') | Some(';') | Some(':') | Some('!') | Some('?') => end -= 1,
+            Some(')') if unbalanced(text, '(', ')') => end -= 1,
+            Some(']') if unbalanced(text, '[', ']') => end -= 1,
+            _ => return end,
+        }
+    }
+}
+
+/// The target of a file path, if the file exists
+fn path_target(path: &str) -> Option<String> {
+    let path = fs::canonicalize(path).ok()?;
+    Some(format!("file://{}", path.to_string_lossy()))
+}
+
+/// The links in a line: URLs and (if enabled) absolute paths and paths that start with `./`
+/// or `../` of files that exist.
+pub(crate) fn find_links(line: &str, paths: bool) -> Vec<Link> {
+    let mut links = vec![];
+    let mut previous: Option<char> = None;
+    let mut position = 0;
+
+    while let Some(c) = line[position..].chars().next() {
+        let rest = &line[position..];
+        let mut link = None;
+
+        if !previous.map_or(false, char::is_alphanumeric) {
+            if let Some(scheme) = SCHEMES.iter().find(|scheme| rest.starts_with(*scheme)) {
+                let end = link_end(line, position);
+                if end > position + scheme.len() {
+                    link = Some(Link {
+                        range: position..end,
+                        target: line[position..end].to_owned(),
+                    });
+                }
+            }
+        }
+
+        let starts_path =
+            rest.starts_with('/') || rest.starts_with("./") || rest.starts_with("../");
+        if link.is_none() && paths && starts_path && !previous.map_or(false, is_link_char) {
+            let end = link_end(line, position);
+            if end > position + 1 {
+                link = path_target(&line[position..end]).map(|target| Link {
+                    range: position..end,
+                    target,
+                });
+            }
+        }
+
+        match link {
+            Some(link) => {
+                position = link.range.end;
+                previous = line[..position].chars().next_back();
+                links.push(link);
+            }
+            None => {
+                position += c.len_utf8();
+                previous = Some(c);
+            }
+        }
+    }
+
+    links
+}
+
+/// Text that is printed as a hyperlink to the target (if there is one)
+pub(crate) struct Hyperlink<'a, T: fmt::Display> {
+    target: Option<&'a str>,
+    text: T,
+}
+
+pub(crate) fn hyperlink<T: fmt::Display>(target: Option<&str>, text: T) -> Hyperlink<'_, T> {
+    Hyperlink { target, text }
+}
+
+impl<'a, T: fmt::Display> fmt::Display for Hyperlink<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.target {
+            Some(target) => write!(f, "\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", target, self.text),
+            None => write!(f, "{}", self.text),
+        }
+    }
+}
+
+#[test]
+fn urls_are_found() {
+    let line = "see https://example.com/a_(b) (or http://x.org/?q=1), not xhttp://y.org";
+    let links = find_links(line, false);
+    let texts: Vec<&str> = links.iter().map(|link| &line[link.range.clone()]).collect();
+    assert_eq!(
+        texts,
+        vec!["https://example.com/a_(b)", "http://x.org/?q=1"]
+    );
+    assert_eq!(links[1].target, "http://x.org/?q=1");
+
+    assert_eq!(find_links("https:// is not a link", false), vec![]);
+    assert_eq!(find_links("\"Cargo.toml\" ./Cargo.toml", false), vec![]);
+
+    assert_eq!(
+        hyperlink(Some("https://example.com"), "example").to_string(),
+        "\x1b]8;;https://example.com\x1b\\example\x1b]8;;\x1b\\"
+    );
+    assert_eq!(hyperlink(None, "example").to_string(), "example");
+}
+
+#[test]
+fn paths_of_existing_files_are_found() {
+    let line = "in ./Cargo.toml. and ./missing.toml or src/lib.rs";
+    let links = find_links(line, true);
+    assert_eq!(links.len(), 1);
+    assert_eq!(&line[links[0].range.clone()], "./Cargo.toml");
+    assert!(links[0].target.starts_with("file:///"));
+    assert!(links[0].target.ends_with("Cargo.toml"));
+}
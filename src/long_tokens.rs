@@ -0,0 +1,152 @@
+//! Elision of extremely long tokens (like base64 blobs) in the middle, to keep files with
+//! embedded keys, certificates or data URIs skimmable.
+
+use std::borrow::Cow;
+use std::ops::Range;
+
+use syntect::highlighting::Style;
+
+use crate::regions::replace_ranges;
+
+/// Byte ranges of the elided middle parts of all tokens (runs of non-whitespace characters)
+/// with more than `max_length` characters. The first and the last `max_length / 2`
+/// characters of such tokens are kept.
+pub(crate) fn elided_ranges(line: &str, max_length: usize) -> Vec<Range<usize>> {
+    let keep = max_length / 2;
+    let mut ranges = vec![];
+
+    let mut token_start = None;
+    for (index, c) in line
+        .char_indices()
+        .chain(std::iter::once((line.len(), ' ')))
+    {
+        match (token_start, c.is_whitespace()) {
+            (None, false) => token_start = Some(index),
+            (Some(start), true) => {
+                let token = &line[start..index];
+                if token.len() > max_length {
+                    let offsets: Vec<usize> = token.char_indices().map(|(i, _)| i).collect();
+                    if offsets.len() > max_length {
+                        let elided_end = if keep == 0 {
+                            token.len()
+                        } else {
+                            offsets[offsets.len() - keep]
+                        };
+                        ranges.push(start + offsets[keep]..start + elided_end);
+                    }
+                }
+                token_start = None;
+            }
+            _ => {}
+        }
+    }
+
+    ranges
+}
+
+/// The text that replaces an elided range
+pub(crate) fn marker(range: &Range<usize>) -> String {
+    format!("<…{} bytes…>", range.end - range.start)
+}
+
+/// Replace the `ranges` (see `elided_ranges`) of a highlighted line by the given `markers`,
+/// which are printed in the `marker_style`
+pub(crate) fn elide_regions<'a>(
+    regions: &[(Style, &'a str)],
+    ranges: &[Range<usize>],
+    markers: &'a [String],
+    marker_style: Style,
+) -> Vec<(Style, &'a str)> {
+    let replacements: Vec<_> = ranges
+        .iter()
+        .zip(markers.iter())
+        .map(|(range, marker)| (range.clone(), vec![(marker_style, marker.as_str())]))
+        .collect();
+
+    replace_ranges(regions, &replacements)
+}
+
+/// The line as it is printed with the given `ranges` elided
+pub(crate) fn elide_line<'a>(line: &'a str, ranges: &[Range<usize>]) -> Cow<'a, str> {
+    if ranges.is_empty() {
+        return Cow::Borrowed(line);
+    }
+
+    let mut result = String::with_capacity(line.len());
+    let mut position = 0;
+    for range in ranges {
+        result.push_str(&line[position..range.start]);
+        result.push_str(&marker(range));
+        position = range.end;
+    }
+    result.push_str(&line[position..]);
+    Cow::Owned(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn elide(line: &str, max_length: usize) -> String {
+        elide_line(line, &elided_ranges(line, max_length)).into_owned()
+    }
+
+    #[test]
+    fn long_tokens_are_elided() {
+        assert_eq!(elide("short words only\n", 8), "short words only\n");
+        assert_eq!(
+            elide("key: 0123456789abcdef\n", 8),
+            "key: 0123<…8 bytes…>cdef\n"
+        );
+        assert_eq!(elide("ääääääääää x", 4), "ää<…12 bytes…>ää x");
+        assert_eq!(elide("0123456789", 0), "<…10 bytes…>");
+    }
+
+    #[test]
+    fn regions_keep_their_style() {
+        let plain = Style::default();
+        let mut string = Style::default();
+        string.foreground.r = 1;
+        let mut marker_style = Style::default();
+        marker_style.foreground.r = 2;
+
+        let line = "x = \"0123456789abcdef\"\n";
+        let regions = [
+            (plain, "x = "),
+            (string, "\"0123456789abcdef\""),
+            (plain, "\n"),
+        ];
+        let ranges = elided_ranges(line, 8);
+        let markers: Vec<String> = ranges.iter().map(marker).collect();
+
+        assert_eq!(
+            elide_regions(&regions, &ranges, &markers, marker_style),
+            vec![
+                (plain, "x = "),
+                (string, "\"012"),
+                (marker_style, "<…10 bytes…>"),
+                (string, "def\""),
+                (plain, "\n"),
+            ]
+        );
+    }
+
+    #[test]
+    fn ranges_can_span_regions() {
+        let a = Style::default();
+        let mut b = Style::default();
+        b.foreground.r = 1;
+        let mut marker_style = Style::default();
+        marker_style.foreground.r = 2;
+
+        let line = "0123456789abcdef";
+        let regions = [(a, "01234567"), (b, "89abcdef")];
+        let ranges = elided_ranges(line, 4);
+        let markers: Vec<String> = ranges.iter().map(marker).collect();
+
+        assert_eq!(
+            elide_regions(&regions, &ranges, &markers, marker_style),
+            vec![(a, "01"), (marker_style, "<…12 bytes…>"), (b, "ef")]
+        );
+    }
+}
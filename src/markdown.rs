@@ -0,0 +1,209 @@
+//! Fenced code blocks of Markdown files, for `Config::markdown_code_blocks`: the content of a
+//! block is highlighted with the syntax of the language in its info string (like
+//! ```` ```rust ````), the prose around the blocks with few styles.
+
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{Color, FontStyle, Style, Theme};
+use syntect::parsing::SyntaxSet;
+
+/// The line that opens a code block
+#[derive(Debug, PartialEq)]
+pub(crate) struct Fence {
+    /// The fence characters (three or more backticks or tildes)
+    marker: String,
+
+    /// The first word of the info string (without the braces and the dot of `{.python}`)
+    pub(crate) language: Option<String>,
+}
+
+/// The fence of a line that consists of the fence characters (indented by at most three
+/// spaces), optionally followed by an info string
+fn fence_prefix(line: &str) -> Option<(&str, &str)> {
+    let content = line.trim_end_matches(&['\r', '\n'][..]);
+    let trimmed = content.trim_start_matches(' ');
+    if content.len() - trimmed.len() > 3 {
+        return None;
+    }
+
+    let fence_char = match trimmed.chars().next()? {
+        c @ '`' | c @ '~' => c,
+        _ => return None,
+    };
+    let length = trimmed.len() - trimmed.trim_start_matches(fence_char).len();
+    if length < 3 {
+        return None;
+    }
+    Some(trimmed.split_at(length))
+}
+
+impl Fence {
+    /// The fence of a line that opens a code block
+    pub(crate) fn parse(line: &str) -> Option<Self> {
+        let (marker, info) = fence_prefix(line)?;
+
+        // The info strings of backtick fences can not contain backticks (that would be inline
+        // code instead)
+        if marker.starts_with('`') && info.contains('`') {
+            return None;
+        }
+
+        let language = info
+            .split_whitespace()
+            .next()
+            .map(|word| word.trim_start_matches('{').trim_start_matches('.'))
+            .map(|word| word.split(&[',', '}'][..]).next().unwrap_or(word))
+            .filter(|word| !word.is_empty())
+            .map(String::from);
+        Some(Fence {
+            marker: marker.to_owned(),
+            language,
+        })
+    }
+
+    /// Whether the line closes the code block of this fence: a fence of the same character
+    /// that is at least as long, without an info string
+    pub(crate) fn is_closed_by(&self, line: &str) -> bool {
+        match fence_prefix(line) {
+            Some((marker, rest)) => {
+                marker.starts_with(&self.marker[..1])
+                    && marker.len() >= self.marker.len()
+                    && rest.trim().is_empty()
+            }
+            None => false,
+        }
+    }
+}
+
+/// The highlighting of a Markdown file with fenced code blocks
+pub(crate) struct CodeBlocks<'a> {
+    syntax_set: &'a SyntaxSet,
+    theme: &'a Theme,
+
+    /// The color of the prose
+    foreground: Color,
+
+    /// The open code block, with a highlighter if its language is known
+    block: Option<(Fence, Option<HighlightLines<'a>>)>,
+}
+
+impl<'a> CodeBlocks<'a> {
+    pub(crate) fn new(syntax_set: &'a SyntaxSet, theme: &'a Theme) -> Self {
+        CodeBlocks {
+            syntax_set,
+            theme,
+            foreground: theme.settings.foreground.unwrap_or(Color::WHITE),
+            block: None,
+        }
+    }
+
+    /// The regions of a line, given the regions of the Markdown syntax: lines in code blocks
+    /// are highlighted with the language of the block, prose is printed in the foreground
+    /// color (with the bold and italic text of the Markdown syntax, and bold headings).
+    pub(crate) fn regions<'t>(
+        &mut self,
+        line: &'t str,
+        markdown: Vec<(Style, &'t str)>,
+    ) -> Vec<(Style, &'t str)> {
+        let foreground = self.foreground;
+        let prose = |font_style| Style {
+            foreground,
+            background: Color::BLACK,
+            font_style,
+        };
+
+        let mut closed = false;
+        if let Some((ref fence, ref mut highlighter)) = self.block {
+            if fence.is_closed_by(line) {
+                closed = true;
+            } else if let Some(highlighter) = highlighter {
+                return highlighter.highlight(line, self.syntax_set);
+            } else {
+                return vec![(prose(FontStyle::empty()), line)];
+            }
+        }
+
+        if closed {
+            self.block = None;
+        } else if let Some(fence) = Fence::parse(line) {
+            let highlighter = fence
+                .language
+                .as_deref()
+                .and_then(|language| self.syntax_set.find_syntax_by_token(language))
+                .map(|syntax| HighlightLines::new(syntax, self.theme));
+            self.block = Some((fence, highlighter));
+        }
+
+        let heading = line.starts_with('#') && self.block.is_none() && !closed;
+        markdown
+            .into_iter()
+            .map(|(style, text)| {
+                let mut font_style = style.font_style & (FontStyle::BOLD | FontStyle::ITALIC);
+                if heading {
+                    font_style |= FontStyle::BOLD;
+                }
+                (prose(font_style), text)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fences_are_parsed() {
+        let fence = Fence::parse("```rust ignore\n").unwrap();
+        assert_eq!(fence.language.as_deref(), Some("rust"));
+        let language = |line: &str| Fence::parse(line).unwrap().language;
+        assert_eq!(language("  ~~~~ {.python}\n").as_deref(), Some("python"));
+        assert_eq!(language("```{r, echo=FALSE}").as_deref(), Some("r"));
+        assert_eq!(language("```\n"), None);
+
+        assert!(Fence::parse("``not a fence``").is_none());
+        assert!(Fence::parse("```a`b```").is_none());
+        assert!(Fence::parse("    ```indented code").is_none());
+    }
+
+    #[test]
+    fn fences_are_closed_by_matching_fences() {
+        let fence = Fence::parse("````md\n").unwrap();
+        assert!(fence.is_closed_by("`````\n"));
+        assert!(fence.is_closed_by("  ````  \r\n"));
+        assert!(!fence.is_closed_by("```\n"));
+        assert!(!fence.is_closed_by("~~~~\n"));
+        assert!(!fence.is_closed_by("```` rust\n"));
+    }
+
+    #[test]
+    fn code_blocks_are_highlighted_with_their_language() {
+        let assets = crate::assets::HighlightingAssets::from_binary();
+        let syntax_set = assets.get_syntax_set();
+        let theme = assets.get_theme("Monokai Extended");
+        let markdown = syntax_set.find_syntax_by_token("md").unwrap();
+        let mut highlighter = HighlightLines::new(markdown, theme);
+        let mut blocks = CodeBlocks::new(syntax_set, theme);
+
+        let text = "# Title\n\n```rust\nfn main() {}\n```\nText\n";
+        let text_lines: Vec<String> = text.lines().map(|line| format!("{}\n", line)).collect();
+        let lines: Vec<Vec<(Style, &str)>> = text_lines
+            .iter()
+            .map(|line| {
+                let regions = highlighter.highlight(line, syntax_set);
+                blocks.regions(line, regions)
+            })
+            .collect();
+
+        let foreground = theme.settings.foreground.unwrap();
+        let prose = |regions: &[(Style, &str)]| {
+            regions
+                .iter()
+                .all(|(style, _)| style.foreground == foreground)
+        };
+        assert!(prose(&lines[0]));
+        assert!(lines[0][0].0.font_style.contains(FontStyle::BOLD));
+        assert!(prose(&lines[2]) && prose(&lines[4]) && prose(&lines[5]));
+        assert!(!prose(&lines[3]));
+        assert_eq!(lines[3][0].1, "fn");
+    }
+}
@@ -0,0 +1,205 @@
+//! A viewer for `grep`-style match lists (like the output of `rg --vimgrep`): every file is
+//! printed with a header, the matched lines with some context and the matches highlighted
+//! (see `PrettyPrinter::print_matches`).
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::error::*;
+use crate::line_range::{LineRange, LineRanges};
+
+/// A match in a line of a file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Match {
+    /// The line number (starting at 1)
+    pub line: usize,
+
+    /// The byte range of the match within the line
+    pub span: Range<usize>,
+}
+
+/// Matches in a list of files, in the order in which the files appear in the list
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MatchList {
+    files: Vec<(PathBuf, Vec<Match>)>,
+}
+
+impl MatchList {
+    /// Parse a list of matches in the `path:line:column:text` format (one per line), like the
+    /// output of `rg --vimgrep` or `grep -Hnb`-style tools. The column is the (1-based) byte
+    /// offset of the match. Since the length of a match is not part of the list, the match is
+    /// assumed to extend to the end of the word at that column.
+    pub fn parse(list: &str) -> Result<Self> {
+        let mut matches = MatchList::default();
+        for entry in list.lines().filter(|entry| !entry.trim().is_empty()) {
+            let (path, found) =
+                parse_entry(entry).ok_or_else(|| format!("Invalid match '{}'", entry))?;
+            matches.add(path, found);
+        }
+        Ok(matches)
+    }
+
+    /// Add a match in the given file
+    pub fn add(&mut self, path: impl Into<PathBuf>, found: Match) {
+        let path = path.into();
+        match self.files.iter_mut().find(|(p, _)| *p == path) {
+            Some((_, matches)) => matches.push(found),
+            None => self.files.push((path, vec![found])),
+        }
+    }
+
+    /// The files with matches and their matches
+    pub fn files(&self) -> impl Iterator<Item = (&Path, &[Match])> {
+        self.files
+            .iter()
+            .map(|(path, matches)| (path.as_path(), matches.as_slice()))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+}
+
+impl FromStr for MatchList {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        MatchList::parse(s)
+    }
+}
+
+/// Parse an entry of the form `path:line:column:text`. The path itself can contain colons
+/// (like `C:\...`), so the first `:<number>:<number>:` sequence separates it from the rest.
+fn parse_entry(entry: &str) -> Option<(&str, Match)> {
+    for (position, _) in entry.match_indices(':') {
+        let mut parts = entry[position + 1..].splitn(3, ':');
+        let line = parts.next().and_then(|line| line.parse::<usize>().ok());
+        let column = parts.next().and_then(|column| column.parse::<usize>().ok());
+
+        if let (Some(line), Some(column)) = (line, column) {
+            if line == 0 || column == 0 {
+                return None;
+            }
+
+            let text = parts.next().unwrap_or("");
+            let start = column - 1;
+            let length = text
+                .get(start..)
+                .and_then(|rest| rest.chars().next().map(|c| (c, rest)))
+                .map(|(first, rest)| {
+                    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+                    if is_word(first) {
+                        rest.find(|c| !is_word(c)).unwrap_or(rest.len())
+                    } else {
+                        first.len_utf8()
+                    }
+                })
+                .unwrap_or(1);
+
+            let found = Match {
+                line,
+                span: start..start + length,
+            };
+            return Some((&entry[..position], found));
+        }
+    }
+
+    None
+}
+
+/// The lines that are shown for the given matches, with `context` lines before and after
+pub(crate) fn visible_lines(matches: &[Match], context: usize) -> LineRanges {
+    LineRanges::from(
+        matches
+            .iter()
+            .map(|found| {
+                LineRange::new(
+                    found.line.saturating_sub(context).max(1),
+                    found.line + context,
+                )
+            })
+            .collect(),
+    )
+}
+
+/// The highlighted spans of the given matches, sorted and merged per line
+pub(crate) fn highlighted_spans(matches: &[Match]) -> BTreeMap<usize, Vec<Range<usize>>> {
+    let mut spans: BTreeMap<usize, Vec<Range<usize>>> = BTreeMap::new();
+    for found in matches {
+        spans
+            .entry(found.line)
+            .or_default()
+            .push(found.span.clone());
+    }
+
+    for line_spans in spans.values_mut() {
+        line_spans.sort_by_key(|span| span.start);
+        let mut merged: Vec<Range<usize>> = Vec::with_capacity(line_spans.len());
+        for span in line_spans.drain(..) {
+            match merged.last_mut() {
+                Some(last) if span.start <= last.end => last.end = last.end.max(span.end),
+                _ => merged.push(span),
+            }
+        }
+        *line_spans = merged;
+    }
+
+    spans
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_vimgrep_output() {
+        let list = MatchList::parse(
+            "src/main.rs:3:9:    let foo_bar = 1;\n\
+             C:\\code\\a.rs:10:1:fn main() {\n\
+             src/main.rs:7:9:        (foo)\n",
+        )
+        .unwrap();
+
+        let files: Vec<_> = list.files().collect();
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].0, Path::new("src/main.rs"));
+        assert_eq!(
+            files[0].1,
+            &[
+                Match {
+                    line: 3,
+                    span: 8..15
+                },
+                Match {
+                    line: 7,
+                    span: 8..9
+                }
+            ]
+        );
+        assert_eq!(files[1].0, Path::new("C:\\code\\a.rs"));
+        assert_eq!(files[1].1[0].span, 0..2);
+
+        assert!(MatchList::parse("no match here\n").is_err());
+    }
+
+    #[test]
+    fn overlapping_spans_are_merged() {
+        let matches = [
+            Match {
+                line: 2,
+                span: 5..8,
+            },
+            Match {
+                line: 2,
+                span: 0..3,
+            },
+            Match {
+                line: 2,
+                span: 2..4,
+            },
+        ];
+        assert_eq!(highlighted_spans(&matches)[&2], vec![0..4, 5..8]);
+    }
+}
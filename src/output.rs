@@ -1,6 +1,18 @@
+#[cfg(feature = "paging")]
+use std::fs;
 use std::io::{self, Write};
 #[cfg(feature = "paging")]
-use std::process::Child;
+use std::path::PathBuf;
+#[cfg(feature = "paging")]
+use std::process::{Child, ChildStdin};
+#[cfg(feature = "paging")]
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "paging")]
+use std::sync::{Arc, Mutex};
+#[cfg(feature = "paging")]
+use std::thread;
+#[cfg(feature = "paging")]
+use std::time::{Duration, Instant};
 
 use crate::error::*;
 #[cfg(feature = "paging")]
@@ -8,33 +20,619 @@ use crate::less::retrieve_less_version;
 #[cfg(feature = "paging")]
 use crate::paging::PagingMode;
 
+/// The default buffer size for `BufferingMode::Block`
+pub const DEFAULT_BLOCK_SIZE: usize = 64 * 1024;
+
+/// How output is buffered before it is written to the terminal, the pager or a pipe
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BufferingMode {
+    /// Line-buffered output for STDIN and custom readers (which might be slow streams),
+    /// block-buffered output for files
+    Auto,
+
+    /// Flush the output after every complete line
+    Line,
+
+    /// Collect up to the given number of bytes before writing them
+    Block(usize),
+
+    /// Write all output immediately
+    Unbuffered,
+}
+
+impl Default for BufferingMode {
+    fn default() -> Self {
+        BufferingMode::Auto
+    }
+}
+
+/// A writer that buffers output according to a `BufferingMode`
+pub(crate) struct BufferedOutput<'a> {
+    inner: &'a mut dyn Write,
+    buffer: Vec<u8>,
+    mode: BufferingMode,
+}
+
+impl<'a> BufferedOutput<'a> {
+    pub(crate) fn new(inner: &'a mut dyn Write) -> Self {
+        BufferedOutput {
+            inner,
+            buffer: vec![],
+            mode: BufferingMode::Line,
+        }
+    }
+
+    /// Switch to another buffering mode. Already buffered output is written first.
+    /// `BufferingMode::Auto` has to be resolved by the caller.
+    pub(crate) fn set_mode(&mut self, mode: BufferingMode) -> io::Result<()> {
+        if mode != self.mode {
+            self.flush()?;
+            self.mode = mode;
+        }
+        Ok(())
+    }
+
+    fn write_buffer(&mut self, len: usize) -> io::Result<()> {
+        if len > 0 {
+            self.inner.write_all(&self.buffer[..len])?;
+            self.buffer.drain(..len);
+        }
+        self.inner.flush()
+    }
+}
+
+impl<'a> Write for BufferedOutput<'a> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self.mode {
+            BufferingMode::Unbuffered => {
+                self.inner.write_all(buf)?;
+                self.inner.flush()?;
+            }
+            BufferingMode::Block(size) => {
+                self.buffer.extend_from_slice(buf);
+                if self.buffer.len() >= size {
+                    self.write_buffer(self.buffer.len())?;
+                }
+            }
+            BufferingMode::Line | BufferingMode::Auto => {
+                self.buffer.extend_from_slice(buf);
+                if let Some(pos) = buf.iter().rposition(|b| *b == b'\n') {
+                    let len = self.buffer.len() - buf.len() + pos + 1;
+                    self.write_buffer(len)?;
+                }
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.write_buffer(self.buffer.len())
+    }
+}
+
+impl<'a> Drop for BufferedOutput<'a> {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+
+/// Information about the printed content that is passed on to the pager
+#[cfg(feature = "paging")]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub(crate) struct PagerHints {
+    /// The width that the content has been laid out for, if it differs from the width of the
+    /// terminal. It is passed to `less` (as `COLUMNS`) so that it doesn't wrap lines that bat
+    /// did not wrap. Otherwise `less` keeps following the size of the terminal when resized.
+    pub(crate) columns: Option<usize>,
+
+    /// Leave long lines to the pager, which cuts them off and scrolls horizontally
+    /// (`less --chop-long-lines`)
+    pub(crate) chop_long_lines: bool,
+
+    /// Let the `Q` key quit `less` with `QUIT_ALL_EXIT_CODE` (instead of 0, like `q`), so that
+    /// the two can be told apart when every input is shown in a pager of its own. This needs
+    /// `less` 582 or newer (for `--lesskey-src`).
+    pub(crate) quit_all_key: bool,
+
+    /// Kill the pager if it is still running this long after it was started
+    pub(crate) timeout: Option<Duration>,
+
+    /// The number of rows at the beginning of the content that `less` keeps at the top of the
+    /// screen (with `--header`, which needs `less` 600 or newer)
+    pub(crate) header_rows: Option<usize>,
+}
+
+/// A writer that puts a prefix in front of every line
+pub(crate) struct IndentedOutput<'a> {
+    inner: &'a mut dyn Write,
+    prefix: &'a str,
+    at_line_start: bool,
+}
+
+impl<'a> IndentedOutput<'a> {
+    pub(crate) fn new(inner: &'a mut dyn Write, prefix: &'a str) -> Self {
+        IndentedOutput {
+            inner,
+            prefix,
+            at_line_start: true,
+        }
+    }
+}
+
+impl<'a> Write for IndentedOutput<'a> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut rest = buf;
+        while !rest.is_empty() {
+            if self.at_line_start {
+                self.inner.write_all(self.prefix.as_bytes())?;
+            }
+            let end = rest
+                .iter()
+                .position(|b| *b == b'\n')
+                .map_or(rest.len(), |pos| pos + 1);
+            self.inner.write_all(&rest[..end])?;
+            self.at_line_start = rest[end - 1] == b'\n';
+            rest = &rest[end..];
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// The line endings of the output
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineEnding {
+    /// `\n` only
+    Lf,
+
+    /// `\r\n`
+    CrLf,
+}
+
+/// The character encoding of the output
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputEncoding {
+    Utf8,
+
+    /// UTF-16 (little endian), starting with a byte order mark
+    Utf16,
+
+    /// ISO 8859-1. Characters that have no Latin-1 representation are replaced by `?`.
+    Latin1,
+}
+
+impl Default for OutputEncoding {
+    fn default() -> Self {
+        OutputEncoding::Utf8
+    }
+}
+
+/// The state of the escape sequence parser of `ConvertedOutput`
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum EscapeState {
+    Text,
+    Start,
+    Csi,
+    Osc,
+    OscEsc,
+}
+
+/// A writer that converts the line endings and the encoding of the output. Escape sequences
+/// are passed through unchanged and don't separate a carriage return from its line feed.
+pub(crate) struct ConvertedOutput<'a> {
+    inner: &'a mut dyn Write,
+    line_ending: Option<LineEnding>,
+    encoding: OutputEncoding,
+
+    escape: EscapeState,
+    last_was_cr: bool,
+
+    /// A carriage return (and the escape sequences following it) that is held back until it
+    /// is known whether a line feed follows
+    pending_cr: Vec<u8>,
+
+    /// Converted bytes that still have to be encoded (including incomplete UTF-8 sequences
+    /// at the end)
+    converted: Vec<u8>,
+
+    byte_order_mark_written: bool,
+}
+
+impl<'a> ConvertedOutput<'a> {
+    pub(crate) fn new(
+        inner: &'a mut dyn Write,
+        line_ending: Option<LineEnding>,
+        encoding: OutputEncoding,
+    ) -> Self {
+        ConvertedOutput {
+            inner,
+            line_ending,
+            encoding,
+            escape: EscapeState::Text,
+            last_was_cr: false,
+            pending_cr: vec![],
+            converted: vec![],
+            byte_order_mark_written: false,
+        }
+    }
+
+    fn emit(&mut self, byte: u8) {
+        if self.pending_cr.is_empty() {
+            self.converted.push(byte);
+        } else {
+            self.pending_cr.push(byte);
+        }
+    }
+
+    fn release_pending_cr(&mut self, keep_cr: bool) {
+        let skip = if keep_cr { 0 } else { 1 };
+        let pending = std::mem::take(&mut self.pending_cr);
+        self.converted.extend(pending.into_iter().skip(skip));
+    }
+
+    fn push(&mut self, byte: u8) {
+        self.escape = match (self.escape, byte) {
+            (EscapeState::Text, 0x1b) => EscapeState::Start,
+            (EscapeState::Text, _) => {
+                self.push_text(byte);
+                return;
+            }
+            (EscapeState::Start, b'[') => EscapeState::Csi,
+            (EscapeState::Start, b']') => EscapeState::Osc,
+            (EscapeState::Csi, 0x40..=0x7e)
+            | (EscapeState::Osc, 0x07)
+            | (EscapeState::OscEsc, _) => EscapeState::Text,
+            (EscapeState::Osc, 0x1b) => EscapeState::OscEsc,
+            (EscapeState::Csi, _) | (EscapeState::Osc, _) => self.escape,
+            (EscapeState::Start, _) => EscapeState::Text,
+        };
+        self.emit(byte);
+    }
+
+    fn push_text(&mut self, byte: u8) {
+        match (self.line_ending, byte) {
+            (Some(LineEnding::Lf), b'\r') => {
+                self.release_pending_cr(true);
+                self.pending_cr.push(byte);
+            }
+            (Some(LineEnding::Lf), b'\n') => {
+                self.release_pending_cr(false);
+                self.converted.push(byte);
+            }
+            (Some(LineEnding::CrLf), b'\n') => {
+                if !self.last_was_cr {
+                    self.converted.push(b'\r');
+                }
+                self.converted.push(byte);
+            }
+            _ => {
+                self.release_pending_cr(true);
+                self.converted.push(byte);
+            }
+        }
+        self.last_was_cr = byte == b'\r';
+    }
+
+    /// Encode and write the converted bytes. An incomplete UTF-8 sequence at the end is kept
+    /// unless `all` is set.
+    fn write_converted(&mut self, all: bool) -> io::Result<()> {
+        if self.encoding == OutputEncoding::Utf8 {
+            self.inner.write_all(&self.converted)?;
+            self.converted.clear();
+            return Ok(());
+        }
+
+        let mut encoded = vec![];
+        if self.encoding == OutputEncoding::Utf16 && !self.byte_order_mark_written {
+            encoded.extend_from_slice(&[0xff, 0xfe]);
+            self.byte_order_mark_written = true;
+        }
+
+        let mut rest = &self.converted[..];
+        loop {
+            let (valid, invalid_len) = match std::str::from_utf8(rest) {
+                Ok(text) => (text, None),
+                Err(error) => (
+                    std::str::from_utf8(&rest[..error.valid_up_to()]).unwrap_or_default(),
+                    error.error_len(),
+                ),
+            };
+            for c in valid.chars() {
+                self.encode(c, &mut encoded);
+            }
+            rest = &rest[valid.len()..];
+
+            match invalid_len {
+                Some(len) => {
+                    self.encode(std::char::REPLACEMENT_CHARACTER, &mut encoded);
+                    rest = &rest[len..];
+                }
+                None if all && !rest.is_empty() => {
+                    self.encode(std::char::REPLACEMENT_CHARACTER, &mut encoded);
+                    rest = &[];
+                }
+                None => break,
+            }
+        }
+
+        let consumed = self.converted.len() - rest.len();
+        self.converted.drain(..consumed);
+        self.inner.write_all(&encoded)
+    }
+
+    fn encode(&self, c: char, out: &mut Vec<u8>) {
+        match self.encoding {
+            OutputEncoding::Utf8 => {
+                let mut buf = [0; 4];
+                out.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            }
+            OutputEncoding::Utf16 => {
+                let mut buf = [0; 2];
+                for unit in c.encode_utf16(&mut buf) {
+                    out.extend_from_slice(&unit.to_le_bytes());
+                }
+            }
+            OutputEncoding::Latin1 => {
+                let code = c as u32;
+                out.push(if code <= 0xff { code as u8 } else { b'?' });
+            }
+        }
+    }
+}
+
+impl<'a> Write for ConvertedOutput<'a> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        for &byte in buf {
+            self.push(byte);
+        }
+        self.write_converted(false)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+impl<'a> Drop for ConvertedOutput<'a> {
+    fn drop(&mut self) {
+        self.release_pending_cr(true);
+        let _ = self.write_converted(true);
+        let _ = self.inner.flush();
+    }
+}
+
+/// The exit code of `less` when it is quit with `Q` (see `PagerHints::quit_all_key`)
+#[cfg(feature = "paging")]
+pub(crate) const QUIT_ALL_EXIT_CODE: i32 = b'Q' as i32;
+
+/// How often a pager with a timeout is checked for having exited
+#[cfg(feature = "paging")]
+const PAGER_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// A running pager and its input. A pager with a timeout is watched by a thread of its own,
+/// which kills it if it is still running when the timeout expires (see `PagerHints::timeout`).
+/// Writing to a killed pager fails with `io::ErrorKind::TimedOut`.
+#[cfg(feature = "paging")]
+#[derive(Debug)]
+pub struct Pager {
+    command: String,
+    child: Arc<Mutex<Child>>,
+    stdin: Option<ChildStdin>,
+    timeout: Option<Duration>,
+    timed_out: Arc<AtomicBool>,
+    _lesskey: Option<LesskeyFile>,
+}
+
+/// A `lesskey` source file in a private directory of its own, which is removed when the file
+/// is dropped. The directory and the file are created exclusively, so that another user can't
+/// replace them (on a shared temporary directory) and concurrent `bat` processes don't clash.
+#[cfg(feature = "paging")]
+#[derive(Debug)]
+struct LesskeyFile {
+    dir: PathBuf,
+}
+
+#[cfg(feature = "paging")]
+impl LesskeyFile {
+    fn create(content: &str) -> io::Result<Self> {
+        let lesskey = LesskeyFile {
+            dir: Self::create_private_dir()?,
+        };
+        let mut file = fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(lesskey.path())?;
+        file.write_all(content.as_bytes())?;
+        Ok(lesskey)
+    }
+
+    fn create_private_dir() -> io::Result<PathBuf> {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|duration| duration.subsec_nanos())
+            .unwrap_or(0);
+        let mut builder = fs::DirBuilder::new();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::DirBuilderExt;
+            builder.mode(0o700);
+        }
+
+        for attempt in 0..100u32 {
+            let name = format!("bat-lesskey-{}-{}-{}", std::process::id(), nanos, attempt);
+            let dir = std::env::temp_dir().join(name);
+            match builder.create(&dir) {
+                Ok(()) => return Ok(dir),
+                Err(ref e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => return Err(e),
+            }
+        }
+        Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            "no unused name for a temporary directory",
+        ))
+    }
+
+    fn path(&self) -> PathBuf {
+        self.dir.join("quit-all.lesskey")
+    }
+}
+
+#[cfg(feature = "paging")]
+impl Drop for LesskeyFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+#[cfg(feature = "paging")]
+impl Pager {
+    fn new(
+        command: String,
+        mut child: Child,
+        timeout: Option<Duration>,
+        lesskey: Option<LesskeyFile>,
+    ) -> Self {
+        let stdin = child.stdin.take();
+        let child = Arc::new(Mutex::new(child));
+        let timed_out = Arc::new(AtomicBool::new(false));
+
+        if let Some(timeout) = timeout {
+            let child = Arc::clone(&child);
+            let timed_out = Arc::clone(&timed_out);
+            thread::spawn(move || Self::watch(&child, timeout, &timed_out));
+        }
+
+        Pager {
+            command,
+            child,
+            stdin,
+            timeout,
+            timed_out,
+            _lesskey: lesskey,
+        }
+    }
+
+    /// Kill the pager after the timeout, unless it exits before
+    fn watch(child: &Mutex<Child>, timeout: Duration, timed_out: &AtomicBool) {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let mut child = match child.lock() {
+                Ok(child) => child,
+                Err(_) => return,
+            };
+            match child.try_wait() {
+                Ok(None) => {}
+                _ => return,
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                timed_out.store(true, Ordering::SeqCst);
+                child.kill().ok();
+                child.wait().ok();
+                return;
+            }
+            drop(child);
+            thread::sleep(PAGER_POLL_INTERVAL.min(deadline - now));
+        }
+    }
+
+    /// The error of a pager that has been killed after its timeout
+    pub(crate) fn timeout_error(&self) -> Option<Error> {
+        match self.timeout {
+            Some(timeout) if self.timed_out.load(Ordering::SeqCst) => {
+                Some(ErrorKind::SubprocessTimeout(self.command.clone(), timeout).into())
+            }
+            _ => None,
+        }
+    }
+
+    /// Close the input of the pager and wait for it to exit. Returns its exit code.
+    fn wait(&mut self) -> Option<i32> {
+        drop(self.stdin.take());
+        if self.timeout.is_none() {
+            let mut child = self.child.lock().ok()?;
+            return child.wait().ok().and_then(|status| status.code());
+        }
+
+        // Leave the child to the watchdog in between, so that it can be killed
+        loop {
+            match self.child.lock().ok()?.try_wait() {
+                Ok(Some(status)) => return status.code(),
+                Ok(None) => thread::sleep(PAGER_POLL_INTERVAL),
+                Err(_) => return None,
+            }
+        }
+    }
+
+    fn io_error(&self, error: io::Error) -> io::Error {
+        match self.timeout_error() {
+            Some(timeout_error) => {
+                io::Error::new(io::ErrorKind::TimedOut, timeout_error.to_string())
+            }
+            None => error,
+        }
+    }
+}
+
+#[cfg(feature = "paging")]
+impl Write for Pager {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self.stdin {
+            Some(ref mut stdin) => stdin.write(buf),
+            None => Err(io::ErrorKind::BrokenPipe.into()),
+        }
+        .map_err(|error| self.io_error(error))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.stdin {
+            Some(ref mut stdin) => stdin.flush(),
+            None => Ok(()),
+        }
+        .map_err(|error| self.io_error(error))
+    }
+}
+
 #[derive(Debug)]
 pub enum OutputType {
     #[cfg(feature = "paging")]
-    Pager(Child),
+    Pager(Pager),
     Stdout(io::Stdout),
 }
 
 impl OutputType {
     #[cfg(feature = "paging")]
-    pub fn from_mode(mode: PagingMode, pager: Option<&str>) -> Result<Self> {
+    pub(crate) fn from_mode(
+        mode: PagingMode,
+        pager: Option<&str>,
+        hints: PagerHints,
+    ) -> Result<Self> {
         use self::PagingMode::*;
         Ok(match mode {
-            Always => OutputType::try_pager(false, pager)?,
-            QuitIfOneScreen => OutputType::try_pager(true, pager)?,
+            Always => OutputType::try_pager(false, pager, hints)?,
+            QuitIfOneScreen => OutputType::try_pager(true, pager, hints)?,
             _ => OutputType::stdout(),
         })
     }
 
     /// Try to launch the pager. Fall back to stdout in case of errors.
     #[cfg(feature = "paging")]
-    fn try_pager(quit_if_one_screen: bool, pager_from_config: Option<&str>) -> Result<Self> {
+    fn try_pager(
+        quit_if_one_screen: bool,
+        pager_from_config: Option<&str>,
+        hints: PagerHints,
+    ) -> Result<Self> {
         use std::env;
         use std::ffi::OsString;
-        use std::path::PathBuf;
         use std::process::{Command, Stdio};
 
         let mut replace_arguments_to_less = false;
+        let mut lesskey = None;
 
         let pager_from_env = match (env::var("BAT_PAGER"), env::var("PAGER")) {
             (Ok(bat_pager), _) => Some(bat_pager),
@@ -103,6 +701,32 @@ impl OutputType {
                     } else {
                         p.args(args);
                     }
+                    if hints.chop_long_lines
+                        && !args
+                            .iter()
+                            .any(|arg| arg == "-S" || arg == "--chop-long-lines")
+                    {
+                        p.arg("--chop-long-lines");
+                    }
+                    if hints.quit_all_key && retrieve_less_version().iter().any(|v| *v >= 582) {
+                        // The extra string of a 'quit' command is used as its exit code
+                        let file = LesskeyFile::create("#command\nQ quit Q\n")
+                            .chain_err(|| "Could not write the key bindings for less")?;
+                        let mut arg = OsString::from("--lesskey-src=");
+                        arg.push(file.path());
+                        p.arg(arg);
+                        lesskey = Some(file);
+                    }
+                    if let Some(rows) = hints.header_rows {
+                        if retrieve_less_version().iter().any(|v| *v >= 600)
+                            && !args.iter().any(|arg| arg.starts_with("--header"))
+                        {
+                            p.arg(format!("--header={}", rows));
+                        }
+                    }
+                    if let Some(columns) = hints.columns {
+                        p.env("COLUMNS", columns.to_string());
+                    }
                     p.env("LESSCHARSET", "UTF-8");
                     p
                 } else {
@@ -114,7 +738,9 @@ impl OutputType {
                 Ok(process
                     .stdin(Stdio::piped())
                     .spawn()
-                    .map(OutputType::Pager)
+                    .map(|child| {
+                        OutputType::Pager(Pager::new(pager, child, hints.timeout, lesskey))
+                    })
                     .unwrap_or_else(|_| OutputType::stdout()))
             }
             None => Ok(OutputType::stdout()),
@@ -139,13 +765,33 @@ impl OutputType {
         false
     }
 
+    /// Close the input of the pager and wait for it to exit. Returns its exit code.
+    #[cfg(feature = "paging")]
+    pub(crate) fn wait(&mut self) -> Option<i32> {
+        match *self {
+            OutputType::Pager(ref mut pager) => pager.wait(),
+            OutputType::Stdout(_) => None,
+        }
+    }
+
+    /// The error of a pager that has been killed after its timeout (see `PagerHints::timeout`)
+    #[cfg(feature = "paging")]
+    pub(crate) fn timeout_error(&self) -> Option<Error> {
+        match *self {
+            OutputType::Pager(ref pager) => pager.timeout_error(),
+            OutputType::Stdout(_) => None,
+        }
+    }
+
     pub fn handle(&mut self) -> Result<&mut dyn Write> {
         Ok(match *self {
             #[cfg(feature = "paging")]
-            OutputType::Pager(ref mut command) => command
-                .stdin
-                .as_mut()
-                .chain_err(|| "Could not open stdin for pager")?,
+            OutputType::Pager(ref mut pager) => {
+                if pager.stdin.is_none() {
+                    return Err("Could not open stdin for pager".into());
+                }
+                pager
+            }
             OutputType::Stdout(ref mut handle) => handle,
         })
     }
@@ -154,8 +800,166 @@ impl OutputType {
 #[cfg(feature = "paging")]
 impl Drop for OutputType {
     fn drop(&mut self) {
-        if let OutputType::Pager(ref mut command) = *self {
-            let _ = command.wait();
+        if let OutputType::Pager(ref mut pager) = *self {
+            let _ = pager.wait();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Records every chunk that is written to it
+    #[derive(Default)]
+    struct Recorder(Vec<Vec<u8>>);
+
+    impl Write for Recorder {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.push(buf.to_vec());
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[cfg(all(feature = "paging", unix))]
+    #[test]
+    fn pagers_are_killed_after_their_timeout() {
+        use std::process::{Command, Stdio};
+
+        let child = Command::new("sleep")
+            .arg("10")
+            .stdin(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut output_type = OutputType::Pager(Pager::new(
+            "sleep 10".into(),
+            child,
+            Some(Duration::from_millis(100)),
+            None,
+        ));
+        assert!(output_type.timeout_error().is_none());
+
+        let started = Instant::now();
+        output_type.wait();
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(
+            output_type.timeout_error().unwrap().to_string(),
+            "'sleep 10' was killed since it was still running after 100ms"
+        );
+    }
+
+    #[cfg(feature = "paging")]
+    #[test]
+    fn lesskey_files_are_private_and_removed() {
+        let first = LesskeyFile::create("#command\nQ quit Q\n").unwrap();
+        let second = LesskeyFile::create("#command\nQ quit Q\n").unwrap();
+        assert_ne!(first.path(), second.path());
+        assert_eq!(
+            fs::read_to_string(first.path()).unwrap(),
+            "#command\nQ quit Q\n"
+        );
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(&first.dir).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o700);
+        }
+
+        let dir = first.dir.clone();
+        drop(first);
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn line_buffering() {
+        let mut recorder = Recorder::default();
+        {
+            let mut output = BufferedOutput::new(&mut recorder);
+            write!(output, "foo").unwrap();
+            output.write_all(b"bar\nba").unwrap();
+            write!(output, "z").unwrap();
+        }
+        assert_eq!(recorder.0, vec![b"foobar\n".to_vec(), b"baz".to_vec()]);
+    }
+
+    #[test]
+    fn block_buffering() {
+        let mut recorder = Recorder::default();
+        {
+            let mut output = BufferedOutput::new(&mut recorder);
+            output.set_mode(BufferingMode::Block(8)).unwrap();
+            writeln!(output, "one").unwrap();
+            writeln!(output, "two").unwrap();
+            writeln!(output, "three").unwrap();
+        }
+        assert_eq!(
+            recorder.0,
+            vec![b"one\ntwo\n".to_vec(), b"three\n".to_vec()]
+        );
+    }
+
+    fn convert(
+        line_ending: Option<LineEnding>,
+        encoding: OutputEncoding,
+        chunks: &[&[u8]],
+    ) -> Vec<u8> {
+        let mut output = vec![];
+        {
+            let mut converted = ConvertedOutput::new(&mut output, line_ending, encoding);
+            for chunk in chunks {
+                converted.write_all(chunk).unwrap();
+            }
+        }
+        output
+    }
+
+    #[test]
+    fn line_endings_are_converted() {
+        let crlf = Some(LineEnding::CrLf);
+        let lf = Some(LineEnding::Lf);
+        let utf8 = OutputEncoding::Utf8;
+
+        assert_eq!(
+            convert(crlf, utf8, &[b"a\n", b"b\r", b"\n\x1b[0m\n"]),
+            b"a\r\nb\r\n\x1b[0m\r\n"
+        );
+        assert_eq!(convert(crlf, utf8, &[b"a\r\x1b[0m\n"]), b"a\r\x1b[0m\n");
+        assert_eq!(
+            convert(lf, utf8, &[b"a\r", b"\x1b[0m\x1b]8;;\x07", b"\nb\rc\r"]),
+            b"a\x1b[0m\x1b]8;;\x07\nb\rc\r"
+        );
+    }
+
+    #[test]
+    fn output_is_encoded() {
+        assert_eq!(
+            convert(None, OutputEncoding::Utf16, &[b"a\xc3", b"\xa4\n"]),
+            vec![0xff, 0xfe, b'a', 0, 0xe4, 0, b'\n', 0]
+        );
+        assert_eq!(
+            convert(
+                Some(LineEnding::CrLf),
+                OutputEncoding::Latin1,
+                &["ä€\n".as_bytes()]
+            ),
+            b"\xe4?\r\n"
+        );
+    }
+
+    #[test]
+    fn unbuffered() {
+        let mut recorder = Recorder::default();
+        {
+            let mut output = BufferedOutput::new(&mut recorder);
+            output.set_mode(BufferingMode::Unbuffered).unwrap();
+            write!(output, "a").unwrap();
+            write!(output, "b").unwrap();
         }
+        assert_eq!(recorder.0, vec![b"a".to_vec(), b"b".to_vec()]);
     }
 }
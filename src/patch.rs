@@ -0,0 +1,475 @@
+//! Structured rendering of unified diffs (like `.patch` files or the output of `git diff`):
+//! files and hunks are separated, the added and removed lines are highlighted in the language
+//! of the file and changed words are emphasized (see `Config::render_diffs`). Commits in the
+//! output of `git show` or `git log` are rendered with styled metadata and messages (see
+//! `Config::render_commits`).
+
+use std::ops::Range;
+
+use syntect::easy::HighlightLines;
+use syntect::highlighting::Theme;
+use syntect::parsing::SyntaxReference;
+
+/// The kind of a line of a unified diff
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum PatchLine {
+    /// `diff --git a/... b/...`, the start of a file
+    FileStart,
+
+    /// Extended headers like `index ...` or `new file mode ...`, and `\ No newline at end
+    /// of file`
+    Meta,
+
+    /// `--- a/path`
+    OldPath,
+
+    /// `+++ b/path`
+    NewPath,
+
+    /// `@@ -1,2 +1,3 @@ context`
+    Hunk,
+
+    Removed,
+    Added,
+    Context,
+
+    /// `commit 1a2b3c4 (HEAD -> master)`, the start of a commit
+    CommitStart,
+
+    /// `Author: ...`, `Date: ...` and the other metadata of a commit
+    CommitHeader,
+
+    /// The first line of a commit message
+    CommitSubject,
+
+    /// The other lines of a commit message
+    CommitMessage,
+
+    /// Lines outside of the files, like the commit message in `git format-patch` output
+    Other,
+}
+
+/// The part of a commit that is being parsed
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CommitPart {
+    Header,
+    Message { subject_seen: bool },
+}
+
+/// Keeps track of the structure of a diff, line by line
+#[derive(Debug, Default)]
+pub(crate) struct PatchParser {
+    /// The number of old and new lines that are left in the current hunk
+    hunk: Option<(usize, usize)>,
+    in_file_header: bool,
+    commit: Option<CommitPart>,
+}
+
+impl PatchParser {
+    /// The kind of the next line of the diff
+    pub(crate) fn classify(&mut self, line: &str) -> PatchLine {
+        let line = line.trim_end_matches(&['\r', '\n'][..]);
+
+        if let Some((old, new)) = self.hunk {
+            let next = match line.bytes().next() {
+                Some(b'-') if old > 0 => Some((PatchLine::Removed, old - 1, new)),
+                Some(b'+') if new > 0 => Some((PatchLine::Added, old, new - 1)),
+                Some(b' ') | None if old > 0 && new > 0 => {
+                    Some((PatchLine::Context, old - 1, new - 1))
+                }
+                Some(b'\\') => Some((PatchLine::Meta, old, new)),
+                _ => None,
+            };
+
+            if let Some((kind, old, new)) = next {
+                self.hunk = if old == 0 && new == 0 {
+                    None
+                } else {
+                    Some((old, new))
+                };
+                return kind;
+            }
+            self.hunk = None;
+        }
+
+        if is_commit_start(line) {
+            self.in_file_header = false;
+            self.commit = Some(CommitPart::Header);
+            return PatchLine::CommitStart;
+        }
+
+        match self.commit {
+            Some(CommitPart::Header) if line.is_empty() => {
+                self.commit = Some(CommitPart::Message {
+                    subject_seen: false,
+                });
+                return PatchLine::Other;
+            }
+            Some(CommitPart::Header) => return PatchLine::CommitHeader,
+            Some(CommitPart::Message { .. }) if line.trim().is_empty() => {
+                return PatchLine::Other;
+            }
+            Some(CommitPart::Message { subject_seen }) if line.starts_with("    ") => {
+                self.commit = Some(CommitPart::Message { subject_seen: true });
+                return if subject_seen {
+                    PatchLine::CommitMessage
+                } else {
+                    PatchLine::CommitSubject
+                };
+            }
+            Some(CommitPart::Message { .. }) => self.commit = None,
+            None => {}
+        }
+
+        if line.starts_with("diff ") {
+            self.in_file_header = true;
+            PatchLine::FileStart
+        } else if line.starts_with("--- ") {
+            self.in_file_header = true;
+            PatchLine::OldPath
+        } else if line.starts_with("+++ ") && self.in_file_header {
+            self.in_file_header = false;
+            PatchLine::NewPath
+        } else if line.starts_with("@@ ") {
+            match hunk_header(line) {
+                Some((old, new, _)) => {
+                    self.in_file_header = false;
+                    self.hunk = Some((old, new)).filter(|&counts| counts != (0, 0));
+                    PatchLine::Hunk
+                }
+                None => PatchLine::Other,
+            }
+        } else if line.starts_with('\\') || self.in_file_header {
+            PatchLine::Meta
+        } else {
+            PatchLine::Other
+        }
+    }
+}
+
+/// A removed or added line, which is printed once the whole block of changes is known
+pub(crate) struct PendingLine {
+    pub(crate) out_of_range: bool,
+    pub(crate) line_number: usize,
+    pub(crate) kind: PatchLine,
+    pub(crate) text: String,
+}
+
+impl PendingLine {
+    /// The line without the `+`, `-` or ` ` marker
+    pub(crate) fn code(&self) -> &str {
+        &self.text[1.min(self.text.len())..]
+    }
+}
+
+/// The state of a diff that is rendered structurally
+pub(crate) struct PatchState<'a> {
+    pub(crate) parser: PatchParser,
+    pub(crate) previous: Option<PatchLine>,
+    pub(crate) old_path: Option<String>,
+
+    /// Highlighters for the old and the new version of the current file
+    pub(crate) old: HighlightLines<'a>,
+    pub(crate) new: HighlightLines<'a>,
+
+    pub(crate) pending: Vec<PendingLine>,
+}
+
+impl<'a> PatchState<'a> {
+    pub(crate) fn new(syntax: &'a SyntaxReference, theme: &'a Theme) -> Self {
+        PatchState {
+            parser: PatchParser::default(),
+            previous: None,
+            old_path: None,
+            old: HighlightLines::new(syntax, theme),
+            new: HighlightLines::new(syntax, theme),
+            pending: vec![],
+        }
+    }
+
+    /// Highlight the following lines (of a new file) with the given syntax
+    pub(crate) fn set_syntax(&mut self, syntax: &'a SyntaxReference, theme: &'a Theme) {
+        self.old = HighlightLines::new(syntax, theme);
+        self.new = HighlightLines::new(syntax, theme);
+    }
+}
+
+/// Whether the line starts a commit in the output of `git show` or `git log`:
+/// `commit <hash>`, optionally followed by the branches and tags that point to it
+fn is_commit_start(line: &str) -> bool {
+    let mut words = line.split_whitespace();
+    words.next() == Some("commit")
+        && words.next().map_or(false, |hash| {
+            hash.len() >= 7 && hash.bytes().all(|b| b.is_ascii_hexdigit())
+        })
+}
+
+/// The byte ranges of the hash and the references (like `(HEAD -> master)`) of the first line
+/// of a commit
+pub(crate) fn commit_parts(line: &str) -> (Range<usize>, Range<usize>) {
+    let hash_start = "commit ".len();
+    let hash_end = line[hash_start..]
+        .find(char::is_whitespace)
+        .map_or(line.len(), |end| hash_start + end);
+    (hash_start..hash_end, hash_end..line.len())
+}
+
+/// Whether a line of a commit message is a trailer like `Signed-off-by: ...`
+pub(crate) fn is_trailer(line: &str) -> bool {
+    let line = line.trim();
+    match line.find(": ") {
+        Some(colon) => {
+            colon > 0
+                && line[..colon]
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || c == '-')
+                && line[..colon].contains('-')
+        }
+        None => false,
+    }
+}
+
+/// Parse a hunk header like `@@ -1,2 +1,3 @@ fn main()`. Returns the number of old and new
+/// lines of the hunk and the end of the `@@ ... @@` part.
+pub(crate) fn hunk_header(line: &str) -> Option<(usize, usize, usize)> {
+    let end = line[2..].find("@@")? + 4;
+    let mut ranges = line[3..end - 2].split_whitespace();
+
+    let count = |range: Option<&str>, sign: char| -> Option<usize> {
+        let range = range?;
+        if !range.starts_with(sign) {
+            return None;
+        }
+        match range[1..].find(',') {
+            Some(comma) => range[comma + 2..].parse().ok(),
+            None => range[1..].parse::<usize>().ok().map(|_| 1),
+        }
+    };
+
+    let old = count(ranges.next(), '-')?;
+    let new = count(ranges.next(), '+')?;
+    Some((old, new, end))
+}
+
+/// The path of a `--- a/path` or `+++ b/path` header, without the `a/` or `b/` prefix of Git.
+/// Returns `None` for `/dev/null` (a file that was added or removed).
+pub(crate) fn header_path(line: &str) -> Option<&str> {
+    let path = line.get(4..)?.trim_end_matches(&['\r', '\n'][..]);
+    // Some tools append a timestamp after a tab
+    let path = path.split('\t').next().unwrap_or(path);
+
+    if path == "/dev/null" {
+        return None;
+    }
+
+    Some(if path.starts_with("a/") || path.starts_with("b/") {
+        &path[2..]
+    } else {
+        path
+    })
+}
+
+/// Tokens for the word-level diff: runs of word characters, runs of whitespace and single
+/// other characters
+fn tokens(text: &str) -> Vec<Range<usize>> {
+    #[derive(PartialEq)]
+    enum Class {
+        Word,
+        Space,
+        Other,
+    }
+    let class = |c: char| {
+        if c.is_alphanumeric() || c == '_' {
+            Class::Word
+        } else if c.is_whitespace() {
+            Class::Space
+        } else {
+            Class::Other
+        }
+    };
+
+    let mut result: Vec<Range<usize>> = vec![];
+    let mut previous = None;
+    for (position, c) in text.char_indices() {
+        let current = class(c);
+        match result.last_mut() {
+            Some(last) if current != Class::Other && previous.as_ref() == Some(&current) => {
+                last.end = position + c.len_utf8();
+            }
+            _ => result.push(position..position + c.len_utf8()),
+        }
+        previous = Some(current);
+    }
+    result
+}
+
+/// The maximum number of token pairs that are compared for a word-level diff. Longer lines
+/// are not diffed word by word.
+const MAX_WORD_DIFF_SIZE: usize = 100_000;
+
+/// Compare a removed line with the added line that replaces it, word by word. Returns the
+/// byte ranges of the changed parts of both lines (merged where they touch).
+pub(crate) fn word_diff(old: &str, new: &str) -> (Vec<Range<usize>>, Vec<Range<usize>>) {
+    let old_tokens = tokens(old);
+    let new_tokens = tokens(new);
+    if old_tokens.len() * new_tokens.len() > MAX_WORD_DIFF_SIZE {
+        return (vec![], vec![]);
+    }
+
+    // Longest common subsequence of the tokens, from the end
+    let width = new_tokens.len() + 1;
+    let mut lengths = vec![0usize; (old_tokens.len() + 1) * width];
+    for i in (0..old_tokens.len()).rev() {
+        for j in (0..new_tokens.len()).rev() {
+            lengths[i * width + j] = if old[old_tokens[i].clone()] == new[new_tokens[j].clone()] {
+                lengths[(i + 1) * width + j + 1] + 1
+            } else {
+                lengths[(i + 1) * width + j].max(lengths[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut old_changes = vec![];
+    let mut new_changes = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < old_tokens.len() || j < new_tokens.len() {
+        if i < old_tokens.len()
+            && j < new_tokens.len()
+            && old[old_tokens[i].clone()] == new[new_tokens[j].clone()]
+        {
+            i += 1;
+            j += 1;
+        } else if j == new_tokens.len()
+            || (i < old_tokens.len() && lengths[(i + 1) * width + j] >= lengths[i * width + j + 1])
+        {
+            push_merged(&mut old_changes, old_tokens[i].clone());
+            i += 1;
+        } else {
+            push_merged(&mut new_changes, new_tokens[j].clone());
+            j += 1;
+        }
+    }
+
+    (old_changes, new_changes)
+}
+
+fn push_merged(ranges: &mut Vec<Range<usize>>, range: Range<usize>) {
+    match ranges.last_mut() {
+        Some(last) if last.end == range.start => last.end = range.end,
+        _ => ranges.push(range),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classify_lines_of_a_diff() {
+        let diff = "From 1234 Mon Sep 17 00:00:00 2001\n\
+                    diff --git a/src/main.rs b/src/main.rs\n\
+                    index 83db48f..bf269f4 100644\n\
+                    --- a/src/main.rs\n\
+                    +++ b/src/main.rs\n\
+                    @@ -1,3 +1,3 @@ fn main() {\n \
+                    let a = 1;\n\
+                    --- a = 2;\n\
+                    +++ a = 3;\n\
+                    \n\
+                    \\ No newline at end of file\n\
+                    @@ -5 +5,0 @@\n\
+                    -gone\n\
+                    trailing text\n";
+
+        let mut parser = PatchParser::default();
+        let kinds: Vec<PatchLine> = diff.lines().map(|line| parser.classify(line)).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                PatchLine::Other,
+                PatchLine::FileStart,
+                PatchLine::Meta,
+                PatchLine::OldPath,
+                PatchLine::NewPath,
+                PatchLine::Hunk,
+                PatchLine::Context,
+                PatchLine::Removed,
+                PatchLine::Added,
+                PatchLine::Context,
+                PatchLine::Meta,
+                PatchLine::Hunk,
+                PatchLine::Removed,
+                PatchLine::Other,
+            ]
+        );
+    }
+
+    #[test]
+    fn classify_lines_of_commits() {
+        let log = "commit 0123456789abcdef (HEAD -> master)\n\
+                   Author: A U Thor <author@example.com>\n\
+                   Date:   Mon Jan 1 00:00:00 2024 +0000\n\
+                   \n    \
+                   Add a feature\n\
+                   \n    \
+                   It is great.\n\
+                   \n\
+                   diff --git a/a b/a\n\
+                   commit 0123456\n";
+
+        let mut parser = PatchParser::default();
+        let kinds: Vec<PatchLine> = log.lines().map(|line| parser.classify(line)).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                PatchLine::CommitStart,
+                PatchLine::CommitHeader,
+                PatchLine::CommitHeader,
+                PatchLine::Other,
+                PatchLine::CommitSubject,
+                PatchLine::Other,
+                PatchLine::CommitMessage,
+                PatchLine::Other,
+                PatchLine::FileStart,
+                PatchLine::CommitStart,
+            ]
+        );
+        assert_eq!(commit_parts("commit 0123456 (tag: v1)"), (7..14, 14..24));
+        assert!(is_trailer(
+            "    Signed-off-by: A U Thor <author@example.com>"
+        ));
+        assert!(!is_trailer("    Note: this is not a trailer"));
+    }
+
+    #[test]
+    fn headers() {
+        assert_eq!(hunk_header("@@ -1,3 +1,4 @@ fn main()"), Some((3, 4, 15)));
+        assert_eq!(hunk_header("@@ -7 +7 @@"), Some((1, 1, 11)));
+        assert_eq!(hunk_header("@@ nonsense @@"), None);
+
+        assert_eq!(header_path("+++ b/src/main.rs\n"), Some("src/main.rs"));
+        assert_eq!(header_path("--- main.c\t2020-01-01 10:00"), Some("main.c"));
+        assert_eq!(header_path("--- /dev/null"), None);
+    }
+
+    #[test]
+    fn changed_words() {
+        let old = "let value = compute(1, 2);";
+        let new = "let result = compute(1, 3);";
+        let (old_changes, new_changes) = word_diff(old, new);
+        assert_eq!(
+            old_changes
+                .iter()
+                .map(|r| &old[r.clone()])
+                .collect::<Vec<_>>(),
+            vec!["value", "2"]
+        );
+        assert_eq!(
+            new_changes
+                .iter()
+                .map(|r| &new[r.clone()])
+                .collect::<Vec<_>>(),
+            vec!["result", "3"]
+        );
+    }
+}
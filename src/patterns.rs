@@ -0,0 +1,338 @@
+//! Highlighting of regex matches with named styles, like errors with a red background and
+//! warnings with a yellow underline. Patterns are applied in priority order: where the matches
+//! of several patterns overlap, the pattern that was added first wins.
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+use syntect::highlighting::{Color, FontStyle, Style};
+use syntect::parsing::{Regex, Region};
+
+use crate::error::*;
+
+/// Colors of the terminal palette (see `terminal::to_ansi_color`)
+const fn ansi(index: u8) -> Color {
+    Color {
+        r: index,
+        g: 0x00,
+        b: 0x00,
+        a: 0x00,
+    }
+}
+
+const ANSI_COLORS: &[(&str, u8)] = &[
+    ("black", 0),
+    ("red", 1),
+    ("green", 2),
+    ("yellow", 3),
+    ("blue", 4),
+    ("magenta", 5),
+    ("cyan", 6),
+    ("white", 7),
+];
+
+/// The prefix of the built-in styles for search patterns (`match-1` to `match-6`)
+const SEARCH_STYLE_PREFIX: &str = "match-";
+
+/// The foreground and background colors of the search styles
+const SEARCH_COLORS: &[(u8, u8)] = &[(0, 3), (0, 2), (0, 6), (0, 5), (7, 4), (7, 1)];
+
+/// The style of the matches of a pattern. Colors and font styles that are not set are taken
+/// from the syntax highlighting.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PatternStyle {
+    pub foreground: Option<Color>,
+    pub background: Option<Color>,
+    pub font_style: FontStyle,
+}
+
+impl PatternStyle {
+    /// The built-in styles: `error` (red background), `warning` (yellow underline), `info`
+    /// (cyan), `match` (yellow background) and `match-1` to `match-6` (backgrounds of distinct
+    /// colors, see `HighlightPatterns::add_search`)
+    pub fn builtin(name: &str) -> Option<Self> {
+        let style = |foreground, background, font_style| PatternStyle {
+            foreground,
+            background,
+            font_style,
+        };
+        match name {
+            "error" => Some(style(Some(ansi(7)), Some(ansi(1)), FontStyle::BOLD)),
+            "warning" => Some(style(Some(ansi(3)), None, FontStyle::UNDERLINE)),
+            "info" => Some(style(Some(ansi(6)), None, FontStyle::empty())),
+            "match" => Some(style(Some(ansi(0)), Some(ansi(3)), FontStyle::empty())),
+            _ if name.starts_with(SEARCH_STYLE_PREFIX) => {
+                let index: usize = name[SEARCH_STYLE_PREFIX.len()..].parse().ok()?;
+                let &(foreground, background) = SEARCH_COLORS.get(index.checked_sub(1)?)?;
+                Some(style(
+                    Some(ansi(foreground)),
+                    Some(ansi(background)),
+                    FontStyle::empty(),
+                ))
+            }
+            _ => None,
+        }
+    }
+
+    /// Parse a style like `fg=#ffffff,bg=red,bold,underline`. Colors are either `#RRGGBB` or
+    /// the name of a terminal color (`black`, `red`, `green`, `yellow`, `blue`, `magenta`,
+    /// `cyan` or `white`).
+    pub fn parse(spec: &str) -> Result<Self> {
+        let invalid = |reason: String| -> Error {
+            format!("Invalid pattern style '{}': {}", spec, reason).into()
+        };
+        let color = |value: &str| -> Result<Color> {
+            parse_color(value).ok_or_else(|| invalid(format!("unknown color '{}'", value)))
+        };
+
+        let mut style = PatternStyle {
+            foreground: None,
+            background: None,
+            font_style: FontStyle::empty(),
+        };
+        for attribute in spec.split(',').map(str::trim) {
+            let mut parts = attribute.splitn(2, '=');
+            match (parts.next().unwrap_or_default(), parts.next()) {
+                ("fg", Some(value)) => style.foreground = Some(color(value)?),
+                ("bg", Some(value)) => style.background = Some(color(value)?),
+                ("bold", None) => style.font_style |= FontStyle::BOLD,
+                ("italic", None) => style.font_style |= FontStyle::ITALIC,
+                ("underline", None) => style.font_style |= FontStyle::UNDERLINE,
+                _ => {
+                    return Err(invalid(format!(
+                        "unknown attribute '{}' (use fg=<color>, bg=<color>, bold, italic or \
+                         underline)",
+                        attribute
+                    )))
+                }
+            }
+        }
+        Ok(style)
+    }
+
+    pub(crate) fn apply(&self, mut style: Style) -> Style {
+        style.foreground = self.foreground.unwrap_or(style.foreground);
+        style.background = self.background.unwrap_or(style.background);
+        style.font_style |= self.font_style;
+        style
+    }
+}
+
+fn parse_color(value: &str) -> Option<Color> {
+    if let Some(&(_, index)) = ANSI_COLORS.iter().find(|(name, _)| *name == value) {
+        return Some(ansi(index));
+    }
+
+    if value.len() != 7 || !value.starts_with('#') {
+        return None;
+    }
+    let rgb = u32::from_str_radix(&value[1..], 16).ok()?;
+    Some(Color {
+        r: (rgb >> 16) as u8,
+        g: (rgb >> 8) as u8,
+        b: rgb as u8,
+        a: 0xff,
+    })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct PatternGroup {
+    style: String,
+    regex: Regex,
+}
+
+/// Regular expressions whose matches are highlighted with a named style. The built-in styles
+/// (see `PatternStyle::builtin`) can be overridden and new styles can be defined.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HighlightPatterns {
+    styles: HashMap<String, PatternStyle>,
+    groups: Vec<PatternGroup>,
+
+    /// The number of search patterns (see `add_search`)
+    searches: usize,
+}
+
+impl HighlightPatterns {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Define (or redefine) a named style
+    pub fn define_style(&mut self, name: impl Into<String>, style: PatternStyle) -> &mut Self {
+        self.styles.insert(name.into(), style);
+        self
+    }
+
+    /// Highlight the matches of the regular expression with the named style. Patterns that
+    /// are added earlier take precedence over later ones.
+    pub fn add(&mut self, style: &str, pattern: &str) -> Result<&mut Self> {
+        if self.style(style).is_none() {
+            return Err(format!(
+                "Unknown pattern style '{}' (built-in styles: error, warning, info, match)",
+                style
+            )
+            .into());
+        }
+        if let Some(error) = Regex::try_compile(pattern) {
+            return Err(format!("Invalid highlight pattern '{}': {}", pattern, error).into());
+        }
+
+        self.groups.push(PatternGroup {
+            style: style.to_owned(),
+            regex: Regex::new(pattern.to_owned()),
+        });
+        Ok(self)
+    }
+
+    /// Highlight the matches of a search pattern, with a background color that differs from
+    /// the ones of the previous search patterns (the styles `match-1` to `match-6`, which are
+    /// used again after the sixth pattern)
+    pub fn add_search(&mut self, pattern: &str) -> Result<&mut Self> {
+        let style = format!(
+            "{}{}",
+            SEARCH_STYLE_PREFIX,
+            self.searches % SEARCH_COLORS.len() + 1
+        );
+        self.add(&style, pattern)?;
+        self.searches += 1;
+        Ok(self)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    fn style(&self, name: &str) -> Option<PatternStyle> {
+        self.styles
+            .get(name)
+            .cloned()
+            .or_else(|| PatternStyle::builtin(name))
+    }
+
+    /// The byte ranges of the matches within the line, with the style of their pattern. The
+    /// ranges are sorted and don't overlap: a match is cut where it overlaps a match of a
+    /// pattern with a higher priority.
+    pub(crate) fn matches(&self, line: &str) -> Vec<(Range<usize>, PatternStyle)> {
+        let mut matches: Vec<(Range<usize>, PatternStyle)> = vec![];
+        let mut region = Region::new();
+
+        for group in &self.groups {
+            let style = match self.style(&group.style) {
+                Some(style) => style,
+                None => continue,
+            };
+
+            let mut position = 0;
+            while position < line.len()
+                && group
+                    .regex
+                    .search(line, position, line.len(), Some(&mut region))
+            {
+                let (start, end) = match region.pos(0) {
+                    Some(pos) => pos,
+                    None => break,
+                };
+                if start == end {
+                    // Skip empty matches, making sure to stay on a character boundary
+                    position = end + line[end..].chars().next().map_or(1, char::len_utf8);
+                    continue;
+                }
+                position = end;
+
+                // Insert the parts of the match that are not covered yet
+                let mut free = start;
+                let mut index = 0;
+                while free < end {
+                    while index < matches.len() && matches[index].0.end <= free {
+                        index += 1;
+                    }
+                    let taken = matches.get(index).map(|(range, _)| range.clone());
+                    match taken {
+                        Some(taken) if taken.start <= free => free = taken.end,
+                        Some(taken) => {
+                            let piece_end = taken.start.min(end);
+                            matches.insert(index, (free..piece_end, style));
+                            index += 1;
+                            free = piece_end;
+                        }
+                        None => {
+                            matches.push((free..end, style));
+                            free = end;
+                        }
+                    }
+                }
+            }
+        }
+
+        matches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_styles() {
+        let style = PatternStyle::parse("fg=#ff8000, bg=blue,bold,underline").unwrap();
+        assert_eq!(
+            style.foreground.map(|c| (c.r, c.g, c.b)),
+            Some((0xff, 0x80, 0x00))
+        );
+        assert_eq!(style.background, Some(ansi(4)));
+        assert_eq!(style.font_style, FontStyle::BOLD | FontStyle::UNDERLINE);
+
+        assert!(PatternStyle::parse("fg=orange").is_err());
+        assert!(PatternStyle::parse("blink").is_err());
+        assert!(PatternStyle::builtin("warning").is_some());
+    }
+
+    #[test]
+    fn search_patterns_have_distinct_colors() {
+        let mut patterns = HighlightPatterns::new();
+        for pattern in &["a", "b", "c", "d", "e", "f", "g"] {
+            patterns.add_search(pattern).unwrap();
+        }
+        assert!(patterns.add_search("(").is_err());
+
+        let styles: Vec<_> = patterns
+            .matches("abcdefg")
+            .into_iter()
+            .map(|(_, style)| style.background)
+            .collect();
+        assert_eq!(
+            styles[0],
+            PatternStyle::builtin("match").unwrap().background
+        );
+        for (index, style) in styles[..6].iter().enumerate() {
+            assert!(styles[..index].iter().all(|other| other != style));
+        }
+        assert_eq!(styles[6], styles[0]);
+        assert!(PatternStyle::builtin("match-7").is_none());
+    }
+
+    #[test]
+    fn overlapping_matches_are_resolved_by_priority() {
+        let mut patterns = HighlightPatterns::new();
+        patterns
+            .define_style("path", PatternStyle::parse("italic").unwrap())
+            .add("error", "ERROR")
+            .unwrap()
+            .add("warning", r"\w+ \w+")
+            .unwrap()
+            .add("path", "[a-z]+/[a-z.]+")
+            .unwrap();
+        assert!(patterns.add("unknown", "x").is_err());
+        assert!(patterns.add("error", "(").is_err());
+
+        let matches = patterns.matches("at main/lib.rs: ERROR in lib");
+        let ranges: Vec<_> = matches.iter().map(|(range, _)| range.clone()).collect();
+        assert_eq!(ranges, vec![0..7, 7..14, 16..21, 21..24]);
+
+        let error = PatternStyle::builtin("error").unwrap();
+        let warning = PatternStyle::builtin("warning").unwrap();
+        assert_eq!(matches[1].1.font_style, FontStyle::ITALIC);
+        assert_eq!(matches[2].1, error);
+        assert_eq!(matches[3].1, warning);
+    }
+}
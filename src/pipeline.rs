@@ -0,0 +1,814 @@
+//! The building blocks of the printer, for applications that assemble their own output
+//! pipeline (for example to show highlighted code in a GUI): a `Highlighter` turns text into
+//! styled spans, a `Decorator` creates the pieces of the gutter in front of a line and a
+//! `Renderer` writes both in an `OutputFormat`. The `Controller` (and `PrettyPrinter`) remain
+//! the batteries-included way of printing files, with headers, paging, wrapping and so on.
+//!
+//! ```
+//! use bat::assets::HighlightingAssets;
+//! use bat::pipeline::{Decorator, Highlighter, OutputFormat, Renderer};
+//! use bat::style::{StyleComponent, StyleComponents};
+//!
+//! let assets = HighlightingAssets::from_binary();
+//! let mut highlighter = Highlighter::new(&assets, "rs", "Monokai Extended").unwrap();
+//! let decorator = Decorator::new(&StyleComponents::new(&[
+//!     StyleComponent::LineNumbers,
+//!     StyleComponent::Grid,
+//! ]));
+//! let mut renderer = Renderer::new(OutputFormat::Plain);
+//!
+//! let mut output = vec![];
+//! for (index, line) in ["fn main() {\n", "}\n"].iter().enumerate() {
+//!     let spans = highlighter.highlight_line(line);
+//!     let gutter = decorator.gutter(index + 1, false);
+//!     renderer.write_line(&mut output, &gutter, &spans).unwrap();
+//! }
+//! renderer.finish(&mut output).unwrap();
+//!
+//! assert_eq!(
+//!     String::from_utf8(output).unwrap(),
+//!     "   1 │ fn main() {\n   2 │ }\n"
+//! );
+//! ```
+
+use std::io::Write;
+
+use ansi_term::Colour::Fixed;
+
+use syntect::easy::HighlightLines;
+use syntect::highlighting::Theme;
+use syntect::parsing::{SyntaxReference, SyntaxSet};
+use syntect::util::LinesWithEndings;
+
+pub use syntect::highlighting::{Color, FontStyle, Style};
+
+use unicode_width::UnicodeWidthStr;
+
+use crate::assets::HighlightingAssets;
+use crate::error::*;
+use crate::line_range::{HighlightedLineRanges, RangeCheckResult};
+use crate::printer::DEFAULT_GUTTER_COLOR;
+use crate::style::StyleComponents;
+use crate::template::Template;
+use crate::terminal::{as_terminal_escaped, to_ansi_color};
+
+/// Highlights text line by line, keeping the state of the syntax highlighter between the
+/// lines (which have to be passed in order)
+pub struct Highlighter<'a> {
+    syntax_set: &'a SyntaxSet,
+    highlighter: HighlightLines<'a>,
+    background: Option<Color>,
+    line_highlight: Option<Color>,
+}
+
+impl<'a> Highlighter<'a> {
+    /// Create a highlighter for the given language (name or file extension) and theme.
+    /// Fails if the language is unknown. Unknown themes fall back to the default theme.
+    pub fn new(assets: &'a HighlightingAssets, language: &str, theme: &str) -> Result<Self> {
+        let syntax = assets.get_syntax_by_token(language)?;
+        Ok(Self::with_syntax(
+            assets.get_syntax_set(),
+            syntax,
+            assets.get_theme(theme),
+        ))
+    }
+
+    pub(crate) fn with_syntax(
+        syntax_set: &'a SyntaxSet,
+        syntax: &SyntaxReference,
+        theme: &'a Theme,
+    ) -> Self {
+        Highlighter {
+            syntax_set,
+            highlighter: HighlightLines::new(syntax, theme),
+            background: theme.settings.background,
+            line_highlight: theme.settings.line_highlight,
+        }
+    }
+
+    /// The background color of the theme (for `Renderer::background`)
+    pub fn background(&self) -> Option<Color> {
+        self.background
+    }
+
+    /// The background color of highlighted lines of the theme (for
+    /// `Renderer::highlight_color`)
+    pub fn line_highlight(&self) -> Option<Color> {
+        self.line_highlight
+    }
+
+    /// Highlight the next line. The line should include its line ending, since some syntaxes
+    /// rely on it.
+    pub fn highlight_line<'b>(&mut self, line: &'b str) -> Vec<(Style, &'b str)> {
+        self.highlighter.highlight(line, self.syntax_set)
+    }
+
+    /// Highlight a whole text (like the content of a text widget), line by line. The spans of
+    /// each line include its line ending.
+    pub fn highlight_text<'b>(&mut self, text: &'b str) -> Vec<Vec<(Style, &'b str)>> {
+        LinesWithEndings::from(text)
+            .map(|line| self.highlight_line(line))
+            .collect()
+    }
+}
+
+/// The kind of a piece of the gutter
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GutterPieceKind {
+    LineNumber,
+    GridBorder,
+}
+
+/// A piece of the gutter in front of a line (without any styling)
+#[derive(Debug, Clone, PartialEq)]
+pub struct GutterPiece {
+    pub kind: GutterPieceKind,
+    pub text: String,
+}
+
+impl GutterPiece {
+    /// The width of the piece in terminal columns
+    pub fn width(&self) -> usize {
+        self.text.width()
+    }
+}
+
+/// Creates the pieces of the gutter (line numbers and the grid border) for the
+/// `StyleComponent`s it is created with
+#[derive(Debug, Clone)]
+pub struct Decorator {
+    numbers: bool,
+    grid: bool,
+    number_width: usize,
+    number_template: Option<Template>,
+}
+
+impl Decorator {
+    pub fn new(components: &StyleComponents) -> Self {
+        Decorator {
+            numbers: components.numbers(),
+            grid: components.grid(),
+            number_width: 4,
+            number_template: None,
+        }
+    }
+
+    /// The largest line number that is decorated, since line numbers with more than four
+    /// digits widen the gutter (default: 9999)
+    pub fn max_line_number(&mut self, max_line_number: usize) -> &mut Self {
+        self.number_width = max_line_number.to_string().len().max(4);
+        self
+    }
+
+    /// Customize the line numbers with a template (see `Template::line_number`)
+    pub fn line_number_template(&mut self, template: Option<Template>) -> &mut Self {
+        self.number_template = template;
+        self
+    }
+
+    /// The pieces of the gutter of a line. Continuation lines (of wrapped lines) have no line
+    /// number.
+    pub fn gutter(&self, line_number: usize, continuation: bool) -> Vec<GutterPiece> {
+        let mut pieces = vec![];
+
+        if self.numbers {
+            let number = if continuation {
+                " ".repeat(self.number_width)
+            } else {
+                format!("{:>width$}", line_number, width = self.number_width)
+            };
+            let text = match self.number_template {
+                Some(ref template) if continuation => {
+                    " ".repeat(template.line_number_width(self.number_width))
+                }
+                Some(ref template) => template.render(|_| number.clone()),
+                None => number,
+            };
+            pieces.push(GutterPiece {
+                kind: GutterPieceKind::LineNumber,
+                text,
+            });
+        }
+
+        if self.grid && !pieces.is_empty() {
+            pieces.push(GutterPiece {
+                kind: GutterPieceKind::GridBorder,
+                text: "│".into(),
+            });
+        }
+
+        pieces
+    }
+
+    /// The width of the gutter in terminal columns, including the spaces that separate the
+    /// pieces from each other and from the code
+    pub fn width(&self) -> usize {
+        let pieces = self.gutter(1, true);
+        pieces.len() + pieces.iter().map(GutterPiece::width).sum::<usize>()
+    }
+}
+
+/// The format that a `Renderer` writes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OutputFormat {
+    /// Text with ANSI escape sequences for terminals
+    Ansi {
+        /// Whether or not to output 24bit colors
+        true_color: bool,
+
+        /// Whether or not to use ANSI italics
+        italics: bool,
+    },
+
+    /// Text without any styling
+    Plain,
+
+    /// An SVG image of the lines (for slides and blogs), with the colors of the theme and a
+    /// monospace font. Tabs should be expanded before, since they don't align in SVG text.
+    /// Numbered lines can be given ids and highlighted (see `Renderer::line_anchors` and
+    /// `Renderer::highlighted_lines`).
+    Svg {
+        /// The size of the font in pixels
+        font_size: u32,
+    },
+
+    /// HTML for a `<pre>` element, with the colors of the spans in `style` attributes. Every
+    /// line is a `<span class="line">` (with the class `highlighted` for highlighted lines),
+    /// followed by its line ending, and the pieces of the gutter have the class `gutter`. The
+    /// anchors of numbered lines work like in SVG images.
+    Html,
+}
+
+/// The width of a character of a monospace font and the height of a line (relative to the
+/// font size) and the padding around the lines of an SVG image (in lines)
+const SVG_CHAR_WIDTH: f64 = 0.6;
+const SVG_LINE_HEIGHT: f64 = 1.4;
+const SVG_PADDING: f64 = 1.0;
+
+/// A color as an SVG (or CSS) color, with the colors of the terminal palette (see
+/// `terminal::to_ansi_color`) in their default values
+pub(crate) fn hex_color(color: Color) -> String {
+    let (r, g, b) = if color.a == 0 || color.a == 0x0f {
+        ansi_colours::rgb_from_ansi256(color.r)
+    } else {
+        (color.r, color.g, color.b)
+    };
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+pub(crate) fn xml_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// A styled piece of a line of an SVG image
+fn svg_span(text: &str, fill: Color, font_style: FontStyle) -> String {
+    let mut attributes = format!("fill=\"{}\"", hex_color(fill));
+    if font_style.contains(FontStyle::BOLD) {
+        attributes.push_str(" font-weight=\"bold\"");
+    }
+    if font_style.contains(FontStyle::ITALIC) {
+        attributes.push_str(" font-style=\"italic\"");
+    }
+    if font_style.contains(FontStyle::UNDERLINE) {
+        attributes.push_str(" text-decoration=\"underline\"");
+    }
+    format!("<tspan {}>{}</tspan>", attributes, xml_escape(text))
+}
+
+/// The class of the text and of the background of highlighted lines in SVG images (and of
+/// highlighted lines in HTML)
+const SVG_HIGHLIGHTED_CLASS: &str = "highlighted";
+
+/// A styled piece of a line of HTML
+fn html_span(text: &str, style: Style) -> String {
+    let mut declarations = format!("color:{}", hex_color(style.foreground));
+    if style.font_style.contains(FontStyle::BOLD) {
+        declarations.push_str(";font-weight:bold");
+    }
+    if style.font_style.contains(FontStyle::ITALIC) {
+        declarations.push_str(";font-style:italic");
+    }
+    if style.font_style.contains(FontStyle::UNDERLINE) {
+        declarations.push_str(";text-decoration:underline");
+    }
+    format!(
+        "<span style=\"{}\">{}</span>",
+        declarations,
+        xml_escape(text)
+    )
+}
+
+/// A line of an SVG image, with its line number (if it was written as a numbered line)
+struct SvgLine {
+    number: Option<usize>,
+    content: String,
+}
+
+/// Writes highlighted lines (with their gutters) in an output format
+pub struct Renderer {
+    format: OutputFormat,
+    gutter_color: Option<Color>,
+    background: Option<Color>,
+    anchor_prefix: Option<String>,
+    highlighted_lines: HighlightedLineRanges,
+    highlight_color: Option<Color>,
+
+    /// The lines of an SVG image, which is written by `finish` (since its size is only known
+    /// after the last line), and the width of the widest line
+    svg_lines: Vec<SvgLine>,
+    svg_columns: usize,
+}
+
+impl Renderer {
+    pub fn new(format: OutputFormat) -> Self {
+        Renderer {
+            format,
+            gutter_color: None,
+            background: None,
+            anchor_prefix: None,
+            highlighted_lines: HighlightedLineRanges::default(),
+            highlight_color: None,
+            svg_lines: vec![],
+            svg_columns: 0,
+        }
+    }
+
+    /// The background color of SVG images, like the one of the theme (see
+    /// `Highlighter::background`). Default: transparent
+    pub fn background(&mut self, color: Option<Color>) -> &mut Self {
+        self.background = color;
+        self
+    }
+
+    /// The color of the gutter (default: a dark gray)
+    pub fn gutter_color(&mut self, color: Option<Color>) -> &mut Self {
+        self.gutter_color = color;
+        self
+    }
+
+    /// Give the numbered lines of SVG images (and HTML) an id (the prefix followed by the line
+    /// number, like `L12`) and link their line numbers to it, so that lines can be linked to
+    /// like on code hosting sites (`image.svg#L12`). Default: none
+    pub fn line_anchors(&mut self, prefix: Option<&str>) -> &mut Self {
+        self.anchor_prefix = prefix.map(String::from);
+        self
+    }
+
+    /// The numbered lines of SVG images (and HTML) that are highlighted: their text and a
+    /// background rectangle get the CSS class `highlighted` (default: none)
+    pub fn highlighted_lines(&mut self, lines: HighlightedLineRanges) -> &mut Self {
+        self.highlighted_lines = lines;
+        self
+    }
+
+    /// The background color of highlighted lines in SVG images, like the line highlight color
+    /// of the theme (see `Highlighter::line_highlight`). Default: a translucent gray
+    pub fn highlight_color(&mut self, color: Option<Color>) -> &mut Self {
+        self.highlight_color = color;
+        self
+    }
+
+    /// Write a line: the pieces of the gutter (followed by a space each) and the styled spans.
+    /// A line ending is added if the last span doesn't end with one.
+    pub fn write_line(
+        &mut self,
+        out: &mut dyn Write,
+        gutter: &[GutterPiece],
+        spans: &[(Style, &str)],
+    ) -> Result<()> {
+        self.write_line_with_number(out, None, gutter, spans)
+    }
+
+    /// Write a line like `write_line`, for the given line number of the input (which is used
+    /// for the anchors and highlighted lines of SVG images). Continuation lines of wrapped
+    /// lines should be written with `write_line`.
+    pub fn write_numbered_line(
+        &mut self,
+        out: &mut dyn Write,
+        line_number: usize,
+        gutter: &[GutterPiece],
+        spans: &[(Style, &str)],
+    ) -> Result<()> {
+        self.write_line_with_number(out, Some(line_number), gutter, spans)
+    }
+
+    fn write_line_with_number(
+        &mut self,
+        out: &mut dyn Write,
+        line_number: Option<usize>,
+        gutter: &[GutterPiece],
+        spans: &[(Style, &str)],
+    ) -> Result<()> {
+        match self.format {
+            OutputFormat::Svg { .. } => {
+                self.add_svg_line(line_number, gutter, spans);
+                return Ok(());
+            }
+            OutputFormat::Html => return self.write_html_line(out, line_number, gutter, spans),
+            _ => {}
+        }
+
+        for piece in gutter {
+            match self.format {
+                OutputFormat::Ansi { true_color, .. } => {
+                    let color = match self.gutter_color {
+                        Some(color) => to_ansi_color(color, true_color),
+                        None => Fixed(DEFAULT_GUTTER_COLOR),
+                    };
+                    write!(out, "{} ", color.paint(piece.text.as_str()))?;
+                }
+                OutputFormat::Plain => write!(out, "{} ", piece.text)?,
+                OutputFormat::Svg { .. } | OutputFormat::Html => {
+                    unreachable!("SVG and HTML lines are written separately")
+                }
+            }
+        }
+
+        for &(style, text) in spans {
+            let code = text.trim_end_matches(&['\r', '\n'][..]);
+            match self.format {
+                OutputFormat::Ansi {
+                    true_color,
+                    italics,
+                } => write!(
+                    out,
+                    "{}",
+                    as_terminal_escaped(style, code, true_color, true, italics, None)
+                )?,
+                OutputFormat::Plain => write!(out, "{}", code)?,
+                OutputFormat::Svg { .. } | OutputFormat::Html => {
+                    unreachable!("SVG and HTML lines are written separately")
+                }
+            }
+        }
+
+        let line_ending = spans.last().map_or("", |(_, text)| {
+            &text[text.trim_end_matches(&['\r', '\n'][..]).len()..]
+        });
+        if line_ending.is_empty() {
+            writeln!(out)?;
+        } else {
+            write!(out, "{}", line_ending)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_html_line(
+        &self,
+        out: &mut dyn Write,
+        line_number: Option<usize>,
+        gutter: &[GutterPiece],
+        spans: &[(Style, &str)],
+    ) -> Result<()> {
+        let anchor = self.anchor(line_number);
+        let mut line = String::from("<span class=\"line");
+        if self.is_highlighted(line_number) {
+            line.push(' ');
+            line.push_str(SVG_HIGHLIGHTED_CLASS);
+        }
+        line.push('"');
+        if let Some(ref anchor) = anchor {
+            line.push_str(&format!(" id=\"{}\"", anchor));
+        }
+        line.push('>');
+
+        for piece in gutter {
+            let text = format!("{} ", xml_escape(&piece.text));
+            match (&anchor, piece.kind) {
+                (Some(anchor), GutterPieceKind::LineNumber) => line.push_str(&format!(
+                    "<a class=\"gutter\" href=\"#{}\">{}</a>",
+                    anchor, text
+                )),
+                _ => line.push_str(&format!("<span class=\"gutter\">{}</span>", text)),
+            }
+        }
+        for &(style, text) in spans {
+            let code = text.trim_end_matches(&['\r', '\n'][..]);
+            if !code.is_empty() {
+                line.push_str(&html_span(code, style));
+            }
+        }
+        line.push_str("</span>");
+
+        let line_ending = spans.last().map_or("", |(_, text)| {
+            &text[text.trim_end_matches(&['\r', '\n'][..]).len()..]
+        });
+        write!(
+            out,
+            "{}{}",
+            line,
+            if line_ending.is_empty() {
+                "\n"
+            } else {
+                line_ending
+            }
+        )?;
+        Ok(())
+    }
+
+    fn add_svg_line(
+        &mut self,
+        line_number: Option<usize>,
+        gutter: &[GutterPiece],
+        spans: &[(Style, &str)],
+    ) {
+        let gutter_color = self.gutter_color.unwrap_or(Color {
+            r: DEFAULT_GUTTER_COLOR,
+            g: 0,
+            b: 0,
+            a: 0,
+        });
+
+        let mut line = String::new();
+        let mut columns = 0;
+        for piece in gutter {
+            let span = svg_span(
+                &format!("{} ", piece.text),
+                gutter_color,
+                FontStyle::empty(),
+            );
+            match (self.anchor(line_number), piece.kind) {
+                (Some(anchor), GutterPieceKind::LineNumber) => {
+                    line.push_str(&format!("<a href=\"#{}\">{}</a>", anchor, span));
+                }
+                _ => line.push_str(&span),
+            }
+            columns += piece.width() + 1;
+        }
+        for &(style, text) in spans {
+            let code = text.trim_end_matches(&['\r', '\n'][..]);
+            if !code.is_empty() {
+                line.push_str(&svg_span(code, style.foreground, style.font_style));
+                columns += code.width();
+            }
+        }
+
+        self.svg_lines.push(SvgLine {
+            number: line_number,
+            content: line,
+        });
+        self.svg_columns = self.svg_columns.max(columns);
+    }
+
+    /// The id of a numbered line of an SVG image (or HTML), if lines have anchors
+    fn anchor(&self, line_number: Option<usize>) -> Option<String> {
+        match (&self.anchor_prefix, line_number) {
+            (Some(prefix), Some(number)) => Some(format!("{}{}", xml_escape(prefix), number)),
+            _ => None,
+        }
+    }
+
+    fn is_highlighted(&self, line_number: Option<usize>) -> bool {
+        match line_number {
+            Some(number) => self.highlighted_lines.0.check(number) == RangeCheckResult::InRange,
+            None => false,
+        }
+    }
+
+    /// Finish the output after the last line
+    pub fn finish(&mut self, out: &mut dyn Write) -> Result<()> {
+        if let OutputFormat::Svg { font_size } = self.format {
+            let font_size = f64::from(font_size);
+            let line_height = SVG_LINE_HEIGHT * font_size;
+            let padding = SVG_PADDING * line_height;
+            let width = 2.0 * padding + self.svg_columns as f64 * SVG_CHAR_WIDTH * font_size;
+            let height = 2.0 * padding + self.svg_lines.len() as f64 * line_height;
+
+            writeln!(
+                out,
+                "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0:.1}\" height=\"{1:.1}\" \
+                 viewBox=\"0 0 {0:.1} {1:.1}\">",
+                width, height
+            )?;
+            let highlight = match self.highlight_color {
+                Some(color) => format!("fill: {};", hex_color(color)),
+                None => "fill: #808080; fill-opacity: 0.25;".into(),
+            };
+            writeln!(
+                out,
+                "<style>text {{ font-family: monospace; font-size: {}px; white-space: pre; }} \
+                 rect.{} {{ {} }}</style>",
+                font_size, SVG_HIGHLIGHTED_CLASS, highlight
+            )?;
+            if let Some(background) = self.background {
+                writeln!(
+                    out,
+                    "<rect width=\"100%\" height=\"100%\" fill=\"{}\"/>",
+                    hex_color(background)
+                )?;
+            }
+            let lines = std::mem::take(&mut self.svg_lines);
+            for (index, line) in lines.iter().enumerate() {
+                let top = padding + index as f64 * line_height;
+                let mut attributes = String::new();
+                if let Some(anchor) = self.anchor(line.number) {
+                    attributes.push_str(&format!(" id=\"{}\"", anchor));
+                }
+                if self.is_highlighted(line.number) {
+                    writeln!(
+                        out,
+                        "<rect class=\"{}\" x=\"0\" y=\"{:.1}\" width=\"100%\" height=\"{:.1}\"/>",
+                        SVG_HIGHLIGHTED_CLASS, top, line_height
+                    )?;
+                    attributes.push_str(&format!(" class=\"{}\"", SVG_HIGHLIGHTED_CLASS));
+                }
+
+                // The baseline is a bit above the bottom of the line, for the descenders
+                let baseline = top + line_height - 0.3 * font_size;
+                writeln!(
+                    out,
+                    "<text{} x=\"{:.1}\" y=\"{:.1}\" xml:space=\"preserve\">{}</text>",
+                    attributes, padding, baseline, line.content
+                )?;
+            }
+            writeln!(out, "</svg>")?;
+            self.svg_columns = 0;
+        }
+
+        out.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::style::StyleComponent;
+
+    #[test]
+    fn gutters_of_decorators() {
+        let mut decorator = Decorator::new(&StyleComponents::new(&[
+            StyleComponent::LineNumbers,
+            StyleComponent::Grid,
+        ]));
+        assert_eq!(decorator.width(), 7);
+
+        decorator
+            .max_line_number(12_345)
+            .line_number_template(Some(Template::line_number("L{number}").unwrap()));
+        let gutter = decorator.gutter(42, false);
+        assert_eq!(gutter[0].text, "L   42");
+        assert_eq!(gutter[1].kind, GutterPieceKind::GridBorder);
+        assert_eq!(decorator.gutter(42, true)[0].text, "      ");
+        assert_eq!(decorator.width(), 9);
+
+        let plain = Decorator::new(&StyleComponents::new(&[StyleComponent::Grid]));
+        assert!(plain.gutter(1, false).is_empty());
+    }
+
+    #[test]
+    fn spans_of_texts() {
+        let assets = HighlightingAssets::from_binary();
+        let mut highlighter = Highlighter::new(&assets, "rs", "Monokai Extended").unwrap();
+        let lines = highlighter.highlight_text("fn main() {\n    /* a\n    b */\n}");
+
+        assert_eq!(lines.len(), 4);
+        let text: Vec<String> = lines
+            .iter()
+            .map(|spans| spans.iter().map(|(_, text)| *text).collect())
+            .collect();
+        assert_eq!(text, vec!["fn main() {\n", "    /* a\n", "    b */\n", "}"]);
+
+        // The state of the highlighter is kept between the lines
+        let comment = lines[1].last().unwrap().0;
+        assert_eq!(lines[2][0].0, comment);
+        assert_ne!(lines[0][0].0, comment);
+    }
+
+    #[test]
+    fn ansi_rendering() {
+        let assets = HighlightingAssets::from_binary();
+        let mut highlighter = Highlighter::new(&assets, "rs", "Monokai Extended").unwrap();
+        let spans = highlighter.highlight_line("let x = 1;\r\n");
+
+        let mut renderer = Renderer::new(OutputFormat::Ansi {
+            true_color: true,
+            italics: false,
+        });
+        let mut output = vec![];
+        renderer.write_line(&mut output, &[], &spans).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(output.starts_with("\x1b[38;2;"));
+        assert!(output.ends_with("\x1b[0m\r\n"));
+        assert_eq!(console::strip_ansi_codes(&output), "let x = 1;\r\n");
+    }
+
+    #[test]
+    fn svg_rendering() {
+        let assets = HighlightingAssets::from_binary();
+        let mut highlighter = Highlighter::new(&assets, "rs", "Monokai Extended").unwrap();
+        let decorator = Decorator::new(&StyleComponents::new(&[StyleComponent::LineNumbers]));
+
+        let mut renderer = Renderer::new(OutputFormat::Svg { font_size: 10 });
+        renderer.background(highlighter.background());
+        let mut output = vec![];
+        for (index, line) in ["a < b\n", "x\n"].iter().enumerate() {
+            let spans = highlighter.highlight_line(line);
+            renderer
+                .write_line(&mut output, &decorator.gutter(index + 1, false), &spans)
+                .unwrap();
+        }
+        assert!(output.is_empty());
+        renderer.finish(&mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+
+        // Two lines of 10 columns (with the line numbers) and the padding
+        assert!(lines[0].contains("width=\"88.0\" height=\"56.0\""));
+        assert_eq!(
+            lines[2],
+            "<rect width=\"100%\" height=\"100%\" fill=\"#222222\"/>"
+        );
+        assert!(lines[3].starts_with("<text x=\"14.0\" y=\"25.0\" xml:space=\"preserve\">"));
+        assert!(lines[3].contains("<tspan fill=\"#444444\">   1 </tspan>"));
+        assert!(lines[3].contains("&lt;"));
+        assert_eq!(lines.len(), 6);
+        assert_eq!(lines[5], "</svg>");
+    }
+
+    #[test]
+    fn html_rendering() {
+        use crate::line_range::{LineRange, LineRanges};
+
+        let decorator = Decorator::new(&StyleComponents::new(&[StyleComponent::LineNumbers]));
+        let mut renderer = Renderer::new(OutputFormat::Html);
+        renderer
+            .line_anchors(Some("L"))
+            .highlighted_lines(HighlightedLineRanges(LineRanges::from(vec![
+                LineRange::new(1, 1),
+            ])));
+
+        let mut style = Style::default();
+        style.font_style = FontStyle::BOLD;
+        let mut output = vec![];
+        renderer
+            .write_numbered_line(
+                &mut output,
+                1,
+                &decorator.gutter(1, false),
+                &[(style, "a < b\r\n")],
+            )
+            .unwrap();
+        renderer
+            .write_line(&mut output, &[], &[(Style::default(), "x")])
+            .unwrap();
+        renderer.finish(&mut output).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "<span class=\"line highlighted\" id=\"L1\">\
+             <a class=\"gutter\" href=\"#L1\">   1 </a>\
+             <span style=\"color:#000000;font-weight:bold\">a &lt; b</span></span>\r\n\
+             <span class=\"line\"><span style=\"color:#000000\">x</span></span>\n"
+        );
+    }
+
+    #[test]
+    fn svg_anchors_and_highlighted_lines() {
+        use crate::line_range::{LineRange, LineRanges};
+
+        let decorator = Decorator::new(&StyleComponents::new(&[
+            StyleComponent::LineNumbers,
+            StyleComponent::Grid,
+        ]));
+        let mut renderer = Renderer::new(OutputFormat::Svg { font_size: 10 });
+        renderer
+            .line_anchors(Some("L"))
+            .highlighted_lines(HighlightedLineRanges(LineRanges::from(vec![
+                LineRange::new(2, 2),
+            ])));
+
+        let mut output = vec![];
+        let spans = [(Style::default(), "x\n")];
+        for line_number in 1..=2 {
+            let gutter = decorator.gutter(line_number, false);
+            renderer
+                .write_numbered_line(&mut output, line_number, &gutter, &spans)
+                .unwrap();
+        }
+        renderer
+            .write_line(&mut output, &decorator.gutter(2, true), &spans)
+            .unwrap();
+        renderer.finish(&mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+
+        assert!(lines[1].contains("rect.highlighted { fill: #808080; fill-opacity: 0.25; }"));
+        assert!(lines[2].starts_with("<text id=\"L1\" x="));
+        assert!(lines[2].contains("<a href=\"#L1\"><tspan fill=\"#444444\">   1 </tspan></a>"));
+        assert!(!lines[2].contains("<a href=\"#L1\"><tspan fill=\"#444444\">│"));
+        assert_eq!(
+            lines[3],
+            "<rect class=\"highlighted\" x=\"0\" y=\"28.0\" width=\"100%\" height=\"14.0\"/>"
+        );
+        assert!(lines[4].starts_with("<text id=\"L2\" class=\"highlighted\" x="));
+
+        // Continuation lines have neither an id nor a link
+        assert!(lines[5].starts_with("<text x="));
+        assert!(!lines[5].contains("<a "));
+    }
+}
@@ -1,7 +1,18 @@
+use std::borrow::Cow;
+
 use console::AnsiCodeIterator;
 
-/// Expand tabs like an ANSI-enabled expand(1).
-pub fn expand_tabs(line: &str, width: usize, cursor: &mut usize) -> String {
+/// Expand tabs like an ANSI-enabled expand(1). Lines without tabs are returned unchanged
+/// (without copying them).
+pub fn expand_tabs<'a>(line: &'a str, width: usize, cursor: &mut usize) -> Cow<'a, str> {
+    if !line.contains('\t') {
+        *cursor += AnsiCodeIterator::new(line)
+            .filter(|(_, is_escape)| !is_escape)
+            .map(|(text, _)| text.len())
+            .sum::<usize>();
+        return Cow::Borrowed(line);
+    }
+
     let mut buffer = String::with_capacity(line.len() * 2);
 
     for chunk in AnsiCodeIterator::new(line) {
@@ -30,7 +41,7 @@ pub fn expand_tabs(line: &str, width: usize, cursor: &mut usize) -> String {
         }
     }
 
-    buffer
+    Cow::Owned(buffer)
 }
 
 fn try_parse_utf8_char(input: &[u8]) -> Option<(char, usize)> {
@@ -145,3 +156,17 @@ fn test_try_parse_utf8_char() {
     assert_eq!(try_parse_utf8_char(&[0xef, 0x20]), None);
     assert_eq!(try_parse_utf8_char(&[0xf0, 0xf0]), None);
 }
+
+#[test]
+fn test_expand_tabs() {
+    let mut cursor = 0;
+    let expanded = expand_tabs("\x1B[31mab\x1B[0mc", 4, &mut cursor);
+    if let Cow::Owned(_) = expanded {
+        panic!("expected the input to be borrowed");
+    }
+    assert_eq!(cursor, 3);
+
+    let mut cursor = 1;
+    assert_eq!(expand_tabs("a\tb\t", 4, &mut cursor), "a  b   ");
+    assert_eq!(cursor, 8);
+}
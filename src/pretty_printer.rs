@@ -1,24 +1,49 @@
+use std::collections::BTreeMap;
 use std::ffi::OsStr;
-use std::io::Read;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+#[cfg(feature = "paging")]
+use std::time::Duration;
 
+use ansi_term::Style;
 use console::Term;
+use syntect::highlighting::Color;
 use syntect::parsing::SyntaxReference;
 
 use crate::{
-    assets::HighlightingAssets,
-    config::{Config, VisibleLines},
-    controller::Controller,
-    error::Result,
+    accessibility::MarkerPalette,
+    assets::{HighlightingAssets, SyntaxDetection, ThemeInfo},
+    card::Card,
+    config::{Config, LineClassifier, PreviewSample, VisibleLines, DETERMINISTIC_TERM_WIDTH},
+    config_layers::{ConfigLayer, Origin},
+    controller::{Controller, InputResult, InputSize},
+    error::{default_error_handler, Error, ErrorKind, Result},
+    gutter::GutterSource,
     input::Input,
+    layout::BlockAlignment,
+    line_processor::LineProcessor,
     line_range::{HighlightedLineRanges, LineRange, LineRanges},
-    style::{StyleComponent, StyleComponents},
+    match_list::{self, MatchList},
+    patterns::PatternStyle,
+    project_config::ProjectSettings,
+    result_cache::{CachedOutput, ResultCache},
+    snippet::SnippetHighlighter,
+    split::SplitView,
+    style::{HeaderComponent, ResponsiveLayout, StyleComponent, StyleComponents},
+    suggestions::closest_matches,
+    template::Template,
+    terminal_features::TerminalFeatures,
+    trace::StackTraceMode,
+    warning::{self, Severity, Warning},
+    BinaryMode, BufferingMode, EmbeddedLanguage, JsonLinesMode, LineEnding, OutputEncoding,
     SyntaxMapping, WrappingMode,
 };
 
 #[cfg(feature = "paging")]
 use crate::paging::PagingMode;
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 struct ActiveStyleComponents {
     header: bool,
     vcs_modification_markers: bool,
@@ -27,14 +52,64 @@ struct ActiveStyleComponents {
     snip: bool,
 }
 
+/// A snapshot of all settings of a `PrettyPrinter` (everything except the inputs)
+#[derive(Clone)]
+struct Profile<'a> {
+    config: Config<'a>,
+    highlighted_lines: Vec<LineRange>,
+    term_width: Option<usize>,
+    active_style_components: ActiveStyleComponents,
+}
+
+/// The settings of a `PrettyPrinter` at some point (see `PrettyPrinter::snapshot`)
+#[derive(Clone)]
+pub struct PrettyPrinterSnapshot<'a>(Profile<'a>);
+
 pub struct PrettyPrinter<'a> {
     inputs: Vec<Input<'a>>,
     config: Config<'a>,
-    assets: HighlightingAssets,
+
+    /// The assets are shared between clones of the printer (and copied when one of them adds
+    /// a theme or a syntax)
+    assets: Arc<HighlightingAssets>,
 
     highlighted_lines: Vec<LineRange>,
     term_width: Option<usize>,
     active_style_components: ActiveStyleComponents,
+
+    profiles: BTreeMap<String, Profile<'a>>,
+    active_profile: Option<String>,
+
+    warning_level: Severity,
+
+    /// The warnings of the last printed inputs, with the names of the inputs
+    warnings: Vec<(String, Warning)>,
+
+    result_cache: Option<ResultCache>,
+}
+
+/// A clone has all the settings, profiles and assets of the printer, but none of its inputs
+/// (which can only be printed once). The assets are shared, so that cloning is cheap.
+impl<'a> Clone for PrettyPrinter<'a> {
+    fn clone(&self) -> Self {
+        PrettyPrinter {
+            inputs: vec![],
+            config: self.config.clone(),
+            assets: Arc::clone(&self.assets),
+
+            highlighted_lines: self.highlighted_lines.clone(),
+            term_width: self.term_width,
+            active_style_components: self.active_style_components.clone(),
+
+            profiles: self.profiles.clone(),
+            active_profile: self.active_profile.clone(),
+
+            warning_level: self.warning_level,
+            warnings: vec![],
+
+            result_cache: self.result_cache.clone(),
+        }
+    }
 }
 
 impl<'a> PrettyPrinter<'a> {
@@ -43,24 +118,46 @@ impl<'a> PrettyPrinter<'a> {
 
         config.colored_output = true;
         config.true_color = true;
+        config.theme_fallback = true;
 
         PrettyPrinter {
             inputs: vec![],
             config,
-            assets: HighlightingAssets::from_binary(),
+            assets: Arc::new(HighlightingAssets::from_binary()),
 
             highlighted_lines: vec![],
             term_width: None,
             active_style_components: ActiveStyleComponents::default(),
+
+            profiles: BTreeMap::new(),
+            active_profile: None,
+
+            warning_level: Severity::default(),
+            warnings: vec![],
+
+            result_cache: None,
         }
     }
 
+    /// Add an input, e.g. with its own settings (like `Input::align_key_values`)
+    pub fn input(&mut self, input: Input<'a>) -> &mut Self {
+        self.inputs.push(input);
+        self
+    }
+
     /// Add a file which should be pretty-printed
     pub fn input_file(&mut self, path: impl AsRef<OsStr>) -> &mut Self {
         self.inputs.push(Input::ordinary_file(path.as_ref()));
         self
     }
 
+    /// Add a resource which is downloaded from an HTTP(S) URL (with `curl`)
+    #[cfg(feature = "http")]
+    pub fn input_url(&mut self, url: &str) -> &mut Self {
+        self.inputs.push(Input::from_url(url));
+        self
+    }
+
     /// Add multiple files which should be pretty-printed
     pub fn input_files<I, P>(&mut self, paths: I) -> &mut Self
     where
@@ -73,6 +170,23 @@ impl<'a> PrettyPrinter<'a> {
         self
     }
 
+    /// Add a side-by-side diff of two versions of an input: both are highlighted and printed
+    /// next to each other, with the lines aligned by a line diff and markers for removed,
+    /// added and changed lines (see `Input::compared_with`)
+    pub fn input_diff(&mut self, old: Input<'a>, new: Input<'a>) -> &mut Self {
+        self.inputs.push(new.compared_with(old));
+        self
+    }
+
+    /// Add a structured diff of two JSON or YAML inputs: the changes of their parsed data are
+    /// printed with the paths of the changed values, highlighted as a diff (see
+    /// `Input::compared_structurally`)
+    #[cfg(feature = "structured-diff")]
+    pub fn input_structured_diff(&mut self, old: Input<'a>, new: Input<'a>) -> &mut Self {
+        self.inputs.push(new.compared_structurally(old));
+        self
+    }
+
     /// Add STDIN as an input
     pub fn input_stdin(&mut self) -> &mut Self {
         self.inputs.push(Input::stdin());
@@ -132,6 +246,14 @@ impl<'a> PrettyPrinter<'a> {
     /// The width of tab characters (default: None - do not turn tabs to spaces)
     pub fn tab_width(&mut self, tab_width: Option<usize>) -> &mut Self {
         self.config.tab_width = tab_width.unwrap_or(0);
+        self.set_explicitly("tabs", self.config.tab_width);
+        self
+    }
+
+    /// How binary inputs are printed: with a warning instead of their content or as a
+    /// hexdump (default: warning)
+    pub fn binary_mode(&mut self, mode: BinaryMode) -> &mut Self {
+        self.config.binary_mode = mode;
         self
     }
 
@@ -150,30 +272,35 @@ impl<'a> PrettyPrinter<'a> {
     /// Whether to show a header with the file name
     pub fn header(&mut self, yes: bool) -> &mut Self {
         self.active_style_components.header = yes;
+        self.set_explicitly("header", yes);
         self
     }
 
     /// Whether to show line numbers
     pub fn line_numbers(&mut self, yes: bool) -> &mut Self {
         self.active_style_components.line_numbers = yes;
+        self.set_explicitly("numbers", yes);
         self
     }
 
     /// Whether to paint a grid, separating line numbers, git changes and the code
     pub fn grid(&mut self, yes: bool) -> &mut Self {
         self.active_style_components.grid = yes;
+        self.set_explicitly("grid", yes);
         self
     }
 
     /// Whether to show modification markers for VCS changes
     pub fn vcs_modification_markers(&mut self, yes: bool) -> &mut Self {
         self.active_style_components.vcs_modification_markers = yes;
+        self.set_explicitly("changes", yes);
         self
     }
 
     /// Whether to show "snip" markers between visible line ranges (default: no)
     pub fn snip(&mut self, yes: bool) -> &mut Self {
         self.active_style_components.snip = yes;
+        self.set_explicitly("snip", yes);
         self
     }
 
@@ -189,6 +316,41 @@ impl<'a> PrettyPrinter<'a> {
         self
     }
 
+    /// Only use the colors and text attributes that the terminal supports: 24-bit colors and
+    /// italics are enabled or disabled according to the features (see
+    /// `TerminalFeatures::detect`)
+    pub fn terminal_features(&mut self, features: TerminalFeatures) -> &mut Self {
+        self.config.true_color = features.true_color;
+        self.config.use_italic_text = features.italics;
+        self
+    }
+
+    /// Elide the middle of tokens (like base64 blobs) that are longer than the given number of
+    /// characters (default: None - print all tokens completely)
+    pub fn max_token_length(&mut self, length: Option<usize>) -> &mut Self {
+        self.config.max_token_length = length;
+        self
+    }
+
+    /// How the output should be buffered (default: line-buffered for STDIN and readers,
+    /// block-buffered for files)
+    pub fn buffering_mode(&mut self, mode: BufferingMode) -> &mut Self {
+        self.config.buffering_mode = mode;
+        self
+    }
+
+    /// Convert all line endings of the output (default: keep the line endings of the input)
+    pub fn line_ending(&mut self, line_ending: Option<LineEnding>) -> &mut Self {
+        self.config.line_ending = line_ending;
+        self
+    }
+
+    /// The character encoding of the output (default: UTF-8)
+    pub fn output_encoding(&mut self, encoding: OutputEncoding) -> &mut Self {
+        self.config.output_encoding = encoding;
+        self
+    }
+
     /// If and how to use a pager (default: no paging)
     #[cfg(feature = "paging")]
     pub fn paging_mode(&mut self, mode: PagingMode) -> &mut Self {
@@ -203,12 +365,77 @@ impl<'a> PrettyPrinter<'a> {
         self
     }
 
+    /// Kill the pager if it is still running after the given time (default: none). Printing
+    /// then fails with `ErrorKind::SubprocessTimeout`.
+    #[cfg(feature = "paging")]
+    pub fn pager_timeout(&mut self, timeout: Option<Duration>) -> &mut Self {
+        self.config.pager_timeout = timeout;
+        self
+    }
+
+    /// Show every input in a pager of its own: quitting the pager moves on to the next input,
+    /// the `Q` key in `less` quits all of them (default: false)
+    #[cfg(feature = "paging")]
+    pub fn pager_per_file(&mut self, yes: bool) -> &mut Self {
+        self.config.pager_per_file = yes;
+        self
+    }
+
+    /// Keep the header of the input at the top of the screen while scrolling in the pager, if
+    /// the pager shows a single input (needs `less` 600 or newer, default: false)
+    #[cfg(feature = "paging")]
+    pub fn sticky_header(&mut self, yes: bool) -> &mut Self {
+        self.config.sticky_header = yes;
+        self
+    }
+
+    /// Leave long lines to the pager, which cuts them off and scrolls horizontally (`less` is
+    /// started with `--chop-long-lines`). Lines are not wrapped while this is enabled.
+    #[cfg(feature = "paging")]
+    pub fn chop_long_lines(&mut self, yes: bool) -> &mut Self {
+        self.config.chop_long_lines = yes;
+        if yes {
+            self.config.wrapping_mode = WrappingMode::NoWrapping;
+        }
+        self
+    }
+
     /// Specify the lines that should be printed (default: all)
     pub fn line_ranges(&mut self, ranges: LineRanges) -> &mut Self {
         self.config.visible_lines = VisibleLines::Ranges(ranges);
         self
     }
 
+    /// Only print the last lines of the inputs (like `tail`). Files are read twice for this
+    /// purpose, other inputs are read into memory.
+    pub fn last_lines(&mut self, lines: usize) -> &mut Self {
+        self.line_ranges(LineRanges::from(vec![LineRange::last(lines)]))
+    }
+
+    /// Only print the definition of a symbol (like a function, a struct or a class) in each
+    /// input, including the comments and attributes above it, instead of the `line_ranges`.
+    /// Printing fails for inputs without a definition of the symbol.
+    pub fn focus_symbol(&mut self, name: &str) -> &mut Self {
+        self.config.focus_symbol = Some(name.to_owned());
+        self
+    }
+
+    /// Only print the lines that have been added, removed or modified with respect to the
+    /// git index, with `context` lines around them, instead of the `line_ranges`. Inputs
+    /// without changes are not printed at all.
+    #[cfg(feature = "git")]
+    pub fn diff_context(&mut self, context: usize) -> &mut Self {
+        self.config.visible_lines = VisibleLines::DiffContext(context);
+        self
+    }
+
+    /// Print two ranges of lines of each input side by side, instead of the `line_ranges`
+    /// (default: none)
+    pub fn split_view(&mut self, left: LineRange, right: LineRange) -> &mut Self {
+        self.config.split_view = Some(SplitView { left, right });
+        self
+    }
+
     /// Specify a line that should be highlighted (default: none).
     /// This can be called multiple times to highlight more than one
     /// line. See also: highlight_range.
@@ -225,9 +452,197 @@ impl<'a> PrettyPrinter<'a> {
         self
     }
 
+    /// Highlight the matches of a regular expression with a named style (see
+    /// `define_pattern_style` and `PatternStyle::builtin`). Where matches overlap, the pattern
+    /// that was added first wins.
+    pub fn highlight_pattern(&mut self, style: &str, pattern: &str) -> Result<&mut Self> {
+        self.config.highlight_patterns.add(style, pattern)?;
+        Ok(self)
+    }
+
+    /// Highlight the matches of a search pattern on top of the syntax highlighting, like
+    /// `grep --color` does. Every search pattern gets its own background color (see
+    /// `HighlightPatterns::add_search`).
+    pub fn highlight_search(&mut self, pattern: &str) -> Result<&mut Self> {
+        self.config.highlight_patterns.add_search(pattern)?;
+        Ok(self)
+    }
+
+    /// Define (or redefine) a named style for `highlight_pattern`
+    pub fn define_pattern_style(
+        &mut self,
+        name: impl Into<String>,
+        style: PatternStyle,
+    ) -> &mut Self {
+        self.config.highlight_patterns.define_style(name, style);
+        self
+    }
+
+    /// Tint lines by custom logic: lines for which the classifier returns a class are printed
+    /// with the background color of the class (see `line_class_color`). Highlighted lines
+    /// keep the highlight color.
+    pub fn line_classifier(
+        &mut self,
+        classifier: impl Fn(usize, &str) -> Option<String> + Send + Sync + 'static,
+    ) -> &mut Self {
+        self.config.line_classifier = Some(LineClassifier::new(classifier));
+        self
+    }
+
+    /// Set the background color of the lines of a class (see `line_classifier`)
+    pub fn line_class_color(&mut self, class: impl Into<String>, color: Color) -> &mut Self {
+        self.config.line_class_colors.insert(class.into(), color);
+        self
+    }
+
+    /// Add a processor that transforms the styled segments of every line before the
+    /// decorations are added. Processors are called in the order in which they were added.
+    /// The output of printers with processors is not cached (see `enable_result_cache`).
+    pub fn line_processor(&mut self, processor: impl LineProcessor + 'static) -> &mut Self {
+        self.config.line_processors.push(Arc::new(processor));
+        self
+    }
+
     /// Specify the highlighting theme
     pub fn theme(&mut self, theme: impl AsRef<str>) -> &mut Self {
         self.config.theme = theme.as_ref().to_owned();
+        self.set_explicitly("theme", theme.as_ref());
+        self
+    }
+
+    fn set_explicitly(&mut self, name: &str, value: impl std::fmt::Display) {
+        let origin = Origin::new(ConfigLayer::Explicit, value);
+        self.config.provenance.record(name, origin);
+    }
+
+    /// The settings that were set explicitly, by the environment or by a project file, with
+    /// the layers that set them (see `Config::describe`)
+    pub fn describe_config(&self) -> String {
+        self.config.describe()
+    }
+
+    /// Apply the environment variables of the `bat` command, so that the output agrees with
+    /// it: `BAT_THEME`, `BAT_STYLE` (only the components that are supported here) and
+    /// `BAT_TABS`. Settings that were set with the methods of the printer take precedence
+    /// over the variables, regardless of the order of the calls. `BAT_PAGER` is always used,
+    /// unless a pager is set with `pager`. Fails if one of the variables has an invalid value.
+    pub fn apply_env_overrides(&mut self) -> Result<&mut Self> {
+        self.apply_overrides(|name| std::env::var(name).ok())
+    }
+
+    fn apply_overrides(&mut self, var: impl Fn(&str) -> Option<String>) -> Result<&mut Self> {
+        let layer = ConfigLayer::Environment;
+        let provenance = &mut self.config.provenance;
+
+        if let Some(theme) = var("BAT_THEME") {
+            if provenance.is_overridden_by("theme", layer) {
+                self.config.theme = if theme == "default" {
+                    HighlightingAssets::default_theme().to_owned()
+                } else {
+                    theme.clone()
+                };
+                provenance.record("theme", Origin::new(layer, theme).with_source("BAT_THEME"));
+            }
+        }
+
+        if let Some(tabs) = var("BAT_TABS") {
+            let tab_width: usize = tabs
+                .parse()
+                .map_err(|_| format!("Invalid tab width '{}' in BAT_TABS", tabs))?;
+            if provenance.is_overridden_by("tabs", layer) {
+                self.config.tab_width = tab_width;
+                provenance.record(
+                    "tabs",
+                    Origin::new(layer, tab_width).with_source("BAT_TABS"),
+                );
+            }
+        }
+
+        if let Some(style) = var("BAT_STYLE") {
+            let interactive = Term::stdout().features().is_attended();
+            let mut components = vec![];
+            for component in style.split(',') {
+                components.extend_from_slice(
+                    component.parse::<StyleComponent>()?.components(interactive),
+                );
+            }
+
+            let active = &mut self.active_style_components;
+            let mut settings = [
+                ("header", StyleComponent::Header, &mut active.header),
+                (
+                    "changes",
+                    StyleComponent::Changes,
+                    &mut active.vcs_modification_markers,
+                ),
+                ("grid", StyleComponent::Grid, &mut active.grid),
+                (
+                    "numbers",
+                    StyleComponent::LineNumbers,
+                    &mut active.line_numbers,
+                ),
+                ("snip", StyleComponent::Snip, &mut active.snip),
+            ];
+            for (name, component, enabled) in settings.iter_mut() {
+                if provenance.is_overridden_by(name, layer) {
+                    **enabled = components.contains(component);
+                    let origin = Origin::new(layer, **enabled).with_source("BAT_STYLE");
+                    provenance.record(name, origin);
+                }
+            }
+        }
+
+        Ok(self)
+    }
+
+    /// Whether or not to synthesize colors for comments and keywords if the theme does not
+    /// define them (default: true)
+    pub fn theme_fallback(&mut self, yes: bool) -> &mut Self {
+        self.config.theme_fallback = yes;
+        self
+    }
+
+    /// Whether or not unknown languages and themes should lead to an error instead of
+    /// falling back to plain text and the default theme (default: false)
+    pub fn strict(&mut self, yes: bool) -> &mut Self {
+        self.config.strict = yes;
+        self
+    }
+
+    /// The minimum severity of the warnings that are collected while printing, like replaced
+    /// invalid UTF-8 or an unknown theme (default: all warnings)
+    pub fn warning_level(&mut self, level: Severity) -> &mut Self {
+        self.warning_level = level;
+        self
+    }
+
+    /// The warnings of the inputs that were printed last (with the names of the inputs), see
+    /// `warning_level`
+    pub fn warnings(&self) -> &[(String, Warning)] {
+        &self.warnings
+    }
+
+    /// Highlight regions of the input (like SQL queries in the `sql!(...)` macro) with the
+    /// syntax of another language. This can be called multiple times to add more rules.
+    pub fn embedded_language(&mut self, rule: EmbeddedLanguage) -> &mut Self {
+        self.config.embedded_languages.push(rule);
+        self
+    }
+
+    /// Produce the same output on every machine, for snapshot tests of the output: the
+    /// terminal width defaults to 80 instead of the width of the terminal, the pager and VCS
+    /// modification markers are not used, and the modification time of files is left out of
+    /// headers
+    pub fn deterministic(&mut self, yes: bool) -> &mut Self {
+        self.config.deterministic = yes;
+        self
+    }
+
+    /// Guarantee that printing performs no filesystem writes and spawns no subprocesses.
+    /// Printing fails with a `SandboxViolation` error if paging, VCS modification markers,
+    /// an asciicast recording or inputs from URLs are requested.
+    pub fn sandbox(&mut self, yes: bool) -> &mut Self {
+        self.config.sandbox = yes;
         self
     }
 
@@ -237,23 +652,638 @@ impl<'a> PrettyPrinter<'a> {
         self
     }
 
+    /// Use the theme, the tab width and the syntax mappings of a project configuration (see
+    /// `ProjectConfig::discover`). Settings that are changed later take precedence.
+    pub fn project_settings(&mut self, settings: &'a ProjectSettings) -> Result<&mut Self> {
+        settings.apply(&mut self.config)?;
+        Ok(self)
+    }
+
+    /// Only show the first and the last lines of files that are larger than the threshold of
+    /// the sample, with a snip marker in between (default: disabled)
+    pub fn preview_sample(&mut self, sample: Option<PreviewSample>) -> &mut Self {
+        self.config.preview_sample = sample;
+        self
+    }
+
+    /// Drop style components (in the order given by the layout) on narrow terminals instead
+    /// of squeezing the code column (default: disabled)
+    pub fn responsive_layout(&mut self, layout: Option<ResponsiveLayout>) -> &mut Self {
+        self.config.responsive_layout = layout;
+        self
+    }
+
+    /// Color every line that contains a JSON object on its own (like in JSON-lines logs), as
+    /// JSON or as flattened `key=value` pairs (default: disabled)
+    pub fn json_lines(&mut self, mode: Option<JsonLinesMode>) -> &mut Self {
+        self.config.json_lines = mode;
+        self
+    }
+
+    /// Align the values of `key = value` lines in config files (INI, TOML, `.properties` and
+    /// git config files) in a column, with distinctly colored keys and sections (default:
+    /// false). Inputs can override this with `Input::align_key_values`.
+    pub fn align_key_values(&mut self, yes: bool) -> &mut Self {
+        self.config.align_key_values = yes;
+        self
+    }
+
+    /// Highlight the fenced code blocks of Markdown files with the languages of their info
+    /// strings, and print the prose around them with few styles (default: false)
+    pub fn markdown_code_blocks(&mut self, yes: bool) -> &mut Self {
+        self.config.markdown_code_blocks = yes;
+        self
+    }
+
+    /// Keep the ANSI escape sequences of inputs that are colored already instead of
+    /// highlighting them (default: false)
+    pub fn ansi_passthrough(&mut self, yes: bool) -> &mut Self {
+        self.config.ansi_passthrough = yes;
+        self
+    }
+
+    /// Highlight the input as a stack trace (of a Rust panic, a Python or Java exception or a
+    /// Node.js error) and optionally inline the source lines that the frames refer to
+    /// (default: disabled)
+    pub fn stack_trace(&mut self, mode: Option<StackTraceMode>) -> &mut Self {
+        self.config.stack_trace = mode;
+        self
+    }
+
+    /// Render unified diffs structurally: files and hunks are separated, added and removed
+    /// lines are highlighted in the language of the file and changed words are emphasized
+    /// (default: false)
+    pub fn render_diffs(&mut self, yes: bool) -> &mut Self {
+        self.config.render_diffs = yes;
+        self
+    }
+
+    /// Render the input as the output of `git show`, `git log` or `git diff`: the metadata and
+    /// messages of commits are styled and diffs are rendered like with `render_diffs`
+    /// (default: false)
+    pub fn render_commits(&mut self, yes: bool) -> &mut Self {
+        self.config.render_commits = yes;
+        self
+    }
+
+    /// Replace the file name in the header by a template (see `Template::header`). The header
+    /// itself is enabled with `header(true)`.
+    pub fn header_template(&mut self, template: Option<Template>) -> &mut Self {
+        self.config.header_template = template;
+        self
+    }
+
+    /// Choose the information about a file in its header, like its size and the time of its
+    /// last modification (default: only the file name)
+    pub fn header_components(&mut self, components: &[HeaderComponent]) -> &mut Self {
+        self.config.header_components = components.to_vec();
+        self
+    }
+
+    /// Print a footer after the contents of each file (see `Template::footer`)
+    pub fn footer_template(&mut self, template: Option<Template>) -> &mut Self {
+        self.config.footer_template = template;
+        self
+    }
+
+    /// Show byte offsets or values that are extracted from the lines (like the timestamps of
+    /// log messages) in the number gutter instead of line numbers
+    pub fn gutter_source(&mut self, source: GutterSource) -> &mut Self {
+        self.config.gutter_source = source;
+        self
+    }
+
+    /// Customize the line numbers with a template (see `Template::line_number`)
+    pub fn line_number_template(&mut self, template: Option<Template>) -> &mut Self {
+        self.config.line_number_template = template;
+        self
+    }
+
+    /// Color Git change markers and log levels with a color-blind friendly palette (see
+    /// `MarkerPalette::for_vision`) and mark highlighted lines and log levels with glyphs
+    pub fn accessible_markers(&mut self, palette: Option<MarkerPalette>) -> &mut Self {
+        self.config.accessible_markers = palette;
+        self
+    }
+
+    /// Lighten (or darken) the foreground colors of the theme that have a lower contrast ratio
+    /// than the given one (from 1 to 21, 4.5 is a good value for text) against the background
+    pub fn minimum_contrast(&mut self, ratio: Option<f64>) -> &mut Self {
+        self.config.minimum_contrast = ratio;
+        self
+    }
+
+    /// The background color of the terminal for `minimum_contrast` (default: the background of
+    /// the theme)
+    pub fn terminal_background(&mut self, color: Option<Color>) -> &mut Self {
+        self.config.terminal_background = color;
+        self
+    }
+
+    /// Put the output into a bordered box of a fixed width (default: none)
+    pub fn card(&mut self, card: Option<Card>) -> &mut Self {
+        self.config.card = card;
+        self
+    }
+
+    /// Collapse runs of empty lines to at most the given number of empty lines (default: none)
+    pub fn squeeze_blank_lines(&mut self, max: Option<usize>) -> &mut Self {
+        self.config.squeeze_blank_lines = max;
+        self
+    }
+
+    /// Keep printing the lines that are appended to files after their end, like `tail -f`
+    /// (default: false). Printing a followed file only finishes when the file is removed, so
+    /// the files after it are not printed before.
+    pub fn follow(&mut self, yes: bool) -> &mut Self {
+        self.config.follow = yes;
+        self
+    }
+
+    /// Turn the URLs in the content into hyperlinks, which terminals that support OSC 8
+    /// escape sequences make clickable (default: false)
+    pub fn linkify(&mut self, yes: bool) -> &mut Self {
+        self.config.linkify = yes;
+        self
+    }
+
+    /// Also turn paths of existing files into hyperlinks, if `linkify` is enabled (default:
+    /// false)
+    pub fn linkify_paths(&mut self, yes: bool) -> &mut Self {
+        self.config.linkify_paths = yes;
+        self
+    }
+
+    /// Limit the width of the output, including the decorations (default: none). On wider
+    /// terminals, the output is positioned with `block_alignment`.
+    pub fn max_width(&mut self, width: Option<usize>) -> &mut Self {
+        self.config.max_width = width;
+        self
+    }
+
+    /// The position of the output on terminals that are wider than `max_width` (default:
+    /// left)
+    pub fn block_alignment(&mut self, alignment: BlockAlignment) -> &mut Self {
+        self.config.block_alignment = alignment;
+        self
+    }
+
+    /// Put a prefix in front of every printed line, including the header, the grid and the
+    /// continuation lines of wrapped lines (default: none). The output is laid out for the
+    /// width of the terminal minus the width of the prefix.
+    pub fn indent(&mut self, prefix: &str) -> &mut Self {
+        self.config.indent = if prefix.is_empty() {
+            None
+        } else {
+            Some(prefix.to_owned())
+        };
+        self
+    }
+
+    /// Highlight up to this many files at the same time, on threads of their own (default: 1).
+    /// The output is still printed in the order of the inputs. Other inputs (like readers)
+    /// are always printed one after another.
+    pub fn parallelism(&mut self, threads: usize) -> &mut Self {
+        self.config.parallelism = threads;
+        self
+    }
+
+    /// Keep the output of the printed inputs in the given directory, keyed by a hash of the
+    /// content and of all settings that affect the output (like the theme, the terminal width
+    /// and the style components), so that printing an unchanged input again (e.g. in the
+    /// preview of a file watcher) only copies its cached output. The cache is not cleaned up,
+    /// and it doesn't know about themes and syntaxes that are added at runtime. No pager is
+    /// used while the cache is enabled. Inputs that are compared with an old version and the
+    /// inputs of printers with line classifiers or line processors are not cached. The cache
+    /// is not used in sandbox mode (see `sandbox`).
+    pub fn enable_result_cache(&mut self, dir: impl Into<PathBuf>) -> &mut Self {
+        self.result_cache = Some(ResultCache::new(dir.into()));
+        self
+    }
+
+    /// Use other highlighting assets than the ones that are integrated into the binary, for
+    /// example a cache in the data directory of the application (see
+    /// `HighlightingAssets::from_cache_dir`) or assets built with `HighlightingAssets::from_files`
+    pub fn assets(&mut self, assets: HighlightingAssets) -> &mut Self {
+        self.assets = Arc::new(assets);
+        self
+    }
+
+    /// Use assets that are shared with other printers (see `assets`)
+    pub fn shared_assets(&mut self, assets: Arc<HighlightingAssets>) -> &mut Self {
+        self.assets = assets;
+        self
+    }
+
+    /// Register a syntax from the contents of a `.sublime-syntax` file (see
+    /// `HighlightingAssets::add_syntax_from_str`)
+    pub fn add_syntax_from_str(&mut self, name: &str, sublime_syntax: &str) -> Result<&mut Self> {
+        Arc::make_mut(&mut self.assets).add_syntax_from_str(name, sublime_syntax)?;
+        Ok(self)
+    }
+
+    /// Register a theme from a `.tmTheme` file, named after the file (without the extension)
+    pub fn add_theme_from_file(&mut self, path: impl AsRef<Path>) -> Result<&mut Self> {
+        Arc::make_mut(&mut self.assets).add_theme_from_file(path.as_ref())?;
+        Ok(self)
+    }
+
+    /// Register a theme from the contents of a `.tmTheme` file
+    pub fn add_theme_from_str(&mut self, name: &str, tmtheme: &str) -> Result<&mut Self> {
+        Arc::make_mut(&mut self.assets).add_theme_from_str(name, tmtheme)?;
+        Ok(self)
+    }
+
+    /// Save all current settings (everything except the inputs) as a named profile. An
+    /// existing profile with the same name is replaced.
+    pub fn save_profile(&mut self, name: impl Into<String>) -> &mut Self {
+        let profile = self.current_profile();
+        self.profiles.insert(name.into(), profile);
+        self
+    }
+
+    /// Use the settings of a profile (see `save_profile`) for the next call to 'print' or
+    /// 'measure', instead of the current settings. The current settings are not modified.
+    /// Unknown profile names are reported as an error by 'print' and 'measure'.
+    pub fn profile(&mut self, name: impl Into<String>) -> &mut Self {
+        self.active_profile = Some(name.into());
+        self
+    }
+
+    /// Take a snapshot of all current settings (everything except the inputs), which can be
+    /// restored later with `restore`
+    pub fn snapshot(&self) -> PrettyPrinterSnapshot<'a> {
+        PrettyPrinterSnapshot(self.current_profile())
+    }
+
+    /// Go back to the settings of a snapshot (see `snapshot`). The inputs are kept.
+    pub fn restore(&mut self, snapshot: &PrettyPrinterSnapshot<'a>) -> &mut Self {
+        self.restore_profile(Some(snapshot.0.clone()));
+        self
+    }
+
+    /// The names of all saved profiles (in alphabetical order)
+    pub fn profiles(&self) -> impl Iterator<Item = &str> {
+        self.profiles.keys().map(String::as_str)
+    }
+
     pub fn themes(&self) -> impl Iterator<Item = &str> {
         self.assets.themes()
     }
 
+    /// The default colors of a theme (see `HighlightingAssets::theme_info`)
+    pub fn theme_info(&self, theme: &str) -> Result<ThemeInfo> {
+        self.assets.theme_info(theme)
+    }
+
     pub fn syntaxes(&self) -> impl Iterator<Item = &SyntaxReference> {
         self.assets.syntaxes().iter()
     }
 
+    /// Explain how the syntax of a file with the given path and content would be detected,
+    /// with the syntax mappings of this printer (see `HighlightingAssets::explain_syntax`)
+    pub fn explain_syntax(&self, path: Option<&Path>, content: &[u8]) -> Result<SyntaxDetection> {
+        self.assets
+            .explain_syntax(path, content, &self.config.syntax_mapping)
+    }
+
+    /// Create a highlighter for text that arrives in chunks (e.g. the input of a REPL), using
+    /// the configured theme, colors and italics setting
+    pub fn snippet_highlighter(&self, language: &str) -> Result<SnippetHighlighter<'_>> {
+        let mut highlighter = SnippetHighlighter::new(&self.assets, language, &self.config.theme)?;
+        highlighter
+            .true_color(self.config.true_color)
+            .use_italics(self.config.use_italic_text);
+        Ok(highlighter)
+    }
+
     /// Pretty-print all specified inputs. This method will "use" all stored inputs.
     /// If you want to call 'print' multiple times, you have to call the appropriate
     /// input_* methods again.
     pub fn print(&mut self) -> Result<bool> {
+        let previous = self.activate_profile()?;
+        self.update_config();
+
+        let mut inputs: Vec<Input> = vec![];
+        std::mem::swap(&mut inputs, &mut self.inputs);
+
+        let results = match self.result_cache {
+            Some(ref cache) if !self.config.sandbox => {
+                self.print_cached(cache, inputs, &mut io::stdout().lock())
+            }
+            _ => {
+                Controller::new(&self.config, &self.assets).run_each(inputs, default_error_handler)
+            }
+        };
+
+        self.restore_profile(previous);
+        Ok(self.keep_warnings(&mut results?))
+    }
+
+    /// Pretty-print all specified inputs like `print`, but write the output to the given
+    /// writer (e.g. a buffer) instead of the terminal. No pager is used.
+    pub fn print_with_writer(&mut self, mut writer: impl Write) -> Result<bool> {
+        let previous = self.activate_profile()?;
+        self.update_config();
+
+        let inputs = std::mem::take(&mut self.inputs);
+        let results = match self.result_cache {
+            Some(ref cache) if !self.config.sandbox => {
+                self.print_cached(cache, inputs, &mut writer)
+            }
+            _ => Controller::new(&self.config, &self.assets).run_each_with_writer(
+                inputs,
+                Some(&mut writer),
+                default_error_handler,
+            ),
+        };
+
+        self.restore_profile(previous);
+        Ok(self.keep_warnings(&mut results?))
+    }
+
+    /// Print the given sample once for every theme (like `bat --list-themes`), each time after
+    /// a header with the name of the theme, e.g. for a theme picker. The language of the
+    /// sample can be set with `language`. The stored inputs are not printed.
+    pub fn print_theme_previews(&mut self, sample: &str) -> Result<bool> {
+        let stdout = io::stdout();
+        let mut stdout = stdout.lock();
+        self.print_theme_previews_with_writer(sample, &mut stdout)
+    }
+
+    /// Print the theme previews like `print_theme_previews`, but write them to the given writer
+    pub fn print_theme_previews_with_writer(
+        &mut self,
+        sample: &str,
+        mut writer: impl Write,
+    ) -> Result<bool> {
+        let previous = self.activate_profile()?;
+        self.update_config();
+        let mut config = self.config.clone();
+        self.restore_profile(previous);
+
+        let mut success = true;
+        for theme in self.assets.themes() {
+            let header = if config.colored_output {
+                Style::new().bold().paint(theme).to_string()
+            } else {
+                theme.to_owned()
+            };
+            writeln!(writer, "Theme: {}\n", header)?;
+
+            config.theme = theme.to_owned();
+            let input = Input::from_reader(Box::new(sample.as_bytes()));
+            success &=
+                Controller::new(&config, &self.assets).run_with_writer(vec![input], &mut writer)?;
+            writeln!(writer)?;
+        }
+
+        Ok(success)
+    }
+
+    /// Write all specified inputs as a self-contained HTML page instead of printing them: a
+    /// sidebar with an index of the inputs and a collapsible section for every input, with the
+    /// colors of the theme inlined into the page. Line numbers and highlighted lines are
+    /// included like in the terminal. This method will "use" all stored inputs, like `print`.
+    pub fn print_html_report(&mut self, mut writer: impl Write, title: &str) -> Result<bool> {
+        let previous = self.activate_profile()?;
+        self.update_config();
+
+        let inputs = std::mem::take(&mut self.inputs);
+        let result = Controller::new(&self.config, &self.assets).write_html_report(
+            inputs,
+            title,
+            &mut writer,
+        );
+
+        self.restore_profile(previous);
+        result
+    }
+
+    /// Print the inputs one by one through the result cache (see `enable_result_cache`): the
+    /// output of an input is copied from the cache, or printed and then stored in the cache
+    /// (unless printing failed). The results of cached inputs have the language and the number
+    /// of lines of the first time, but no warnings.
+    fn print_cached(
+        &self,
+        cache: &ResultCache,
+        inputs: Vec<Input>,
+        writer: &mut dyn Write,
+    ) -> Result<Vec<InputResult>> {
+        let controller = Controller::new(&self.config, &self.assets);
+        let mut results = vec![];
+        for mut input in inputs {
+            let key = cache.key(&self.config, &mut input)?;
+            if let Some(cached) = key.as_ref().and_then(|key| cache.load(key)) {
+                writer.write_all(&cached.output)?;
+                results.push(InputResult {
+                    name: input.name(),
+                    language: cached.language,
+                    lines: cached.lines,
+                    error: None,
+                    warnings: vec![],
+                });
+                continue;
+            }
+
+            let mut output = vec![];
+            let mut input_results = controller.run_each_with_writer(
+                vec![input],
+                Some(&mut output),
+                default_error_handler,
+            )?;
+            writer.write_all(&output)?;
+            if let (Some(key), [result]) = (key, input_results.as_slice()) {
+                if result.success() {
+                    let cached = CachedOutput {
+                        language: result.language.clone(),
+                        lines: result.lines,
+                        output,
+                    };
+                    // The cache is only an optimization, the output has been printed anyway
+                    cache.store(&key, &cached).ok();
+                }
+            }
+            results.append(&mut input_results);
+        }
+        Ok(results)
+    }
+
+    /// Pretty-print all specified inputs into memory, one input at a time, and return the rows
+    /// of the output (as they would appear on the terminal, without the line endings). The
+    /// next input is only printed once all rows of the previous one have been consumed. If an
+    /// input can not be printed, its error is returned after the rows that were printed before
+    /// the error occurred. This method will "use" all stored inputs, like `print`.
+    ///
+    /// The rows are not streamed: the output of the largest input has to fit into memory, and
+    /// inputs that are followed (see `follow`) never return any rows. Use `print_with_writer`
+    /// to stream the output instead.
+    pub fn print_to_rows(&mut self) -> Result<impl Iterator<Item = Result<String>> + '_> {
+        let previous = self.activate_profile()?;
+        self.update_config();
+        let config = self.config.clone();
+        self.restore_profile(previous);
+
+        Ok(OutputRows {
+            config,
+            assets: &self.assets,
+            inputs: std::mem::take(&mut self.inputs).into_iter(),
+            rows: vec![].into_iter(),
+            error: None,
+        })
+    }
+
+    /// Pretty-print all specified inputs like `print`, but return the outcome of every input:
+    /// the language it was highlighted with, the number of printed lines and the error (if
+    /// any). Errors that concern a single input are not printed, but returned in its result.
+    pub fn print_each(&mut self) -> Result<Vec<InputResult>> {
+        let previous = self.activate_profile()?;
+        self.update_config();
+
+        let inputs = std::mem::take(&mut self.inputs);
+        let results = Controller::new(&self.config, &self.assets).run_each(inputs, |_, _| {});
+
+        self.restore_profile(previous);
+        let mut results = results?;
+        self.keep_warnings(&mut results);
+        Ok(results)
+    }
+
+    /// Filter the warnings of the results by `warning_level` and keep them for `warnings`.
+    /// Returns whether all inputs were printed successfully.
+    fn keep_warnings(&mut self, results: &mut [InputResult]) -> bool {
+        self.warnings.clear();
+        for result in results.iter_mut() {
+            result.warnings =
+                warning::filter(std::mem::take(&mut result.warnings), self.warning_level);
+            self.warnings.extend(
+                result
+                    .warnings
+                    .iter()
+                    .map(|warning| (result.name.clone(), warning.clone())),
+            );
+        }
+        results.iter().all(InputResult::success)
+    }
+
+    /// Print the files of a `grep`-style match list (see `MatchList::parse`): every file with
+    /// a header (if enabled), the lines with matches with `context` lines before and after
+    /// and the matches highlighted. Already added inputs are not printed.
+    pub fn print_matches(&mut self, matches: &MatchList, context: usize) -> Result<bool> {
+        self.print_matches_to(matches, context, None)
+    }
+
+    /// Print the files of a match list to the given writer, or to the terminal. The profile
+    /// (if any) is used for all files, each file is printed with its own visible lines and
+    /// highlighted spans.
+    fn print_matches_to(
+        &mut self,
+        matches: &MatchList,
+        context: usize,
+        mut writer: Option<&mut dyn Write>,
+    ) -> Result<bool> {
+        let previous = self.activate_profile()?;
+        self.update_config();
+
+        let mut results = vec![];
+        let mut error = None;
+        for (path, file_matches) in matches.files() {
+            let mut config = self.config.clone();
+            config.visible_lines =
+                VisibleLines::Ranges(match_list::visible_lines(file_matches, context));
+            config.highlighted_spans = match_list::highlighted_spans(file_matches);
+
+            let input = Input::ordinary_file(path.as_os_str());
+            match Controller::new(&config, &self.assets).run_each_with_writer(
+                vec![input],
+                writer
+                    .as_mut()
+                    .map(|writer| &mut **writer as &mut dyn Write),
+                default_error_handler,
+            ) {
+                Ok(mut file_results) => results.append(&mut file_results),
+                Err(file_error) => {
+                    error = Some(file_error);
+                    break;
+                }
+            }
+        }
+
+        self.restore_profile(previous);
+        match error {
+            Some(error) => Err(error),
+            None => Ok(self.keep_warnings(&mut results)),
+        }
+    }
+
+    /// Determine the number of lines and the number of terminal rows (given the wrapping mode
+    /// and terminal width) of all specified inputs, without printing anything. Unlike 'print',
+    /// this method does not "use" the inputs. STDIN and custom readers are kept in memory, such
+    /// that they can still be printed afterwards.
+    pub fn measure(&mut self) -> Result<Vec<InputSize>> {
+        let previous = self.activate_profile()?;
+        self.update_config();
+
+        let controller = Controller::new(&self.config, &self.assets);
+        let result = self
+            .inputs
+            .iter_mut()
+            .map(|input| controller.measure(input.try_clone()?))
+            .collect();
+
+        self.restore_profile(previous);
+        result
+    }
+
+    fn current_profile(&self) -> Profile<'a> {
+        Profile {
+            config: self.config.clone(),
+            highlighted_lines: self.highlighted_lines.clone(),
+            term_width: self.term_width,
+            active_style_components: self.active_style_components.clone(),
+        }
+    }
+
+    /// Switch to the requested profile (if any) and return the previous settings
+    fn activate_profile(&mut self) -> Result<Option<Profile<'a>>> {
+        let name = match self.active_profile.take() {
+            Some(name) => name,
+            None => return Ok(None),
+        };
+
+        let profile = match self.profiles.get(&name) {
+            Some(profile) => profile.clone(),
+            None => {
+                let suggestions = closest_matches(&name, self.profiles())
+                    .into_iter()
+                    .map(String::from)
+                    .collect();
+                return Err(ErrorKind::UnknownProfile(name, suggestions).into());
+            }
+        };
+
+        let previous = self.current_profile();
+        self.restore_profile(Some(profile));
+        Ok(Some(previous))
+    }
+
+    fn restore_profile(&mut self, profile: Option<Profile<'a>>) {
+        if let Some(profile) = profile {
+            self.config = profile.config;
+            self.highlighted_lines = profile.highlighted_lines;
+            self.term_width = profile.term_width;
+            self.active_style_components = profile.active_style_components;
+        }
+    }
+
+    fn update_config(&mut self) {
         self.config.highlighted_lines =
             HighlightedLineRanges(LineRanges::from(self.highlighted_lines.clone()));
-        self.config.term_width = self
-            .term_width
-            .unwrap_or_else(|| Term::stdout().size().1 as usize);
+        self.config.term_width = match self.term_width {
+            Some(width) => width,
+            None if self.config.deterministic => DETERMINISTIC_TERM_WIDTH,
+            None => Term::stdout().size().1 as usize,
+        };
 
         let mut style_components = vec![];
         if self.active_style_components.grid {
@@ -272,11 +1302,958 @@ impl<'a> PrettyPrinter<'a> {
             style_components.push(StyleComponent::Changes);
         }
         self.config.style_components = StyleComponents::new(&style_components);
+    }
+}
 
-        let mut inputs: Vec<Input> = vec![];
-        std::mem::swap(&mut inputs, &mut self.inputs);
+/// The rows of the output of a `PrettyPrinter` (see `PrettyPrinter::print_to_rows`)
+struct OutputRows<'p, 'a> {
+    config: Config<'a>,
+    assets: &'p HighlightingAssets,
+    inputs: std::vec::IntoIter<Input<'a>>,
 
-        let controller = Controller::new(&self.config, &self.assets);
-        controller.run(inputs)
+    /// The remaining rows of the current input, which is printed completely before its rows
+    /// are returned
+    rows: std::vec::IntoIter<String>,
+
+    /// The error of the current input, which is returned after its rows
+    error: Option<Error>,
+}
+
+impl<'p, 'a> Iterator for OutputRows<'p, 'a> {
+    type Item = Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(row) = self.rows.next() {
+                return Some(Ok(row));
+            }
+            if let Some(error) = self.error.take() {
+                return Some(Err(error));
+            }
+
+            let input = self.inputs.next()?;
+            let mut output = vec![];
+            let controller = Controller::new(&self.config, self.assets);
+            match controller.run_each_with_writer(vec![input], Some(&mut output), |_, _| {}) {
+                Ok(mut results) => self.error = results.pop().and_then(|result| result.error),
+                Err(error) => {
+                    // The configuration is invalid, none of the inputs can be printed
+                    self.error = Some(error);
+                    self.inputs = vec![].into_iter();
+                }
+            }
+            self.rows = String::from_utf8_lossy(&output)
+                .lines()
+                .map(String::from)
+                .collect::<Vec<_>>()
+                .into_iter();
+        }
+    }
+}
+
+#[test]
+fn measure_does_not_use_inputs() {
+    let mut printer = PrettyPrinter::new();
+    printer
+        .input_from_bytes(b"short\n0123456789abcdef\n")
+        .input_from_reader(&b"\tx\n"[..])
+        .term_width(10)
+        .tab_width(Some(4))
+        .wrapping_mode(WrappingMode::Character);
+
+    let expected = vec![
+        InputSize {
+            lines: 2,
+            display_rows: 3,
+        },
+        InputSize {
+            lines: 1,
+            display_rows: 1,
+        },
+    ];
+    assert_eq!(printer.measure().unwrap(), expected);
+
+    printer.line_numbers(true);
+    assert_eq!(printer.measure().unwrap()[0].display_rows, 5);
+    assert_eq!(printer.measure().unwrap()[1].lines, 1);
+}
+
+#[test]
+fn print_each_returns_results_per_input() {
+    let mut printer = PrettyPrinter::new();
+    printer
+        .input_from_bytes_with_name(b"", "empty.rs")
+        .input_file("does-not-exist.rs")
+        .input_from_bytes(b"# comment\nx = 1\n")
+        .language("Python")
+        .line_ranges(LineRanges::from(vec![LineRange::new(2, 3)]));
+
+    let results = printer.print_each().unwrap();
+    assert_eq!(results.len(), 3);
+
+    assert!(results[0].success());
+    assert_eq!(results[0].name, "empty.rs");
+    assert_eq!(results[0].language.as_deref(), Some("Python"));
+
+    assert!(!results[1].success());
+    assert_eq!(results[1].name, "does-not-exist.rs");
+    assert_eq!(results[1].language, None);
+
+    assert_eq!(results[2].lines, 1);
+    assert!(printer.inputs.is_empty());
+}
+
+#[test]
+fn print_with_writer_captures_the_output() {
+    let mut output = vec![];
+    let success = PrettyPrinter::new()
+        .input_from_bytes(b"fn main() {}\n")
+        .language("rust")
+        .line_numbers(true)
+        .colored_output(true)
+        .print_with_writer(&mut output)
+        .unwrap();
+    assert!(success);
+
+    let output = String::from_utf8(output).unwrap();
+    assert!(output.contains("\x1b["));
+    assert_eq!(console::strip_ansi_codes(&output), "   1 fn main() {}\n");
+}
+
+#[test]
+fn indented_output_keeps_the_width() {
+    let mut output = vec![];
+    PrettyPrinter::new()
+        .input_from_bytes(b"abcdefghij\n")
+        .line_numbers(true)
+        .wrapping_mode(WrappingMode::Character)
+        .term_width(14)
+        .colored_output(false)
+        .indent("> ")
+        .print_with_writer(&mut output)
+        .unwrap();
+
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        ">    1 abcdefg\n>      hij\n"
+    );
+}
+
+#[test]
+fn printers_are_cloned_and_restored() {
+    let mut base = PrettyPrinter::new();
+    base.colored_output(false).line_numbers(true);
+    let snapshot = base.snapshot();
+
+    let mut variant = base.clone();
+    variant.line_numbers(false);
+    variant
+        .add_theme_from_str(
+            "Custom",
+            &std::fs::read_to_string("assets/themes/ansi-dark.tmTheme").unwrap(),
+        )
+        .unwrap();
+    assert!(variant.themes().any(|theme| theme == "Custom"));
+    assert!(base.themes().all(|theme| theme != "Custom"));
+
+    let print = |printer: &mut PrettyPrinter| {
+        let mut output = vec![];
+        printer
+            .input_from_bytes(b"x\n")
+            .print_with_writer(&mut output)
+            .unwrap();
+        String::from_utf8(output).unwrap()
+    };
+    assert_eq!(print(&mut base), "   1 x\n");
+    assert_eq!(print(&mut variant), "x\n");
+
+    variant.restore(&snapshot);
+    assert_eq!(print(&mut variant), "   1 x\n");
+}
+
+#[test]
+fn side_by_side_diff() {
+    let mut output = vec![];
+    PrettyPrinter::new()
+        .input_diff(
+            Input::from_reader(Box::new(&b"a\nb\nc\n"[..])),
+            Input::from_reader(Box::new(&b"a\nB\nc\nd\n"[..])),
+        )
+        .term_width(23)
+        .colored_output(false)
+        .print_with_writer(&mut output)
+        .unwrap();
+
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "  a        │   a\n\
+         ~ b        │ ~ B\n\
+        \x20 c        │   c\n\
+        \x20          │ + d\n"
+    );
+}
+
+#[test]
+fn output_is_returned_row_by_row() {
+    let mut printer = PrettyPrinter::new();
+    printer
+        .input_from_bytes(b"a\nb\n")
+        .input_file("does-not-exist.txt")
+        .input_from_bytes(b"c\n")
+        .line_numbers(true)
+        .colored_output(false);
+
+    let mut rows = printer.print_to_rows().unwrap();
+    assert_eq!(rows.next().unwrap().unwrap(), "   1 a");
+    assert_eq!(rows.next().unwrap().unwrap(), "   2 b");
+    assert!(rows.next().unwrap().is_err());
+    assert_eq!(rows.next().unwrap().unwrap(), "   1 c");
+    assert!(rows.next().is_none());
+}
+
+#[test]
+fn profiles_are_used_for_a_single_call() {
+    let mut printer = PrettyPrinter::new();
+    printer
+        .term_width(10)
+        .wrapping_mode(WrappingMode::Character)
+        .save_profile("wrap")
+        .wrapping_mode(WrappingMode::NoWrapping)
+        .input_from_bytes(b"0123456789abcdef\n");
+
+    assert_eq!(printer.measure().unwrap()[0].display_rows, 1);
+    assert_eq!(
+        printer.profile("wrap").measure().unwrap()[0].display_rows,
+        2
+    );
+    assert_eq!(printer.measure().unwrap()[0].display_rows, 1);
+
+    match printer.profile("warp").measure().unwrap_err().kind() {
+        ErrorKind::UnknownProfile(name, suggestions) => {
+            assert_eq!(name, "warp");
+            assert_eq!(suggestions, &vec!["wrap".to_owned()]);
+        }
+        _ => panic!("expected an UnknownProfile error"),
+    }
+}
+
+#[test]
+#[cfg(all(feature = "paging", feature = "git"))]
+fn sandbox_rejects_pager_and_git() {
+    let mut printer = PrettyPrinter::new();
+    printer
+        .sandbox(true)
+        .input_from_bytes(b"x\n")
+        .paging_mode(PagingMode::Always);
+
+    match printer.measure().unwrap_err().kind() {
+        ErrorKind::SandboxViolation(feature) => assert_eq!(feature, "Paging"),
+        _ => panic!("expected a SandboxViolation error"),
     }
+
+    printer
+        .paging_mode(PagingMode::Never)
+        .vcs_modification_markers(true);
+    assert!(printer.measure().is_err());
+
+    printer.vcs_modification_markers(false);
+    assert!(printer.measure().is_ok());
+}
+
+#[test]
+#[cfg(feature = "http")]
+fn sandbox_rejects_urls() {
+    let mut printer = PrettyPrinter::new();
+    printer
+        .sandbox(true)
+        .input_url("https://example.com/main.rs");
+
+    let mut output = Vec::new();
+    match printer.print_with_writer(&mut output).unwrap_err().kind() {
+        ErrorKind::SandboxViolation(feature) => {
+            assert_eq!(feature, "Downloading inputs from URLs")
+        }
+        _ => panic!("expected a SandboxViolation error"),
+    }
+}
+
+#[test]
+fn deterministic_mode_uses_a_fixed_width() {
+    let mut printer = PrettyPrinter::new();
+    printer
+        .deterministic(true)
+        .wrapping_mode(WrappingMode::Character)
+        .input_from_bytes(&[b'x'; 80]);
+
+    assert_eq!(printer.measure().unwrap()[0].display_rows, 1);
+    assert_eq!(printer.term_width(40).measure().unwrap()[0].display_rows, 2);
+}
+
+#[test]
+fn lines_are_wrapped_at_words() {
+    let mut output = vec![];
+    PrettyPrinter::new()
+        .colored_output(false)
+        .term_width(12)
+        .wrapping_mode(WrappingMode::Word {
+            break_chars: WrappingMode::DEFAULT_BREAK_CHARS,
+        })
+        .input_from_bytes(b"    aaaa bbbb cccc-dddd\nshort\n")
+        .print_with_writer(&mut output)
+        .unwrap();
+
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "    aaaa \n    bbbb \n    cccc-\n    dddd\nshort\n"
+    );
+}
+
+#[test]
+fn warnings_are_collected() {
+    use crate::warning::WarningKind;
+
+    let mut printer = PrettyPrinter::new();
+    printer
+        .colored_output(false)
+        .term_width(20)
+        .wrapping_mode(WrappingMode::Chop)
+        .theme("unknown theme");
+    let mut output = vec![];
+    printer
+        .input_from_bytes(b"a\xffb\nthis line is too long for the terminal\n")
+        .print_with_writer(&mut output)
+        .unwrap();
+
+    let warnings: Vec<&Warning> = printer.warnings().iter().map(|(_, w)| w).collect();
+    assert_eq!(
+        warnings,
+        vec![
+            &Warning::new(WarningKind::ThemeFallback("unknown theme".into())),
+            &Warning::at_line(WarningKind::InvalidUtf8, 1),
+            &Warning::at_line(WarningKind::LineTruncated, 2),
+        ]
+    );
+
+    printer.warning_level(Severity::Warning);
+    printer
+        .input_from_bytes(b"this line is too long for the terminal\n")
+        .print_with_writer(&mut output)
+        .unwrap();
+    assert_eq!(
+        printer.warnings(),
+        &[(
+            "READER".to_owned(),
+            Warning::new(WarningKind::ThemeFallback("unknown theme".into()))
+        )]
+    );
+}
+
+#[test]
+fn environment_overrides_are_applied() {
+    let env = |name: &str| match name {
+        "BAT_THEME" => Some("default".to_owned()),
+        "BAT_STYLE" => Some("numbers,grid".to_owned()),
+        "BAT_TABS" => Some("2".to_owned()),
+        _ => None,
+    };
+
+    let mut printer = PrettyPrinter::new();
+    printer.apply_overrides(env).unwrap();
+    assert_eq!(printer.config.theme, HighlightingAssets::default_theme());
+    assert_eq!(printer.config.tab_width, 2);
+    assert!(printer.active_style_components.line_numbers);
+    assert!(printer.active_style_components.grid);
+    assert!(!printer.active_style_components.header);
+
+    // Settings of the builder take precedence, even if they are set before
+    let mut printer = PrettyPrinter::new();
+    printer.theme("Nord").header(true);
+    printer.apply_overrides(env).unwrap();
+    assert_eq!(printer.config.theme, "Nord");
+    assert_eq!(printer.config.tab_width, 2);
+    assert!(printer.active_style_components.header);
+    assert!(printer.active_style_components.grid);
+    assert_eq!(
+        printer.describe_config(),
+        "changes = false (environment: BAT_STYLE)\n\
+         grid = true (environment: BAT_STYLE)\n\
+         header = true (explicit)\n\
+         numbers = true (environment: BAT_STYLE)\n\
+         snip = false (environment: BAT_STYLE)\n\
+         tabs = 2 (environment: BAT_TABS)\n\
+         theme = Nord (explicit)\n"
+    );
+
+    let invalid = |name: &str| match name {
+        "BAT_TABS" => Some("wide".to_owned()),
+        _ => None,
+    };
+    assert!(printer.apply_overrides(invalid).is_err());
+}
+
+#[test]
+fn files_are_printed_in_order_in_parallel() {
+    let print = |threads: usize| {
+        let mut printer = PrettyPrinter::new();
+        printer
+            .header(true)
+            .line_numbers(true)
+            .parallelism(threads)
+            .input_files(&[
+                "tests/examples/multiline.txt",
+                "tests/examples/cache.c",
+                "tests/examples/missing.txt",
+                "tests/examples/tabs.txt",
+            ])
+            .input_from_bytes(b"reader\n")
+            .input_file("tests/examples/test.txt");
+        printer.update_config();
+
+        // Error messages are collected instead of being written to STDERR
+        let inputs = std::mem::take(&mut printer.inputs);
+        let errors = std::cell::RefCell::new(vec![]);
+        let mut output = vec![];
+        let results = Controller::new(&printer.config, &printer.assets)
+            .run_each_with_writer(inputs, Some(&mut output), |error, _| {
+                errors.borrow_mut().push(error.to_string());
+            })
+            .unwrap();
+        let failed: Vec<bool> = results.iter().map(|r| r.error.is_some()).collect();
+        (failed, errors.into_inner(), output)
+    };
+
+    let sequential = print(1);
+    assert_eq!(sequential.0, vec![false, false, true, false, false, false]);
+    assert_eq!(sequential.1.len(), 1);
+    // With fewer threads than files, the threads take on further files when they are done
+    assert_eq!(print(2), sequential);
+    assert_eq!(print(3), sequential);
+    assert_eq!(print(8), sequential);
+}
+
+#[test]
+fn output_is_aligned_within_the_maximum_width() {
+    let mut output = vec![];
+    PrettyPrinter::new()
+        .colored_output(false)
+        .term_width(30)
+        .max_width(Some(20))
+        .block_alignment(BlockAlignment::Center)
+        .header(true)
+        .grid(true)
+        .input_from_bytes(b"x\n")
+        .print_with_writer(&mut output)
+        .unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert_eq!(
+        output,
+        format!(
+            "     {0}\n     READER\n     {0}\n     x\n     {0}\n",
+            "─".repeat(20)
+        )
+    );
+}
+
+#[test]
+fn header_components_are_shown() {
+    let path = "tests/examples/single-line.txt";
+    let metadata = std::fs::metadata(path).unwrap();
+
+    let mut output = vec![];
+    PrettyPrinter::new()
+        .colored_output(false)
+        .header(true)
+        .header_components(&[
+            HeaderComponent::Filename,
+            HeaderComponent::Size,
+            HeaderComponent::Modified,
+            HeaderComponent::Permissions,
+        ])
+        .input_file(path)
+        .input_from_bytes(b"x\n")
+        .print_with_writer(&mut output)
+        .unwrap();
+    let output = String::from_utf8(output).unwrap();
+    assert_eq!(
+        output,
+        format!(
+            "File: {}   {}   {}   {}\nSingle Line\nREADER\nx\n",
+            path,
+            crate::template::human_size(metadata.len()),
+            crate::template::utc_time(metadata.modified().unwrap()),
+            crate::template::permissions(&metadata)
+        )
+    );
+}
+
+#[test]
+fn deterministic_headers_leave_out_the_modification_time() {
+    let path = "tests/examples/single-line.txt";
+
+    let mut output = vec![];
+    PrettyPrinter::new()
+        .colored_output(false)
+        .deterministic(true)
+        .header(true)
+        .header_components(&[HeaderComponent::Filename, HeaderComponent::Modified])
+        .input_file(path)
+        .print_with_writer(&mut output)
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        format!("File: {}\nSingle Line\n", path)
+    );
+
+    let mut output = vec![];
+    PrettyPrinter::new()
+        .colored_output(false)
+        .deterministic(true)
+        .header(true)
+        .header_template(Some(Template::header("{name} [{modified}]").unwrap()))
+        .input_file(path)
+        .print_with_writer(&mut output)
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "single-line.txt []\nSingle Line\n"
+    );
+}
+
+#[cfg(feature = "structured-diff")]
+#[test]
+fn structured_diffs_are_printed() {
+    let mut output = vec![];
+    PrettyPrinter::new()
+        .colored_output(false)
+        .header(true)
+        .input_structured_diff(
+            Input::from_reader(Box::new(&b"{\"a\": 1, \"b\": [1, 2]}"[..]))
+                .with_name(Some("old.json".as_ref())),
+            Input::from_reader(Box::new(&b"b: [1, 2, 3]\n"[..]))
+                .with_name(Some("new.yml".as_ref())),
+        )
+        .print_with_writer(&mut output)
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "File: new.yml\n- $.a: 1\n+ $.b[2]: 3\n"
+    );
+}
+
+#[test]
+fn results_are_cached() {
+    let dir = tempdir::TempDir::new("bat_result_cache").unwrap();
+    let print = |content: &'static [u8]| {
+        let mut output = vec![];
+        PrettyPrinter::new()
+            .enable_result_cache(dir.path())
+            .language("rust")
+            .input_from_bytes(content)
+            .print_with_writer(&mut output)
+            .unwrap();
+        String::from_utf8(output).unwrap()
+    };
+
+    let first = print(b"fn main() {}\n");
+    assert!(first.contains("\x1b["));
+    let entries: Vec<_> = std::fs::read_dir(dir.path()).unwrap().collect();
+    assert_eq!(entries.len(), 1);
+
+    // The second time, the output comes from the cache
+    let entry = entries[0].as_ref().unwrap().path();
+    std::fs::write(&entry, "{\"language\":\"Rust\",\"lines\":1}\ncached\n").unwrap();
+    assert_eq!(print(b"fn main() {}\n"), "cached\n");
+    assert_ne!(print(b"fn main() { }\n"), "cached\n");
+
+    // Cached inputs have results as well
+    let mut printer = PrettyPrinter::new();
+    printer.enable_result_cache(dir.path()).language("rust");
+    printer.update_config();
+    let cache = printer.result_cache.clone().unwrap();
+    let inputs = vec![
+        Input::from_reader(Box::new(&b"fn main() {}\n"[..])),
+        Input::from_reader(Box::new(&b"fn main() {}\n"[..])),
+    ];
+    let results = printer.print_cached(&cache, inputs, &mut vec![]).unwrap();
+    assert_eq!(results.len(), 2);
+    assert!(results.iter().all(InputResult::success));
+    assert_eq!(results[0].language.as_deref(), Some("Rust"));
+    assert_eq!(results[0].lines, 1);
+
+    // The cache is neither read nor written in sandbox mode
+    let mut output = vec![];
+    PrettyPrinter::new()
+        .enable_result_cache(dir.path())
+        .sandbox(true)
+        .language("rust")
+        .input_from_bytes(b"fn main() {}\n")
+        .input_from_bytes(b"fn sandboxed() {}\n")
+        .print_with_writer(&mut output)
+        .unwrap();
+    assert!(!String::from_utf8(output).unwrap().contains("cached"));
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 2);
+}
+
+#[test]
+fn appended_lines_are_followed() {
+    use std::fs::OpenOptions;
+    use std::sync::mpsc;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    struct ChannelWriter(mpsc::Sender<Vec<u8>>);
+
+    impl Write for ChannelWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.send(buf.to_vec()).ok();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let dir = tempdir::TempDir::new("bat_follow").unwrap();
+    let path = dir.path().join("log.txt");
+    std::fs::write(&path, "first\n").unwrap();
+
+    // The printer returns when the file is removed
+    let (sender, receiver) = mpsc::channel();
+    let followed = path.clone();
+    let printer = thread::spawn(move || {
+        PrettyPrinter::new()
+            .follow(true)
+            .input_file(&followed)
+            .print_with_writer(ChannelWriter(sender))
+    });
+
+    let mut output = String::new();
+    let mut wait_for = |text: &str| {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while !output.contains(text) {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            let chunk = receiver.recv_timeout(timeout).expect(text);
+            output.push_str(&String::from_utf8_lossy(&chunk));
+        }
+    };
+
+    wait_for("first");
+    let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+    file.write_all(b"sec").unwrap();
+    file.flush().unwrap();
+    thread::sleep(Duration::from_millis(300));
+    file.write_all(b"ond\n").unwrap();
+    wait_for("second");
+
+    drop(file);
+    std::fs::remove_file(&path).unwrap();
+    assert!(printer.join().unwrap().unwrap());
+}
+
+#[test]
+fn utf16le_files_are_not_followed() {
+    let dir = tempdir::TempDir::new("bat_follow_utf16").unwrap();
+    let path = dir.path().join("log.txt");
+    std::fs::write(&path, b"\xFF\xFEa\x00\n\x00").unwrap();
+
+    // Printing finishes at the end of the file
+    let mut output = vec![];
+    PrettyPrinter::new()
+        .colored_output(false)
+        .follow(true)
+        .input_file(&path)
+        .print_with_writer(&mut output)
+        .unwrap();
+    assert_eq!(String::from_utf8(output).unwrap(), "\u{feff}a\n");
+}
+
+#[test]
+fn key_values_are_aligned() {
+    let config = b"[user]\nname = me\nemail=me@example.com\n";
+
+    let mut output = vec![];
+    PrettyPrinter::new()
+        .colored_output(false)
+        .align_key_values(true)
+        .input_from_bytes_with_name(config, "config.ini")
+        .input_from_bytes_with_name(config, "config.txt")
+        .input(Input::from_reader(Box::new(&config[..])).align_key_values(true))
+        .print_with_writer(&mut output)
+        .unwrap();
+
+    let aligned = "[user]\nname  = me\nemail = me@example.com\n";
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        [aligned, std::str::from_utf8(config).unwrap(), aligned].concat()
+    );
+}
+
+#[test]
+fn focused_symbols_are_printed() {
+    let code = b"fn first() {\n    1\n}\n\n/// Docs\nfn second() {\n    2\n}\n";
+
+    let mut output = vec![];
+    let success = PrettyPrinter::new()
+        .colored_output(false)
+        .language("rust")
+        .input_from_bytes(code)
+        .focus_symbol("second")
+        .print_with_writer(&mut output)
+        .unwrap();
+    assert!(success);
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "/// Docs\nfn second() {\n    2\n}\n"
+    );
+
+    let mut printer = PrettyPrinter::new();
+    printer.language("rust").focus_symbol("first");
+    assert_eq!(
+        printer.input_from_bytes(code).measure().unwrap()[0].display_rows,
+        3
+    );
+
+    let mut output = vec![];
+    let success = printer
+        .focus_symbol("third")
+        .input_from_bytes(code)
+        .print_with_writer(&mut output)
+        .unwrap();
+    assert!(!success);
+    assert!(output.is_empty());
+}
+
+#[test]
+fn last_lines_are_printed() {
+    let mut output = vec![];
+    PrettyPrinter::new()
+        .colored_output(false)
+        .input_from_bytes(b"1\n2\n3\n4\n5\n")
+        .last_lines(2)
+        .print_with_writer(&mut output)
+        .unwrap();
+    assert_eq!(String::from_utf8(output).unwrap(), "4\n5\n");
+
+    let mut output = vec![];
+    PrettyPrinter::new()
+        .colored_output(false)
+        .input_from_bytes(b"1\n2\n3\n4\n5\n")
+        .line_ranges(LineRanges::from(vec![LineRange::from("2:-3").unwrap()]))
+        .print_with_writer(&mut output)
+        .unwrap();
+    assert_eq!(String::from_utf8(output).unwrap(), "2\n3\n");
+}
+
+#[test]
+fn html_reports_contain_all_inputs() {
+    let mut output = vec![];
+    let mut printer = PrettyPrinter::new();
+    let success = printer
+        .input_from_bytes_with_name(b"fn main() {}\n", "main.rs")
+        .input_from_bytes_with_name(b"x = 1\ny = 2\n", "config.toml")
+        .line_numbers(true)
+        .highlight(2)
+        .print_html_report(&mut output, "Example")
+        .unwrap();
+    let output = String::from_utf8(output).unwrap();
+
+    assert!(success);
+    assert!(output.contains("<title>Example</title>"));
+    assert!(output.contains("<a href=\"#file-1\">main.rs</a> <span class=\"meta\">Rust, 1 line"));
+    assert!(output.contains("<details id=\"file-2\" open>\n<summary>config.toml"));
+    assert!(output.contains(
+        "<span class=\"line highlighted\" id=\"file-2-L2\">\
+         <a class=\"gutter\" href=\"#file-2-L2\">   2 </a>"
+    ));
+    assert!(printer.inputs.is_empty());
+}
+
+#[test]
+fn theme_previews_have_a_header_per_theme() {
+    let mut output = vec![];
+    let mut printer = PrettyPrinter::new();
+    printer
+        .input_from_bytes(b"not printed\n")
+        .language("rs")
+        .colored_output(false)
+        .print_theme_previews_with_writer("fn main() {}\n", &mut output)
+        .unwrap();
+    let output = String::from_utf8(output).unwrap();
+
+    let themes: Vec<&str> = printer.themes().collect();
+    assert_eq!(output.matches("Theme: ").count(), themes.len());
+    assert!(output.contains("Theme: Monokai Extended\n\nfn main() {}\n\n"));
+    assert!(!output.contains("not printed"));
+    assert_eq!(printer.inputs.len(), 1);
+}
+
+#[test]
+fn escape_sequences_are_passed_through() {
+    let colored = b"\x1b[31mred\x1b[0m\tx\n\x1b[1mabcdefghij\x1b[0m\n";
+    let print = |colored_output: bool, wrapping_mode: WrappingMode| {
+        let mut output = vec![];
+        PrettyPrinter::new()
+            .colored_output(colored_output)
+            .ansi_passthrough(true)
+            .line_numbers(true)
+            .tab_width(Some(4))
+            .term_width(12)
+            .wrapping_mode(wrapping_mode)
+            .input_from_bytes(colored)
+            .print_with_writer(&mut output)
+            .unwrap();
+        String::from_utf8(output).unwrap()
+    };
+
+    let wrapped = print(true, WrappingMode::Character);
+    assert!(wrapped.contains("\x1b[31mred\x1b[0m"));
+    assert_eq!(
+        console::strip_ansi_codes(&wrapped),
+        "   1 red x\n   2 abcdefg\n     hij\n"
+    );
+
+    let unwrapped = print(true, WrappingMode::NoWrapping);
+    assert_eq!(
+        console::strip_ansi_codes(&unwrapped),
+        "   1 red x\n   2 abcdefghij\n"
+    );
+
+    assert_eq!(
+        print(false, WrappingMode::Character),
+        "   1 red x\n   2 abcdefg\n     hij\n"
+    );
+}
+
+#[test]
+fn line_processors_transform_the_lines() {
+    use crate::line_processor::StyledSegment;
+
+    let mut output = vec![];
+    PrettyPrinter::new()
+        .colored_output(false)
+        .line_numbers(true)
+        .line_processor(|_, segments: Vec<StyledSegment>| {
+            segments
+                .into_iter()
+                .map(|(style, text)| (style, text.replace("hunter2", "*******")))
+                .collect()
+        })
+        .line_processor(|line_number, mut segments: Vec<StyledSegment>| {
+            if line_number == 1 {
+                let style = segments[0].0;
+                segments.insert(0, (style, "[note] ".to_owned()));
+            }
+            segments
+        })
+        .input_from_bytes(b"password = hunter2\nuser = bat\n")
+        .language("ini")
+        .print_with_writer(&mut output)
+        .unwrap();
+
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        "   1 [note] password = *******\n   2 user = bat\n"
+    );
+}
+
+#[test]
+fn line_classifiers_can_capture_their_environment() {
+    // The classes are computed at runtime from a captured threshold
+    let threshold = 2;
+    let mut output = vec![];
+    PrettyPrinter::new()
+        .colored_output(true)
+        .true_color(true)
+        .line_classifier(move |line_number, _| Some(format!("level-{}", line_number / threshold)))
+        .line_class_color(
+            "level-1",
+            Color {
+                r: 255,
+                g: 0,
+                b: 0,
+                a: 0xFF,
+            },
+        )
+        .input_from_bytes(b"a\nb\nc\nd\n")
+        .language("txt")
+        .print_with_writer(&mut output)
+        .unwrap();
+
+    let output = String::from_utf8(output).unwrap();
+    let tinted: Vec<bool> = output
+        .lines()
+        .map(|line| line.contains("48;2;255;0;0"))
+        .collect();
+    assert_eq!(tinted, vec![false, true, true, false]);
+}
+
+#[test]
+fn match_lists_are_printed_with_the_profile() {
+    let dir = tempdir::TempDir::new("bat_print_matches").unwrap();
+    let first = dir.path().join("first.txt");
+    let second = dir.path().join("second.txt");
+    std::fs::write(&first, "a\nb\nc\nd\n").unwrap();
+    std::fs::write(&second, "e\nf\ng\nh\n").unwrap();
+
+    let mut matches = MatchList::default();
+    let found = |line| match_list::Match { line, span: 0..1 };
+    matches.add(&first, found(2));
+    matches.add(&second, found(4));
+
+    let mut printer = PrettyPrinter::new();
+    printer
+        .colored_output(false)
+        .line_numbers(true)
+        .save_profile("numbers")
+        .line_numbers(false);
+
+    // Both files are printed with the profile, only with the lines of their matches and with
+    // the matches highlighted
+    let span = |text| format!("\u{1b}[48;2;255;231;146m{}\u{1b}[0m", text);
+    let mut output = vec![];
+    printer.profile("numbers");
+    printer
+        .print_matches_to(&matches, 0, Some(&mut output))
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        format!("   2 {}\n   4 {}\n", span("b"), span("h"))
+    );
+
+    // The profile was only used for one call
+    let mut output = vec![];
+    printer
+        .print_matches_to(&matches, 0, Some(&mut output))
+        .unwrap();
+    assert_eq!(
+        String::from_utf8(output).unwrap(),
+        format!("{}\n{}\n", span("b"), span("h"))
+    );
+}
+
+#[test]
+fn markdown_code_blocks_are_highlighted_with_their_language() {
+    let print = |markdown_code_blocks| {
+        let mut output = vec![];
+        PrettyPrinter::new()
+            .colored_output(true)
+            .true_color(true)
+            .theme("Monokai Extended")
+            .markdown_code_blocks(markdown_code_blocks)
+            .input_from_bytes(b"# Title\n```ini\nkey = value\n```\n")
+            .language("md")
+            .print_with_writer(&mut output)
+            .unwrap();
+        String::from_utf8(output).unwrap()
+    };
+
+    let output = print(true);
+    let lines: Vec<&str> = output.lines().collect();
+    // The prose is bold or plain in the foreground color, the code block highlighted as INI
+    assert!(lines[0].starts_with("\u{1b}[1;38;2;248;248;242m"));
+    assert_eq!(lines[1], "\u{1b}[38;2;248;248;242m```ini\u{1b}[0m");
+    assert!(lines[2].starts_with("\u{1b}[38;2;249;38;114mkey"));
+    assert!(lines[2].contains("\u{1b}[38;2;230;219;116mvalue"));
+    assert_ne!(output, print(false));
 }
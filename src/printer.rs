@@ -1,23 +1,30 @@
+use std::borrow::Cow;
+use std::fs;
 use std::io::Write;
+use std::mem;
+use std::ops::Range;
+use std::path::Path;
 use std::vec::Vec;
 
 use ansi_term::Colour::{Fixed, Green, Red, Yellow};
 use ansi_term::Style;
 
-use console::AnsiCodeIterator;
+use console::{measure_text_width, strip_ansi_codes, AnsiCodeIterator};
 
 use syntect::easy::HighlightLines;
 use syntect::highlighting::Color;
-use syntect::highlighting::Theme;
-use syntect::parsing::SyntaxSet;
+use syntect::highlighting::{self, FontStyle, Theme};
+use syntect::parsing::{ParseState, Scope, ScopeStackOp, SyntaxSet};
+use syntect::util::LinesWithEndings;
 
 use content_inspector::ContentType;
 
 use encoding::all::{UTF_16BE, UTF_16LE};
 use encoding::{DecoderTrap, Encoding};
 
-use unicode_width::UnicodeWidthChar;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
+use crate::accessibility::{self, MarkerPalette};
 use crate::assets::HighlightingAssets;
 use crate::config::Config;
 #[cfg(feature = "git")]
@@ -26,11 +33,25 @@ use crate::decorations::{Decoration, GridBorderDecoration, LineNumberDecoration}
 #[cfg(feature = "git")]
 use crate::diff::LineChanges;
 use crate::error::*;
-use crate::input::OpenedInput;
+use crate::gutter::GutterSource;
+use crate::hexdump::{self, BinaryMode, HexRow};
+use crate::input::{OpenedInput, OpenedInputKind};
+use crate::json_lines::{self, FormattedLine, LogLevel, Token, TokenStyles};
+use crate::key_values::{self, KeyValueLayout, KEY_VALUE_SYNTAXES};
+use crate::line_processor::StyledSegment;
 use crate::line_range::RangeCheckResult;
+use crate::links::{self, hyperlink};
+use crate::long_tokens;
+use crate::markdown::CodeBlocks;
+use crate::patch::{self, PatchLine, PatchState, PendingLine};
 use crate::preprocessor::{expand_tabs, replace_nonprintable};
+use crate::regions::{replace_ranges, restyle_ranges, Replacement};
+use crate::style::HeaderComponent;
+use crate::template::{self, Template};
 use crate::terminal::{as_terminal_escaped, to_ansi_color};
-use crate::wrapping::WrappingMode;
+use crate::trace::{self, Frame, StackTraceMode};
+use crate::warning::{Warning, WarningKind};
+use crate::wrapping::{self, WrappingMode};
 
 pub(crate) trait Printer {
     fn print_header(&mut self, handle: &mut dyn Write, input: &OpenedInput) -> Result<()>;
@@ -38,6 +59,17 @@ pub(crate) trait Printer {
 
     fn print_snip(&mut self, handle: &mut dyn Write) -> Result<()>;
 
+    /// A snip marker that tells how many lines have been left out
+    fn print_omitted(&mut self, handle: &mut dyn Write, lines: usize) -> Result<()>;
+
+    /// The name of the syntax that the input is highlighted with
+    fn language(&self) -> Option<&str>;
+
+    /// The warnings about the input that have been collected so far
+    fn take_warnings(&mut self) -> Vec<Warning> {
+        vec![]
+    }
+
     fn print_line(
         &mut self,
         out_of_range: bool,
@@ -68,6 +100,14 @@ impl Printer for SimplePrinter {
         Ok(())
     }
 
+    fn print_omitted(&mut self, _handle: &mut dyn Write, _lines: usize) -> Result<()> {
+        Ok(())
+    }
+
+    fn language(&self) -> Option<&str> {
+        None
+    }
+
     fn print_line(
         &mut self,
         out_of_range: bool,
@@ -94,31 +134,87 @@ pub(crate) struct InteractivePrinter<'a> {
     highlighter: Option<HighlightLines<'a>>,
     syntax_set: &'a SyntaxSet,
     background_color_highlight: Option<Color>,
+    language: Option<&'a str>,
+    pub(crate) show_status_line: bool,
+    lines_seen: usize,
+
+    /// The number of bytes of the lines that have been seen so far
+    bytes_seen: usize,
+
+    /// The width of the values in the number gutter (unless it shows line numbers)
+    gutter_width: Option<usize>,
+
+    /// The value in the number gutter for the current line, if it doesn't show line numbers
+    pub(crate) gutter_value: Option<String>,
+
+    /// The style of hexdumps (see `BinaryMode::Hex`), if the input is printed as one
+    hexdump_style: Option<highlighting::Style>,
+    printed_range: Option<(usize, usize)>,
+    minified: bool,
+    elision_marker_style: highlighting::Style,
+    theme: &'a Theme,
+    embedded: Option<(usize, HighlightLines<'a>)>,
+    section_parser: Option<(ParseState, Vec<Scope>)>,
+    key_values: Option<KeyValueLayout>,
+    markdown_blocks: Option<CodeBlocks<'a>>,
+    token_styles: Option<TokenStyles>,
+    span_colors: (Option<Color>, Color),
+    default_background: Color,
+    patch: Option<PatchState<'a>>,
+    warnings: Vec<Warning>,
 }
 
 impl<'a> InteractivePrinter<'a> {
     pub(crate) fn new(
         config: &'a Config,
         assets: &'a HighlightingAssets,
+        theme: &'a Theme,
         input: &mut OpenedInput,
-        #[cfg(feature = "git")]
-        line_changes: &'a Option<LineChanges>,
-    ) -> Self {
-        let theme = assets.get_theme(&config.theme);
-
+        #[cfg(feature = "git")] line_changes: &'a Option<LineChanges>,
+    ) -> Result<Self> {
         let background_color_highlight = theme.settings.line_highlight;
 
+        let default_background = theme.settings.background.unwrap_or(Color::BLACK);
+        let elision_marker_style = highlighting::Style {
+            foreground: theme.settings.gutter_foreground.unwrap_or(Color {
+                r: DEFAULT_GUTTER_COLOR,
+                g: 0,
+                b: 0,
+                a: 0,
+            }),
+            background: default_background,
+            font_style: FontStyle::empty(),
+        };
+
         let colors = if config.colored_output {
-            Colors::colored(theme, config.true_color)
+            Colors::colored(theme, config.true_color, config.accessible_markers.as_ref())
         } else {
             Colors::plain()
         };
 
+        let hexdump = config.binary_mode == BinaryMode::Hex
+            && !config.show_nonprintable
+            && input.reader.content_type == Some(ContentType::BINARY);
+        if hexdump {
+            input.reader.row_width = Some(hexdump::BYTES_PER_ROW);
+        }
+
+        // The width of the values in the number gutter, unless it shows line numbers
+        let gutter_width = match config.gutter_source {
+            _ if hexdump => Some(hexdump::OFFSET_WIDTH),
+            GutterSource::LineNumber => None,
+            ref source => Some(source.width(&String::from_utf8_lossy(&input.reader.first_line))),
+        };
+
         // Create decorations.
         let mut decorations: Vec<Box<dyn Decoration>> = Vec::new();
 
         if config.style_components.numbers() {
-            decorations.push(Box::new(LineNumberDecoration::new(&colors)));
+            decorations.push(Box::new(LineNumberDecoration::new(
+                &colors,
+                config.line_number_template.as_ref(),
+                gutter_width,
+            )));
         }
 
         #[cfg(feature = "git")]
@@ -147,6 +243,12 @@ impl<'a> InteractivePrinter<'a> {
             panel_width = 0;
         }
 
+        let mut language = None;
+        let mut warnings = vec![];
+        let mut minified = false;
+        let mut section_parser = None;
+        let mut key_values = None;
+        let mut markdown_blocks = None;
         let highlighter = if input
             .reader
             .content_type
@@ -155,11 +257,81 @@ impl<'a> InteractivePrinter<'a> {
             None
         } else {
             // Determine the type of syntax for highlighting
-            let syntax = assets.get_syntax(config.language, input, &config.syntax_mapping);
+            let syntax = match assets.get_syntax(config.language, input, &config.syntax_mapping) {
+                Ok(syntax) => syntax,
+                Err(error) if !config.strict => {
+                    warnings.push(Warning::new(WarningKind::UnknownSyntax(error.to_string())));
+                    assets.get_syntax_set().find_syntax_plain_text()
+                }
+                Err(e) => return Err(e),
+            };
+
+            minified = match config.minified_thresholds {
+                Some(ref thresholds) if config.language.is_none() => {
+                    let beginning = input.reader.peek();
+                    thresholds.is_minified(
+                        &input.description().name,
+                        &input.reader.first_line,
+                        &beginning,
+                    )
+                }
+                _ => false,
+            };
+            // Colored input keeps its own colors (see `Config::ansi_passthrough`)
+            let syntax = if minified || config.ansi_passthrough {
+                assets.get_syntax_set().find_syntax_plain_text()
+            } else {
+                syntax
+            };
+
+            language = Some(syntax.name.as_str());
+            if config.style_components.sections() {
+                let scopes = SECTION_SCOPES
+                    .iter()
+                    .map(|scope| Scope::new(scope).expect("valid scope"))
+                    .collect();
+                section_parser = Some((ParseState::new(syntax), scopes));
+            }
+            let align_key_values = match input.metadata.align_key_values {
+                Some(yes) => yes,
+                None => {
+                    config.align_key_values && KEY_VALUE_SYNTAXES.contains(&syntax.name.as_str())
+                }
+            };
+            if align_key_values {
+                key_values =
+                    KeyValueLayout::measure(&String::from_utf8_lossy(&input.peek_content()?));
+            }
+            if config.markdown_code_blocks && syntax.name == "Markdown" {
+                markdown_blocks = Some(CodeBlocks::new(assets.get_syntax_set(), theme));
+            }
             Some(HighlightLines::new(syntax, theme))
         };
 
-        InteractivePrinter {
+        let patch = if config.render_commits || (config.render_diffs && language == Some("Diff")) {
+            Some(PatchState::new(
+                assets.get_syntax_set().find_syntax_plain_text(),
+                theme,
+            ))
+        } else {
+            None
+        };
+
+        let token_styles = if config.json_lines.is_some()
+            || config.stack_trace.is_some()
+            || patch.is_some()
+            || key_values.is_some()
+        {
+            let mut styles = TokenStyles::new(theme);
+            if let Some(palette) = config.accessible_markers {
+                styles.recolor_levels(|level| palette.level(level));
+            }
+            Some(styles)
+        } else {
+            None
+        };
+
+        Ok(InteractivePrinter {
             panel_width,
             colors,
             config,
@@ -169,9 +341,40 @@ impl<'a> InteractivePrinter<'a> {
             #[cfg(feature = "git")]
             line_changes,
             highlighter,
-            syntax_set: &assets.syntax_set,
+            syntax_set: assets.get_syntax_set(),
             background_color_highlight,
-        }
+            language,
+            show_status_line: false,
+            lines_seen: 0,
+            bytes_seen: 0,
+            gutter_width,
+            gutter_value: None,
+            hexdump_style: if hexdump {
+                Some(highlighting::Style {
+                    foreground: theme.settings.foreground.unwrap_or(Color::WHITE),
+                    background: default_background,
+                    font_style: FontStyle::empty(),
+                })
+            } else {
+                None
+            },
+            printed_range: None,
+            minified,
+            elision_marker_style,
+            theme,
+            embedded: None,
+            section_parser,
+            key_values,
+            markdown_blocks,
+            default_background,
+            span_colors: (
+                theme.settings.find_highlight_foreground,
+                theme.settings.find_highlight.unwrap_or(ANSI_YELLOW),
+            ),
+            patch,
+            token_styles,
+            warnings,
+        })
     }
 
     fn print_horizontal_line(&mut self, handle: &mut dyn Write, grid_char: char) -> Result<()> {
@@ -190,6 +393,41 @@ impl<'a> InteractivePrinter<'a> {
         Ok(())
     }
 
+    /// A snip marker with the given title in the middle
+    fn print_snip_with_title(&mut self, handle: &mut dyn Write, title: &str) -> Result<()> {
+        self.flush_pending_patch_lines(handle)?;
+
+        let panel = self.create_fake_panel(" ...");
+        let panel_count = panel.chars().count();
+
+        let title_count = title.chars().count();
+
+        let snip_left = "─ ".repeat(
+            self.config
+                .term_width
+                .saturating_sub(panel_count + title_count / 2)
+                / 4,
+        );
+        let snip_left_count = snip_left.chars().count(); // Can't use .len() with Unicode.
+
+        let snip_right = " ─".repeat(
+            self.config
+                .term_width
+                .saturating_sub(panel_count + snip_left_count + title_count)
+                / 2,
+        );
+
+        writeln!(
+            handle,
+            "{}",
+            self.colors
+                .grid
+                .paint(format!("{}{}{}{}", panel, snip_left, title, snip_right))
+        )?;
+
+        Ok(())
+    }
+
     fn create_fake_panel(&self, text: &str) -> String {
         if self.panel_width == 0 {
             "".to_string()
@@ -208,163 +446,783 @@ impl<'a> InteractivePrinter<'a> {
         }
     }
 
-    fn preprocess(&self, text: &str, cursor: &mut usize) -> String {
-        if self.config.tab_width > 0 {
-            expand_tabs(text, self.config.tab_width, cursor)
-        } else {
-            *cursor += text.len();
-            text.to_string()
-        }
+    /// Whether the content is printed: text, or binary content with non-printable characters
+    /// replaced or as a hexdump
+    fn shows_content(&self) -> bool {
+        self.content_type.map_or(false, |c| c.is_text())
+            || self.config.show_nonprintable
+            || self.hexdump_style.is_some()
     }
-}
 
-impl<'a> Printer for InteractivePrinter<'a> {
-    fn print_header(&mut self, handle: &mut dyn Write, input: &OpenedInput) -> Result<()> {
-        if !self.config.style_components.header() {
-            if Some(ContentType::BINARY) == self.content_type && !self.config.show_nonprintable {
-                writeln!(
-                    handle,
-                    "{}: Binary content from {} will not be printed to the terminal \
-                     (but will be present if the output of 'bat' is piped). You can use 'bat -A' \
-                     to show the binary file contents.",
-                    Yellow.paint("[bat warning]"),
-                    input.description().full,
-                )?;
-            } else if self.config.style_components.grid() {
-                self.print_horizontal_line(handle, '┬')?;
-            }
-            return Ok(());
+    /// The content type (or minification) of the input, as shown in the header
+    fn mode(&self) -> &'static str {
+        match self.content_type {
+            Some(ContentType::BINARY) => "<BINARY>",
+            Some(ContentType::UTF_16LE) => "<UTF-16LE>",
+            Some(ContentType::UTF_16BE) => "<UTF-16BE>",
+            None => "<EMPTY>",
+            _ if self.minified => "<MINIFIED, NOT HIGHLIGHTED>",
+            _ => "",
         }
+    }
 
-        if self.config.style_components.grid() {
-            self.print_horizontal_line(handle, '┬')?;
-
-            write!(
-                handle,
-                "{}{}",
-                " ".repeat(self.panel_width),
-                self.colors
-                    .grid
-                    .paint(if self.panel_width > 0 { "│ " } else { "" }),
-            )?;
+    /// The column at which the text of the header starts
+    fn text_offset(&self) -> usize {
+        if self.config.style_components.grid() && self.panel_width > 0 {
+            self.panel_width + 2
         } else {
-            write!(handle, "{}", " ".repeat(self.panel_width))?;
+            self.panel_width
         }
+    }
 
-        let mode = match self.content_type {
-            Some(ContentType::BINARY) => "   <BINARY>",
-            Some(ContentType::UTF_16LE) => "   <UTF-16LE>",
-            Some(ContentType::UTF_16BE) => "   <UTF-16BE>",
-            None => "   <EMPTY>",
-            _ => "",
-        };
+    /// Fill in the fields of a header or footer template
+    fn render_template(&self, template: &Template, input: &OpenedInput) -> String {
+        template.render(|field| self.field_value(field, input))
+    }
 
+    /// The value of a field of the header and footer templates
+    fn field_value(&self, field: &str, input: &OpenedInput) -> String {
         let description = input.description();
+        let metadata = || match input.kind {
+            OpenedInputKind::OrdinaryFile(ref path) => fs::metadata(path).ok(),
+            _ => None,
+        };
+        match field {
+            "path" => self.colors.filename.paint(&description.name).to_string(),
+            "name" => {
+                let name = Path::new(&description.name)
+                    .file_name()
+                    .map_or(description.name.clone(), |name| {
+                        name.to_string_lossy().into_owned()
+                    });
+                self.colors.filename.paint(name).to_string()
+            }
+            "language" => self.language.unwrap_or("").to_owned(),
+            "size" => metadata()
+                .map(|metadata| template::human_size(metadata.len()))
+                .unwrap_or_default(),
+            // The modification time differs between checkouts of the same file
+            "modified" if self.config.deterministic => String::new(),
+            "modified" => metadata()
+                .and_then(|metadata| metadata.modified().ok())
+                .map(template::utc_time)
+                .unwrap_or_default(),
+            "permissions" => metadata()
+                .map(|metadata| template::permissions(&metadata))
+                .unwrap_or_default(),
+            "mode" => self.mode().to_owned(),
+            "revision" => input.metadata.revision.clone().unwrap_or_default(),
+            "lines" => self.lines_seen.to_string(),
+            "range" => self
+                .printed_range
+                .map(|(first, last)| format!("{}-{}", first, last))
+                .unwrap_or_default(),
+            _ => String::new(),
+        }
+    }
+
+    fn print_status_line(&mut self, handle: &mut dyn Write, input: &OpenedInput) -> Result<()> {
+        let mut fields = vec![input.description().name];
+        if let Some(language) = self.language {
+            fields.push(language.to_owned());
+        }
+        fields.push(format!(
+            "{} line{}",
+            self.lines_seen,
+            if self.lines_seen == 1 { "" } else { "s" }
+        ));
+        if let Some((first, last)) = self.printed_range {
+            fields.push(format!("{}-{}", first, last));
+        }
+
+        // Wide characters (in file names, for instance) take up two columns
+        let status =
+            template::truncate(&format!(" {}", fields.join(" │ ")), self.config.term_width);
+        let padding = self.config.term_width.saturating_sub(status.width());
 
         writeln!(
             handle,
-            "{}{}{}",
-            description.prefix,
-            self.colors.filename.paint(&description.name),
-            mode
+            "{}",
+            self.colors
+                .status_line
+                .paint(format!("{}{}", status, " ".repeat(padding)))
         )?;
 
-        if self.config.style_components.grid() {
-            if self.content_type.map_or(false, |c| c.is_text()) || self.config.show_nonprintable {
-                self.print_horizontal_line(handle, '┼')?;
-            } else {
-                self.print_horizontal_line(handle, '┴')?;
-            }
+        Ok(())
+    }
+
+    /// Apply the styles of the highlight patterns (see `Config::highlight_patterns`) to their
+    /// matches within the line. Highlighted spans (like search results) keep their style.
+    fn highlight_patterns<'b>(
+        &self,
+        mut regions: Vec<(highlighting::Style, &'b str)>,
+        line: &str,
+    ) -> Vec<(highlighting::Style, &'b str)> {
+        let default_background = self.default_background;
+        for (range, pattern_style) in self.config.highlight_patterns.matches(line) {
+            regions = restyle_ranges(&regions, &[range], |style| {
+                if style.background == default_background {
+                    pattern_style.apply(style)
+                } else {
+                    style
+                }
+            });
         }
+        regions
+    }
 
-        Ok(())
+    /// The background color of a region: highlighted spans have a background of their own,
+    /// everything else has the background of the line
+    fn region_background(
+        &self,
+        style: highlighting::Style,
+        line_background: Option<Color>,
+    ) -> Option<Color> {
+        if style.background != self.default_background {
+            Some(style.background)
+        } else {
+            line_background
+        }
     }
 
-    fn print_footer(&mut self, handle: &mut dyn Write, _input: &OpenedInput) -> Result<()> {
-        if self.config.style_components.grid()
-            && (self.content_type.map_or(false, |c| c.is_text()) || self.config.show_nonprintable)
-        {
-            self.print_horizontal_line(handle, '┴')
+    /// The background color of a line: highlighted lines (see `Config::highlighted_lines`) or
+    /// the color of the class of the line
+    fn line_background(&self, line_number: usize, line: &str) -> Option<Color> {
+        let highlight_this_line =
+            self.config.highlighted_lines.0.check(line_number) == RangeCheckResult::InRange;
+
+        self.background_color_highlight
+            .filter(|_| highlight_this_line)
+            .or_else(|| self.class_color(line_number, line))
+    }
+
+    /// The background color of the class of the line (see `Config::line_classifier`)
+    fn class_color(&self, line_number: usize, line: &str) -> Option<Color> {
+        let classifier = self.config.line_classifier.as_ref()?;
+        let class = classifier.classify(line_number, line.trim_end_matches(&['\r', '\n'][..]))?;
+        self.config.line_class_colors.get(&class).cloned()
+    }
+
+    /// Decode a line of the input, along with whether invalid UTF-8 in it was replaced.
+    /// Returns `None` for content that is not printed.
+    fn decode_line<'b>(&self, line_buffer: &'b [u8]) -> Result<Option<(Cow<'b, str>, bool)>> {
+        let mut replaced = false;
+        let line: Cow<str> = if self.config.show_nonprintable {
+            replace_nonprintable(&line_buffer, self.config.tab_width).into()
         } else {
-            Ok(())
+            match self.content_type {
+                Some(ContentType::BINARY) | None => {
+                    return Ok(None);
+                }
+                Some(ContentType::UTF_16LE) => UTF_16LE
+                    .decode(&line_buffer, DecoderTrap::Replace)
+                    .map_err(|_| "Invalid UTF-16LE")?
+                    .into(),
+                Some(ContentType::UTF_16BE) => UTF_16BE
+                    .decode(&line_buffer, DecoderTrap::Replace)
+                    .map_err(|_| "Invalid UTF-16BE")?
+                    .into(),
+                // The line is validated once: valid lines are borrowed, only invalid ones are
+                // copied
+                _ => match std::str::from_utf8(&line_buffer) {
+                    Ok(line) => line.into(),
+                    Err(_) => {
+                        replaced = true;
+                        String::from_utf8_lossy(&line_buffer).into_owned().into()
+                    }
+                },
+            }
+        };
+
+        // Without colors, the escape sequences of colored input are of no use
+        if self.config.ansi_passthrough && !self.config.colored_output {
+            return Ok(Some((
+                strip_ansi_codes(&line).into_owned().into(),
+                replaced,
+            )));
         }
+
+        Ok(Some((line, replaced)))
     }
 
-    fn print_snip(&mut self, handle: &mut dyn Write) -> Result<()> {
-        let panel = self.create_fake_panel(" ...");
-        let panel_count = panel.chars().count();
+    /// The number of terminal rows that a line of the input occupies when it is printed
+    /// (without highlighting it)
+    pub(crate) fn display_rows(&self, line_buffer: &[u8]) -> Result<usize> {
+        if self.highlighter.is_none() {
+            return Ok(0);
+        }
+        let line = match self.decode_line(line_buffer)? {
+            Some((line, _)) => line,
+            None => return Ok(0),
+        };
 
-        let title = "8<";
-        let title_count = title.chars().count();
+        let content_width = if self.panel_width > 0 {
+            self.decorations
+                .iter()
+                .fold(self.config.term_width, |w, d| {
+                    w.saturating_sub(d.width() + 1)
+                })
+        } else {
+            self.config.term_width
+        };
 
-        let snip_left = "─ ".repeat((self.config.term_width - panel_count - (title_count / 2)) / 4);
-        let snip_left_count = snip_left.chars().count(); // Can't use .len() with Unicode.
+        let formatted = self.format_line(&line);
+        let line: &str = match formatted {
+            Some(ref formatted) => &formatted.text,
+            None => &line,
+        };
+
+        let line = long_tokens::elide_line(line, &self.elided_ranges(line));
+        Ok(self
+            .config
+            .wrapping_mode
+            .display_rows(&self.preprocess(&line, &mut 0), content_width))
+    }
 
-        let snip_right =
-            " ─".repeat((self.config.term_width - panel_count - snip_left_count - title_count) / 2);
+    /// Highlight the parts of the line that belong to embedded languages (see
+    /// `Config::embedded_languages`)
+    fn highlight_embedded<'t>(&mut self, line: &'t str) -> Vec<Replacement<'t>> {
+        let mut result = vec![];
+        let rules = &self.config.embedded_languages;
+
+        let mut position = 0;
+        loop {
+            match self.embedded.take() {
+                Some((index, mut highlighter)) => {
+                    let end = rules[index].find_end(&line[position..], position == 0);
+                    let content = match end {
+                        Some((end, _)) => position..position + end,
+                        None => position..line.len(),
+                    };
+                    if content.start < content.end {
+                        let regions =
+                            highlighter.highlight(&line[content.clone()], self.syntax_set);
+                        result.push((content, regions));
+                    }
+
+                    match end {
+                        Some((_, after_end)) => position += after_end,
+                        None => {
+                            self.embedded = Some((index, highlighter));
+                            break;
+                        }
+                    }
+                }
+                None => {
+                    let next = rules
+                        .iter()
+                        .enumerate()
+                        .filter_map(|(index, rule)| {
+                            rule.find_start(&line[position..])
+                                .map(|(start, content)| (start, content, index))
+                        })
+                        .min();
+                    let (_, content, index) = match next {
+                        Some(next) => next,
+                        None => break,
+                    };
+
+                    position += content;
+                    if let Some(syntax) = self
+                        .syntax_set
+                        .find_syntax_by_token(rules[index].language())
+                    {
+                        self.embedded = Some((index, HighlightLines::new(syntax, self.theme)));
+                    }
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Whether the line contains a top-level declaration (like a function, class or heading).
+    /// Has to be called for every line to keep track of the parser state.
+    fn starts_section(&mut self, line: &str) -> bool {
+        let (parser, section_scopes) = match self.section_parser {
+            Some((ref mut parser, ref scopes)) => (parser, scopes),
+            None => return false,
+        };
+        let ops = parser.parse_line(line, self.syntax_set);
+
+        if line.starts_with(char::is_whitespace) {
+            return false;
+        }
+
+        ops.iter().any(|(_, op)| match op {
+            ScopeStackOp::Push(scope) => section_scopes
+                .iter()
+                .any(|prefix| prefix.is_prefix_of(*scope)),
+            _ => false,
+        })
+    }
 
+    fn print_section_separator(&mut self, handle: &mut dyn Write) -> Result<()> {
+        let panel = if self.panel_width > 0 && !self.config.style_components.grid() {
+            " ".repeat(self.panel_width)
+        } else {
+            self.create_fake_panel("")
+        };
+        let width = self.config.term_width.saturating_sub(panel.chars().count());
         writeln!(
             handle,
             "{}",
             self.colors
                 .grid
-                .paint(format!("{}{}{}{}", panel, snip_left, title, snip_right))
+                .paint(format!("{}{}", panel, "╌".repeat(width)))
         )?;
-
         Ok(())
     }
 
-    fn print_line(
-        &mut self,
-        out_of_range: bool,
+    /// The parts of the line that are elided because they belong to very long tokens
+    fn elided_ranges(&self, line: &str) -> Vec<Range<usize>> {
+        match self.config.max_token_length {
+            Some(max_length) => long_tokens::elided_ranges(line, max_length),
+            None => vec![],
+        }
+    }
+
+    /// Format lines with JSON objects (see `Config::json_lines`), stack traces (see
+    /// `Config::stack_trace`) and the lines of config files (see `Config::align_key_values`) on
+    /// their own
+    fn format_line(&self, line: &str) -> Option<FormattedLine> {
+        if let Some(layout) = self.key_values {
+            return key_values::format_line(line, layout);
+        }
+
+        let json = self
+            .config
+            .json_lines
+            .and_then(|mode| json_lines::format_line(line, mode));
+        let formatted = match self.config.stack_trace {
+            Some(_) if json.is_none() => Some(trace::format_line(line)),
+            _ => json,
+        };
+        match self.config.accessible_markers {
+            Some(_) => formatted.map(accessibility::mark_levels),
+            None => formatted,
+        }
+    }
+
+    /// Whether the grid border of a line marks it as highlighted (see
+    /// `Config::accessible_markers`)
+    pub(crate) fn marks_highlighted_line(&self, line_number: usize) -> bool {
+        self.config.accessible_markers.is_some()
+            && self.config.highlighted_lines.0.check(line_number) == RangeCheckResult::InRange
+    }
+
+    /// Print the source lines that a frame of a stack trace refers to (with `context` lines
+    /// before and after them) beneath the frame. Nothing is printed if the file doesn't exist.
+    fn print_frame_source(
+        &self,
         handle: &mut dyn Write,
         line_number: usize,
-        line_buffer: &[u8],
+        frame: &Frame,
+        context: usize,
     ) -> Result<()> {
-        let line = if self.config.show_nonprintable {
-            replace_nonprintable(&line_buffer, self.config.tab_width)
+        let path = match frame.source_file() {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let content = match fs::read(&path) {
+            Ok(content) => String::from_utf8_lossy(&content).into_owned(),
+            Err(_) => return Ok(()),
+        };
+        let syntax = self
+            .syntax_set
+            .find_syntax_for_file(&path)
+            .ok()
+            .flatten()
+            .unwrap_or_else(|| self.syntax_set.find_syntax_plain_text());
+        let mut highlighter = HighlightLines::new(syntax, self.theme);
+
+        let panel = if self.panel_width > 0 {
+            let decorations: Vec<String> = self
+                .decorations
+                .iter()
+                .map(|d| d.generate(line_number, true, self).text)
+                .collect();
+            format!("{} ", decorations.join(" "))
         } else {
-            match self.content_type {
-                Some(ContentType::BINARY) | None => {
-                    return Ok(());
+            String::new()
+        };
+        let panel_width: usize = if self.panel_width > 0 {
+            self.decorations.iter().map(|d| d.width() + 1).sum()
+        } else {
+            0
+        };
+
+        let first = frame.line.saturating_sub(context).max(1);
+        let last = frame.line + context;
+        let number_width = last.to_string().len();
+        // The source lines are indented below the frame: `  ▶ 12 │ code`
+        let code_width = self
+            .config
+            .term_width
+            .saturating_sub(panel_width + number_width + 6);
+
+        for (index, source_line) in LinesWithEndings::from(&content).enumerate().take(last) {
+            // Highlight every line, to keep track of the parser state
+            let regions = highlighter.highlight(source_line, self.syntax_set);
+            let number = index + 1;
+            if number < first {
+                continue;
+            }
+
+            let focus = number == frame.line;
+            let background = self
+                .background_color_highlight
+                .filter(|_| focus && self.config.colored_output);
+            write!(
+                handle,
+                "{}{}{}",
+                panel,
+                self.colors.line_number.paint(format!(
+                    "  {} {:>width$} ",
+                    if focus { "▶" } else { " " },
+                    number,
+                    width = number_width
+                )),
+                self.colors.grid.paint("│ ")
+            )?;
+
+            let mut cursor_total = 0;
+            let mut width = 0;
+            for &(style, region) in regions.iter() {
+                let text = self.preprocess(region, &mut cursor_total);
+                let text = text.trim_end_matches(&['\r', '\n'][..]);
+                let mut end = text.len();
+                for (position, c) in text.char_indices() {
+                    let char_width = c.width().unwrap_or(0);
+                    if width + char_width > code_width {
+                        end = position;
+                        break;
+                    }
+                    width += char_width;
                 }
-                Some(ContentType::UTF_16LE) => UTF_16LE
-                    .decode(&line_buffer, DecoderTrap::Replace)
-                    .map_err(|_| "Invalid UTF-16LE")?,
-                Some(ContentType::UTF_16BE) => UTF_16BE
-                    .decode(&line_buffer, DecoderTrap::Replace)
-                    .map_err(|_| "Invalid UTF-16BE")?,
-                _ => String::from_utf8_lossy(&line_buffer).to_string(),
+
+                write!(
+                    handle,
+                    "{}",
+                    as_terminal_escaped(
+                        style,
+                        &text[..end],
+                        self.config.true_color,
+                        self.config.colored_output,
+                        self.config.use_italic_text,
+                        background
+                    )
+                )?;
+                if end < text.len() {
+                    write!(handle, "{}", CHOP_MARKER)?;
+                    width = code_width + CHOP_MARKER_WIDTH;
+                    break;
+                }
+            }
+
+            if let Some(background) = background {
+                let ansi_style = Style {
+                    background: Some(to_ansi_color(background, self.config.true_color)),
+                    ..Style::default()
+                };
+                let padding = code_width.saturating_sub(width);
+                write!(handle, "{}", ansi_style.paint(" ".repeat(padding)))?;
             }
+            writeln!(handle)?;
+        }
+
+        Ok(())
+    }
+
+    /// Print a line of a unified diff that is rendered structurally (see
+    /// `Config::render_diffs`). Removed and added lines are printed together with the rest of
+    /// their block of changes, such that changed words can be emphasized.
+    fn print_patch_line(
+        &mut self,
+        handle: &mut dyn Write,
+        patch: &mut PatchState<'a>,
+        out_of_range: bool,
+        line_number: usize,
+        line: &str,
+    ) -> Result<()> {
+        let kind = patch.parser.classify(line);
+        let pending = PendingLine {
+            out_of_range,
+            line_number,
+            kind,
+            text: line.to_owned(),
         };
 
-        let regions = {
-            let highlighter = match self.highlighter {
-                Some(ref mut highlighter) => highlighter,
-                _ => {
-                    return Ok(());
-                }
-            };
-            highlighter.highlight(line.as_ref(), self.syntax_set)
+        match kind {
+            PatchLine::Removed | PatchLine::Added => {
+                patch.pending.push(pending);
+                Ok(())
+            }
+            _ => {
+                self.flush_patch(handle, patch)?;
+                self.print_patch_lines(handle, patch, &[pending])
+            }
+        }
+    }
+
+    /// Print the pending block of removed and added lines of a diff (if any) before anything
+    /// else is printed
+    fn flush_pending_patch_lines(&mut self, handle: &mut dyn Write) -> Result<()> {
+        if let Some(mut patch) = self.patch.take() {
+            let result = self.flush_patch(handle, &mut patch);
+            self.patch = Some(patch);
+            result?;
+        }
+        Ok(())
+    }
+
+    /// Print the pending block of removed and added lines of a diff
+    fn flush_patch(&mut self, handle: &mut dyn Write, patch: &mut PatchState<'a>) -> Result<()> {
+        let pending = mem::take(&mut patch.pending);
+        self.print_patch_lines(handle, patch, &pending)
+    }
+
+    /// Print lines of a diff. If a block of changes has as many removed as added lines, each
+    /// removed line is compared with the corresponding added line word by word.
+    fn print_patch_lines(
+        &mut self,
+        handle: &mut dyn Write,
+        patch: &mut PatchState<'a>,
+        lines: &[PendingLine],
+    ) -> Result<()> {
+        let of_kind = |kind: PatchLine| -> Vec<usize> {
+            (0..lines.len())
+                .filter(|&index| lines[index].kind == kind)
+                .collect()
         };
+        let removed = of_kind(PatchLine::Removed);
+        let added = of_kind(PatchLine::Added);
+
+        let mut emphasized = vec![vec![]; lines.len()];
+        if removed.len() == added.len() {
+            for (&old, &new) in removed.iter().zip(added.iter()) {
+                let (old_changes, new_changes) =
+                    patch::word_diff(lines[old].code(), lines[new].code());
+                emphasized[old] = old_changes;
+                emphasized[new] = new_changes;
+            }
+        }
 
-        if out_of_range {
+        for (line, emphasized) in lines.iter().zip(emphasized.iter()) {
+            self.print_patch_line_regions(handle, patch, line, emphasized)?;
+            patch.previous = Some(line.kind);
+        }
+
+        Ok(())
+    }
+
+    fn print_patch_line_regions(
+        &mut self,
+        handle: &mut dyn Write,
+        patch: &mut PatchState<'a>,
+        pending: &PendingLine,
+        emphasized: &[Range<usize>],
+    ) -> Result<()> {
+        let line = pending.text.as_str();
+        let code = pending.code();
+
+        match pending.kind {
+            PatchLine::OldPath => patch.old_path = patch::header_path(line).map(String::from),
+            PatchLine::NewPath => {
+                let path = patch::header_path(line).or(patch.old_path.as_deref());
+                let syntax = path
+                    .and_then(|path| self.syntax_set.find_syntax_for_file(path).ok().flatten())
+                    .unwrap_or_else(|| self.syntax_set.find_syntax_plain_text());
+                patch.set_syntax(syntax, self.theme);
+            }
+            _ => {}
+        }
+
+        // Every line of the code is highlighted, to keep track of the parser states
+        let code_regions = match pending.kind {
+            PatchLine::Removed => patch.old.highlight(code, self.syntax_set),
+            PatchLine::Added => patch.new.highlight(code, self.syntax_set),
+            PatchLine::Context => {
+                patch.old.highlight(code, self.syntax_set);
+                patch.new.highlight(code, self.syntax_set)
+            }
+            _ => vec![],
+        };
+
+        if pending.out_of_range {
             return Ok(());
         }
 
+        // Separate commits, files and hunks (but not at the start of the printed range)
+        if self.printed_range.is_some() {
+            match pending.kind {
+                PatchLine::CommitStart | PatchLine::FileStart => {
+                    self.print_horizontal_line(handle, '─')?
+                }
+                PatchLine::OldPath
+                    if patch.previous != Some(PatchLine::FileStart)
+                        && patch.previous != Some(PatchLine::Meta) =>
+                {
+                    self.print_horizontal_line(handle, '─')?
+                }
+                PatchLine::Hunk if patch.previous != Some(PatchLine::NewPath) => {
+                    self.print_section_separator(handle)?
+                }
+                _ => {}
+            }
+        }
+
+        let styles = match self.token_styles {
+            Some(ref styles) => styles,
+            None => return Ok(()),
+        };
+        let style = |token: Token| highlighting::Style {
+            background: self.default_background,
+            ..styles.get(token)
+        };
+
+        let (marker, emphasis) = match pending.kind {
+            PatchLine::Removed => (Token::Level(LogLevel::Error), REMOVED_EMPHASIS),
+            _ => (Token::Level(LogLevel::Info), ADDED_EMPHASIS),
+        };
+        let bold = |mut style: highlighting::Style| {
+            style.font_style |= FontStyle::BOLD;
+            style
+        };
+        let regions = match pending.kind {
+            PatchLine::CommitStart => {
+                let (hash, references) = patch::commit_parts(line);
+                vec![
+                    (style(Token::Keyword), &line[..hash.start]),
+                    (bold(style(Token::Level(LogLevel::Warning))), &line[hash]),
+                    (style(Token::Function), &line[references]),
+                ]
+            }
+            PatchLine::CommitHeader => {
+                let key = line.find(':').map_or(0, |colon| colon + 1);
+                vec![
+                    (style(Token::Key), &line[..key]),
+                    (style(Token::Punctuation), &line[key..]),
+                ]
+            }
+            PatchLine::CommitSubject => vec![(bold(style(Token::Punctuation)), line)],
+            PatchLine::CommitMessage if patch::is_trailer(line) => {
+                vec![(style(Token::Comment), line)]
+            }
+            PatchLine::CommitMessage => vec![(style(Token::Punctuation), line)],
+            PatchLine::FileStart => vec![(bold(style(Token::Punctuation)), line)],
+            PatchLine::Meta => vec![(style(Token::Comment), line)],
+            PatchLine::OldPath => vec![(style(Token::Level(LogLevel::Error)), line)],
+            PatchLine::NewPath => vec![(style(Token::Level(LogLevel::Info)), line)],
+            PatchLine::Hunk => {
+                let end = patch::hunk_header(line).map_or(line.len(), |(_, _, end)| end);
+                vec![
+                    (style(Token::Keyword), &line[..end]),
+                    (style(Token::Function), &line[end..]),
+                ]
+            }
+            PatchLine::Removed | PatchLine::Added | PatchLine::Context => {
+                let mut regions = code_regions;
+                for (region_style, _) in regions.iter_mut() {
+                    region_style.background = self.default_background;
+                }
+                if !emphasized.is_empty() && self.config.colored_output {
+                    regions = restyle_ranges(&regions, emphasized, |mut style| {
+                        style.background = emphasis;
+                        style
+                    });
+                }
+
+                let marker = if pending.kind == PatchLine::Context {
+                    style(Token::Punctuation)
+                } else {
+                    style(marker)
+                };
+                regions.insert(0, (marker, &line[..line.len() - code.len()]));
+                regions
+            }
+            PatchLine::Other => vec![(style(Token::Punctuation), line)],
+        };
+
+        let background = match pending.kind {
+            PatchLine::Removed if self.config.colored_output => Some(REMOVED_BACKGROUND),
+            PatchLine::Added if self.config.colored_output => Some(ADDED_BACKGROUND),
+            _ => self.line_background(pending.line_number, line),
+        };
+
+        self.write_line(handle, pending.line_number, line, regions, background)
+    }
+
+    /// Write a line, given by its styled regions, with its decorations (wrapping or chopping it
+    /// if necessary)
+    fn write_line(
+        &mut self,
+        handle: &mut dyn Write,
+        line_number: usize,
+        line: &str,
+        regions: Vec<(highlighting::Style, &str)>,
+        background_color: Option<Color>,
+    ) -> Result<()> {
+        let processed: Vec<StyledSegment>;
+        let processed_line: String;
+        let (line, regions) = if self.config.line_processors.is_empty() {
+            (line, regions)
+        } else {
+            processed = self.config.line_processors.process(line_number, &regions);
+            processed_line = processed.iter().map(|(_, text)| text.as_str()).collect();
+            let regions = processed
+                .iter()
+                .map(|(style, text)| (*style, text.as_str()))
+                .collect();
+            (processed_line.as_str(), regions)
+        };
+
+        let elided_ranges = self.elided_ranges(line);
+        let links = if self.config.linkify && elided_ranges.is_empty() {
+            links::find_links(line, self.config.linkify_paths)
+        } else {
+            vec![]
+        };
+        let markers: Vec<String> = elided_ranges.iter().map(long_tokens::marker).collect();
+        let regions = if !links.is_empty() {
+            let ranges: Vec<_> = links.iter().map(|link| link.range.clone()).collect();
+            restyle_ranges(&regions, &ranges, |style| style)
+        } else if elided_ranges.is_empty() {
+            regions
+        } else {
+            long_tokens::elide_regions(
+                &regions,
+                &elided_ranges,
+                &markers,
+                self.elision_marker_style,
+            )
+        };
+
+        self.printed_range = match self.printed_range {
+            Some((first, _)) => Some((first, line_number)),
+            None => Some((line_number, line_number)),
+        };
+
+        // The target of the link that each region is part of
+        let mut offset = 0;
+        let region_links: Vec<Option<&str>> = regions
+            .iter()
+            .map(|(_, region)| {
+                let start = offset;
+                offset += region.len();
+                links
+                    .iter()
+                    .find(|link| link.range.start <= start && start < link.range.end)
+                    .map(|link| link.target.as_str())
+            })
+            .collect();
+        let link_target = |index: usize| region_links.get(index).cloned().flatten();
+
         let mut cursor: usize = 0;
         let mut cursor_max: usize = self.config.term_width;
         let mut cursor_total: usize = 0;
         let mut panel_wrap: Option<String> = None;
 
-        // Line highlighting
-        let highlight_this_line =
-            self.config.highlighted_lines.0.check(line_number) == RangeCheckResult::InRange;
-
-        let background_color = self
-            .background_color_highlight
-            .filter(|_| highlight_this_line);
-
         // Line decorations.
         if self.panel_width > 0 {
             let decorations = self
@@ -385,19 +1243,27 @@ impl<'a> Printer for InteractivePrinter<'a> {
             let colored_output = self.config.colored_output;
             let italics = self.config.use_italic_text;
 
-            for &(style, region) in regions.iter() {
-                let text = &*self.preprocess(region, &mut cursor_total);
+            for (index, &(style, region)) in regions.iter().enumerate() {
+                let region_background = self.region_background(style, background_color);
+                let text = &*if self.config.ansi_passthrough {
+                    self.preprocess_escaped(region, &mut cursor_total)
+                } else {
+                    self.preprocess(region, &mut cursor_total)
+                };
                 let text_trimmed = text.trim_end_matches(|c| c == '\r' || c == '\n');
                 write!(
                     handle,
                     "{}",
-                    as_terminal_escaped(
-                        style,
-                        text_trimmed,
-                        true_color,
-                        colored_output,
-                        italics,
-                        background_color
+                    hyperlink(
+                        link_target(index),
+                        as_terminal_escaped(
+                            style,
+                            text_trimmed,
+                            true_color,
+                            colored_output,
+                            italics,
+                            region_background
+                        )
                     )
                 )?;
 
@@ -420,7 +1286,56 @@ impl<'a> Printer for InteractivePrinter<'a> {
                 writeln!(handle)?;
             }
         } else {
-            for &(style, region) in regions.iter() {
+            // In chop mode, the continuation marker takes up the last column of long lines.
+            let chop_width = if self.config.wrapping_mode == WrappingMode::Chop
+                && measure_text_width(
+                    &self.preprocess(&long_tokens::elide_line(line, &elided_ranges), &mut 0),
+                ) > cursor_max
+            {
+                Some(cursor_max.saturating_sub(CHOP_MARKER_WIDTH))
+            } else {
+                None
+            };
+            if chop_width.is_some() {
+                self.warnings
+                    .push(Warning::at_line(WarningKind::LineTruncated, line_number));
+            }
+            let mut chopped = false;
+
+            // In word mode, the rows of the line are planned in advance: the positions (in
+            // characters) where new rows start, and the indentation of the continuation rows.
+            let word_wrap = match self.config.wrapping_mode {
+                WrappingMode::Word { break_chars } => {
+                    let mut text = String::new();
+                    let mut cursor_total = 0;
+                    for (_, region) in &regions {
+                        for (chunk, is_escape) in AnsiCodeIterator::new(region) {
+                            if !is_escape {
+                                text.push_str(&self.preprocess(
+                                    chunk.trim_end_matches(&['\r', '\n'][..]),
+                                    &mut cursor_total,
+                                ));
+                            }
+                        }
+                    }
+                    let indent = wrapping::continuation_indent(&text, cursor_max);
+                    let breaks = wrapping::word_breaks(&text, cursor_max, break_chars, indent);
+                    Some((breaks, indent))
+                }
+                _ => None,
+            };
+            let mut char_index = 0;
+            let mut next_break = 0;
+
+            // line buffer (avoid calling write! for every character)
+            let mut line_buf = String::with_capacity(cursor_max * 4);
+
+            for (index, &(style, region)) in regions.iter().enumerate() {
+                let region_background = self.region_background(style, background_color);
+                if chopped {
+                    break;
+                }
+
                 let ansi_iterator = AnsiCodeIterator::new(region);
                 let mut ansi_prefix: String = String::new();
                 for chunk in ansi_iterator {
@@ -458,64 +1373,113 @@ impl<'a> Printer for InteractivePrinter<'a> {
 
                             let mut max_width = cursor_max - cursor;
 
-                            // line buffer (avoid calling write! for every character)
-                            let mut line_buf = String::with_capacity(max_width * 4);
+                            line_buf.clear();
 
                             // Displayed width of line_buf
                             let mut current_width = 0;
 
-                            for c in text.chars() {
-                                // calculate the displayed width for next character
-                                let cw = c.width().unwrap_or(0);
-                                current_width += cw;
-
-                                // if next character cannot be printed on this line,
-                                // flush the buffer.
-                                if current_width > max_width {
-                                    // Generate wrap padding if not already generated.
-                                    if panel_wrap.is_none() {
-                                        panel_wrap = if self.panel_width > 0 {
-                                            Some(format!(
-                                                "{} ",
-                                                self.decorations
-                                                    .iter()
-                                                    .map(|ref d| d
-                                                        .generate(line_number, true, self)
-                                                        .text)
-                                                    .collect::<Vec<String>>()
-                                                    .join(" ")
-                                            ))
-                                        } else {
-                                            Some("".to_string())
+                            // Fast path: the whole chunk fits into the current row
+                            let text_width = text.width();
+                            let text_chars = text.chars().count();
+                            let fits = match word_wrap {
+                                Some((ref breaks, _)) => !breaks
+                                    .get(next_break)
+                                    .map_or(false, |&b| b < char_index + text_chars),
+                                None => {
+                                    text_width <= max_width
+                                        && !chop_width.map_or(false, |w| cursor + text_width > w)
+                                }
+                            };
+
+                            if fits {
+                                line_buf.push_str(&text);
+                                current_width = text_width;
+                                char_index += text_chars;
+                            } else {
+                                for c in text.chars() {
+                                    // calculate the displayed width for next character
+                                    let cw = c.width().unwrap_or(0);
+                                    current_width += cw;
+
+                                    let wraps = match word_wrap {
+                                        Some((ref breaks, _)) => {
+                                            breaks.get(next_break) == Some(&char_index)
+                                        }
+                                        None => current_width > max_width,
+                                    };
+                                    char_index += 1;
+
+                                    // if next character cannot be printed on this line, either
+                                    // cut the line off or flush the buffer.
+                                    if chop_width.map_or(false, |w| cursor + current_width > w) {
+                                        line_buf.push_str(CHOP_MARKER);
+                                        current_width = current_width - cw + CHOP_MARKER_WIDTH;
+                                        chopped = true;
+                                        break;
+                                    } else if wraps {
+                                        // Generate wrap padding if not already generated.
+                                        if panel_wrap.is_none() {
+                                            panel_wrap = if self.panel_width > 0 {
+                                                Some(format!(
+                                                    "{} ",
+                                                    self.decorations
+                                                        .iter()
+                                                        .map(|ref d| d
+                                                            .generate(line_number, true, self)
+                                                            .text)
+                                                        .collect::<Vec<String>>()
+                                                        .join(" ")
+                                                ))
+                                            } else {
+                                                Some("".to_string())
+                                            }
                                         }
-                                    }
 
-                                    // It wraps.
-                                    write!(
-                                        handle,
-                                        "{}\n{}",
-                                        as_terminal_escaped(
-                                            style,
-                                            &*format!(
-                                                "{}{}{}",
-                                                self.ansi_prefix_sgr, ansi_prefix, line_buf
+                                        let indent = match word_wrap {
+                                            Some((_, indent)) => indent,
+                                            None => 0,
+                                        };
+
+                                        // It wraps.
+                                        write!(
+                                            handle,
+                                            "{}\n{}{}",
+                                            hyperlink(
+                                                link_target(index),
+                                                as_terminal_escaped(
+                                                    style,
+                                                    &*format!(
+                                                        "{}{}{}",
+                                                        self.ansi_prefix_sgr, ansi_prefix, line_buf
+                                                    ),
+                                                    self.config.true_color,
+                                                    self.config.colored_output,
+                                                    self.config.use_italic_text,
+                                                    region_background
+                                                )
                                             ),
-                                            self.config.true_color,
-                                            self.config.colored_output,
-                                            self.config.use_italic_text,
-                                            background_color
-                                        ),
-                                        panel_wrap.clone().unwrap()
-                                    )?;
-
-                                    cursor = 0;
-                                    max_width = cursor_max;
+                                            panel_wrap.clone().unwrap(),
+                                            " ".repeat(indent)
+                                        )?;
+
+                                        cursor = indent;
+                                        max_width = cursor_max - indent;
+
+                                        line_buf.clear();
+                                        current_width = cw;
+
+                                        // Word-wrapped rows do not start with whitespace
+                                        if word_wrap.is_some() {
+                                            next_break += 1;
+                                            if c.is_whitespace() {
+                                                current_width = 0;
+                                                continue;
+                                            }
+                                        }
+                                    }
 
-                                    line_buf.clear();
-                                    current_width = cw;
+                                    line_buf.push(c);
                                 }
-
-                                line_buf.push(c);
                             }
 
                             // flush the buffer
@@ -523,21 +1487,28 @@ impl<'a> Printer for InteractivePrinter<'a> {
                             write!(
                                 handle,
                                 "{}",
-                                as_terminal_escaped(
-                                    style,
-                                    &*format!(
-                                        "{}{}{}",
-                                        self.ansi_prefix_sgr, ansi_prefix, line_buf
-                                    ),
-                                    self.config.true_color,
-                                    self.config.colored_output,
-                                    self.config.use_italic_text,
-                                    background_color
+                                hyperlink(
+                                    link_target(index),
+                                    as_terminal_escaped(
+                                        style,
+                                        &*format!(
+                                            "{}{}{}",
+                                            self.ansi_prefix_sgr, ansi_prefix, line_buf
+                                        ),
+                                        self.config.true_color,
+                                        self.config.colored_output,
+                                        self.config.use_italic_text,
+                                        region_background
+                                    )
                                 )
                             )?;
 
                             // Clear the ANSI prefix buffer.
                             ansi_prefix.clear();
+
+                            if chopped {
+                                break;
+                            }
                         }
                     }
                 }
@@ -559,9 +1530,368 @@ impl<'a> Printer for InteractivePrinter<'a> {
 
         Ok(())
     }
+
+    /// Preprocess the text between the escape sequences of a region, which are kept as they
+    /// are (see `Config::ansi_passthrough`)
+    fn preprocess_escaped<'t>(&self, text: &'t str, cursor: &mut usize) -> Cow<'t, str> {
+        AnsiCodeIterator::new(text)
+            .map(|(chunk, is_escape)| {
+                if is_escape {
+                    Cow::Borrowed(chunk)
+                } else {
+                    self.preprocess(chunk, cursor)
+                }
+            })
+            .collect::<String>()
+            .into()
+    }
+
+    fn preprocess<'t>(&self, text: &'t str, cursor: &mut usize) -> Cow<'t, str> {
+        if self.config.tab_width > 0 {
+            expand_tabs(text, self.config.tab_width, cursor)
+        } else {
+            *cursor += text.len();
+            Cow::Borrowed(text)
+        }
+    }
 }
 
-const DEFAULT_GUTTER_COLOR: u8 = 238;
+impl<'a> Printer for InteractivePrinter<'a> {
+    fn print_header(&mut self, handle: &mut dyn Write, input: &OpenedInput) -> Result<()> {
+        if !self.config.style_components.header() {
+            if Some(ContentType::BINARY) == self.content_type && !self.shows_content() {
+                writeln!(
+                    handle,
+                    "{}: Binary content from {} will not be printed to the terminal \
+                     (but will be present if the output of 'bat' is piped). You can use 'bat -A' \
+                     to show the binary file contents.",
+                    Yellow.paint("[bat warning]"),
+                    input.description().full,
+                )?;
+            } else if self.config.style_components.grid() {
+                self.print_horizontal_line(handle, '┬')?;
+            }
+            return Ok(());
+        }
+
+        if self.config.style_components.grid() {
+            self.print_horizontal_line(handle, '┬')?;
+
+            write!(
+                handle,
+                "{}{}",
+                " ".repeat(self.panel_width),
+                self.colors
+                    .grid
+                    .paint(if self.panel_width > 0 { "│ " } else { "" }),
+            )?;
+        } else {
+            write!(handle, "{}", " ".repeat(self.panel_width))?;
+        }
+
+        if let Some(ref template) = self.config.header_template {
+            let header = self.render_template(template, input);
+            writeln!(
+                handle,
+                "{}",
+                template::truncate(&header, self.config.term_width - self.text_offset())
+            )?;
+        } else {
+            let description = input.description();
+            let components = match self.config.header_components.as_slice() {
+                [] => &[HeaderComponent::Filename],
+                components => components,
+            };
+
+            let mut fields: Vec<String> = components
+                .iter()
+                .map(|component| match component {
+                    HeaderComponent::Filename => format!(
+                        "{}{}{}",
+                        description.prefix,
+                        self.colors.filename.paint(&description.name),
+                        input
+                            .metadata
+                            .revision
+                            .as_ref()
+                            .map_or(String::new(), |revision| format!(" @ {}", revision)),
+                    ),
+                    component => self.field_value(component.field(), input),
+                })
+                .filter(|field| !field.is_empty())
+                .collect();
+            let mode = self.mode();
+            if !mode.is_empty() {
+                fields.push(mode.to_owned());
+            }
+
+            writeln!(handle, "{}", fields.join("   "))?;
+        }
+
+        if self.config.style_components.grid() {
+            if self.shows_content() {
+                self.print_horizontal_line(handle, '┼')?;
+            } else {
+                self.print_horizontal_line(handle, '┴')?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn print_footer(&mut self, handle: &mut dyn Write, input: &OpenedInput) -> Result<()> {
+        self.flush_pending_patch_lines(handle)?;
+
+        if self.config.style_components.grid() && self.shows_content() {
+            self.print_horizontal_line(handle, '┴')?;
+        }
+
+        if let Some(ref template) = self.config.footer_template {
+            let offset = self.text_offset();
+            let footer = self.render_template(template, input);
+            writeln!(
+                handle,
+                "{}{}",
+                " ".repeat(offset),
+                template::truncate(&footer, self.config.term_width - offset)
+            )?;
+        }
+
+        if self.show_status_line {
+            self.print_status_line(handle, input)?;
+        }
+
+        Ok(())
+    }
+
+    fn print_snip(&mut self, handle: &mut dyn Write) -> Result<()> {
+        self.print_snip_with_title(handle, "8<")
+    }
+
+    fn print_omitted(&mut self, handle: &mut dyn Write, lines: usize) -> Result<()> {
+        let title = format!(
+            "8< {} line{} omitted",
+            lines,
+            if lines == 1 { "" } else { "s" }
+        );
+        self.print_snip_with_title(handle, &title)
+    }
+
+    fn language(&self) -> Option<&str> {
+        self.language
+    }
+
+    fn take_warnings(&mut self) -> Vec<Warning> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    fn print_line(
+        &mut self,
+        out_of_range: bool,
+        handle: &mut dyn Write,
+        line_number: usize,
+        line_buffer: &[u8],
+    ) -> Result<()> {
+        self.lines_seen = line_number;
+        let byte_offset = self.bytes_seen;
+        self.bytes_seen += line_buffer.len();
+
+        if let Some(style) = self.hexdump_style {
+            if out_of_range {
+                return Ok(());
+            }
+            let offset = if self.config.style_components.numbers() {
+                self.gutter_value =
+                    Some(format!("{:0w$x}", byte_offset, w = hexdump::OFFSET_WIDTH));
+                None
+            } else {
+                Some(byte_offset)
+            };
+            let row = HexRow::new(line_buffer, offset);
+            let background_color = self.line_background(line_number, &row.text);
+            return self.write_line(
+                handle,
+                line_number,
+                &row.text,
+                row.regions(style),
+                background_color,
+            );
+        }
+
+        let (line, replaced_invalid_utf8) = match self.decode_line(line_buffer)? {
+            Some(decoded) => decoded,
+            None => return Ok(()),
+        };
+        if !out_of_range && replaced_invalid_utf8 {
+            self.warnings
+                .push(Warning::at_line(WarningKind::InvalidUtf8, line_number));
+        }
+
+        if let Some(width) = self.gutter_width {
+            if !out_of_range {
+                self.gutter_value = Some(self.config.gutter_source.value(
+                    line_number,
+                    byte_offset,
+                    &line,
+                    width,
+                ));
+            }
+        }
+
+        if let Some(mut patch) = self.patch.take() {
+            let result =
+                self.print_patch_line(handle, &mut patch, out_of_range, line_number, &line);
+            self.patch = Some(patch);
+            return result;
+        }
+
+        let regions = {
+            let highlighter = match self.highlighter {
+                Some(ref mut highlighter) => highlighter,
+                _ => {
+                    return Ok(());
+                }
+            };
+            highlighter.highlight(line.as_ref(), self.syntax_set)
+        };
+        let embedded = self.highlight_embedded(&line);
+        let mut regions = if embedded.is_empty() {
+            regions
+        } else {
+            replace_ranges(&regions, &embedded)
+        };
+        if let Some(ref mut blocks) = self.markdown_blocks {
+            regions = blocks.regions(line.as_ref(), regions);
+        }
+
+        // Only highlighted spans have a background color of their own
+        for (style, _) in regions.iter_mut() {
+            style.background = self.default_background;
+        }
+
+        let regions = match self.config.highlighted_spans.get(&line_number) {
+            Some(spans) => {
+                let (foreground, background) = self.span_colors;
+                restyle_ranges(&regions, spans, |mut style| {
+                    style.foreground = foreground.unwrap_or(style.foreground);
+                    style.background = background;
+                    style
+                })
+            }
+            None => regions,
+        };
+
+        let starts_section = self.starts_section(&line);
+
+        if out_of_range {
+            return Ok(());
+        }
+
+        // Lines with JSON objects and stack traces are formatted on their own, independent of
+        // the syntax
+        let formatted = self.format_line(&line);
+        let line: &str = match formatted {
+            Some(ref formatted) => &formatted.text,
+            None => &line,
+        };
+        let regions = match (&formatted, &self.token_styles) {
+            (Some(formatted), Some(styles)) => formatted
+                .tokens
+                .iter()
+                .map(|(range, token)| {
+                    let mut style = styles.get(*token);
+                    style.background = self.default_background;
+                    (style, &formatted.text[range.clone()])
+                })
+                .collect(),
+            _ => regions,
+        };
+
+        let regions = if self.config.highlight_patterns.is_empty() {
+            regions
+        } else {
+            self.highlight_patterns(regions, line)
+        };
+
+        // Separate top-level declarations from the preceding code (but not at the start of
+        // the printed range)
+        if starts_section && self.printed_range.is_some() {
+            self.print_section_separator(handle)?;
+        }
+
+        let background_color = self.line_background(line_number, line);
+
+        self.write_line(handle, line_number, line, regions, background_color)?;
+
+        if let Some(StackTraceMode {
+            source_context: Some(context),
+        }) = self.config.stack_trace
+        {
+            if let Some(frame) = trace::parse_frame(line) {
+                self.print_frame_source(handle, line_number, &frame, context)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub(crate) const DEFAULT_GUTTER_COLOR: u8 = 238;
+
+/// Yellow in the encoding for terminal palette colors (see `to_ansi_color`), for highlighted
+/// spans if the theme does not define a color for search results
+const ANSI_YELLOW: Color = Color {
+    r: 0x03,
+    g: 0x00,
+    b: 0x00,
+    a: 0x00,
+};
+
+/// Backgrounds of removed and added lines of diffs (see `Config::render_diffs`), and of the
+/// words that were changed within them
+pub(crate) const REMOVED_BACKGROUND: Color = Color {
+    r: 0x5f,
+    g: 0x00,
+    b: 0x00,
+    a: 0xff,
+};
+pub(crate) const REMOVED_EMPHASIS: Color = Color {
+    r: 0x87,
+    g: 0x00,
+    b: 0x00,
+    a: 0xff,
+};
+pub(crate) const ADDED_BACKGROUND: Color = Color {
+    r: 0x00,
+    g: 0x5f,
+    b: 0x00,
+    a: 0xff,
+};
+pub(crate) const ADDED_EMPHASIS: Color = Color {
+    r: 0x00,
+    g: 0x87,
+    b: 0x00,
+    a: 0xff,
+};
+
+/// Scopes of top-level declarations that start a new section (see `StyleComponent::Sections`)
+pub(crate) const SECTION_SCOPES: &[&str] = &[
+    "entity.name.function",
+    "entity.name.class",
+    "entity.name.struct",
+    "entity.name.enum",
+    "entity.name.trait",
+    "entity.name.impl",
+    "entity.name.interface",
+    "entity.name.type",
+    "entity.name.module",
+    "entity.name.namespace",
+    "markup.heading",
+];
+
+/// Marks the position where a line was cut off in `WrappingMode::Chop`
+const CHOP_MARKER: &str = "…";
+const CHOP_MARKER_WIDTH: usize = 1;
 
 #[derive(Debug, Default)]
 pub struct Colors {
@@ -571,14 +1901,19 @@ pub struct Colors {
     pub git_removed: Style,
     pub git_modified: Style,
     pub line_number: Style,
+    pub status_line: Style,
 }
 
 impl Colors {
-    fn plain() -> Self {
+    pub(crate) fn plain() -> Self {
         Colors::default()
     }
 
-    fn colored(theme: &Theme, true_color: bool) -> Self {
+    pub(crate) fn colored(
+        theme: &Theme,
+        true_color: bool,
+        palette: Option<&MarkerPalette>,
+    ) -> Self {
         let gutter_color = theme
             .settings
             .gutter_foreground
@@ -588,10 +1923,28 @@ impl Colors {
         Colors {
             grid: gutter_color.normal(),
             filename: Style::new().bold(),
-            git_added: Green.normal(),
-            git_removed: Red.normal(),
-            git_modified: Yellow.normal(),
+            git_added: palette
+                .map_or(Green, |p| to_ansi_color(p.added, true_color))
+                .normal(),
+            git_removed: palette
+                .map_or(Red, |p| to_ansi_color(p.removed, true_color))
+                .normal(),
+            git_modified: palette
+                .map_or(Yellow, |p| to_ansi_color(p.modified, true_color))
+                .normal(),
             line_number: gutter_color.normal(),
+            status_line: Style {
+                background: theme
+                    .settings
+                    .line_highlight
+                    .map(|c| to_ansi_color(c, true_color)),
+                ..theme
+                    .settings
+                    .foreground
+                    .map(|c| to_ansi_color(c, true_color))
+                    .unwrap_or(gutter_color)
+                    .normal()
+            },
         }
     }
 }
@@ -0,0 +1,289 @@
+//! Per-project configuration: a `.bat.toml` file in the directory of a file (or in one of the
+//! parent directories) with the theme, the tab width and syntax mappings for the files of a
+//! project. The settings are merged beneath the user's configuration, which takes precedence.
+//!
+//! ```toml
+//! theme = "GitHub"
+//! tab-width = 2
+//!
+//! [mappings]
+//! "*.conf" = "INI"
+//! "Jenkinsfile" = "Groovy"
+//! ```
+//!
+//! Only this subset of TOML is supported: strings, integers, comments and the `mappings`
+//! table.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::Config;
+use crate::config_layers::{ConfigLayer, Origin};
+use crate::error::*;
+use crate::syntax_mapping::MappingTarget;
+
+/// The name of the per-project configuration file
+pub const PROJECT_CONFIG_FILE_NAME: &str = ".bat.toml";
+
+/// The settings of a project configuration file
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProjectSettings {
+    pub theme: Option<String>,
+    pub tab_width: Option<usize>,
+
+    /// Glob patterns and the names of the syntaxes they are mapped to, in the order of their
+    /// definition (later mappings take precedence)
+    pub mappings: Vec<(String, String)>,
+}
+
+impl ProjectSettings {
+    /// Parse the contents of a `.bat.toml` file
+    pub fn parse(content: &str) -> Result<Self> {
+        let mut settings = ProjectSettings::default();
+        let mut in_mappings = false;
+
+        for (index, line) in content.lines().enumerate() {
+            let invalid =
+                |reason: &str| -> Error { format!("line {}: {}", index + 1, reason).into() };
+
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if line.starts_with('[') {
+                let end = line.find(']').ok_or_else(|| invalid("expected ']'"))?;
+                if line[1..end].trim() != "mappings" || !end_of_line(&line[end + 1..]) {
+                    return Err(invalid("unknown table (only [mappings] is supported)"));
+                }
+                in_mappings = true;
+                continue;
+            }
+
+            let (key, rest) = parse_key(line).ok_or_else(|| invalid("expected a key"))?;
+            let rest = rest.trim_start();
+            if !rest.starts_with('=') {
+                return Err(invalid("expected '=' after the key"));
+            }
+            let value = parse_value(rest[1..].trim_start())
+                .ok_or_else(|| invalid("expected a string or an integer"))?;
+
+            match (in_mappings, key.as_str(), value) {
+                (true, _, Value::String(syntax)) => settings.mappings.push((key, syntax)),
+                (false, "theme", Value::String(theme)) => settings.theme = Some(theme),
+                (false, "tab-width", Value::Integer(width)) => settings.tab_width = Some(width),
+                (true, _, _) => return Err(invalid("syntax names have to be strings")),
+                (false, "theme", _) | (false, "tab-width", _) => {
+                    return Err(invalid(&format!("invalid value for '{}'", key)))
+                }
+                (false, _, _) => {
+                    return Err(invalid(&format!(
+                        "unknown setting '{}' (use theme, tab-width or a [mappings] table)",
+                        key
+                    )))
+                }
+            }
+        }
+
+        Ok(settings)
+    }
+
+    /// Merge these settings beneath the given ones: the theme and the tab width of `upper`
+    /// take precedence if they are set, the mappings of both are kept (with the ones of
+    /// `upper` defined later, so that they win).
+    pub fn merged_beneath(&self, upper: &ProjectSettings) -> ProjectSettings {
+        ProjectSettings {
+            theme: upper.theme.clone().or_else(|| self.theme.clone()),
+            tab_width: upper.tab_width.or(self.tab_width),
+            mappings: self
+                .mappings
+                .iter()
+                .chain(upper.mappings.iter())
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Apply the settings to a configuration, unless they were set by a layer with a higher
+    /// precedence (see `Config::provenance`). The mappings are added to the syntax mapping of
+    /// the configuration.
+    pub fn apply<'a>(&'a self, config: &mut Config<'a>) -> Result<()> {
+        let layer = ConfigLayer::ProjectFile;
+        if let Some(ref theme) = self.theme {
+            if config.provenance.is_overridden_by("theme", layer) {
+                config.theme = theme.clone();
+                config.provenance.record("theme", Origin::new(layer, theme));
+            }
+        }
+        if let Some(tab_width) = self.tab_width {
+            if config.provenance.is_overridden_by("tabs", layer) {
+                config.tab_width = tab_width;
+                config
+                    .provenance
+                    .record("tabs", Origin::new(layer, tab_width));
+            }
+        }
+        for (from, to) in &self.mappings {
+            config
+                .syntax_mapping
+                .insert(from, MappingTarget::MapTo(to))?;
+        }
+        Ok(())
+    }
+}
+
+/// A project configuration file with its settings
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProjectConfig {
+    pub path: PathBuf,
+    pub settings: ProjectSettings,
+}
+
+impl ProjectConfig {
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path).chain_err(|| {
+            format!(
+                "Could not read project configuration '{}'",
+                path.to_string_lossy()
+            )
+        })?;
+        let settings = ProjectSettings::parse(&content)
+            .chain_err(|| format!("Invalid project configuration '{}'", path.to_string_lossy()))?;
+
+        Ok(ProjectConfig {
+            path: path.to_owned(),
+            settings,
+        })
+    }
+
+    /// Find the `.bat.toml` file that is closest to the given directory: in the directory
+    /// itself or in one of its parent directories
+    pub fn discover(dir: &Path) -> Result<Option<Self>> {
+        for dir in dir.ancestors() {
+            let path = dir.join(PROJECT_CONFIG_FILE_NAME);
+            if path.is_file() {
+                return Self::from_file(&path).map(Some);
+            }
+        }
+        Ok(None)
+    }
+}
+
+enum Value {
+    String(String),
+    Integer(usize),
+}
+
+/// Whether nothing but a comment follows
+fn end_of_line(rest: &str) -> bool {
+    let rest = rest.trim_start();
+    rest.is_empty() || rest.starts_with('#')
+}
+
+/// A bare or quoted key, and the rest of the line
+fn parse_key(line: &str) -> Option<(String, &str)> {
+    if line.starts_with('"') || line.starts_with('\'') {
+        return parse_string(line);
+    }
+
+    let end = line
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == '_'))
+        .unwrap_or(line.len());
+    if end == 0 {
+        None
+    } else {
+        Some((line[..end].to_owned(), &line[end..]))
+    }
+}
+
+/// A basic (`"..."`) or literal (`'...'`) string, and the rest of the line
+fn parse_string(text: &str) -> Option<(String, &str)> {
+    let quote = text.chars().next()?;
+    let mut value = String::new();
+    let mut chars = text.char_indices().skip(1);
+
+    while let Some((index, c)) = chars.next() {
+        match c {
+            c if c == quote => return Some((value, &text[index + 1..])),
+            '\\' if quote == '"' => match chars.next()?.1 {
+                'n' => value.push('\n'),
+                't' => value.push('\t'),
+                c @ '"' | c @ '\\' => value.push(c),
+                _ => return None,
+            },
+            c => value.push(c),
+        }
+    }
+    None
+}
+
+fn parse_value(text: &str) -> Option<Value> {
+    let (value, rest) = if text.starts_with('"') || text.starts_with('\'') {
+        let (string, rest) = parse_string(text)?;
+        (Value::String(string), rest)
+    } else {
+        let end = text
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(text.len());
+        (Value::Integer(text[..end].parse().ok()?), &text[end..])
+    };
+
+    if end_of_line(rest) {
+        Some(value)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_project_settings() {
+        let settings = ProjectSettings::parse(
+            "# The style of the project\n\
+             theme = \"GitHub\"  # light\n\
+             tab-width = 2\n\
+             \n\
+             [mappings]\n\
+             \"*.conf\" = 'INI'\n\
+             Jenkinsfile = \"Groovy\"\n",
+        )
+        .unwrap();
+        assert_eq!(settings.theme.as_deref(), Some("GitHub"));
+        assert_eq!(settings.tab_width, Some(2));
+        assert_eq!(
+            settings.mappings,
+            vec![
+                ("*.conf".to_owned(), "INI".to_owned()),
+                ("Jenkinsfile".to_owned(), "Groovy".to_owned())
+            ]
+        );
+
+        assert!(ProjectSettings::parse("tab-width = \"2\"").is_err());
+        assert!(ProjectSettings::parse("colour = \"always\"").is_err());
+        assert!(ProjectSettings::parse("theme = \"GitHub").is_err());
+        assert!(ProjectSettings::parse("[other]").is_err());
+    }
+
+    #[test]
+    fn user_settings_take_precedence() {
+        let project = ProjectSettings {
+            theme: Some("GitHub".into()),
+            tab_width: Some(2),
+            mappings: vec![("*.conf".into(), "INI".into())],
+        };
+        let user = ProjectSettings {
+            theme: Some("TwoDark".into()),
+            tab_width: None,
+            mappings: vec![("*.conf".into(), "Bourne Again Shell (bash)".into())],
+        };
+
+        let merged = project.merged_beneath(&user);
+        assert_eq!(merged.theme.as_deref(), Some("TwoDark"));
+        assert_eq!(merged.tab_width, Some(2));
+        assert_eq!(merged.mappings.len(), 2);
+        assert_eq!(merged.mappings[1].1, "Bourne Again Shell (bash)");
+    }
+}
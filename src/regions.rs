@@ -0,0 +1,133 @@
+//! Helpers for highlighted lines, i.e. lists of styled regions that make up a line.
+
+use std::ops::Range;
+
+use syntect::highlighting::Style;
+
+/// Styled regions that replace a byte range of a line
+pub(crate) type Replacement<'a> = (Range<usize>, Vec<(Style, &'a str)>);
+
+/// Replace the given byte ranges of a highlighted line by other styled regions. The
+/// `replacements` have to be sorted and must not overlap.
+pub(crate) fn replace_ranges<'a>(
+    regions: &[(Style, &'a str)],
+    replacements: &[Replacement<'a>],
+) -> Vec<(Style, &'a str)> {
+    let mut result = Vec::with_capacity(regions.len() + 2 * replacements.len());
+    let mut replacements = replacements.iter().peekable();
+
+    let mut offset = 0;
+    for &(style, region) in regions {
+        let region_range = offset..offset + region.len();
+        offset = region_range.end;
+
+        let mut position = region_range.start;
+        while let Some((range, replacement)) = replacements.peek() {
+            if range.start >= region_range.end {
+                break;
+            }
+
+            if range.start >= position {
+                result.push((
+                    style,
+                    &region[position - region_range.start..range.start - region_range.start],
+                ));
+                result.extend(replacement.iter().cloned());
+            }
+
+            position = range.end.max(position).min(region_range.end);
+            if range.end > region_range.end {
+                break;
+            }
+            replacements.next();
+        }
+
+        if position < region_range.end {
+            result.push((style, &region[position - region_range.start..]));
+        }
+    }
+
+    result.retain(|(_, text)| !text.is_empty());
+    result
+}
+
+/// Change the style of the given byte ranges of a highlighted line. The `ranges` have to be
+/// sorted and must not overlap.
+pub(crate) fn restyle_ranges<'a>(
+    regions: &[(Style, &'a str)],
+    ranges: &[Range<usize>],
+    restyle: impl Fn(Style) -> Style,
+) -> Vec<(Style, &'a str)> {
+    let mut result = Vec::with_capacity(regions.len() + 2 * ranges.len());
+
+    let mut offset = 0;
+    for &(style, region) in regions {
+        let region_range = offset..offset + region.len();
+        offset = region_range.end;
+
+        let mut position = region_range.start;
+        for range in ranges {
+            let start = range.start.max(position);
+            let end = range.end.min(region_range.end);
+            if start >= end {
+                continue;
+            }
+
+            let relative = |index: usize| index - region_range.start;
+            result.push((style, &region[relative(position)..relative(start)]));
+            result.push((restyle(style), &region[relative(start)..relative(end)]));
+            position = end;
+        }
+        result.push((style, &region[position - region_range.start..]));
+    }
+
+    result.retain(|(_, text)| !text.is_empty());
+    result
+}
+
+#[test]
+fn replacements_can_span_regions() {
+    let a = Style::default();
+    let mut b = Style::default();
+    b.foreground.r = 1;
+    let mut c = Style::default();
+    c.foreground.r = 2;
+
+    let regions = [(a, "01234567"), (b, "89abcdef")];
+    assert_eq!(
+        replace_ranges(&regions, &[(2..14, vec![(c, "x"), (a, "y")])]),
+        vec![(a, "01"), (c, "x"), (a, "y"), (b, "ef")]
+    );
+    assert_eq!(
+        replace_ranges(&regions, &[(0..2, vec![(c, "x")]), (9..10, vec![])]),
+        vec![(c, "x"), (a, "234567"), (b, "8"), (b, "abcdef")]
+    );
+}
+
+#[test]
+fn restyled_ranges_keep_their_text() {
+    let a = Style::default();
+    let mut b = Style::default();
+    b.foreground.r = 1;
+    let mut restyled = Style::default();
+    restyled.background.r = 9;
+    let restyle = |mut style: Style| {
+        style.background.r = 9;
+        style
+    };
+
+    let regions = [(a, "0123"), (b, "4567")];
+    let mut b_restyled = b;
+    b_restyled.background.r = 9;
+    assert_eq!(
+        restyle_ranges(&regions, &[1..2, 3..5], restyle),
+        vec![
+            (a, "0"),
+            (restyled, "1"),
+            (a, "2"),
+            (restyled, "3"),
+            (b_restyled, "4"),
+            (b, "567")
+        ]
+    );
+}
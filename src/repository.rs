@@ -0,0 +1,288 @@
+//! An index of the files of a directory (like a repository), as a tree with the sizes and the
+//! languages of the files. bat prints it before the files with `--repo`, as a lightweight code
+//! browser. Hidden files are skipped, and so are the files that git ignores (with the `git`
+//! feature).
+
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use content_inspector::ContentType;
+use unicode_width::UnicodeWidthStr;
+
+use crate::assets::HighlightingAssets;
+use crate::error::*;
+use crate::syntax_mapping::SyntaxMapping;
+use crate::template::human_size;
+
+/// The number of bytes that are read from a file to detect its language
+const DETECTION_SIZE: u64 = 1024;
+
+/// A file of the index
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexEntry {
+    /// The path relative to the root of the index
+    pub path: PathBuf,
+    pub size: u64,
+
+    /// The name of the syntax of the file, or `binary` for binary files
+    pub language: String,
+}
+
+/// The files of a directory, sorted by their paths (files before subdirectories)
+#[derive(Debug, Clone)]
+pub struct RepositoryIndex {
+    root: PathBuf,
+    entries: Vec<IndexEntry>,
+}
+
+#[cfg(feature = "git")]
+struct Ignored(Option<(git2::Repository, PathBuf)>);
+
+#[cfg(feature = "git")]
+impl Ignored {
+    fn new(root: &Path) -> Self {
+        let repository = fs::canonicalize(root).ok().and_then(|root| {
+            let repo = git2::Repository::discover(&root).ok()?;
+            let workdir = fs::canonicalize(repo.workdir()?).ok()?;
+            Some((repo, workdir))
+        });
+        Ignored(repository)
+    }
+
+    fn contains(&self, path: &Path) -> bool {
+        let (repo, workdir) = match self.0 {
+            Some(ref repository) => repository,
+            None => return false,
+        };
+        match fs::canonicalize(path) {
+            Ok(path) => match path.strip_prefix(workdir) {
+                Ok(relative) => repo.is_path_ignored(relative).unwrap_or(false),
+                Err(_) => false,
+            },
+            Err(_) => false,
+        }
+    }
+}
+
+#[cfg(not(feature = "git"))]
+struct Ignored;
+
+#[cfg(not(feature = "git"))]
+impl Ignored {
+    fn new(_root: &Path) -> Self {
+        Ignored
+    }
+
+    fn contains(&self, _path: &Path) -> bool {
+        false
+    }
+}
+
+impl RepositoryIndex {
+    /// Index the files of a directory and its subdirectories. The languages are detected like
+    /// the syntaxes of printed files (from the path and the beginning of the content).
+    pub fn scan(
+        root: impl Into<PathBuf>,
+        assets: &HighlightingAssets,
+        mapping: &SyntaxMapping,
+    ) -> Result<Self> {
+        let root = root.into();
+        if !root.is_dir() {
+            return Err(format!("'{}' is not a directory", root.to_string_lossy()).into());
+        }
+
+        let mut index = RepositoryIndex {
+            root,
+            entries: vec![],
+        };
+        let ignored = Ignored::new(&index.root);
+        index.scan_dir(Path::new(""), &ignored, assets, mapping)?;
+        Ok(index)
+    }
+
+    fn scan_dir(
+        &mut self,
+        dir: &Path,
+        ignored: &Ignored,
+        assets: &HighlightingAssets,
+        mapping: &SyntaxMapping,
+    ) -> Result<()> {
+        let path = self.root.join(dir);
+        let mut children = fs::read_dir(&path)
+            .and_then(|entries| entries.collect::<std::io::Result<Vec<_>>>())
+            .chain_err(|| format!("Could not read '{}'", path.to_string_lossy()))?;
+        children.sort_by_key(|child| (child.path().is_dir(), child.file_name()));
+
+        for child in children {
+            let name = child.file_name();
+            if name.to_string_lossy().starts_with('.') || ignored.contains(&child.path()) {
+                continue;
+            }
+
+            let relative = dir.join(&name);
+            let metadata = child.metadata()?;
+            if metadata.is_dir() {
+                self.scan_dir(&relative, ignored, assets, mapping)?;
+            } else if metadata.is_file() {
+                let language = language(&child.path(), assets, mapping)?;
+                self.entries.push(IndexEntry {
+                    path: relative,
+                    size: metadata.len(),
+                    language,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn entries(&self) -> &[IndexEntry] {
+        &self.entries
+    }
+
+    /// The paths of the files (within the root), in the order of the index
+    pub fn files(&self) -> impl Iterator<Item = PathBuf> + '_ {
+        self.entries
+            .iter()
+            .map(move |entry| self.root.join(&entry.path))
+    }
+
+    /// The index as a tree, with the sizes and the languages of the files in aligned columns
+    pub fn render(&self) -> String {
+        let mut rows = vec![];
+        let entries: Vec<&IndexEntry> = self.entries.iter().collect();
+        tree_rows(Path::new(""), &entries, "", &mut rows);
+
+        let name_width = rows.iter().map(|(name, _)| name.width()).max().unwrap_or(0);
+        let sizes: Vec<String> = rows
+            .iter()
+            .map(|(_, entry)| entry.map_or(String::new(), |entry| human_size(entry.size)))
+            .collect();
+        let size_width = sizes.iter().map(String::len).max().unwrap_or(0);
+
+        let mut rendered = format!("{}/\n", self.root.to_string_lossy().trim_end_matches('/'));
+        for ((name, entry), size) in rows.iter().zip(&sizes) {
+            match entry {
+                Some(entry) => rendered.push_str(&format!(
+                    "{}{}  {:>size_width$}  {}\n",
+                    name,
+                    " ".repeat(name_width - name.width()),
+                    size,
+                    entry.language,
+                    size_width = size_width
+                )),
+                None => {
+                    rendered.push_str(name);
+                    rendered.push('\n');
+                }
+            }
+        }
+        rendered
+    }
+}
+
+/// The rows of the tree of a directory: the files and the subdirectories (followed by their
+/// rows), with the entry of every file. `entries` are the files within the directory.
+fn tree_rows<'a>(
+    dir: &Path,
+    entries: &[&'a IndexEntry],
+    prefix: &str,
+    rows: &mut Vec<(String, Option<&'a IndexEntry>)>,
+) {
+    // The children in the order of the index, with the files within every subdirectory
+    let mut children: Vec<(PathBuf, Vec<&IndexEntry>)> = vec![];
+    for &entry in entries {
+        let relative = entry.path.strip_prefix(dir).unwrap_or(&entry.path);
+        let child = dir.join(
+            relative
+                .components()
+                .next()
+                .map_or(Path::new(""), |c| Path::new(c.as_os_str())),
+        );
+        match children.last_mut() {
+            Some((last, files)) if *last == child && child != entry.path => files.push(entry),
+            _ => children.push((child, vec![entry])),
+        }
+    }
+
+    for (position, (child, files)) in children.iter().enumerate() {
+        let is_last = position + 1 == children.len();
+        let branch = if is_last { "└── " } else { "├── " };
+        let name = child.file_name().unwrap_or_default().to_string_lossy();
+        if *child == files[0].path {
+            rows.push((format!("{}{}{}", prefix, branch, name), Some(files[0])));
+        } else {
+            rows.push((format!("{}{}{}/", prefix, branch, name), None));
+            let prefix = format!("{}{}", prefix, if is_last { "    " } else { "│   " });
+            tree_rows(child, files, &prefix, rows);
+        }
+    }
+}
+
+/// The language of a file (`binary` for binary files)
+fn language(path: &Path, assets: &HighlightingAssets, mapping: &SyntaxMapping) -> Result<String> {
+    let mut content = vec![];
+    fs::File::open(path)
+        .and_then(|file| file.take(DETECTION_SIZE).read_to_end(&mut content))
+        .chain_err(|| format!("Could not read '{}'", path.to_string_lossy()))?;
+
+    if content_inspector::inspect(&content) == ContentType::BINARY {
+        return Ok("binary".into());
+    }
+    let detection = assets.explain_syntax(Some(path), &content, mapping)?;
+    Ok(match detection.syntax() {
+        "" => "Plain Text".into(),
+        syntax => syntax.to_owned(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use tempdir::TempDir;
+
+    #[test]
+    fn directories_are_indexed() {
+        let dir = TempDir::new("bat_repository").unwrap();
+        let root = dir.path();
+        fs::create_dir_all(root.join("src/bin")).unwrap();
+        fs::create_dir_all(root.join(".hidden")).unwrap();
+        fs::write(root.join("README.md"), "# bat\n").unwrap();
+        fs::write(root.join("src/lib.rs"), "fn main() {}\n").unwrap();
+        fs::write(root.join("src/bin/main.rs"), "fn main() {}\n").unwrap();
+        fs::write(root.join("src/logo.png"), b"\x89PNG\r\n\x1a\n\x00\x00").unwrap();
+        fs::write(root.join(".hidden/secret"), "").unwrap();
+
+        let assets = HighlightingAssets::from_binary();
+        let index = RepositoryIndex::scan(root, &assets, &SyntaxMapping::builtin()).unwrap();
+        let paths: Vec<&Path> = index.entries().iter().map(|e| e.path.as_path()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                Path::new("README.md"),
+                Path::new("src/lib.rs"),
+                Path::new("src/logo.png"),
+                Path::new("src/bin/main.rs"),
+            ]
+        );
+
+        let rendered = index.render();
+        let tree = &rendered[rendered.find('\n').unwrap() + 1..];
+        assert_eq!(
+            tree,
+            concat!(
+                "├── README.md         6 B  Markdown\n",
+                "└── src/\n",
+                "    ├── lib.rs       13 B  Rust\n",
+                "    ├── logo.png     10 B  binary\n",
+                "    └── bin/\n",
+                "        └── main.rs  13 B  Rust\n",
+            )
+        );
+    }
+}
@@ -0,0 +1,248 @@
+//! An on-disk cache of the output of printed inputs (see `PrettyPrinter::enable_result_cache`).
+//! The output of an input is stored under a hash of its content and of all settings that
+//! affect how it is printed, so that an unchanged input is printed without highlighting it
+//! again. Entries are never invalidated, since a changed input or setting simply leads to
+//! another key. An entry starts with a line of JSON with the language and the number of lines
+//! of the input, which is followed by the output.
+
+use std::collections::hash_map::DefaultHasher;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io::{self, Cursor, Read};
+use std::path::PathBuf;
+use std::process;
+
+use serde::{Deserialize, Serialize};
+
+use crate::config::Config;
+use crate::error::*;
+use crate::input::{Input, InputKind};
+#[cfg(feature = "paging")]
+use crate::paging::PagingMode;
+use crate::style::{HeaderComponent, StyleComponents};
+use crate::syntax_mapping::SyntaxMapping;
+use crate::template;
+
+#[derive(Debug, Clone)]
+pub(crate) struct ResultCache {
+    dir: PathBuf,
+}
+
+/// The output of an input that has been printed before, and what is known about the input
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct CachedOutput {
+    pub(crate) language: Option<String>,
+    pub(crate) lines: usize,
+
+    #[serde(skip)]
+    pub(crate) output: Vec<u8>,
+}
+
+impl ResultCache {
+    pub(crate) fn new(dir: PathBuf) -> Self {
+        ResultCache { dir }
+    }
+
+    /// The key of the output of an input, or `None` if the input can not be cached (inputs
+    /// that are compared with an old version, followed files, URLs, files that can not be
+    /// read, and the inputs of printers with settings whose effect is not known, see
+    /// `settings`). STDIN and readers are read into memory for this purpose.
+    pub(crate) fn key(&self, config: &Config, input: &mut Input) -> Result<Option<String>> {
+        let settings = match settings(config) {
+            Some(settings) if input.diff_base.is_none() && !config.follow => settings,
+            _ => return Ok(None),
+        };
+
+        let mut hasher = DefaultHasher::new();
+        match input.kind {
+            InputKind::OrdinaryFile(ref path) => match fs::read(path) {
+                Ok(content) => {
+                    content.hash(&mut hasher);
+                    if uses_file_metadata(config) {
+                        // The header shows data that changes without the content
+                        let metadata = fs::metadata(path)?;
+                        format!("{:?}", metadata.modified().ok()).hash(&mut hasher);
+                        template::permissions(&metadata).hash(&mut hasher);
+                    }
+                }
+                Err(_) => return Ok(None),
+            },
+            InputKind::ThemePreviewFile => "theme preview".hash(&mut hasher),
+            InputKind::StdIn | InputKind::CustomReader(_) => {
+                let mut content = vec![];
+                match input.kind {
+                    InputKind::CustomReader(ref mut reader) => reader.read_to_end(&mut content)?,
+                    _ => io::stdin().lock().read_to_end(&mut content)?,
+                };
+                content.hash(&mut hasher);
+                input.kind = InputKind::CustomReader(Box::new(Cursor::new(content)));
+            }
+            #[cfg(feature = "http")]
+            InputKind::Url(_) => return Ok(None),
+        }
+
+        input.name().hash(&mut hasher);
+        input.metadata.revision.hash(&mut hasher);
+        input.metadata.content_type.hash(&mut hasher);
+        input.metadata.align_key_values.hash(&mut hasher);
+        settings.hash(&mut hasher);
+        Ok(Some(format!("{:016x}", hasher.finish())))
+    }
+
+    /// The cached output for a key, if there is a valid entry
+    pub(crate) fn load(&self, key: &str) -> Option<CachedOutput> {
+        let entry = fs::read(self.dir.join(key)).ok()?;
+        let end_of_header = entry.iter().position(|&b| b == b'\n')?;
+        let mut cached: CachedOutput = serde_json::from_slice(&entry[..end_of_header]).ok()?;
+        cached.output = entry[end_of_header + 1..].to_vec();
+        Some(cached)
+    }
+
+    /// Store the output for a key. The output is written to a temporary file first, so that
+    /// other processes never load an incomplete output.
+    pub(crate) fn store(&self, key: &str, cached: &CachedOutput) -> Result<()> {
+        let mut entry = serde_json::to_vec(cached).chain_err(|| "Could not encode the entry")?;
+        entry.push(b'\n');
+        entry.extend_from_slice(&cached.output);
+
+        fs::create_dir_all(&self.dir)?;
+        let temporary = self.dir.join(format!("{}.{}.tmp", key, process::id()));
+        fs::write(&temporary, entry)?;
+        fs::rename(&temporary, self.dir.join(key))?;
+        Ok(())
+    }
+}
+
+/// All settings of the config that affect the output, or `None` if the effect of a setting
+/// can not be described (like the one of functions, line processors and the attributes of
+/// git repositories)
+fn settings(config: &Config) -> Option<String> {
+    if config.line_classifier.is_some()
+        || !config.line_processors.is_empty()
+        || config.asciicast_file.is_some()
+        || config.syntax_mapping.uses_git_attributes()
+    {
+        return None;
+    }
+
+    let mut config = config.clone();
+
+    // These only concern the pager and how the output is written, not the output itself
+    #[cfg(feature = "paging")]
+    {
+        config.paging_mode = PagingMode::default();
+    }
+    config.pager = None;
+    config.pager_timeout = None;
+    config.pager_per_file = false;
+    config.sticky_header = false;
+    config.sandbox = false;
+    config.strict = false;
+    config.parallelism = 0;
+    config.buffering_mode = Default::default();
+    config.provenance = Default::default();
+
+    // These are described separately, since their debug output differs between processes
+    // (the order of hash sets and maps, the addresses of functions)
+    let mut components: Vec<String> = config
+        .style_components
+        .0
+        .iter()
+        .map(|component| format!("{:?}", component))
+        .collect();
+    components.sort();
+    config.style_components = StyleComponents::default();
+    let syntax_mapping = config.syntax_mapping.fingerprint();
+    config.syntax_mapping = SyntaxMapping::empty();
+    config.line_class_colors.clear();
+
+    Some(format!(
+        "{}\0{:?}\0{}\0{:?}",
+        env!("CARGO_PKG_VERSION"),
+        components,
+        syntax_mapping,
+        config,
+    ))
+}
+
+/// Whether the header or the footer shows the modification time or the permissions of files
+fn uses_file_metadata(config: &Config) -> bool {
+    let uses_field = |template: &Option<template::Template>| {
+        template.as_ref().map_or(false, |template| {
+            template.uses_field("modified") || template.uses_field("permissions")
+        })
+    };
+
+    uses_field(&config.header_template)
+        || uses_field(&config.footer_template)
+        || config
+            .header_components
+            .iter()
+            .any(|component| match component {
+                HeaderComponent::Modified | HeaderComponent::Permissions => true,
+                _ => false,
+            })
+}
+
+#[test]
+fn keys_depend_on_content_and_settings() {
+    let cache = ResultCache::new(PathBuf::from("unused"));
+    let mut config = Config::default();
+    let key = |config: &Config, content: &'static [u8]| {
+        let mut input = Input::from_reader(Box::new(content));
+        let key = cache.key(config, &mut input).unwrap();
+
+        // The content can still be printed
+        let mut rest = vec![];
+        if let InputKind::CustomReader(ref mut reader) = input.kind {
+            reader.read_to_end(&mut rest).unwrap();
+        }
+        assert_eq!(rest, content);
+        key.unwrap()
+    };
+
+    let original = key(&config, b"fn main() {}\n");
+    assert_eq!(key(&config, b"fn main() {}\n"), original);
+    assert_ne!(key(&config, b"fn main() { }\n"), original);
+
+    config.term_width = 120;
+    let wide = key(&config, b"fn main() {}\n");
+    assert_ne!(wide, original);
+    config.theme = "ansi".into();
+    let ansi = key(&config, b"fn main() {}\n");
+    assert_ne!(ansi, wide);
+
+    // Settings that are rarely used are part of the key as well
+    config.squeeze_blank_lines = Some(1);
+    assert_ne!(key(&config, b"fn main() {}\n"), ansi);
+
+    // Settings that don't affect the output are not
+    config.squeeze_blank_lines = None;
+    config.parallelism = 4;
+    config.sandbox = true;
+    assert_eq!(key(&config, b"fn main() {}\n"), ansi);
+
+    config.line_classifier = Some(crate::config::LineClassifier::new(|_, _| None));
+    let mut input = Input::from_reader(Box::new(&b"x\n"[..]));
+    assert!(cache.key(&config, &mut input).unwrap().is_none());
+}
+
+#[test]
+fn entries_contain_the_language_and_the_number_of_lines() {
+    let dir = tempdir::TempDir::new("bat_result_cache_entries").unwrap();
+    let cache = ResultCache::new(dir.path().to_owned());
+    let cached = CachedOutput {
+        language: Some("Rust".into()),
+        lines: 2,
+        output: b"fn main() {\n}\n".to_vec(),
+    };
+    cache.store("key", &cached).unwrap();
+
+    let loaded = cache.load("key").unwrap();
+    assert_eq!(loaded.language, cached.language);
+    assert_eq!(loaded.lines, 2);
+    assert_eq!(loaded.output, cached.output);
+
+    fs::write(dir.path().join("invalid"), "output\n").unwrap();
+    assert!(cache.load("invalid").is_none());
+}
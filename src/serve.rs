@@ -0,0 +1,367 @@
+//! A long-running highlighting server (`bat --serve`), so that programs that highlight many
+//! small snippets (like shell prompts and editors) load the syntaxes and themes only once.
+//!
+//! Requests and responses are JSON-RPC 2.0 messages, one per line, read from STDIN (or from
+//! the connections to a Unix socket). The `highlight` method renders the `content` of its
+//! parameters:
+//!
+//! ```json
+//! {"jsonrpc": "2.0", "id": 1, "method": "highlight",
+//!  "params": {"content": "fn main() {}\n", "language": "rs", "format": "ansi"}}
+//! {"jsonrpc": "2.0", "id": 1, "result": {"output": "\u001b[38;2;...m..."}}
+//! ```
+//!
+//! The other parameters are all optional: `file_name` (to detect the syntax from it, instead
+//! of the `language`), `theme`, `format` (`ansi`, `plain` or `html`), `line_numbers`, `grid`,
+//! `header`, `tab_width` and `term_width`. The `shutdown` method stops the server.
+
+#[cfg(unix)]
+use std::io::BufReader;
+use std::io::{BufRead, Write};
+#[cfg(unix)]
+use std::os::unix::net::UnixListener;
+#[cfg(unix)]
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+#[cfg(unix)]
+use std::thread;
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::assets::HighlightingAssets;
+use crate::error::*;
+use crate::pipeline::{hex_color, Decorator, Highlighter, OutputFormat, Renderer};
+use crate::style::{StyleComponent, StyleComponents};
+use crate::PrettyPrinter;
+
+/// The error codes of JSON-RPC
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const HIGHLIGHT_ERROR: i64 = -32000;
+
+#[derive(Deserialize)]
+struct Request {
+    #[serde(default)]
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default, deny_unknown_fields)]
+struct HighlightParams {
+    content: String,
+    language: Option<String>,
+    file_name: Option<String>,
+    theme: Option<String>,
+    format: Format,
+    line_numbers: bool,
+    grid: bool,
+    header: bool,
+    tab_width: Option<usize>,
+    term_width: Option<usize>,
+}
+
+#[derive(Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Format {
+    Ansi,
+    Plain,
+    Html,
+}
+
+impl Default for Format {
+    fn default() -> Self {
+        Format::Ansi
+    }
+}
+
+/// The response to a request, and whether the server should stop afterwards
+enum Reply {
+    Response(Value),
+    Shutdown(Value),
+}
+
+/// A server that highlights the content of requests with shared assets
+pub struct Server {
+    assets: Arc<HighlightingAssets>,
+    theme: String,
+}
+
+impl Server {
+    pub fn new(assets: Arc<HighlightingAssets>) -> Self {
+        Server {
+            assets,
+            theme: HighlightingAssets::default_theme().to_owned(),
+        }
+    }
+
+    /// The theme of requests without a `theme` (default: the default theme)
+    pub fn theme(&mut self, theme: impl Into<String>) -> &mut Self {
+        self.theme = theme.into();
+        self
+    }
+
+    /// Answer the requests of a reader (like STDIN), until the end of the input or until a
+    /// `shutdown` request. Returns `true` after a `shutdown` request. STDIN must not be locked
+    /// while serving, since the printer locks it to open its inputs.
+    pub fn serve(&self, reader: impl BufRead, mut writer: impl Write) -> Result<bool> {
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let (response, shutdown) = match self.reply(&line) {
+                Reply::Response(response) => (response, false),
+                Reply::Shutdown(response) => (response, true),
+            };
+            writeln!(writer, "{}", response)?;
+            writer.flush()?;
+            if shutdown {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Answer the requests of the connections to a Unix socket (each one in a thread of its
+    /// own), until a `shutdown` request. A socket that exists at the path is replaced, any
+    /// other file is an error.
+    #[cfg(unix)]
+    pub fn serve_unix_socket(self, path: &Path) -> Result<()> {
+        use std::os::unix::fs::FileTypeExt;
+
+        if let Ok(metadata) = path.symlink_metadata() {
+            if !metadata.file_type().is_socket() {
+                return Err(format!(
+                    "Could not listen on '{}': the file exists and is not a socket",
+                    path.to_string_lossy()
+                )
+                .into());
+            }
+            std::fs::remove_file(path)?;
+        }
+        let listener = UnixListener::bind(path)
+            .chain_err(|| format!("Could not listen on '{}'", path.to_string_lossy()))?;
+        let socket = SocketFile(path.to_owned());
+
+        let server = Arc::new(self);
+        let (shutdown_sender, shutdown) = std::sync::mpsc::channel();
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let server = Arc::clone(&server);
+                let shutdown_sender = shutdown_sender.clone();
+                thread::spawn(move || {
+                    let reader = match stream.try_clone() {
+                        Ok(stream) => BufReader::new(stream),
+                        Err(_) => return,
+                    };
+                    if let Ok(true) = server.serve(reader, stream) {
+                        shutdown_sender.send(()).ok();
+                    }
+                });
+            }
+        });
+
+        // The connections that are still open are closed when the process exits
+        shutdown.recv().ok();
+        drop(socket);
+        Ok(())
+    }
+
+    fn reply(&self, line: &str) -> Reply {
+        let request: Request = match serde_json::from_str(line) {
+            Ok(request) => request,
+            Err(e) => return Reply::Response(error(Value::Null, PARSE_ERROR, e.to_string())),
+        };
+        let id = request.id;
+
+        match request.method.as_str() {
+            "highlight" => {
+                let params: HighlightParams = match serde_json::from_value(request.params) {
+                    Ok(params) => params,
+                    Err(e) => return Reply::Response(error(id, INVALID_PARAMS, e.to_string())),
+                };
+                Reply::Response(match self.highlight(&params) {
+                    Ok(output) => json!({"jsonrpc": "2.0", "id": id, "result": {"output": output}}),
+                    Err(e) => error(id, HIGHLIGHT_ERROR, e.to_string()),
+                })
+            }
+            "shutdown" => Reply::Shutdown(json!({"jsonrpc": "2.0", "id": id, "result": null})),
+            method => Reply::Response(error(
+                id,
+                METHOD_NOT_FOUND,
+                format!("Unknown method '{}'", method),
+            )),
+        }
+    }
+
+    fn highlight(&self, params: &HighlightParams) -> Result<String> {
+        let theme = params.theme.as_deref().unwrap_or(&self.theme);
+        if params.format == Format::Html {
+            return self.highlight_html(params, theme);
+        }
+
+        let mut printer = PrettyPrinter::new();
+        printer
+            .shared_assets(Arc::clone(&self.assets))
+            .theme(theme)
+            .colored_output(params.format == Format::Ansi)
+            .line_numbers(params.line_numbers)
+            .grid(params.grid)
+            .header(params.header)
+            .tab_width(params.tab_width)
+            .term_width(params.term_width.unwrap_or(80));
+        if let Some(ref language) = params.language {
+            printer.language(language);
+        }
+        match params.file_name {
+            Some(ref name) => printer.input_from_bytes_with_name(params.content.as_bytes(), name),
+            None => printer.input_from_bytes(params.content.as_bytes()),
+        };
+
+        let mut output = vec![];
+        printer.print_with_writer(&mut output)?;
+        Ok(String::from_utf8_lossy(&output).into_owned())
+    }
+
+    /// A `<pre>` element with the highlighted lines (see `OutputFormat::Html`)
+    fn highlight_html(&self, params: &HighlightParams, theme: &str) -> Result<String> {
+        let extension = params.file_name.as_ref().and_then(|name| {
+            PathBuf::from(name)
+                .extension()
+                .map(|extension| extension.to_string_lossy().into_owned())
+        });
+        let language = params
+            .language
+            .as_deref()
+            .or(extension.as_deref())
+            .unwrap_or("txt");
+
+        let mut highlighter = Highlighter::new(&self.assets, language, theme)?;
+        let mut components = vec![];
+        if params.line_numbers {
+            components.push(StyleComponent::LineNumbers);
+        }
+        if params.grid {
+            components.push(StyleComponent::Grid);
+        }
+        let mut decorator = Decorator::new(&StyleComponents::new(&components));
+        decorator.max_line_number(params.content.lines().count());
+        let mut renderer = Renderer::new(OutputFormat::Html);
+        renderer.background(highlighter.background());
+
+        let mut output = vec![];
+        match highlighter.background() {
+            Some(color) => write!(
+                output,
+                "<pre style=\"background-color: {}\">",
+                hex_color(color)
+            )?,
+            None => write!(output, "<pre>")?,
+        }
+        for (index, spans) in highlighter
+            .highlight_text(&params.content)
+            .iter()
+            .enumerate()
+        {
+            renderer.write_line(&mut output, &decorator.gutter(index + 1, false), spans)?;
+        }
+        renderer.finish(&mut output)?;
+        write!(output, "</pre>")?;
+        Ok(String::from_utf8_lossy(&output).into_owned())
+    }
+}
+
+fn error(id: Value, code: i64, message: String) -> Value {
+    json!({"jsonrpc": "2.0", "id": id, "error": {"code": code, "message": message}})
+}
+
+/// Removes the socket file when the server stops
+#[cfg(unix)]
+struct SocketFile(PathBuf);
+
+#[cfg(unix)]
+impl Drop for SocketFile {
+    fn drop(&mut self) {
+        std::fs::remove_file(&self.0).ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn serve(requests: &str) -> Vec<Value> {
+        let server = Server::new(Arc::new(HighlightingAssets::from_binary()));
+        let mut output = vec![];
+        server.serve(requests.as_bytes(), &mut output).unwrap();
+        String::from_utf8(output)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn requests_are_answered() {
+        let responses = serve(
+            "{\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"highlight\",\"params\":\
+             {\"content\":\"fn main() {}\\n\",\"language\":\"rs\",\"format\":\"plain\",\
+             \"line_numbers\":true}}\n\
+             \n\
+             {\"jsonrpc\":\"2.0\",\"id\":2,\"method\":\"highlight\",\"params\":\
+             {\"content\":\"x = 1\\n\",\"file_name\":\"a.py\"}}\n\
+             {\"jsonrpc\":\"2.0\",\"id\":3,\"method\":\"highlight\",\"params\":\
+             {\"content\":\"<b>\\n\",\"format\":\"html\"}}\n\
+             {\"jsonrpc\":\"2.0\",\"id\":4,\"method\":\"shutdown\"}\n\
+             {\"jsonrpc\":\"2.0\",\"id\":5,\"method\":\"highlight\"}\n",
+        );
+
+        assert_eq!(responses.len(), 4);
+        assert_eq!(responses[0]["id"], 1);
+        assert_eq!(responses[0]["result"]["output"], "   1 fn main() {}\n");
+        let ansi = responses[1]["result"]["output"].as_str().unwrap();
+        assert!(ansi.contains("\x1b[") && console::strip_ansi_codes(ansi) == "x = 1\n");
+        let html = responses[2]["result"]["output"].as_str().unwrap();
+        assert!(html.starts_with("<pre style=\"background-color: #"));
+        assert!(html.contains("&lt;b&gt;") && html.ends_with("</pre>"));
+        assert_eq!(responses[3]["result"], Value::Null);
+    }
+
+    #[test]
+    fn invalid_requests_are_errors() {
+        let responses = serve(
+            "not json\n\
+             {\"jsonrpc\":\"2.0\",\"id\":1,\"method\":\"format\"}\n\
+             {\"jsonrpc\":\"2.0\",\"id\":2,\"method\":\"highlight\",\"params\":{\"size\":1}}\n\
+             {\"jsonrpc\":\"2.0\",\"id\":3,\"method\":\"highlight\",\"params\":\
+             {\"content\":\"\",\"language\":\"nope\",\"format\":\"html\"}}\n",
+        );
+
+        let codes: Vec<&Value> = responses
+            .iter()
+            .map(|response| &response["error"]["code"])
+            .collect();
+        assert_eq!(codes, vec![-32700, -32601, -32602, -32000]);
+        assert_eq!(responses[0]["id"], Value::Null);
+        assert_eq!(responses[3]["id"], 3);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn other_files_are_not_replaced_by_the_socket() {
+        let dir = tempdir::TempDir::new("bat-serve").unwrap();
+        let path = dir.path().join("notes.txt");
+        std::fs::write(&path, "notes\n").unwrap();
+
+        let server = Server::new(Arc::new(HighlightingAssets::from_binary()));
+        assert!(server.serve_unix_socket(&path).is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "notes\n");
+    }
+}
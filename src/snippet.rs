@@ -0,0 +1,172 @@
+//! Incremental highlighting of text that arrives in chunks, e.g. the input of a REPL.
+
+use syntect::highlighting::{HighlightIterator, HighlightState, Highlighter};
+use syntect::parsing::{ParseState, ScopeStack, SyntaxSet};
+
+use crate::assets::HighlightingAssets;
+use crate::error::*;
+use crate::terminal::as_terminal_escaped;
+
+/// The output of `SnippetHighlighter::push_str`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct HighlightedDelta {
+    /// Highlighted lines that have been completed by the last chunk (including the line
+    /// endings). These do not change anymore and can be appended to the output.
+    pub lines: String,
+
+    /// The highlighted (incomplete) line after the last line ending. This replaces the
+    /// previously returned pending line, since the highlighting of a line can change when
+    /// more text arrives.
+    pub pending: String,
+}
+
+/// Highlights text that is fed in chunks of arbitrary size, keeping the state of the
+/// syntax highlighter between the chunks.
+///
+/// ```
+/// use bat::assets::HighlightingAssets;
+/// use bat::snippet::SnippetHighlighter;
+///
+/// let assets = HighlightingAssets::from_binary();
+/// let mut highlighter = SnippetHighlighter::new(&assets, "rust", "Monokai Extended").unwrap();
+///
+/// let delta = highlighter.push_str("let x = \"he");
+/// assert!(delta.lines.is_empty());
+///
+/// let delta = highlighter.push_str("llo\";\nx");
+/// assert!(!delta.lines.is_empty());
+/// assert_eq!(highlighter.pending_text(), "x");
+/// ```
+pub struct SnippetHighlighter<'a> {
+    syntax_set: &'a SyntaxSet,
+    highlighter: Highlighter<'a>,
+    parse_state: ParseState,
+    highlight_state: HighlightState,
+    line: String,
+    true_color: bool,
+    italics: bool,
+}
+
+impl<'a> SnippetHighlighter<'a> {
+    /// Create a highlighter for the given language (name or file extension) and theme.
+    /// Fails if the language is unknown. Unknown themes fall back to the default theme.
+    pub fn new(assets: &'a HighlightingAssets, language: &str, theme: &str) -> Result<Self> {
+        let syntax = assets.get_syntax_by_token(language)?;
+        let highlighter = Highlighter::new(assets.get_theme(theme));
+        let highlight_state = HighlightState::new(&highlighter, ScopeStack::new());
+
+        Ok(SnippetHighlighter {
+            syntax_set: assets.get_syntax_set(),
+            highlighter,
+            parse_state: ParseState::new(syntax),
+            highlight_state,
+            line: String::new(),
+            true_color: true,
+            italics: false,
+        })
+    }
+
+    /// Whether or not to output 24bit colors (default: true)
+    pub fn true_color(&mut self, yes: bool) -> &mut Self {
+        self.true_color = yes;
+        self
+    }
+
+    /// Whether or not to use ANSI italics (default: off)
+    pub fn use_italics(&mut self, yes: bool) -> &mut Self {
+        self.italics = yes;
+        self
+    }
+
+    /// Feed the next chunk of text and get the highlighted output for it
+    pub fn push_str(&mut self, chunk: &str) -> HighlightedDelta {
+        let mut delta = HighlightedDelta::default();
+
+        let mut rest = chunk;
+        while let Some(pos) = rest.find('\n') {
+            self.line.push_str(&rest[..=pos]);
+            rest = &rest[pos + 1..];
+
+            let line = std::mem::take(&mut self.line);
+            delta.lines += &self.highlight(&line, true);
+        }
+        self.line.push_str(rest);
+
+        let line = std::mem::take(&mut self.line);
+        delta.pending = self.highlight(&line, false);
+        self.line = line;
+
+        delta
+    }
+
+    /// The text of the incomplete line after the last line ending
+    pub fn pending_text(&self) -> &str {
+        &self.line
+    }
+
+    /// Highlight a line. If `keep_state` is false, the state of the highlighter is left
+    /// untouched (used for incomplete lines).
+    fn highlight(&mut self, line: &str, keep_state: bool) -> String {
+        let mut parse_state = self.parse_state.clone();
+        let mut highlight_state = self.highlight_state.clone();
+
+        let ops = parse_state.parse_line(line, self.syntax_set);
+        let mut output = String::with_capacity(line.len() * 2);
+        for (style, region) in
+            HighlightIterator::new(&mut highlight_state, &ops, line, &self.highlighter)
+        {
+            let text = region.trim_end_matches(&['\r', '\n'][..]);
+            output += &as_terminal_escaped(style, text, self.true_color, true, self.italics, None)
+                .to_string();
+            output += &region[text.len()..];
+        }
+
+        if keep_state {
+            self.parse_state = parse_state;
+            self.highlight_state = highlight_state;
+        }
+
+        output
+    }
+}
+
+#[test]
+fn chunks_are_highlighted_like_complete_input() {
+    let assets = HighlightingAssets::from_binary();
+    let input = "fn main() {\n    // comment\n    let s = \"str\";\n}\n";
+
+    let mut complete = SnippetHighlighter::new(&assets, "rs", "Monokai Extended").unwrap();
+    let expected = complete.push_str(input);
+    assert!(expected.pending.is_empty());
+
+    let mut chunked = SnippetHighlighter::new(&assets, "rs", "Monokai Extended").unwrap();
+    let mut lines = String::new();
+    for chunk in &[
+        "fn ma",
+        "in() {\n    //",
+        " comment\n",
+        "    let s = \"s",
+        "tr\";\n}\n",
+    ] {
+        lines += &chunked.push_str(chunk).lines;
+    }
+    assert_eq!(lines, expected.lines);
+}
+
+#[test]
+fn pending_line_is_redrawn() {
+    let assets = HighlightingAssets::from_binary();
+    let mut highlighter = SnippetHighlighter::new(&assets, "rs", "Monokai Extended").unwrap();
+
+    let delta = highlighter.push_str("f");
+    assert!(delta.lines.is_empty());
+    assert_eq!(highlighter.pending_text(), "f");
+
+    let delta = highlighter.push_str("n");
+    assert_eq!(highlighter.pending_text(), "fn");
+    assert_eq!(
+        console::strip_ansi_codes(&delta.pending),
+        highlighter.pending_text()
+    );
+    assert!(SnippetHighlighter::new(&assets, "no-such-language", "").is_err());
+}
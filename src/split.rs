@@ -0,0 +1,63 @@
+//! A split view of a single input: two line ranges of the same file are printed side by side
+//! (for example a function definition and one of its call sites). Both panes are printed like
+//! separate inputs with half of the terminal width.
+
+use console::measure_text_width;
+
+use crate::line_range::LineRange;
+use crate::template::truncate;
+
+/// The separator between the panes
+pub(crate) const SEPARATOR: &str = " │ ";
+
+/// The line ranges of the left and the right pane
+#[derive(Debug, Clone)]
+pub struct SplitView {
+    pub left: LineRange,
+    pub right: LineRange,
+}
+
+/// The width of each pane on a terminal of the given width
+pub(crate) fn pane_width(term_width: usize) -> usize {
+    term_width.saturating_sub(measure_text_width(SEPARATOR)) / 2
+}
+
+/// Put the lines of the two panes next to each other. The lines of the left pane are padded
+/// (or cut) to the given width, the shorter pane is filled up with empty lines.
+pub(crate) fn side_by_side(left: &str, right: &str, width: usize, separator: &str) -> String {
+    let mut left_lines = left.lines();
+    let mut right_lines = right.lines();
+
+    let mut result = String::with_capacity(left.len() + right.len());
+    loop {
+        let (left_line, right_line) = match (left_lines.next(), right_lines.next()) {
+            (None, None) => break,
+            (left_line, right_line) => (left_line.unwrap_or(""), right_line.unwrap_or("")),
+        };
+
+        let left_line = truncate(left_line, width);
+        result.push_str(&left_line);
+        if left_line.contains('\x1b') {
+            result.push_str("\x1b[0m");
+        }
+        result.push_str(&" ".repeat(width.saturating_sub(measure_text_width(&left_line))));
+        result.push_str(separator);
+        result.push_str(right_line);
+        result.push('\n');
+    }
+    result
+}
+
+#[test]
+fn panes_are_put_side_by_side() {
+    assert_eq!(pane_width(23), 10);
+    assert_eq!(
+        side_by_side(
+            "fn a() {\n\x1b[1mtoo long line\x1b[0m\n}\n",
+            "a();\n",
+            6,
+            " | "
+        ),
+        "fn a() | a();\n\x1b[1mtoo lo\x1b[0m\x1b[0m | \n}      | \n"
+    );
+}
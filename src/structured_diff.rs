@@ -0,0 +1,174 @@
+//! Structured diffs of JSON and YAML inputs: the parsed values are compared instead of the
+//! lines, so that reformatted or reordered documents only show the changes of their data.
+//! Every change is printed with the path of the changed value (like `$.dependencies[2]`) and
+//! highlighted like a diff (see `PrettyPrinter::input_structured_diff`).
+#![cfg(feature = "structured-diff")]
+
+use std::path::Path;
+
+use serde_json::{Map, Value};
+
+use crate::error::*;
+use crate::line_diff::{diff_rows, DiffRow};
+
+/// A change between two values, at the path of the value within the document
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    Added {
+        path: String,
+        value: Value,
+    },
+    Removed {
+        path: String,
+        value: Value,
+    },
+    Changed {
+        path: String,
+        old: Value,
+        new: Value,
+    },
+}
+
+/// The changes between two parsed documents. Objects are compared key by key, arrays with a
+/// diff of their elements (so that an inserted element doesn't change all the following
+/// ones).
+pub fn diff(old: &Value, new: &Value) -> Vec<Change> {
+    let mut changes = vec![];
+    diff_values("$", old, new, &mut changes);
+    changes
+}
+
+fn diff_values(path: &str, old: &Value, new: &Value, changes: &mut Vec<Change>) {
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => diff_objects(path, old, new, changes),
+        (Value::Array(old), Value::Array(new)) => {
+            for row in diff_rows(old, new) {
+                match row {
+                    DiffRow::Unchanged(_, _) => {}
+                    DiffRow::Removed(index) => changes.push(Change::Removed {
+                        path: format!("{}[{}]", path, index),
+                        value: old[index].clone(),
+                    }),
+                    DiffRow::Added(index) => changes.push(Change::Added {
+                        path: format!("{}[{}]", path, index),
+                        value: new[index].clone(),
+                    }),
+                    DiffRow::Changed(old_index, new_index) => diff_values(
+                        &format!("{}[{}]", path, new_index),
+                        &old[old_index],
+                        &new[new_index],
+                        changes,
+                    ),
+                }
+            }
+        }
+        _ if old != new => changes.push(Change::Changed {
+            path: path.to_owned(),
+            old: old.clone(),
+            new: new.clone(),
+        }),
+        _ => {}
+    }
+}
+
+fn diff_objects(
+    path: &str,
+    old: &Map<String, Value>,
+    new: &Map<String, Value>,
+    changes: &mut Vec<Change>,
+) {
+    for (key, old_value) in old {
+        let path = key_path(path, key);
+        match new.get(key) {
+            Some(new_value) => diff_values(&path, old_value, new_value, changes),
+            None => changes.push(Change::Removed {
+                path,
+                value: old_value.clone(),
+            }),
+        }
+    }
+    for (key, new_value) in new {
+        if !old.contains_key(key) {
+            changes.push(Change::Added {
+                path: key_path(path, key),
+                value: new_value.clone(),
+            });
+        }
+    }
+}
+
+/// The path of a value within an object: `.key` for identifiers, `["key"]` otherwise
+fn key_path(path: &str, key: &str) -> String {
+    let is_identifier = key
+        .chars()
+        .next()
+        .map_or(false, |c| c.is_alphabetic() || c == '_')
+        && key
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-');
+    if is_identifier {
+        format!("{}.{}", path, key)
+    } else {
+        format!("{}[{}]", path, Value::from(key))
+    }
+}
+
+/// Parse a JSON or YAML document. YAML is detected by the extension of the name (`.yaml` or
+/// `.yml`), everything else is parsed as JSON.
+pub(crate) fn parse(name: &str, content: &[u8]) -> Result<Value> {
+    let extension = Path::new(name)
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase());
+    match extension.as_deref() {
+        Some("yaml") | Some("yml") => serde_yaml::from_slice(content)
+            .map_err(|error| format!("Could not parse '{}' as YAML: {}", name, error).into()),
+        _ => serde_json::from_slice(content)
+            .map_err(|error| format!("Could not parse '{}' as JSON: {}", name, error).into()),
+    }
+}
+
+/// The changes as the lines of a diff: removed values start with `-`, added values with `+`
+/// (a changed value is removed and added), each with its path
+pub(crate) fn render(changes: &[Change]) -> String {
+    let mut rendered = String::new();
+    let mut line = |sign: char, path: &str, value: &Value| {
+        rendered.push_str(&format!("{} {}: {}\n", sign, path, value));
+    };
+    for change in changes {
+        match change {
+            Change::Added { path, value } => line('+', path, value),
+            Change::Removed { path, value } => line('-', path, value),
+            Change::Changed { path, old, new } => {
+                line('-', path, old);
+                line('+', path, new);
+            }
+        }
+    }
+    rendered
+}
+
+#[test]
+fn documents_are_compared_structurally() {
+    let old = parse(
+        "old.json",
+        br#"{"name": "bat", "tags": ["a", "b", "c"], "nested": {"x": 1, "my key": true}}"#,
+    )
+    .unwrap();
+    let new = parse(
+        "new.yaml",
+        b"nested:\n  x: 2\n  my key: true\ntags: [a, new, b, c]\nversion: 1\n",
+    )
+    .unwrap();
+
+    assert_eq!(
+        render(&diff(&old, &new)),
+        "- $.name: \"bat\"\n\
+         - $.nested.x: 1\n\
+         + $.nested.x: 2\n\
+         + $.tags[1]: \"new\"\n\
+         + $.version: 1\n"
+    );
+    assert_eq!(diff(&new, &new), vec![]);
+    assert_eq!(key_path("$", "my key"), "$[\"my key\"]".to_owned());
+    assert!(parse("broken.json", b"{").is_err());
+}
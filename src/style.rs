@@ -2,6 +2,7 @@ use std::collections::HashSet;
 use std::str::FromStr;
 
 use crate::error::*;
+use crate::layout;
 
 #[derive(Debug, Eq, PartialEq, Copy, Clone, Hash)]
 pub enum StyleComponent {
@@ -11,6 +12,8 @@ pub enum StyleComponent {
     Header,
     LineNumbers,
     Snip,
+    StatusLine,
+    Sections,
     Full,
     Plain,
 }
@@ -30,6 +33,8 @@ impl StyleComponent {
             StyleComponent::Header => &[StyleComponent::Header],
             StyleComponent::LineNumbers => &[StyleComponent::LineNumbers],
             StyleComponent::Snip => &[StyleComponent::Snip],
+            StyleComponent::StatusLine => &[StyleComponent::StatusLine],
+            StyleComponent::Sections => &[StyleComponent::Sections],
             StyleComponent::Full => &[
                 StyleComponent::Changes,
                 StyleComponent::Grid,
@@ -53,6 +58,8 @@ impl FromStr for StyleComponent {
             "header" => Ok(StyleComponent::Header),
             "numbers" => Ok(StyleComponent::LineNumbers),
             "snip" => Ok(StyleComponent::Snip),
+            "status" => Ok(StyleComponent::StatusLine),
+            "sections" => Ok(StyleComponent::Sections),
             "full" => Ok(StyleComponent::Full),
             "plain" => Ok(StyleComponent::Plain),
             _ => Err(format!("Unknown style '{}'", s).into()),
@@ -60,6 +67,47 @@ impl FromStr for StyleComponent {
     }
 }
 
+/// The information about a file in its header, see `Config::header_components`
+#[derive(Debug, Eq, PartialEq, Copy, Clone, Hash)]
+pub enum HeaderComponent {
+    Filename,
+
+    /// The size of the file, like `1.5 KiB`
+    Size,
+
+    /// The time of the last modification (in UTC), like `2020-05-04 13:37`
+    Modified,
+
+    /// The permissions of the file, like `-rw-r--r--`
+    Permissions,
+}
+
+impl HeaderComponent {
+    /// The field of header templates with the same value (see `template::HEADER_FIELDS`)
+    pub fn field(self) -> &'static str {
+        match self {
+            HeaderComponent::Filename => "path",
+            HeaderComponent::Size => "size",
+            HeaderComponent::Modified => "modified",
+            HeaderComponent::Permissions => "permissions",
+        }
+    }
+}
+
+impl FromStr for HeaderComponent {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "filename" => Ok(HeaderComponent::Filename),
+            "size" => Ok(HeaderComponent::Size),
+            "modified" => Ok(HeaderComponent::Modified),
+            "permissions" => Ok(HeaderComponent::Permissions),
+            _ => Err(format!("Unknown header component '{}'", s).into()),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct StyleComponents(pub HashSet<StyleComponent>);
 
@@ -89,7 +137,84 @@ impl StyleComponents {
         self.0.contains(&StyleComponent::Snip)
     }
 
+    pub fn status_line(&self) -> bool {
+        self.0.contains(&StyleComponent::StatusLine)
+    }
+
+    pub fn sections(&self) -> bool {
+        self.0.contains(&StyleComponent::Sections)
+    }
+
     pub fn plain(&self) -> bool {
         self.0.iter().all(|c| c == &StyleComponent::Plain)
     }
 }
+
+/// Responsive behavior for narrow terminals: the style components in `priority` are dropped,
+/// one after the other, as long as the code column would be narrower than `min_content_width`
+/// characters.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResponsiveLayout {
+    pub min_content_width: usize,
+    pub priority: Vec<StyleComponent>,
+}
+
+impl Default for ResponsiveLayout {
+    fn default() -> Self {
+        ResponsiveLayout {
+            min_content_width: 20,
+            priority: vec![
+                StyleComponent::Grid,
+                StyleComponent::LineNumbers,
+                StyleComponent::Header,
+            ],
+        }
+    }
+}
+
+impl ResponsiveLayout {
+    /// The style components that are shown on a terminal with the given width
+    pub fn apply(&self, components: &StyleComponents, term_width: usize) -> StyleComponents {
+        let mut components = components.clone();
+        let mut priority = self.priority.iter();
+
+        while content_width(&components, term_width) < self.min_content_width {
+            match priority.next() {
+                Some(component) => {
+                    for component in component.components(true) {
+                        components.0.remove(component);
+                    }
+                }
+                None => break,
+            }
+        }
+
+        components
+    }
+}
+
+/// The width of the code column next to the panel
+fn content_width(components: &StyleComponents, term_width: usize) -> usize {
+    term_width - layout::panel_width(components, term_width, 0, None)
+}
+
+#[test]
+fn narrow_terminals_drop_components() {
+    let full = StyleComponents::new(&[
+        StyleComponent::Grid,
+        StyleComponent::Header,
+        StyleComponent::LineNumbers,
+        StyleComponent::Snip,
+    ]);
+    let layout = ResponsiveLayout::default();
+
+    assert_eq!(layout.apply(&full, 80).0, full.0);
+
+    // Grid first, then line numbers
+    let narrow = layout.apply(&full, 26);
+    assert!(!narrow.grid() && narrow.numbers() && narrow.header());
+    let narrow = layout.apply(&full, 24);
+    assert!(!narrow.grid() && !narrow.numbers() && narrow.header());
+    let narrow = layout.apply(&full, 19);
+    assert!(!narrow.header() && narrow.snip());
+}
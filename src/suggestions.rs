@@ -0,0 +1,89 @@
+//! Fuzzy matching of (misspelled) names against a list of known names.
+
+/// Maximum number of suggestions returned by `closest_matches`
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Returns the candidates that are similar to `query`, best matches first.
+///
+/// The comparison is case-insensitive and based on the edit distance between the two
+/// strings. Candidates that contain the query are always considered to be close.
+pub fn closest_matches<'a, I>(query: &str, candidates: I) -> Vec<&'a str>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let query = query.to_lowercase();
+    let max_distance = std::cmp::max(2, query.chars().count() / 3);
+
+    let mut matches: Vec<(usize, &str)> = candidates
+        .into_iter()
+        .filter_map(|candidate| {
+            let lowercase = candidate.to_lowercase();
+            if query.len() >= 3 && lowercase.contains(&query) {
+                return Some((0, candidate));
+            }
+            let distance = edit_distance(&query, &lowercase);
+            if distance <= max_distance {
+                Some((distance, candidate))
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    matches.sort();
+    matches.dedup_by(|a, b| a.1 == b.1);
+
+    matches
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, candidate)| candidate)
+        .collect()
+}
+
+/// Levenshtein distance between two strings (in characters)
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for (i, ca) in a.chars().enumerate() {
+        current[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + if ca == *cb { 0 } else { 1 };
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+#[test]
+fn test_edit_distance() {
+    assert_eq!(0, edit_distance("rust", "rust"));
+    assert_eq!(1, edit_distance("rust", "rus"));
+    assert_eq!(2, edit_distance("rsut", "rust"));
+    assert_eq!(4, edit_distance("", "rust"));
+}
+
+#[test]
+fn test_closest_matches() {
+    let candidates = vec![
+        "Rust",
+        "Ruby",
+        "Python",
+        "Monokai Extended",
+        "Monokai Extended Light",
+    ];
+
+    assert_eq!(vec!["Rust"], closest_matches("rsut", candidates.clone()));
+    assert_eq!(
+        vec!["Ruby", "Rust"],
+        closest_matches("rub", candidates.clone())
+    );
+    assert_eq!(
+        vec!["Monokai Extended", "Monokai Extended Light"],
+        closest_matches("monokai", candidates.clone())
+    );
+    assert!(closest_matches("haskell", candidates).is_empty());
+}
@@ -0,0 +1,289 @@
+//! Locating the definitions of symbols (like functions, structs or classes) in a text via the
+//! scopes of its syntax, for `Config::focus_symbol`.
+//!
+//! A definition starts at the line that declares the name (including the comments and
+//! attributes directly above it) and ends with its block: at the matching closing brace, at a
+//! `;` or, for languages without braces, after the lines that are indented further (like in
+//! Python). Headings of Markdown files extend to the next heading of the same level.
+
+use syntect::parsing::{ParseState, Scope, ScopeStack, ScopeStackOp, SyntaxReference, SyntaxSet};
+use syntect::util::LinesWithEndings;
+
+use crate::printer::SECTION_SCOPES;
+
+/// A line of the text, analyzed by the parser
+struct ParsedLine<'t> {
+    text: &'t str,
+
+    /// The text of the line with strings and comments replaced by spaces
+    code: String,
+
+    /// The names that are declared in the line
+    names: Vec<&'t str>,
+
+    /// The level of the Markdown heading in the line
+    heading: Option<usize>,
+}
+
+impl<'t> ParsedLine<'t> {
+    fn is_blank(&self) -> bool {
+        self.text.trim().is_empty()
+    }
+
+    /// Whether the line belongs to a definition below it, like a doc comment, an attribute
+    /// or a decorator
+    fn is_attached(&self) -> bool {
+        let code = self.code.trim_start();
+        !self.is_blank() && (code.is_empty() || code.starts_with("#[") || code.starts_with('@'))
+    }
+
+    fn indentation(&self) -> usize {
+        self.text.len() - self.text.trim_start().len()
+    }
+}
+
+fn parse_lines<'t>(
+    syntax_set: &SyntaxSet,
+    syntax: &SyntaxReference,
+    text: &'t str,
+) -> Vec<ParsedLine<'t>> {
+    let declaration_scopes: Vec<Scope> = SECTION_SCOPES
+        .iter()
+        .map(|scope| Scope::new(scope).expect("valid scope"))
+        .collect();
+    let literal_scopes = [
+        Scope::new("string").expect("valid scope"),
+        Scope::new("comment").expect("valid scope"),
+    ];
+    let heading_scope = Scope::new("markup.heading").expect("valid scope");
+
+    let mut parser = ParseState::new(syntax);
+    let mut stack = ScopeStack::new();
+    let mut lines = vec![];
+
+    for text in LinesWithEndings::from(text) {
+        let mut line = ParsedLine {
+            text,
+            code: String::with_capacity(text.len()),
+            names: vec![],
+            heading: None,
+        };
+
+        // The text up to each operation is classified with the scopes before it, and the text
+        // after the last one with the scopes at the end of the line
+        let ops = parser.parse_line(text, syntax_set);
+        let ops = ops
+            .iter()
+            .map(|(index, op)| (*index, Some(op)))
+            .chain(Some((text.len(), None)));
+
+        let mut position = 0;
+        for (index, op) in ops {
+            let segment = &text[position..index];
+            let in_literal = stack
+                .as_slice()
+                .iter()
+                .any(|scope| literal_scopes.iter().any(|l| l.is_prefix_of(*scope)));
+            if in_literal {
+                line.code.extend(segment.chars().map(|_| ' '));
+            } else {
+                line.code.push_str(segment);
+            }
+            position = index;
+
+            if let Some(op) = op {
+                if let ScopeStackOp::Push(ref scope) = op {
+                    if heading_scope.is_prefix_of(*scope) {
+                        let heading = text.trim();
+                        let title = heading.trim_start_matches('#');
+                        line.heading = Some(heading.len() - title.len());
+                        line.names.push(title.trim());
+                    } else if declaration_scopes.iter().any(|d| d.is_prefix_of(*scope)) {
+                        let rest = &text[index..];
+                        let name = &rest[..rest
+                            .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$'))
+                            .unwrap_or(rest.len())];
+                        if !name.is_empty() && !line.names.contains(&name) {
+                            line.names.push(name);
+                        }
+                    }
+                }
+                stack.apply(op);
+            }
+        }
+
+        lines.push(line);
+    }
+
+    lines
+}
+
+/// The declarations of a text
+pub(crate) struct Symbols<'t> {
+    lines: Vec<ParsedLine<'t>>,
+}
+
+impl<'t> Symbols<'t> {
+    pub(crate) fn parse(syntax_set: &SyntaxSet, syntax: &SyntaxReference, text: &'t str) -> Self {
+        Symbols {
+            lines: parse_lines(syntax_set, syntax, text),
+        }
+    }
+
+    /// The names that are declared in the text (without duplicates)
+    pub(crate) fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = vec![];
+        for name in self.lines.iter().flat_map(|line| line.names.iter()) {
+            if !names.contains(name) {
+                names.push(name);
+            }
+        }
+        names
+    }
+
+    /// The first and the last line (1-based) of the (first) definition of the given name
+    pub(crate) fn definition(&self, name: &str) -> Option<(usize, usize)> {
+        let lines = &self.lines;
+        let start = lines.iter().position(|line| line.names.contains(&name))?;
+
+        let mut end = match lines[start].heading {
+            Some(level) => section_end(lines, start, level),
+            None => block_end(lines, start),
+        };
+        while end > start && lines[end].is_blank() {
+            end -= 1;
+        }
+
+        let mut first = start;
+        while first > 0 && lines[first - 1].is_attached() {
+            first -= 1;
+        }
+
+        Some((first + 1, end + 1))
+    }
+}
+
+/// The last line of a definition with a block in braces (or with a block that is indented)
+fn block_end(lines: &[ParsedLine], start: usize) -> usize {
+    let mut depth = 0usize;
+    let mut opened = false;
+
+    for (index, line) in lines.iter().enumerate().skip(start) {
+        for c in line.code.chars() {
+            match c {
+                '{' if depth == 0 => {
+                    opened = true;
+                    depth += 1;
+                }
+                '{' | '(' | '[' => depth += 1,
+                '}' | ')' | ']' => depth = depth.saturating_sub(1),
+                ';' if depth == 0 => return index,
+                _ => {}
+            }
+
+            if opened && depth == 0 {
+                return index;
+            }
+        }
+
+        if !opened && depth == 0 && line.code.trim_end().ends_with(':') {
+            return indented_end(lines, start);
+        }
+    }
+
+    indented_end(lines, start)
+}
+
+/// The last line of the lines after `start` that are indented further (followed by a line
+/// with an `end` keyword, like in Ruby or Lua)
+fn indented_end(lines: &[ParsedLine], start: usize) -> usize {
+    let indentation = lines[start].indentation();
+    let mut end = start;
+
+    for (index, line) in lines.iter().enumerate().skip(start + 1) {
+        if line.is_blank() {
+            continue;
+        }
+        if line.indentation() > indentation {
+            end = index;
+        } else {
+            if line.code.trim_start().starts_with("end") {
+                end = index;
+            }
+            break;
+        }
+    }
+
+    end
+}
+
+/// The last line before the next heading that is not nested in the heading at `start`
+fn section_end(lines: &[ParsedLine], start: usize, level: usize) -> usize {
+    lines
+        .iter()
+        .enumerate()
+        .skip(start + 1)
+        .find(|(_, line)| line.heading.map_or(false, |other| other <= level))
+        .map(|(index, _)| index - 1)
+        .unwrap_or(lines.len() - 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assets::HighlightingAssets;
+
+    fn find(language: &str, text: &str, name: &str) -> Option<(usize, usize)> {
+        let assets = HighlightingAssets::from_binary();
+        let syntax = assets.get_syntax_by_token(language).unwrap();
+        Symbols::parse(assets.get_syntax_set(), syntax, text).definition(name)
+    }
+
+    #[test]
+    fn definitions_with_braces() {
+        let rust = "use std::io;\n\
+                    \n\
+                    /// Say hello\n\
+                    #[inline]\n\
+                    fn greet(name: &str) {\n\
+                    \x20   println!(\"}} {}\", name);\n\
+                    }\n\
+                    \n\
+                    struct Unit;\n\
+                    \n\
+                    fn main() {\n\
+                    \x20   greet(\"world\");\n\
+                    }\n";
+
+        assert_eq!(find("rs", rust, "greet"), Some((3, 7)));
+        assert_eq!(find("rs", rust, "Unit"), Some((9, 9)));
+        assert_eq!(find("rs", rust, "main"), Some((11, 13)));
+        assert_eq!(find("rs", rust, "io"), None);
+        assert_eq!(find("rs", rust, "missing"), None);
+    }
+
+    #[test]
+    fn indented_definitions() {
+        let python = "import os\n\
+                      \n\
+                      @decorated\n\
+                      def first(a, b={}):\n\
+                      \x20   return a\n\
+                      \n\
+                      \x20   # still part of it\n\
+                      \n\
+                      def second():\n\
+                      \x20   pass\n";
+
+        assert_eq!(find("py", python, "first"), Some((3, 7)));
+        assert_eq!(find("py", python, "second"), Some((9, 10)));
+    }
+
+    #[test]
+    fn markdown_sections() {
+        let markdown = "# Title\n\nText\n\n## Usage\n\nMore\n\n### Details\n\n## Other\n";
+
+        assert_eq!(find("md", markdown, "Usage"), Some((5, 9)));
+        assert_eq!(find("md", markdown, "Title"), Some((1, 11)));
+    }
+}
@@ -1,6 +1,7 @@
 use std::path::Path;
 
 use crate::error::Result;
+use crate::heuristics;
 
 use globset::{Candidate, GlobBuilder, GlobMatcher};
 
@@ -10,9 +11,38 @@ pub enum MappingTarget<'a> {
     MapToUnknown,
 }
 
+/// Chooses between several syntaxes that claim the same file extension. The function gets
+/// the file extension, the names of the candidate syntaxes and the beginning of the file
+/// content. It returns the index of the chosen candidate (or `None` if it can not decide).
+pub type Disambiguator = fn(extension: &str, candidates: &[&str], content: &str) -> Option<usize>;
+
+/// How a conflict between several syntaxes for the same file extension was resolved
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConflictResolution {
+    /// One of the registered disambiguators inspected the content and made a choice
+    Heuristic,
+
+    /// A syntax from the list of preferred syntaxes was chosen
+    Preference,
+
+    /// Neither the disambiguators nor the preferences applied. The syntax that was defined
+    /// last wins.
+    LastDefined,
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct SyntaxMapping<'a> {
     mappings: Vec<(GlobMatcher, MappingTarget<'a>)>,
+    preferred_syntaxes: Vec<&'a str>,
+    disambiguators: Vec<Disambiguator>,
+
+    /// Whether the language of files without a known extension is detected from their
+    /// content (see `detect_from_content`)
+    detect_from_content: bool,
+
+    /// Whether the `linguist-language` attributes of git repositories are used (see
+    /// `use_git_attributes`)
+    use_git_attributes: bool,
 }
 
 impl<'a> SyntaxMapping<'a> {
@@ -22,7 +52,8 @@ impl<'a> SyntaxMapping<'a> {
 
     pub fn builtin() -> SyntaxMapping<'a> {
         let mut mapping = Self::empty();
-        mapping.insert("*.h", MappingTarget::MapTo("C++")).unwrap();
+        mapping.prefer_syntax("C++");
+        mapping.add_disambiguator(heuristics::disambiguate);
         mapping
             .insert("build", MappingTarget::MapToUnknown)
             .unwrap();
@@ -54,6 +85,86 @@ impl<'a> SyntaxMapping<'a> {
         Ok(())
     }
 
+    /// Prefer the syntax with the given name whenever multiple syntaxes claim the same file
+    /// extension. Syntaxes that are preferred later take precedence.
+    pub fn prefer_syntax(&mut self, syntax_name: &'a str) {
+        self.preferred_syntaxes.push(syntax_name);
+    }
+
+    /// Register a content-based heuristic to choose between several syntaxes that claim the
+    /// same file extension. Disambiguators that are added later are consulted first and take
+    /// precedence over the list of preferred syntaxes.
+    pub fn add_disambiguator(&mut self, disambiguator: Disambiguator) {
+        self.disambiguators.push(disambiguator);
+    }
+
+    /// Detect the language of files whose syntax is not known from their name (or their first
+    /// line) by their content: Emacs and Vim modelines (`-*- mode: ruby -*-`,
+    /// `vim: ft=python`), the interpreter in the shebang line and distinctive contents like
+    /// XML declarations or JSON (default: false)
+    pub fn detect_from_content(&mut self, yes: bool) {
+        self.detect_from_content = yes;
+    }
+
+    pub(crate) fn detects_from_content(&self) -> bool {
+        self.detect_from_content
+    }
+
+    /// Use the `linguist-language` attributes in the `.gitattributes` files of git repositories
+    /// (like `*.inc linguist-language=PHP`) for files that are not mapped explicitly. The
+    /// attributes take precedence over the file names and extensions. Needs the `git` feature
+    /// (default: false).
+    pub fn use_git_attributes(&mut self, yes: bool) {
+        self.use_git_attributes = yes;
+    }
+
+    pub(crate) fn uses_git_attributes(&self) -> bool {
+        self.use_git_attributes
+    }
+
+    /// A description of the mappings that is the same in every process, for the keys of the
+    /// result cache. Of the disambiguators (which are functions) only the number is included.
+    pub(crate) fn fingerprint(&self) -> String {
+        format!(
+            "{:?}\0{:?}\0{}\0{}\0{}",
+            self.mappings,
+            self.preferred_syntaxes,
+            self.disambiguators.len(),
+            self.detect_from_content,
+            self.use_git_attributes
+        )
+    }
+
+    pub(crate) fn needs_content_for_conflicts(&self) -> bool {
+        !self.disambiguators.is_empty()
+    }
+
+    /// Pick one of the candidates (given in order of their definition, last one first) that
+    /// claim the file extension.
+    pub(crate) fn resolve_conflict(
+        &self,
+        extension: &str,
+        candidates: &[&str],
+        content: &str,
+    ) -> (usize, ConflictResolution) {
+        for disambiguator in self.disambiguators.iter().rev() {
+            match disambiguator(extension, candidates, content) {
+                Some(index) if index < candidates.len() => {
+                    return (index, ConflictResolution::Heuristic)
+                }
+                _ => {}
+            }
+        }
+
+        for preferred in self.preferred_syntaxes.iter().rev() {
+            if let Some(index) = candidates.iter().position(|c| c == preferred) {
+                return (index, ConflictResolution::Preference);
+            }
+        }
+
+        (0, ConflictResolution::LastDefined)
+    }
+
     pub(crate) fn get_syntax_for(&self, path: impl AsRef<Path>) -> Option<MappingTarget<'a>> {
         let candidate = Candidate::new(path.as_ref());
         let canddidate_filename = path.as_ref().file_name().map(Candidate::new);
@@ -115,3 +226,39 @@ fn builtin_mappings() {
         Some(MappingTarget::MapToUnknown)
     );
 }
+
+#[test]
+fn conflict_resolution() {
+    fn prefer_c_for_includes(_: &str, candidates: &[&str], content: &str) -> Option<usize> {
+        if content.starts_with("#include") {
+            candidates.iter().position(|c| *c == "C")
+        } else {
+            None
+        }
+    }
+
+    let candidates = ["Objective-C", "C++", "C"];
+    let mut map = SyntaxMapping::empty();
+
+    assert_eq!(
+        map.resolve_conflict("h", &candidates, ""),
+        (0, ConflictResolution::LastDefined)
+    );
+
+    map.prefer_syntax("C");
+    map.prefer_syntax("C++");
+    assert_eq!(
+        map.resolve_conflict("h", &candidates, ""),
+        (1, ConflictResolution::Preference)
+    );
+
+    map.add_disambiguator(prefer_c_for_includes);
+    assert_eq!(
+        map.resolve_conflict("h", &candidates, "#include <stdio.h>"),
+        (2, ConflictResolution::Heuristic)
+    );
+    assert_eq!(
+        map.resolve_conflict("h", &candidates, "int x;"),
+        (1, ConflictResolution::Preference)
+    );
+}
@@ -0,0 +1,315 @@
+//! Templates for the header, the footer and the line number gutter, like
+//! `"{path} • {language} • {size}"`. Fields are written in braces (`{{` and `}}` are literal
+//! braces) and `\e` inserts an escape character, so that templates can contain their own
+//! ANSI color sequences.
+
+use std::fs::Metadata;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use console::{measure_text_width, AnsiCodeIterator};
+
+use unicode_width::UnicodeWidthChar;
+
+use crate::error::*;
+
+/// The fields of header templates
+pub const HEADER_FIELDS: &[&str] = &[
+    "path",
+    "name",
+    "language",
+    "size",
+    "modified",
+    "permissions",
+    "mode",
+    "revision",
+];
+
+/// The fields of footer templates. The number of lines and the range of printed lines are
+/// only known after the input has been printed.
+pub const FOOTER_FIELDS: &[&str] = &[
+    "path",
+    "name",
+    "language",
+    "size",
+    "modified",
+    "permissions",
+    "mode",
+    "revision",
+    "lines",
+    "range",
+];
+
+/// The fields of line number templates
+pub const LINE_NUMBER_FIELDS: &[&str] = &["number"];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Part {
+    Text(String),
+    Field(String),
+}
+
+/// A parsed template
+#[derive(Debug, Clone, PartialEq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+impl Template {
+    /// Parse a template for the header of a file (see `HEADER_FIELDS`)
+    pub fn header(template: &str) -> Result<Self> {
+        Template::parse(template, HEADER_FIELDS)
+    }
+
+    /// Parse a template for the footer of a file (see `FOOTER_FIELDS`)
+    pub fn footer(template: &str) -> Result<Self> {
+        Template::parse(template, FOOTER_FIELDS)
+    }
+
+    /// Parse a template for the line numbers (see `LINE_NUMBER_FIELDS`). The `{number}` field
+    /// is padded to (at least) four characters.
+    pub fn line_number(template: &str) -> Result<Self> {
+        Template::parse(template, LINE_NUMBER_FIELDS)
+    }
+
+    /// Whether the template contains the given field
+    pub(crate) fn uses_field(&self, field: &str) -> bool {
+        self.parts.iter().any(|part| match part {
+            Part::Field(name) => name == field,
+            Part::Text(_) => false,
+        })
+    }
+
+    /// Parse a template that may only use the given fields
+    pub fn parse(template: &str, fields: &[&str]) -> Result<Self> {
+        let invalid = |reason: String| -> Error {
+            format!("Invalid template '{}': {}", template, reason).into()
+        };
+
+        let mut parts = vec![];
+        let mut text = String::new();
+        let mut chars = template.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '\\' if chars.peek() == Some(&'e') => {
+                    chars.next();
+                    text.push('\x1b');
+                }
+                '\\' if chars.peek() == Some(&'\\') => {
+                    chars.next();
+                    text.push('\\');
+                }
+                '{' => {
+                    let mut field = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => field.push(c),
+                            None => return Err(invalid("unclosed '{'".into())),
+                        }
+                    }
+
+                    let field = field.trim();
+                    if !fields.contains(&field) {
+                        return Err(invalid(format!(
+                            "unknown field '{{{}}}' (available fields: {})",
+                            field,
+                            fields
+                                .iter()
+                                .map(|field| format!("{{{}}}", field))
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        )));
+                    }
+
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(Part::Field(field.to_owned()));
+                }
+                '}' => {
+                    return Err(invalid(
+                        "unmatched '}' (use '}}' for a literal brace)".into(),
+                    ))
+                }
+                c => text.push(c),
+            }
+        }
+
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+
+        Ok(Template { parts })
+    }
+
+    /// Fill in the fields of the template with the given values
+    pub(crate) fn render(&self, value: impl Fn(&str) -> String) -> String {
+        let mut rendered = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => rendered.push_str(text),
+                Part::Field(field) => rendered.push_str(&value(field)),
+            }
+        }
+        rendered
+    }
+
+    /// The width of a rendered line number (without escape sequences) for line numbers with
+    /// the given number of characters
+    pub(crate) fn line_number_width(&self, number_width: usize) -> usize {
+        measure_text_width(&self.render(|_| " ".repeat(number_width)))
+    }
+}
+
+/// Cut the given text to the given width (in terminal columns). Escape sequences don't take up
+/// any space and are all kept, so that colors are reset even if the text is cut.
+pub(crate) fn truncate(text: &str, width: usize) -> String {
+    if measure_text_width(text) <= width {
+        return text.to_owned();
+    }
+
+    let mut truncated = String::with_capacity(text.len());
+    let mut remaining = width;
+    for (chunk, is_escape) in AnsiCodeIterator::new(text) {
+        if is_escape {
+            truncated.push_str(chunk);
+            continue;
+        }
+
+        for c in chunk.chars() {
+            let c_width = c.width().unwrap_or(0);
+            if c_width > remaining {
+                remaining = 0;
+                break;
+            }
+            remaining -= c_width;
+            truncated.push(c);
+        }
+    }
+    truncated
+}
+
+/// A file size in a human-readable form (like `12.3 KiB`)
+pub(crate) fn human_size(bytes: u64) -> String {
+    const UNITS: &[&str] = &["KiB", "MiB", "GiB", "TiB"];
+
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+/// A point in time as a UTC date and time (like `2020-05-04 13:37`)
+pub(crate) fn utc_time(time: SystemTime) -> String {
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+    let (days, seconds) = (seconds / 86400, seconds % 86400);
+
+    // The civil date of the days since 1970-01-01, in eras of 400 years that start on March 1
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds % 3600 / 60
+    )
+}
+
+/// The permissions of a file like `ls -l` shows them (`-rw-r--r--`), or whether the file is
+/// read-only on systems without Unix permissions
+pub(crate) fn permissions(metadata: &Metadata) -> String {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mode = metadata.permissions().mode();
+        let mut permissions = String::from(if metadata.is_dir() { "d" } else { "-" });
+        for shift in &[6, 3, 0] {
+            let bits = mode >> shift;
+            permissions.push(if bits & 0o4 != 0 { 'r' } else { '-' });
+            permissions.push(if bits & 0o2 != 0 { 'w' } else { '-' });
+            permissions.push(if bits & 0o1 != 0 { 'x' } else { '-' });
+        }
+        permissions
+    }
+
+    #[cfg(not(unix))]
+    {
+        if metadata.permissions().readonly() {
+            "read-only".into()
+        } else {
+            "read-write".into()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_templates() {
+        let template = Template::header("{path} • {language} {{{size}}}").unwrap();
+        let rendered = template.render(|field| match field {
+            "path" => "src/main.rs".into(),
+            "language" => "Rust".into(),
+            _ => "1.0 KiB".into(),
+        });
+        assert_eq!(rendered, "src/main.rs • Rust {1.0 KiB}");
+
+        let numbers = Template::line_number("\\e[1m{number}\\e[0m:").unwrap();
+        assert_eq!(numbers.line_number_width(4), 5);
+
+        assert!(Template::header("{lines}").is_err());
+        assert!(Template::footer("{lines} lines").is_ok());
+        assert!(Template::footer("{path").is_err());
+        assert!(Template::footer("path}").is_err());
+    }
+
+    #[test]
+    fn truncate_keeps_escape_sequences() {
+        assert_eq!(truncate("\x1b[1mabc•def\x1b[0m", 4), "\x1b[1mabc•\x1b[0m");
+        assert_eq!(truncate("abc", 4), "abc");
+        assert_eq!(human_size(512), "512 B");
+        assert_eq!(human_size(1536), "1.5 KiB");
+        assert_eq!(human_size(5 * 1024 * 1024), "5.0 MiB");
+
+        let time = |seconds| UNIX_EPOCH + std::time::Duration::from_secs(seconds);
+        assert_eq!(utc_time(time(0)), "1970-01-01 00:00");
+        assert_eq!(utc_time(time(1_588_599_420)), "2020-05-04 13:37");
+        assert_eq!(utc_time(time(951_782_400)), "2000-02-29 00:00");
+    }
+}
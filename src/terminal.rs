@@ -1,5 +1,5 @@
 use ansi_term::Color::{self, Fixed, RGB};
-use ansi_term::{self, Style};
+use ansi_term::{self, ANSIString, Style};
 
 use syntect::highlighting::{self, FontStyle};
 
@@ -39,16 +39,16 @@ pub fn to_ansi_color(color: highlighting::Color, true_color: bool) -> ansi_term:
     }
 }
 
-pub fn as_terminal_escaped(
+pub fn as_terminal_escaped<'a>(
     style: highlighting::Style,
-    text: &str,
+    text: &'a str,
     true_color: bool,
     colored: bool,
     italics: bool,
     background_color: Option<highlighting::Color>,
-) -> String {
+) -> ANSIString<'a> {
     if text.is_empty() {
-        return text.to_string();
+        return Style::default().paint(text);
     }
 
     let mut style = if !colored {
@@ -68,5 +68,5 @@ pub fn as_terminal_escaped(
     };
 
     style.background = background_color.map(|c| to_ansi_color(c, true_color));
-    style.paint(text).to_string()
+    style.paint(text)
 }
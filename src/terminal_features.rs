@@ -0,0 +1,355 @@
+//! The features of the terminal that bat prints to, like italics and 24-bit colors, which are
+//! detected from its terminfo entry and from `COLORTERM` (see `TerminalFeatures::detect`).
+//! Terminals whose terminfo entries are known to be wrong or incomplete (or that use the entry
+//! of another terminal, like `xterm-256color`) are corrected by a table of quirks.
+
+use std::convert::TryFrom;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+
+/// The directories that are searched for terminfo entries after `TERMINFO`, `~/.terminfo` and
+/// `TERMINFO_DIRS`
+const TERMINFO_DIRS: &[&str] = &["/etc/terminfo", "/lib/terminfo", "/usr/share/terminfo"];
+
+/// The magic numbers of compiled terminfo entries with 16-bit and 32-bit numbers
+const MAGIC_LEGACY: u16 = 0o432;
+const MAGIC_32BIT: u16 = 0o1036;
+
+/// The index of `enter_italics_mode` (`sitm`) in the standard string capabilities
+const ENTER_ITALICS_MODE: usize = 311;
+
+/// The features that a terminal supports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TerminalFeatures {
+    pub italics: bool,
+
+    /// Curly underlines (`CSI 4:3 m`)
+    pub undercurl: bool,
+
+    /// 24-bit colors
+    pub true_color: bool,
+}
+
+/// How a quirky terminal is recognized
+enum Terminal {
+    /// A prefix of `TERM`
+    Term(&'static str),
+
+    /// The value of `TERM_PROGRAM`
+    Program(&'static str),
+}
+
+/// The features of a terminal that are known better than its terminfo entry knows them
+struct Quirk {
+    terminal: Terminal,
+    italics: Option<bool>,
+    undercurl: Option<bool>,
+    true_color: Option<bool>,
+}
+
+const fn quirk(
+    terminal: Terminal,
+    italics: Option<bool>,
+    undercurl: Option<bool>,
+    true_color: Option<bool>,
+) -> Quirk {
+    Quirk {
+        terminal,
+        italics,
+        undercurl,
+        true_color,
+    }
+}
+
+/// Whether a quirky terminal supports a feature, `None` if its terminfo entry is right
+const YES: Option<bool> = Some(true);
+const NO: Option<bool> = Some(false);
+
+const QUIRKS: &[Quirk] = &[
+    // The Linux console has neither italics nor 24-bit colors, `COLORTERM` may be inherited
+    quirk(Terminal::Term("linux"), NO, NO, NO),
+    quirk(Terminal::Term("xterm-kitty"), YES, YES, YES),
+    quirk(Terminal::Term("foot"), YES, YES, YES),
+    quirk(Terminal::Term("alacritty"), YES, None, YES),
+    quirk(Terminal::Term("wezterm"), YES, YES, YES),
+    quirk(Terminal::Program("WezTerm"), YES, YES, YES),
+    quirk(Terminal::Program("iTerm.app"), YES, None, YES),
+    quirk(Terminal::Program("vscode"), YES, YES, YES),
+    // Terminal.app uses the `xterm-256color` entry, but it doesn't support 24-bit colors
+    quirk(Terminal::Program("Apple_Terminal"), None, NO, NO),
+];
+
+impl TerminalFeatures {
+    /// Detect the features of the terminal from the environment: the terminfo entry of
+    /// `TERM`, `COLORTERM` and the quirks of known terminals
+    pub fn detect() -> Self {
+        Self::detect_with(|name| env::var(name).ok())
+    }
+
+    fn detect_with(var: impl Fn(&str) -> Option<String>) -> Self {
+        let term = var("TERM").unwrap_or_default();
+        let mut features = terminfo_paths(&term, &var)
+            .into_iter()
+            .find_map(|path| fs::read(path).ok())
+            .and_then(|entry| Self::from_terminfo(&entry))
+            .unwrap_or_default();
+
+        if let Some("truecolor") | Some("24bit") = var("COLORTERM").as_deref() {
+            features.true_color = true;
+        }
+
+        let program = var("TERM_PROGRAM").unwrap_or_default();
+        for quirk in QUIRKS {
+            let applies = match quirk.terminal {
+                Terminal::Term(prefix) => term.starts_with(prefix),
+                Terminal::Program(name) => program == name,
+            };
+            if applies {
+                features.italics = quirk.italics.unwrap_or(features.italics);
+                features.undercurl = quirk.undercurl.unwrap_or(features.undercurl);
+                features.true_color = quirk.true_color.unwrap_or(features.true_color);
+            }
+        }
+
+        features
+    }
+
+    /// The features of a compiled terminfo entry, or `None` if it is invalid
+    fn from_terminfo(entry: &[u8]) -> Option<Self> {
+        let terminfo = Terminfo::parse(entry)?;
+        Some(TerminalFeatures {
+            italics: terminfo
+                .strings
+                .get(ENTER_ITALICS_MODE)
+                .map_or(false, Option::is_some),
+            undercurl: terminfo.extended("Smulx") || terminfo.extended("Su"),
+            true_color: terminfo.extended("Tc") || terminfo.extended("RGB"),
+        })
+    }
+}
+
+/// Where the terminfo entry of a terminal can be, in the order of the lookup of ncurses
+fn terminfo_paths(term: &str, var: impl Fn(&str) -> Option<String>) -> Vec<PathBuf> {
+    let first = match term.chars().next() {
+        Some(first) if !term.contains('/') => first,
+        _ => return vec![],
+    };
+
+    let mut dirs: Vec<PathBuf> = vec![];
+    dirs.extend(var("TERMINFO").map(PathBuf::from));
+    dirs.extend(var("HOME").map(|home| PathBuf::from(home).join(".terminfo")));
+    if let Some(terminfo_dirs) = var("TERMINFO_DIRS") {
+        for dir in terminfo_dirs.split(':') {
+            // An empty entry stands for the default directories
+            if dir.is_empty() {
+                dirs.extend(TERMINFO_DIRS.iter().map(PathBuf::from));
+            } else {
+                dirs.push(dir.into());
+            }
+        }
+    }
+    dirs.extend(TERMINFO_DIRS.iter().map(PathBuf::from));
+
+    dirs.iter()
+        .flat_map(|dir| {
+            vec![
+                dir.join(first.to_string()).join(term),
+                // The layout of macOS
+                dir.join(format!("{:x}", first as u32)).join(term),
+            ]
+        })
+        .collect()
+}
+
+/// The capabilities of a compiled terminfo entry (see `term(5)`) that are needed to detect
+/// the features
+struct Terminfo {
+    strings: Vec<Option<Vec<u8>>>,
+
+    /// The names of the extended capabilities that are set
+    extended: Vec<String>,
+}
+
+/// A cursor over the little-endian data of a compiled terminfo entry
+struct Reader<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, count: usize) -> Option<&'a [u8]> {
+        let bytes = self
+            .data
+            .get(self.position..self.position.checked_add(count)?)?;
+        self.position += count;
+        Some(bytes)
+    }
+
+    fn i16(&mut self) -> Option<i16> {
+        let bytes = self.bytes(2)?;
+        Some(i16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn count(&mut self) -> Option<usize> {
+        match self.i16()? {
+            count if count >= 0 => Some(count as usize),
+            -1 => Some(0),
+            _ => None,
+        }
+    }
+
+    fn offsets(&mut self, count: usize) -> Option<Vec<i16>> {
+        (0..count).map(|_| self.i16()).collect()
+    }
+
+    /// Sections start at even positions
+    fn align(&mut self) {
+        self.position += self.position % 2;
+    }
+
+    fn at_end(&self) -> bool {
+        self.position >= self.data.len()
+    }
+}
+
+/// The null-terminated string at an offset of a string table
+fn table_string(table: &[u8], offset: i16) -> Option<&[u8]> {
+    let rest = table.get(usize::try_from(offset).ok()?..)?;
+    Some(&rest[..rest.iter().position(|&byte| byte == 0)?])
+}
+
+impl Terminfo {
+    fn parse(entry: &[u8]) -> Option<Self> {
+        let mut reader = Reader {
+            data: entry,
+            position: 0,
+        };
+        let number_size = match reader.i16()? as u16 {
+            MAGIC_LEGACY => 2,
+            MAGIC_32BIT => 4,
+            _ => return None,
+        };
+        let names_size = reader.count()?;
+        let boolean_count = reader.count()?;
+        let number_count = reader.count()?;
+        let string_count = reader.count()?;
+        let table_size = reader.count()?;
+
+        reader.bytes(names_size)?;
+        reader.bytes(boolean_count)?;
+        reader.align();
+        reader.bytes(number_count * number_size)?;
+        let offsets = reader.offsets(string_count)?;
+        let table = reader.bytes(table_size)?;
+        let strings = offsets
+            .iter()
+            .map(|&offset| table_string(table, offset).map(<[u8]>::to_vec))
+            .collect();
+
+        let mut terminfo = Terminfo {
+            strings,
+            extended: vec![],
+        };
+
+        reader.align();
+        if reader.at_end() {
+            return Some(terminfo);
+        }
+        let booleans = reader.count()?;
+        let numbers = reader.count()?;
+        let strings = reader.count()?;
+        let _items = reader.count()?;
+        let table_size = reader.count()?;
+
+        let boolean_values = reader.bytes(booleans)?;
+        reader.align();
+        let number_values = reader.bytes(numbers * number_size)?;
+        let string_offsets = reader.offsets(strings)?;
+        let name_offsets = reader.offsets(booleans + numbers + strings)?;
+        let table = reader.bytes(table_size)?;
+
+        // The names follow the values of the strings in the table
+        let names_start = string_offsets
+            .iter()
+            .filter_map(|&offset| {
+                let value = table_string(table, offset)?;
+                Some(offset as usize + value.len() + 1)
+            })
+            .max()
+            .unwrap_or(0);
+        let names = &table[names_start.min(table.len())..];
+
+        let is_set = |index: usize| {
+            if index < booleans {
+                boolean_values[index] == 1
+            } else if index < booleans + numbers {
+                let start = (index - booleans) * number_size;
+                // Absent numbers are negative
+                number_values[start + number_size - 1] & 0x80 == 0
+            } else {
+                string_offsets[index - booleans - numbers] >= 0
+            }
+        };
+        for (index, &offset) in name_offsets.iter().enumerate() {
+            if is_set(index) {
+                let name = table_string(names, offset)?;
+                terminfo
+                    .extended
+                    .push(String::from_utf8_lossy(name).into_owned());
+            }
+        }
+
+        Some(terminfo)
+    }
+
+    fn extended(&self, name: &str) -> bool {
+        self.extended.iter().any(|extended| extended == name)
+    }
+}
+
+#[test]
+fn features_are_read_from_terminfo() {
+    let entry = include_bytes!("../tests/terminfo/b/bat-test");
+    assert_eq!(
+        TerminalFeatures::from_terminfo(entry),
+        Some(TerminalFeatures {
+            italics: true,
+            undercurl: true,
+            true_color: true,
+        })
+    );
+    assert_eq!(
+        TerminalFeatures::from_terminfo(b"not a terminfo entry"),
+        None
+    );
+}
+
+#[test]
+fn features_are_detected_from_the_environment() {
+    let terminfo = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/terminfo");
+    let detect = |vars: &[(&str, &str)]| {
+        TerminalFeatures::detect_with(|name| {
+            vars.iter()
+                .chain(&[("TERMINFO", terminfo)])
+                .find(|(var, _)| *var == name)
+                .map(|(_, value)| value.to_string())
+        })
+    };
+
+    assert!(detect(&[("TERM", "bat-test")]).undercurl);
+    assert_eq!(detect(&[("TERM", "unknown")]), TerminalFeatures::default());
+    assert!(detect(&[("TERM", "unknown"), ("COLORTERM", "truecolor")]).true_color);
+    assert_eq!(
+        detect(&[("TERM", "linux"), ("COLORTERM", "truecolor")]),
+        TerminalFeatures::default()
+    );
+    assert_eq!(
+        detect(&[("TERM", "bat-test"), ("TERM_PROGRAM", "Apple_Terminal")]),
+        TerminalFeatures {
+            italics: true,
+            undercurl: false,
+            true_color: false,
+        }
+    );
+}
@@ -0,0 +1,364 @@
+//! Conversion of highlighting themes into the color schemes of terminal emulators (and CSS
+//! variables), so that the terminal can be matched to the theme that bat uses.
+//!
+//! The sixteen palette colors are derived from the colors of the theme: each of the six
+//! "colorful" palette entries is taken from the theme color with the closest hue (if there is
+//! one that is close enough), black and white are the background and the foreground color.
+
+use std::fmt::Write;
+
+use syntect::highlighting::{Color, Highlighter, Theme};
+use syntect::parsing::Scope;
+
+use crate::error::*;
+use crate::theme_fallback::is_rgb;
+
+/// The format of an exported theme
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ThemeExportFormat {
+    /// The `colors` section of an Alacritty configuration file (TOML)
+    Alacritty,
+
+    /// A WezTerm color scheme file (TOML)
+    WezTerm,
+
+    /// The color settings of a Kitty configuration file
+    Kitty,
+
+    /// CSS custom properties (`--bat-foreground`, `--bat-red`, ...) on `:root`
+    Css,
+}
+
+impl ThemeExportFormat {
+    /// The format with the given (lowercase) name: `alacritty`, `wezterm`, `kitty` or `css`
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "alacritty" => Some(ThemeExportFormat::Alacritty),
+            "wezterm" => Some(ThemeExportFormat::WezTerm),
+            "kitty" => Some(ThemeExportFormat::Kitty),
+            "css" => Some(ThemeExportFormat::Css),
+            _ => None,
+        }
+    }
+}
+
+/// The names of the palette colors, in the order of their (ANSI) indices
+const COLOR_NAMES: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+/// The colors of the xterm palette, for palette entries that the theme has no color for
+const XTERM_COLORS: [(u8, u8, u8); 8] = [
+    (0x00, 0x00, 0x00),
+    (0xcd, 0x00, 0x00),
+    (0x00, 0xcd, 0x00),
+    (0xcd, 0xcd, 0x00),
+    (0x00, 0x00, 0xee),
+    (0xcd, 0x00, 0xcd),
+    (0x00, 0xcd, 0xcd),
+    (0xe5, 0xe5, 0xe5),
+];
+
+/// The hues (in degrees) of the palette colors (black and white are not taken from the hues)
+const HUES: [f32; 8] = [0.0, 0.0, 120.0, 60.0, 240.0, 300.0, 180.0, 0.0];
+
+/// The largest difference in hue (in degrees) between a palette entry and a theme color
+const MAX_HUE_DISTANCE: f32 = 45.0;
+
+/// The colors of a terminal, derived from a theme
+struct TerminalColors {
+    foreground: Color,
+    background: Color,
+    cursor: Color,
+    selection_foreground: Color,
+    selection_background: Color,
+
+    /// The normal (0-7) and the bright (8-15) palette colors
+    palette: [Color; 16],
+}
+
+impl TerminalColors {
+    fn new(name: &str, theme: &Theme) -> Result<Self> {
+        let (foreground, background) = match (theme.settings.foreground, theme.settings.background)
+        {
+            (Some(foreground), Some(background)) if is_rgb(foreground) && is_rgb(background) => {
+                (opaque(foreground), opaque(background))
+            }
+            _ => {
+                return Err(format!(
+                    "The theme '{}' uses the colors of the terminal and can not be exported",
+                    name
+                )
+                .into())
+            }
+        };
+        let setting = |color: Option<Color>, default: Color| match color {
+            Some(color) if is_rgb(color) => blend(color, background),
+            _ => default,
+        };
+
+        let theme_colors: Vec<Color> = theme
+            .scopes
+            .iter()
+            .filter_map(|item| item.style.foreground)
+            .filter(|color| is_rgb(*color))
+            .map(|color| blend(color, background))
+            .collect();
+
+        let mut palette = [background; 16];
+        for (index, &(r, g, b)) in XTERM_COLORS.iter().enumerate() {
+            let xterm_color = Color { r, g, b, a: 0xff };
+            let color = match index {
+                0 => background,
+                7 => foreground,
+                _ => closest_hue(&theme_colors, HUES[index]).unwrap_or(xterm_color),
+            };
+            palette[index] = color;
+            palette[index + 8] = mix(color, WHITE, 0.25);
+        }
+        palette[8] = comment_color(theme).map_or_else(
+            || mix(foreground, background, 0.5),
+            |color| blend(color, background),
+        );
+
+        Ok(TerminalColors {
+            foreground,
+            background,
+            cursor: setting(theme.settings.caret, foreground),
+            selection_foreground: setting(theme.settings.selection_foreground, foreground),
+            selection_background: setting(
+                theme.settings.selection,
+                mix(foreground, background, 0.75),
+            ),
+            palette,
+        })
+    }
+
+    fn normal(&self) -> &[Color] {
+        &self.palette[..8]
+    }
+
+    fn bright(&self) -> &[Color] {
+        &self.palette[8..]
+    }
+}
+
+const WHITE: Color = Color {
+    r: 0xff,
+    g: 0xff,
+    b: 0xff,
+    a: 0xff,
+};
+
+fn opaque(color: Color) -> Color {
+    Color { a: 0xff, ..color }
+}
+
+/// Mix two colors: `amount` is the part of the second color
+fn mix(a: Color, b: Color, amount: f32) -> Color {
+    let channel =
+        |a: u8, b: u8| (f32::from(a) * (1.0 - amount) + f32::from(b) * amount).round() as u8;
+    Color {
+        r: channel(a.r, b.r),
+        g: channel(a.g, b.g),
+        b: channel(a.b, b.b),
+        a: 0xff,
+    }
+}
+
+/// Put a (semi-transparent) color on the background
+fn blend(color: Color, background: Color) -> Color {
+    mix(background, color, f32::from(color.a) / 255.0)
+}
+
+/// The hue (in degrees) and the saturation of a color
+fn hue_and_saturation(color: Color) -> (f32, f32) {
+    let (r, g, b) = (
+        f32::from(color.r) / 255.0,
+        f32::from(color.g) / 255.0,
+        f32::from(color.b) / 255.0,
+    );
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let delta = max - min;
+    if delta == 0.0 {
+        return (0.0, 0.0);
+    }
+
+    let hue = if max == r {
+        60.0 * ((g - b) / delta)
+    } else if max == g {
+        60.0 * ((b - r) / delta + 2.0)
+    } else {
+        60.0 * ((r - g) / delta + 4.0)
+    };
+    ((hue + 360.0) % 360.0, delta / max)
+}
+
+/// The (saturated) color with the hue that is closest to the given one
+fn closest_hue(colors: &[Color], hue: f32) -> Option<Color> {
+    colors
+        .iter()
+        .filter_map(|&color| {
+            let (color_hue, saturation) = hue_and_saturation(color);
+            let distance = (color_hue - hue).abs();
+            let distance = distance.min(360.0 - distance);
+            if saturation >= 0.25 && distance <= MAX_HUE_DISTANCE {
+                Some((distance, color))
+            } else {
+                None
+            }
+        })
+        .min_by(|a, b| a.0.partial_cmp(&b.0).expect("hue distances are not NaN"))
+        .map(|(_, color)| color)
+}
+
+fn comment_color(theme: &Theme) -> Option<Color> {
+    let scope = Scope::new("comment").expect("valid scope");
+    Highlighter::new(theme)
+        .style_mod_for_stack(&[scope])
+        .foreground
+        .filter(|color| is_rgb(*color))
+}
+
+fn hex(color: Color) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b)
+}
+
+fn quoted_list(colors: &[Color]) -> String {
+    let colors: Vec<String> = colors
+        .iter()
+        .map(|color| format!("\"{}\"", hex(*color)))
+        .collect();
+    format!("[{}]", colors.join(", "))
+}
+
+/// Convert a theme into the given format
+pub(crate) fn export_theme(name: &str, theme: &Theme, format: ThemeExportFormat) -> Result<String> {
+    let colors = TerminalColors::new(name, theme)?;
+    let mut out = String::new();
+
+    // Writing into a String can not fail
+    match format {
+        ThemeExportFormat::Alacritty => {
+            let _ = writeln!(out, "# The colors of the '{}' theme of bat\n", name);
+            let _ = writeln!(out, "[colors.primary]");
+            let _ = writeln!(out, "foreground = '{}'", hex(colors.foreground));
+            let _ = writeln!(out, "background = '{}'\n", hex(colors.background));
+            let _ = writeln!(out, "[colors.cursor]");
+            let _ = writeln!(out, "text = '{}'", hex(colors.background));
+            let _ = writeln!(out, "cursor = '{}'\n", hex(colors.cursor));
+            let _ = writeln!(out, "[colors.selection]");
+            let _ = writeln!(out, "text = '{}'", hex(colors.selection_foreground));
+            let _ = writeln!(out, "background = '{}'", hex(colors.selection_background));
+            for (section, palette) in &[("normal", colors.normal()), ("bright", colors.bright())] {
+                let _ = writeln!(out, "\n[colors.{}]", section);
+                for (name, color) in COLOR_NAMES.iter().zip(palette.iter()) {
+                    let _ = writeln!(out, "{} = '{}'", name, hex(*color));
+                }
+            }
+        }
+        ThemeExportFormat::WezTerm => {
+            let _ = writeln!(out, "# The colors of the '{}' theme of bat\n", name);
+            let _ = writeln!(out, "[metadata]");
+            let _ = writeln!(out, "name = \"{}\"\n", name.replace('"', "\\\""));
+            let _ = writeln!(out, "[colors]");
+            let _ = writeln!(out, "foreground = \"{}\"", hex(colors.foreground));
+            let _ = writeln!(out, "background = \"{}\"", hex(colors.background));
+            let _ = writeln!(out, "cursor_bg = \"{}\"", hex(colors.cursor));
+            let _ = writeln!(out, "cursor_fg = \"{}\"", hex(colors.background));
+            let _ = writeln!(out, "cursor_border = \"{}\"", hex(colors.cursor));
+            let _ = writeln!(
+                out,
+                "selection_fg = \"{}\"",
+                hex(colors.selection_foreground)
+            );
+            let _ = writeln!(
+                out,
+                "selection_bg = \"{}\"",
+                hex(colors.selection_background)
+            );
+            let _ = writeln!(out, "ansi = {}", quoted_list(colors.normal()));
+            let _ = writeln!(out, "brights = {}", quoted_list(colors.bright()));
+        }
+        ThemeExportFormat::Kitty => {
+            let _ = writeln!(out, "# The colors of the '{}' theme of bat\n", name);
+            let _ = writeln!(out, "foreground {}", hex(colors.foreground));
+            let _ = writeln!(out, "background {}", hex(colors.background));
+            let _ = writeln!(out, "cursor {}", hex(colors.cursor));
+            let _ = writeln!(out, "cursor_text_color {}", hex(colors.background));
+            let _ = writeln!(
+                out,
+                "selection_foreground {}",
+                hex(colors.selection_foreground)
+            );
+            let _ = writeln!(
+                out,
+                "selection_background {}",
+                hex(colors.selection_background)
+            );
+            for (index, color) in colors.palette.iter().enumerate() {
+                let _ = writeln!(out, "color{} {}", index, hex(*color));
+            }
+        }
+        ThemeExportFormat::Css => {
+            let _ = writeln!(out, "/* The colors of the '{}' theme of bat */", name);
+            let _ = writeln!(out, ":root {{");
+            let _ = writeln!(out, "  --bat-foreground: {};", hex(colors.foreground));
+            let _ = writeln!(out, "  --bat-background: {};", hex(colors.background));
+            let _ = writeln!(out, "  --bat-cursor: {};", hex(colors.cursor));
+            let _ = writeln!(
+                out,
+                "  --bat-selection-foreground: {};",
+                hex(colors.selection_foreground)
+            );
+            let _ = writeln!(
+                out,
+                "  --bat-selection-background: {};",
+                hex(colors.selection_background)
+            );
+            for (prefix, palette) in &[("", colors.normal()), ("bright-", colors.bright())] {
+                for (name, color) in COLOR_NAMES.iter().zip(palette.iter()) {
+                    let _ = writeln!(out, "  --bat-{}{}: {};", prefix, name, hex(*color));
+                }
+            }
+            let _ = writeln!(out, "}}");
+        }
+    }
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::assets::HighlightingAssets;
+
+    #[test]
+    fn palette_colors_are_taken_from_the_theme() {
+        let assets = HighlightingAssets::from_binary();
+        let kitty = assets
+            .export_theme("OneHalfDark", ThemeExportFormat::Kitty)
+            .unwrap();
+
+        assert!(kitty.contains("background #282c34\n"));
+        assert!(kitty.contains("color0 #282c34\n"));
+        assert!(kitty.contains("color1 #e06c75\n"));
+        assert!(kitty.contains("color4 #61afef\n"));
+        assert!(kitty.contains("color7 #dcdfe4\n"));
+        assert_eq!(kitty.lines().filter(|l| l.starts_with("color")).count(), 16);
+
+        let css = assets
+            .export_theme("OneHalfDark", ThemeExportFormat::Css)
+            .unwrap();
+        assert!(css.contains("  --bat-red: #e06c75;\n"));
+
+        assert!(assets
+            .export_theme("ansi-dark", ThemeExportFormat::Css)
+            .is_err());
+        assert!(assets
+            .export_theme("no-such-theme", ThemeExportFormat::Css)
+            .is_err());
+    }
+}
@@ -0,0 +1,169 @@
+//! Synthesized styles for (minimal, custom) themes that do not style the most common scopes.
+
+use std::borrow::Cow;
+use std::str::FromStr;
+
+use syntect::highlighting::{
+    Color, FontStyle, Highlighter, ScopeSelectors, StyleModifier, Theme, ThemeItem,
+};
+use syntect::parsing::Scope;
+
+/// "Bright black" in the encoding for terminal palette colors (see `to_ansi_color`)
+const ANSI_BRIGHT_BLACK: Color = Color {
+    r: 0x08,
+    g: 0x00,
+    b: 0x00,
+    a: 0x00,
+};
+
+/// Add rules for comments and keywords if the theme does not style them, deriving their colors
+/// from the base colors of the theme. Themes that style both (like all integrated themes) are
+/// returned as they are.
+pub(crate) fn with_fallback_styles(theme: &Theme) -> Cow<'_, Theme> {
+    let foreground = match theme.settings.foreground {
+        Some(foreground) => foreground,
+        None => return Cow::Borrowed(theme),
+    };
+
+    // Themes often only style some of the more specific scopes (like `keyword.control`)
+    let highlighter = Highlighter::new(theme);
+    let is_styled = |scopes: &[&str]| {
+        scopes.iter().any(|scope| {
+            let scope = Scope::new(scope).expect("valid scope");
+            highlighter
+                .style_mod_for_stack(&[scope])
+                .foreground
+                .is_some()
+        })
+    };
+    let comment_is_styled = is_styled(&["comment.line", "comment.block"]);
+    let keyword_is_styled = is_styled(&["keyword.control", "keyword.other", "storage.type"]);
+
+    if comment_is_styled && keyword_is_styled {
+        return Cow::Borrowed(theme);
+    }
+
+    let mut theme = theme.clone();
+    if !comment_is_styled {
+        theme.scopes.push(rule(
+            "comment",
+            comment_color(foreground, theme.settings.background),
+            FontStyle::empty(),
+        ));
+    }
+    if !keyword_is_styled {
+        let keyword_color = match theme.settings.accent {
+            Some(accent) if is_rgb(accent) && is_rgb(foreground) => accent,
+            _ => foreground,
+        };
+        theme
+            .scopes
+            .push(rule("keyword, storage", keyword_color, FontStyle::BOLD));
+    }
+
+    Cow::Owned(theme)
+}
+
+fn rule(selector: &str, foreground: Color, font_style: FontStyle) -> ThemeItem {
+    ThemeItem {
+        scope: ScopeSelectors::from_str(selector).expect("valid scope selector"),
+        style: StyleModifier {
+            foreground: Some(foreground),
+            background: None,
+            font_style: Some(font_style),
+        },
+    }
+}
+
+/// Whether the color is an RGB color (as opposed to a color of the terminal palette)
+pub(crate) fn is_rgb(color: Color) -> bool {
+    color.a != 0x00 && color.a != 0x0f
+}
+
+/// Comments are dimmed: halfway between the foreground and the background color
+fn comment_color(foreground: Color, background: Option<Color>) -> Color {
+    if !is_rgb(foreground) {
+        return ANSI_BRIGHT_BLACK;
+    }
+
+    let background = match background {
+        Some(background) if is_rgb(background) => background,
+        _ => {
+            let luminance =
+                (u32::from(foreground.r) + u32::from(foreground.g) + u32::from(foreground.b)) / 3;
+            let value = if luminance > 0x7f { 0x00 } else { 0xff };
+            Color {
+                r: value,
+                g: value,
+                b: value,
+                a: 0xff,
+            }
+        }
+    };
+
+    let mix = |a: u8, b: u8| ((u16::from(a) + u16::from(b)) / 2) as u8;
+    Color {
+        r: mix(foreground.r, background.r),
+        g: mix(foreground.g, background.g),
+        b: mix(foreground.b, background.b),
+        a: 0xff,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assets::HighlightingAssets;
+
+    fn minimal_theme() -> Theme {
+        let mut theme = Theme::default();
+        theme.settings.foreground = Some(Color {
+            r: 0xf0,
+            g: 0xf0,
+            b: 0xf0,
+            a: 0xff,
+        });
+        theme.settings.background = Some(Color {
+            r: 0x10,
+            g: 0x10,
+            b: 0x10,
+            a: 0xff,
+        });
+        theme
+    }
+
+    #[test]
+    fn integrated_themes_are_not_modified() {
+        let assets = HighlightingAssets::from_binary();
+        for name in assets.themes() {
+            let theme = assets.get_theme(name);
+            if let Cow::Owned(_) = with_fallback_styles(theme) {
+                panic!("theme '{}' was modified", name);
+            }
+        }
+    }
+
+    #[test]
+    fn minimal_theme_gets_comment_and_keyword_styles() {
+        let theme = minimal_theme();
+        let theme = with_fallback_styles(&theme);
+        let highlighter = Highlighter::new(&theme);
+        let style = |scope| highlighter.style_for_stack(&[Scope::new(scope).unwrap()]);
+
+        let comment = style("comment.line.double-slash.rust");
+        assert_eq!(
+            (
+                comment.foreground.r,
+                comment.foreground.g,
+                comment.foreground.b
+            ),
+            (0x80, 0x80, 0x80)
+        );
+
+        let keyword = style("keyword.control.rust");
+        assert_eq!(keyword.foreground.r, 0xf0);
+        assert!(keyword.font_style.contains(FontStyle::BOLD));
+
+        assert_eq!(style("source.rust").font_style, FontStyle::empty());
+    }
+}
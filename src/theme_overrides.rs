@@ -0,0 +1,183 @@
+//! Small adjustments of themes, so that bundled themes can be tweaked without copying their
+//! whole `.tmTheme` file. The overrides of a theme are kept in a YAML file next to the custom
+//! themes (`<theme name>.overrides.yaml` in the `themes` folder), which is applied to the theme
+//! when the cache is built (see `HighlightingAssets::from_files`):
+//!
+//! ```yaml
+//! # Monokai Extended.overrides.yaml
+//! italic: false
+//! scopes:
+//!   comment: fg=#88aa88
+//!   keyword, storage.type: fg=#f92672,bold
+//! ```
+//!
+//! `italic`, `bold` and `underline` remove these font styles from all rules of the theme. The
+//! styles of `scopes` (in the syntax of `PatternStyle::parse`) take precedence over the rules
+//! of the theme with the same scope selectors.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use serde::Deserialize;
+use syntect::highlighting::{FontStyle, ScopeSelectors, StyleModifier, Theme, ThemeItem};
+
+use crate::error::*;
+use crate::patterns::PatternStyle;
+
+/// The suffix of the names of override files, after the name of the theme
+pub const OVERRIDES_SUFFIX: &str = ".overrides.yaml";
+
+/// Adjustments of a theme
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThemeOverrides {
+    /// Whether the italic, bold and underlined font styles of the theme are kept
+    pub italic: bool,
+    pub bold: bool,
+    pub underline: bool,
+
+    /// Styles for scope selectors, in order of precedence
+    pub scopes: Vec<(String, PatternStyle)>,
+}
+
+impl Default for ThemeOverrides {
+    fn default() -> Self {
+        ThemeOverrides {
+            italic: true,
+            bold: true,
+            underline: true,
+            scopes: vec![],
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct OverridesFile {
+    italic: Option<bool>,
+    bold: Option<bool>,
+    underline: Option<bool>,
+    #[serde(default)]
+    scopes: BTreeMap<String, String>,
+}
+
+impl ThemeOverrides {
+    /// Parse the content of an override file
+    pub fn from_yaml(yaml: &str) -> Result<Self> {
+        let file: OverridesFile = serde_yaml::from_str(yaml)?;
+        let defaults = ThemeOverrides::default();
+        let scopes = file
+            .scopes
+            .into_iter()
+            .map(|(selector, style)| Ok((selector, PatternStyle::parse(&style)?)))
+            .collect::<Result<_>>()?;
+
+        Ok(ThemeOverrides {
+            italic: file.italic.unwrap_or(defaults.italic),
+            bold: file.bold.unwrap_or(defaults.bold),
+            underline: file.underline.unwrap_or(defaults.underline),
+            scopes,
+        })
+    }
+
+    /// Adjust the theme. Fails if one of the scope selectors is invalid (leaving the theme
+    /// unchanged).
+    pub fn apply(&self, theme: &mut Theme) -> Result<()> {
+        let mut rules = Vec::with_capacity(self.scopes.len());
+        for (selector, style) in &self.scopes {
+            let scope = ScopeSelectors::from_str(selector)
+                .map_err(|_| format!("Invalid scope selector '{}'", selector))?;
+            rules.push(ThemeItem {
+                scope,
+                style: StyleModifier {
+                    foreground: style.foreground,
+                    background: style.background,
+                    font_style: Some(style.font_style).filter(|style| !style.is_empty()),
+                },
+            });
+        }
+
+        let mut removed = FontStyle::empty();
+        removed.set(FontStyle::ITALIC, !self.italic);
+        removed.set(FontStyle::BOLD, !self.bold);
+        removed.set(FontStyle::UNDERLINE, !self.underline);
+        for item in &mut theme.scopes {
+            if let Some(ref mut font_style) = item.style.font_style {
+                font_style.remove(removed);
+            }
+        }
+
+        // Of the rules with equally specific selectors, the first one wins
+        theme.scopes.splice(0..0, rules);
+        Ok(())
+    }
+}
+
+/// The override files in a folder, with the names of the themes that they adjust
+pub(crate) fn overrides_in_folder(dir: &Path) -> Result<Vec<(String, ThemeOverrides)>> {
+    let mut overrides = vec![];
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return Ok(overrides),
+    };
+
+    for entry in entries {
+        let path = entry?.path();
+        let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+        if file_name.ends_with(OVERRIDES_SUFFIX) {
+            let theme = &file_name[..file_name.len() - OVERRIDES_SUFFIX.len()];
+            let yaml = fs::read_to_string(&path)?;
+            let theme_overrides = ThemeOverrides::from_yaml(&yaml)
+                .chain_err(|| format!("Invalid theme overrides '{}'", path.to_string_lossy()))?;
+            overrides.push((theme.to_owned(), theme_overrides));
+        }
+    }
+
+    overrides.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(overrides)
+}
+
+#[test]
+fn overrides_adjust_the_theme() {
+    use syntect::highlighting::{Color, Highlighter};
+    use syntect::parsing::Scope;
+
+    let overrides = ThemeOverrides::from_yaml(
+        "italic: false\nscopes:\n  comment: fg=#88aa88\n  string: bold\n",
+    )
+    .unwrap();
+    assert!(!overrides.italic && overrides.bold);
+
+    let mut theme = crate::assets::HighlightingAssets::from_binary()
+        .get_theme("Monokai Extended")
+        .clone();
+    let italic = |theme: &Theme| {
+        let highlighter = Highlighter::new(theme);
+        highlighter
+            .style_for_stack(&[Scope::new("storage.type").unwrap()])
+            .font_style
+            .contains(FontStyle::ITALIC)
+    };
+    assert!(italic(&theme));
+    overrides.apply(&mut theme).unwrap();
+
+    let highlighter = Highlighter::new(&theme);
+    let style = |scope: &str| highlighter.style_for_stack(&[Scope::new(scope).unwrap()]);
+    let comment = style("comment");
+    assert_eq!(
+        comment.foreground,
+        Color {
+            r: 0x88,
+            g: 0xaa,
+            b: 0x88,
+            a: 0xff
+        }
+    );
+    assert!(!italic(&theme));
+    assert!(style("string").font_style.contains(FontStyle::BOLD));
+
+    let invalid = ThemeOverrides::from_yaml("scopes:\n  comment: fg=nope\n");
+    assert!(invalid.is_err());
+    assert!(ThemeOverrides::from_yaml("colors: {}\n").is_err());
+}
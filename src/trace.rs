@@ -0,0 +1,443 @@
+//! Stack traces of Rust panics, Python exceptions, Java exceptions and Node.js errors: the
+//! messages and frames of a trace are highlighted on their own (independent of the syntax),
+//! and the source lines that the frames refer to can be inlined beneath them (see
+//! `Config::stack_trace`).
+
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use crate::json_lines::{cover, FormattedLine, LogLevel, Token};
+
+/// How stack traces are printed
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct StackTraceMode {
+    /// Inline the source lines that the frames refer to (if the files exist locally), with
+    /// this many lines of context before and after them
+    pub source_context: Option<usize>,
+}
+
+/// A frame of a stack trace that refers to a location in a source file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Frame {
+    /// The function of the frame, if the trace mentions it
+    pub function: Option<String>,
+
+    /// The path of the source file, as it appears in the trace
+    pub path: String,
+
+    /// The line number (starting at 1)
+    pub line: usize,
+
+    /// The column (starting at 1), if the trace mentions it
+    pub column: Option<usize>,
+}
+
+impl Frame {
+    /// The local file that the frame refers to, if it exists. Relative paths are resolved
+    /// against the current directory. Java traces only mention the file name, so the file is
+    /// also looked up in the directory of the package (and in `src/main/java`).
+    pub fn source_file(&self) -> Option<PathBuf> {
+        let path = self.path.trim_start_matches("file://");
+        let mut candidates = vec![PathBuf::from(path)];
+
+        if let Some(ref function) = self.function {
+            let qualified: Vec<&str> = function.split('.').collect();
+            if !path.contains(&['/', '\\'][..]) && qualified.len() > 2 {
+                // The last two parts are the class and the method
+                let package: PathBuf = qualified[..qualified.len() - 2].iter().collect();
+                candidates.push(package.join(path));
+                candidates.push(Path::new("src/main/java").join(package).join(path));
+            }
+        }
+
+        candidates.into_iter().find(|candidate| candidate.is_file())
+    }
+}
+
+/// The frames of a stack trace that refer to source files, in the order of the trace
+pub fn frames(trace: &str) -> Vec<Frame> {
+    let mut frames = vec![];
+    let mut function = None;
+
+    for line in trace.lines() {
+        let parsed = parse_line(line);
+        if let Some(mut frame) = parsed.frame {
+            // Rust backtraces mention the function on the line before the location
+            if frame.function.is_none() {
+                frame.function = function.take();
+            }
+            frames.push(frame);
+        }
+        function = parsed.function;
+    }
+
+    frames
+}
+
+#[derive(Debug, Default)]
+struct TraceLine {
+    tokens: Vec<(Range<usize>, Token)>,
+    frame: Option<Frame>,
+
+    /// The function of a numbered frame of a Rust backtrace (whose location is on the next
+    /// line)
+    function: Option<String>,
+}
+
+/// Highlight a line of a stack trace. Lines that are not part of a trace are kept as they are.
+pub(crate) fn format_line(line: &str) -> FormattedLine {
+    FormattedLine {
+        text: line.to_owned(),
+        tokens: cover(line.len(), parse_line(line).tokens),
+    }
+}
+
+/// The frame on a line of a stack trace, if any
+pub(crate) fn parse_frame(line: &str) -> Option<Frame> {
+    parse_line(line).frame
+}
+
+fn parse_line(line: &str) -> TraceLine {
+    let text = line.trim_end_matches(&['\r', '\n'][..]);
+    let start = text.len() - text.trim_start().len();
+    let mut parsed = TraceLine::default();
+
+    if start == text.len() {
+        // Empty line
+    } else if start == 0 {
+        parse_message(text, &mut parsed);
+    } else if !parse_python_frame(text, start, &mut parsed)
+        && !parse_at_frame(text, start, &mut parsed)
+    {
+        parse_numbered_frame(text, start, &mut parsed);
+    }
+
+    parsed
+}
+
+/// The headline of a trace, like `thread 'main' panicked at src/main.rs:2:5:` or
+/// `java.lang.IllegalStateException: message`
+fn parse_message(text: &str, parsed: &mut TraceLine) {
+    const PANIC: &str = " panicked at ";
+    const HEADINGS: &[&str] = &["Traceback (most recent call last)", "stack backtrace:"];
+    const MESSAGES: &[&str] = &["Exception in thread ", "Caused by: ", "Uncaught "];
+    const ERROR_SUFFIXES: &[&str] = &["Error", "Exception", "Exit", "Interrupt"];
+
+    if let Some(position) = text.find(PANIC) {
+        // The location is either at the end (`panicked at src/main.rs:2:5:`) or follows the
+        // message (`panicked at 'message', src/main.rs:2:5`)
+        let after = position + PANIC.len();
+        let end = after + text[after..].trim_end_matches(':').len();
+        let start = match text[after..end].rfind(", ") {
+            Some(separator) => after + separator + 2,
+            None => after,
+        };
+
+        match parse_location(text, start..end) {
+            Some(location) => {
+                parsed
+                    .tokens
+                    .push((0..start, Token::Level(LogLevel::Error)));
+                parsed.frame = Some(location.frame(text, None));
+                parsed.tokens.extend(location.tokens());
+                if end < text.len() {
+                    parsed
+                        .tokens
+                        .push((end..text.len(), Token::Level(LogLevel::Error)));
+                }
+            }
+            None => parsed
+                .tokens
+                .push((0..text.len(), Token::Level(LogLevel::Error))),
+        }
+        return;
+    }
+
+    if HEADINGS.iter().any(|heading| text.starts_with(heading)) {
+        parsed.tokens.push((0..text.len(), Token::Keyword));
+        return;
+    }
+
+    // `ValueError: ...`, `java.io.IOException: ...`, `Error: ...`
+    let name = &text[..text.find(':').unwrap_or(text.len())];
+    let is_error_name = !name.is_empty()
+        && !name.contains(char::is_whitespace)
+        && ERROR_SUFFIXES.iter().any(|suffix| name.ends_with(suffix));
+    if is_error_name || MESSAGES.iter().any(|message| text.starts_with(message)) {
+        parsed
+            .tokens
+            .push((0..text.len(), Token::Level(LogLevel::Error)));
+    }
+}
+
+/// A Python frame: `File "app.py", line 3, in main`
+fn parse_python_frame(text: &str, start: usize, parsed: &mut TraceLine) -> bool {
+    const FILE: &str = "File \"";
+    const LINE: &str = "\", line ";
+    const IN: &str = ", in ";
+
+    if !text[start..].starts_with(FILE) {
+        return false;
+    }
+    let path = start + FILE.len()..match text[start + FILE.len()..].find(LINE) {
+        Some(end) => start + FILE.len() + end,
+        None => return false,
+    };
+    let number = number_at(text, path.end + LINE.len());
+    let line = match text[number.clone()].parse() {
+        Ok(line) => line,
+        Err(_) => return false,
+    };
+
+    parsed.tokens.push((start..start + 4, Token::Keyword));
+    parsed
+        .tokens
+        .push((path.start - 1..path.end + 1, Token::String));
+    parsed
+        .tokens
+        .push((path.end + 3..path.end + 7, Token::Keyword));
+    parsed.tokens.push((number.clone(), Token::Number));
+
+    let mut function = None;
+    if text[number.end..].starts_with(IN) {
+        let name = number.end + IN.len()..text.len();
+        parsed
+            .tokens
+            .push((number.end + 2..number.end + 4, Token::Keyword));
+        parsed.tokens.push((name.clone(), Token::Function));
+        function = Some(text[name].to_owned());
+    }
+
+    parsed.frame = Some(Frame {
+        function,
+        path: text[path].to_owned(),
+        line,
+        column: None,
+    });
+    true
+}
+
+/// A Java, Node.js or Rust frame: `at com.example.Main.run(Main.java:12)`,
+/// `at run (/app/index.js:3:9)`, `at /app/index.js:3:9` or `at ./src/main.rs:2:5`
+fn parse_at_frame(text: &str, start: usize, parsed: &mut TraceLine) -> bool {
+    const AT: &str = "at ";
+
+    if !text[start..].starts_with(AT) {
+        return false;
+    }
+    parsed.tokens.push((start..start + 2, Token::Keyword));
+
+    let rest = start + AT.len();
+    let (function, location) = match text[rest..].rfind('(') {
+        Some(open) if text.ends_with(')') => {
+            let open = rest + open;
+            let function = rest..rest + text[rest..open].trim_end().len();
+            (Some(function), open + 1..text.len() - 1)
+        }
+        _ => (None, rest..text.len()),
+    };
+
+    if let Some(ref function) = function {
+        parsed.tokens.push((function.clone(), Token::Function));
+    }
+    if let Some(location) = parse_location(text, location) {
+        let function = function.map(|function| text[function].to_owned());
+        parsed.frame = Some(location.frame(text, function));
+        parsed.tokens.extend(location.tokens());
+    }
+    true
+}
+
+/// The function of a numbered Rust frame: `3: app::main`
+fn parse_numbered_frame(text: &str, start: usize, parsed: &mut TraceLine) {
+    let number = number_at(text, start);
+    if number.start == number.end || !text[number.end..].starts_with(": ") {
+        return;
+    }
+
+    let function = number.end + 2..text.len();
+    parsed.tokens.push((number, Token::Number));
+    parsed.tokens.push((function.clone(), Token::Function));
+    parsed.function = Some(text[function].to_owned());
+}
+
+/// The position of a `path:line[:column]` location in a line
+struct Location {
+    path: Range<usize>,
+    line: Range<usize>,
+    column: Option<Range<usize>>,
+}
+
+impl Location {
+    fn frame(&self, text: &str, function: Option<String>) -> Frame {
+        Frame {
+            function,
+            path: text[self.path.clone()].to_owned(),
+            line: text[self.line.clone()].parse().unwrap_or(1),
+            column: self
+                .column
+                .as_ref()
+                .and_then(|column| text[column.clone()].parse().ok()),
+        }
+    }
+
+    fn tokens(&self) -> Vec<(Range<usize>, Token)> {
+        let mut tokens = vec![
+            (self.path.clone(), Token::String),
+            (self.line.clone(), Token::Number),
+        ];
+        if let Some(ref column) = self.column {
+            tokens.push((column.clone(), Token::Number));
+        }
+        tokens
+    }
+}
+
+/// Parse a `path:line[:column]` location. The path can contain colons itself (like
+/// `C:\...` or `file:///...`), so the numbers are taken from the end.
+fn parse_location(text: &str, range: Range<usize>) -> Option<Location> {
+    let trailing_number = |end: usize| {
+        let colon = text[range.start..end].rfind(':')? + range.start;
+        let number = colon + 1..end;
+        let is_number =
+            number.start < number.end && text[number.clone()].bytes().all(|b| b.is_ascii_digit());
+        if is_number {
+            Some((colon, number))
+        } else {
+            None
+        }
+    };
+
+    let (colon, last) = trailing_number(range.end)?;
+    let (path_end, line, column) = match trailing_number(colon) {
+        Some((colon, line)) => (colon, line, Some(last)),
+        None => (colon, last, None),
+    };
+
+    if path_end == range.start {
+        return None;
+    }
+
+    Some(Location {
+        path: range.start..path_end,
+        line,
+        column,
+    })
+}
+
+/// The range of the (possibly empty) number at the given position
+fn number_at(text: &str, start: usize) -> Range<usize> {
+    let length = text[start..]
+        .bytes()
+        .take_while(|b| b.is_ascii_digit())
+        .count();
+    start..start + length
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame(function: Option<&str>, path: &str, line: usize, column: Option<usize>) -> Frame {
+        Frame {
+            function: function.map(|function| function.to_owned()),
+            path: path.to_owned(),
+            line,
+            column,
+        }
+    }
+
+    #[test]
+    fn frames_of_common_formats() {
+        let rust = "thread 'main' panicked at src/main.rs:2:5:\nboom\nstack backtrace:\n   \
+                    0: rust_begin_unwind\n             at /rustc/library/std/src/panicking.rs:645:5\n   \
+                    1: app::main\n             at ./src/main.rs:2:5\n";
+        assert_eq!(
+            frames(rust),
+            vec![
+                frame(None, "src/main.rs", 2, Some(5)),
+                frame(
+                    Some("rust_begin_unwind"),
+                    "/rustc/library/std/src/panicking.rs",
+                    645,
+                    Some(5)
+                ),
+                frame(Some("app::main"), "./src/main.rs", 2, Some(5)),
+            ]
+        );
+
+        let python =
+            "Traceback (most recent call last):\n  File \"app.py\", line 3, in <module>\n    \
+                      main()\nValueError: boom\n";
+        assert_eq!(
+            frames(python),
+            vec![frame(Some("<module>"), "app.py", 3, None)]
+        );
+
+        let java = "Exception in thread \"main\" java.lang.IllegalStateException: boom\n\
+                    \tat com.example.Main.run(Main.java:12)\n\tat java.base/jdk.internal.Foo(Native Method)\n";
+        assert_eq!(
+            frames(java),
+            vec![frame(Some("com.example.Main.run"), "Main.java", 12, None)]
+        );
+
+        let node = "Error: boom\n    at run (/app/index.js:3:9)\n    at C:\\app\\main.js:10:1\n";
+        assert_eq!(
+            frames(node),
+            vec![
+                frame(Some("run"), "/app/index.js", 3, Some(9)),
+                frame(None, "C:\\app\\main.js", 10, Some(1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn highlighted_trace_lines() {
+        let parts = |line: &str| -> Vec<(String, Token)> {
+            let formatted = format_line(line);
+            formatted
+                .tokens
+                .iter()
+                .map(|(range, token)| (formatted.text[range.clone()].to_owned(), *token))
+                .collect()
+        };
+        let part = |text: &str, token| (text.to_owned(), token);
+
+        assert_eq!(
+            parts("  File \"app.py\", line 3, in main\n"),
+            vec![
+                part("  ", Token::Punctuation),
+                part("File", Token::Keyword),
+                part(" ", Token::Punctuation),
+                part("\"app.py\"", Token::String),
+                part(", ", Token::Punctuation),
+                part("line", Token::Keyword),
+                part(" ", Token::Punctuation),
+                part("3", Token::Number),
+                part(", ", Token::Punctuation),
+                part("in", Token::Keyword),
+                part(" ", Token::Punctuation),
+                part("main", Token::Function),
+                part("\n", Token::Punctuation),
+            ]
+        );
+        assert_eq!(
+            parts("thread 'main' panicked at 'boom', src/main.rs:2:5"),
+            vec![
+                part(
+                    "thread 'main' panicked at 'boom', ",
+                    Token::Level(LogLevel::Error)
+                ),
+                part("src/main.rs", Token::String),
+                part(":", Token::Punctuation),
+                part("2", Token::Number),
+                part(":", Token::Punctuation),
+                part("5", Token::Number),
+            ]
+        );
+        assert_eq!(
+            parts("just some output"),
+            vec![part("just some output", Token::Punctuation)]
+        );
+    }
+}
@@ -0,0 +1,113 @@
+//! Anomalies that don't stop an input from being printed, like replaced invalid UTF-8 or a
+//! theme that had to be replaced by the default one. They are collected for every input (see
+//! `InputResult::warnings`), so that applications can show them to their users.
+
+use std::fmt;
+
+/// How severe a warning is. Warnings can be filtered by their minimum severity (see
+/// `PrettyPrinter::warning_level`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Severity {
+    /// The output is complete, but looks different than it could (like a cut off line)
+    Info,
+
+    /// Parts of the output are missing or wrong (like replaced characters)
+    Warning,
+}
+
+impl Default for Severity {
+    fn default() -> Self {
+        Severity::Info
+    }
+}
+
+/// What happened
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WarningKind {
+    /// Invalid UTF-8 was replaced by replacement characters
+    InvalidUtf8,
+
+    /// A line was cut off because it is too long for the terminal
+    LineTruncated,
+
+    /// The syntax of the input is unknown, it was printed as plain text
+    UnknownSyntax(String),
+
+    /// The theme is unknown, the default theme was used instead
+    ThemeFallback(String),
+}
+
+impl WarningKind {
+    pub fn severity(&self) -> Severity {
+        match self {
+            WarningKind::InvalidUtf8 | WarningKind::ThemeFallback(_) => Severity::Warning,
+            WarningKind::LineTruncated | WarningKind::UnknownSyntax(_) => Severity::Info,
+        }
+    }
+}
+
+/// A warning about an input
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Warning {
+    pub kind: WarningKind,
+
+    /// The (one-based) number of the line that the warning is about, if any
+    pub line: Option<usize>,
+}
+
+impl Warning {
+    pub(crate) fn new(kind: WarningKind) -> Self {
+        Warning { kind, line: None }
+    }
+
+    pub(crate) fn at_line(kind: WarningKind, line: usize) -> Self {
+        Warning {
+            kind,
+            line: Some(line),
+        }
+    }
+
+    pub fn severity(&self) -> Severity {
+        self.kind.severity()
+    }
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(line) = self.line {
+            write!(f, "line {}: ", line)?;
+        }
+        match self.kind {
+            WarningKind::InvalidUtf8 => write!(f, "invalid UTF-8 was replaced"),
+            WarningKind::LineTruncated => write!(f, "the line was cut off"),
+            WarningKind::UnknownSyntax(ref reason) => {
+                write!(f, "printed as plain text: {}", reason)
+            }
+            WarningKind::ThemeFallback(ref theme) => {
+                write!(f, "unknown theme '{}', using the default theme", theme)
+            }
+        }
+    }
+}
+
+/// Keep the warnings with at least the given severity
+pub(crate) fn filter(warnings: Vec<Warning>, level: Severity) -> Vec<Warning> {
+    warnings
+        .into_iter()
+        .filter(|warning| warning.severity() >= level)
+        .collect()
+}
+
+#[test]
+fn warnings_are_filtered_by_severity() {
+    let warnings = vec![
+        Warning::at_line(WarningKind::LineTruncated, 3),
+        Warning::new(WarningKind::ThemeFallback("unknown".into())),
+    ];
+    assert_eq!(warnings[0].to_string(), "line 3: the line was cut off");
+    assert_eq!(filter(warnings.clone(), Severity::Info).len(), 2);
+    assert_eq!(
+        filter(warnings, Severity::Warning),
+        vec![Warning::new(WarningKind::ThemeFallback("unknown".into()))]
+    );
+}
@@ -1,7 +1,140 @@
+use console::AnsiCodeIterator;
+use unicode_width::UnicodeWidthChar;
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum WrappingMode {
     Character,
     NoWrapping,
+    /// Cut lines at the available width and mark the cut with a continuation marker. This
+    /// matches the behavior of pagers like `less -S` that do their own horizontal scrolling.
+    Chop,
+    /// Wrap long lines at word boundaries: after whitespace or after one of the `break_chars`
+    /// (like `-` or `/`). Words that are longer than a row are wrapped like in
+    /// `WrappingMode::Character`. Continuation lines are indented like the line itself.
+    Word {
+        break_chars: &'static str,
+    },
+}
+
+impl WrappingMode {
+    /// The characters after which `--wrap=word` breaks lines, in addition to whitespace
+    pub const DEFAULT_BREAK_CHARS: &'static str = "-/";
+
+    /// The number of terminal rows that a (tab-expanded) line of text occupies when it is
+    /// printed into a content column of the given width. ANSI escape sequences are ignored.
+    pub fn display_rows(self, text: &str, width: usize) -> usize {
+        if width == 0 {
+            return 1;
+        }
+        if let WrappingMode::Word { break_chars } = self {
+            let text = plain_text(text);
+            let indent = continuation_indent(&text, width);
+            return word_breaks(&text, width, break_chars, indent).len() + 1;
+        }
+        if self != WrappingMode::Character {
+            return 1;
+        }
+
+        let mut rows = 1;
+        let mut current_width = 0;
+        for chunk in AnsiCodeIterator::new(text) {
+            if let (text, false) = chunk {
+                for c in text.chars().filter(|c| *c != '\r' && *c != '\n') {
+                    let cw = c.width().unwrap_or(0);
+                    current_width += cw;
+                    if current_width > width {
+                        rows += 1;
+                        current_width = cw;
+                    }
+                }
+            }
+        }
+
+        rows
+    }
+}
+
+/// The text of a line without ANSI escape sequences and line endings
+fn plain_text(text: &str) -> String {
+    AnsiCodeIterator::new(text)
+        .filter(|(_, is_escape)| !is_escape)
+        .flat_map(|(text, _)| text.chars())
+        .filter(|c| *c != '\r' && *c != '\n')
+        .collect()
+}
+
+/// The width of the indentation of the continuation lines of a word-wrapped line: the width of
+/// the leading whitespace of the line, unless that takes up half of the row (or more)
+pub(crate) fn continuation_indent(text: &str, width: usize) -> usize {
+    let indent = text
+        .chars()
+        .take_while(|c| c.is_whitespace())
+        .map(|c| c.width().unwrap_or(0))
+        .sum();
+    if indent * 2 < width {
+        indent
+    } else {
+        0
+    }
+}
+
+/// The character indices at which the rows of a word-wrapped line start (after the first row).
+/// The continuation rows are `indent` columns narrower. A whitespace character at the start of
+/// a row is not printed (and does not take up space).
+pub(crate) fn word_breaks(
+    text: &str,
+    width: usize,
+    break_chars: &str,
+    indent: usize,
+) -> Vec<usize> {
+    let mut breaks = vec![];
+    let mut available = width;
+
+    let mut row_start = 0;
+    let mut row_width = 0;
+    // The last position in the current row where the line may be broken, and the width of the
+    // row up to there
+    let mut last_break = 0;
+    let mut width_at_break = 0;
+
+    for (index, c) in text.chars().enumerate() {
+        let cw = c.width().unwrap_or(0);
+
+        if row_width + cw > available && index > row_start {
+            if c.is_whitespace() {
+                breaks.push(index);
+                row_start = index;
+                row_width = 0;
+                available = width.saturating_sub(indent).max(1);
+                continue;
+            }
+
+            if last_break > row_start {
+                breaks.push(last_break);
+                row_start = last_break;
+                row_width -= width_at_break;
+            } else {
+                breaks.push(index);
+                row_start = index;
+                row_width = 0;
+            }
+            available = width.saturating_sub(indent).max(1);
+
+            if row_width + cw > available && index > row_start {
+                breaks.push(index);
+                row_start = index;
+                row_width = 0;
+            }
+        }
+
+        row_width += cw;
+        if c.is_whitespace() || break_chars.contains(c) {
+            last_break = index + 1;
+            width_at_break = row_width;
+        }
+    }
+
+    breaks
 }
 
 impl Default for WrappingMode {
@@ -9,3 +142,52 @@ impl Default for WrappingMode {
         WrappingMode::NoWrapping
     }
 }
+
+#[test]
+fn display_rows_character() {
+    let mode = WrappingMode::Character;
+
+    assert_eq!(1, mode.display_rows("", 10));
+    assert_eq!(1, mode.display_rows("0123456789\n", 10));
+    assert_eq!(2, mode.display_rows("0123456789a", 10));
+    assert_eq!(2, mode.display_rows("\x1B[31m0123456789\x1B[0ma", 10));
+    assert_eq!(3, mode.display_rows("ビタミンビタミン", 6));
+}
+
+#[test]
+fn display_rows_no_wrapping() {
+    assert_eq!(1, WrappingMode::NoWrapping.display_rows("0123456789a", 10));
+    assert_eq!(1, WrappingMode::Chop.display_rows("0123456789a", 10));
+}
+
+#[test]
+fn word_breaks_at_word_boundaries() {
+    let text = "The quick brown fox jumps";
+    assert_eq!(vec![10, 20], word_breaks(text, 10, "", 0));
+    assert_eq!(vec![4, 10, 16, 20], word_breaks(text, 6, "", 0));
+
+    let path = "/usr/share/bat";
+    assert_eq!(vec![5, 11], word_breaks(path, 6, "/", 0));
+    assert_eq!(vec![6, 12], word_breaks(path, 6, "", 0));
+
+    // Continuation lines are indented
+    let indented = "    aaaa bbbb cccc";
+    assert_eq!(4, continuation_indent(indented, 10));
+    assert_eq!(vec![9, 14], word_breaks(indented, 10, "", 4));
+    assert_eq!(0, continuation_indent(indented, 8));
+}
+
+#[test]
+fn display_rows_word() {
+    let mode = WrappingMode::Word {
+        break_chars: WrappingMode::DEFAULT_BREAK_CHARS,
+    };
+
+    assert_eq!(1, mode.display_rows("", 10));
+    assert_eq!(1, mode.display_rows("The quick\n", 10));
+    assert_eq!(3, mode.display_rows("The quick brown fox jumps", 10));
+    assert_eq!(
+        3,
+        mode.display_rows("\x1B[31mThe quick\x1B[0m brown fox jumps", 10)
+    );
+}
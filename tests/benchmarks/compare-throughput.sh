@@ -0,0 +1,44 @@
+#!/bin/bash
+
+# Compare the throughput of two (or more) builds of bat for large inputs, e.g. a release
+# build of the main branch and one of a branch that changes the printing loop:
+#
+#     ./compare-throughput.sh path/to/old/bat path/to/new/bat
+
+if ! which hyperfine > /dev/null 2>&1; then
+    echo "'hyperfine' does not seem to be installed."
+    echo "You can get it here: https://github.com/sharkdp/hyperfine"
+    exit 1
+fi
+
+if [ $# -lt 2 ]; then
+    echo "Usage: $0 <bat> <bat> [<bat>...]"
+    exit 1
+fi
+
+# About 270 MB of input. It is printed as plain text in order to measure bat's own
+# overhead, once with decorations and wrapping and once without.
+BIG_SRC="$(mktemp)"
+trap 'rm -f "$BIG_SRC"' EXIT
+for _ in $(seq 1000); do
+    cat test-src/jquery-3.3.1.js
+done > "$BIG_SRC"
+
+echo "### Throughput with decorations and wrapping"
+echo
+
+cmds=()
+for BAT in "$@"; do
+    cmds+=("'$BAT' --language txt --style=full --color=always --paging=never '$BIG_SRC'")
+done
+hyperfine --warmup 1 "${cmds[@]}"
+
+echo
+echo "### Throughput without decorations"
+echo
+
+cmds=()
+for BAT in "$@"; do
+    cmds+=("'$BAT' --language txt --plain --wrap=never --tabs=0 --color=always --paging=never '$BIG_SRC'")
+done
+hyperfine --warmup 1 "${cmds[@]}"
@@ -24,3 +24,19 @@ echo
 for SRC in test-src/*; do
     hyperfine --warmup 3 "bat --style=full --color=always --paging=never '$SRC'"
 done
+
+echo
+echo "### Throughput for large inputs"
+echo
+
+# About 270 MB of input. It is printed as plain text in order to measure bat's own
+# overhead, once with decorations and wrapping and once without.
+BIG_SRC="$(mktemp)"
+trap 'rm -f "$BIG_SRC"' EXIT
+for _ in $(seq 1000); do
+    cat test-src/jquery-3.3.1.js
+done > "$BIG_SRC"
+
+hyperfine --warmup 1 \
+    "bat --language txt --style=full --color=always --paging=never '$BIG_SRC'" \
+    "bat --language txt --plain --wrap=never --tabs=0 --color=always --paging=never '$BIG_SRC'"
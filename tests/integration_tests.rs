@@ -388,6 +388,71 @@ fn pager_overwrite() {
         .stdout("pager-output\n");
 }
 
+#[test]
+fn pager_per_file() {
+    bat()
+        .env("PAGER", "echo pager-output")
+        .arg("--paging=always")
+        .arg("--pager-per-file")
+        .arg("test.txt")
+        .arg("single-line.txt")
+        .assert()
+        .success()
+        .stdout("pager-output\npager-output\n");
+}
+
+#[test]
+fn preview_sample_of_large_files() {
+    bat()
+        .arg("--preview-sample=1")
+        .arg("--preview-threshold=0")
+        .arg("--decorations=always")
+        .arg("--style=numbers")
+        .arg("--terminal-width=40")
+        .arg("multiline.txt")
+        .assert()
+        .success()
+        .stdout(
+            "   1 line 1\n \
+             ...─ ─ ─ ─ ─ ─ 8< 2 lines omitted ─ ─ ─\n   \
+             4 line 4\n",
+        );
+
+    bat()
+        .arg("--preview-sample=1")
+        .arg("--preview-threshold=1G")
+        .arg("--decorations=always")
+        .arg("--style=numbers")
+        .arg("multiline.txt")
+        .assert()
+        .success()
+        .stdout("   1 line 1\n   2 line 2\n   3 line 3\n   4 line 4\n");
+}
+
+#[test]
+fn gutter_with_byte_offsets() {
+    bat()
+        .arg("--decorations=always")
+        .arg("--style=numbers")
+        .arg("--gutter=byte-offset")
+        .arg("multiline.txt")
+        .assert()
+        .success()
+        .stdout("       0 line 1\n       7 line 2\n      14 line 3\n      21 line 4\n");
+}
+
+#[test]
+fn gutter_with_extracted_values() {
+    bat()
+        .arg("--decorations=always")
+        .arg("--style=numbers")
+        .arg("--gutter=line (\\d)")
+        .arg("multiline.txt")
+        .assert()
+        .success()
+        .stdout("1    line 1\n2    line 2\n3    line 3\n4    line 4\n");
+}
+
 #[test]
 fn pager_disable() {
     bat()
@@ -510,6 +575,72 @@ fn unicode_wrap() {
         );
 }
 
+#[test]
+fn wrap_pager_leaves_long_lines_to_the_pager() {
+    bat()
+        .arg("multiline.txt")
+        .arg("--style=numbers")
+        .arg("--decorations=always")
+        .arg("--wrap=pager")
+        .arg("--paging=always")
+        .arg("--pager=cat")
+        .arg("--terminal-width=10")
+        .arg("--line-range=1:2")
+        .assert()
+        .success()
+        .stdout("   1 line 1\n   2 line 2\n");
+}
+
+#[test]
+fn wrap_pager_without_pager_wraps_characters() {
+    bat()
+        .arg("multiline.txt")
+        .arg("--style=numbers")
+        .arg("--decorations=always")
+        .arg("--wrap=pager")
+        .arg("--paging=never")
+        .arg("--terminal-width=10")
+        .arg("--line-range=1")
+        .assert()
+        .success()
+        .stdout("   1 line \n     1\n");
+}
+
+#[test]
+fn chop_long_lines_leaves_long_lines_to_the_pager() {
+    bat()
+        .arg("multiline.txt")
+        .arg("--style=numbers")
+        .arg("--decorations=always")
+        .arg("--chop-long-lines")
+        .arg("--paging=always")
+        .arg("--pager=cat")
+        .arg("--terminal-width=10")
+        .arg("--line-range=1:2")
+        .assert()
+        .success()
+        .stdout("   1 line 1\n   2 line 2\n");
+}
+
+#[test]
+fn line_endings_and_encoding_of_the_output() {
+    bat()
+        .arg("multiline.txt")
+        .arg("--line-range=1:2")
+        .arg("--line-endings=crlf")
+        .assert()
+        .success()
+        .stdout("line 1\r\nline 2\r\n");
+
+    bat()
+        .arg("multiline.txt")
+        .arg("--line-range=1")
+        .arg("--output-encoding=utf-16")
+        .assert()
+        .success()
+        .stdout(&b"\xff\xfel\x00i\x00n\x00e\x00 \x001\x00\n\x00"[..]);
+}
+
 #[test]
 fn snip() {
     bat()
@@ -530,6 +661,125 @@ fn snip() {
         );
 }
 
+#[test]
+fn status_line() {
+    bat()
+        .arg("multiline.txt")
+        .arg("--style=status")
+        .arg("--decorations=always")
+        .arg("--line-range=2:3")
+        .arg("--terminal-width=43")
+        .assert()
+        .success()
+        .stdout(
+            "line 2
+line 3
+ multiline.txt │ Plain Text │ 4 lines │ 2-3
+",
+        );
+}
+
+#[test]
+fn status_line_with_wide_characters() {
+    // "日本語.txt" is 10 columns wide, the status line is cut to the width of the terminal
+    bat()
+        .arg("--file-name=日本語.txt")
+        .arg("--style=status")
+        .arg("--decorations=always")
+        .arg("--terminal-width=20")
+        .write_stdin("x\n")
+        .assert()
+        .success()
+        .stdout("x\n 日本語.txt │ Plain \n");
+    bat()
+        .arg("--file-name=日本語.txt")
+        .arg("--style=status")
+        .arg("--decorations=always")
+        .arg("--terminal-width=40")
+        .write_stdin("x\n")
+        .assert()
+        .success()
+        .stdout("x\n 日本語.txt │ Plain Text │ 1 line │ 1-1 \n");
+}
+
+#[test]
+fn minified_input_is_not_highlighted() {
+    bat()
+        .arg("--file-name=app.min.js")
+        .arg("--style=header,status")
+        .arg("--decorations=always")
+        .arg("--terminal-width=39")
+        .write_stdin("var a=1;\n")
+        .assert()
+        .success()
+        .stdout(
+            "File: app.min.js   <MINIFIED, NOT HIGHLIGHTED>
+var a=1;
+ app.min.js │ Plain Text │ 1 line │ 1-1
+",
+        );
+}
+
+#[test]
+fn long_tokens_are_elided() {
+    bat()
+        .arg("--max-token-length=8")
+        .arg("--style=numbers")
+        .arg("--decorations=always")
+        .arg("--terminal-width=20")
+        .arg("--wrap=character")
+        .write_stdin("key: 0123456789abcdef\n")
+        .assert()
+        .success()
+        .stdout("   1 key: 0123<…8 by\n     tes…>cdef\n");
+}
+
+#[test]
+fn json_lines_are_flattened() {
+    bat()
+        .arg("--json-lines=flattened")
+        .arg("--style=plain")
+        .arg("--decorations=always")
+        .write_stdin("{\"level\": \"info\", \"req\": {\"id\": 7}}\nplain\n")
+        .assert()
+        .success()
+        .stdout("level=info req.id=7\nplain\n");
+}
+
+#[test]
+fn narrow_terminals_drop_line_numbers() {
+    bat()
+        .arg("--style=numbers,grid")
+        .arg("--decorations=always")
+        .arg("--terminal-width=12")
+        .arg("--min-content-width=10")
+        .write_stdin("fn main() {}\n")
+        .assert()
+        .success()
+        .stdout("fn main() {}\n");
+}
+
+#[test]
+fn sections_are_separated() {
+    bat()
+        .arg("--style=numbers,sections")
+        .arg("--decorations=always")
+        .arg("--terminal-width=20")
+        .arg("--language=rust")
+        .write_stdin("fn a() {\n    fn b() {}\n}\n\nstruct C;\n")
+        .assert()
+        .success()
+        .stdout(
+            "   1 fn a() {
+   2     fn b() {}
+   3 }
+   4 
+     ╌╌╌╌╌╌╌╌╌╌╌╌╌╌╌
+   5 struct C;
+",
+        );
+}
+
 #[test]
 fn empty_file_leads_to_empty_output_with_grid_enabled() {
     bat()
@@ -654,6 +904,62 @@ fn file_with_invalid_utf8_filename() {
         .stdout("dummy content\n");
 }
 
+#[test]
+fn export_asciicast() {
+    use tempdir::TempDir;
+
+    let tmp_dir = TempDir::new("bat_test").expect("can create temporary directory");
+    let cast_path = tmp_dir.path().join("output.cast");
+
+    bat()
+        .arg("multiline.txt")
+        .arg("--export-asciicast")
+        .arg(&cast_path)
+        .assert()
+        .success()
+        .stdout("line 1\nline 2\nline 3\nline 4\n");
+
+    let cast = std::fs::read_to_string(&cast_path).expect("can read asciicast file");
+    let mut lines = cast.lines();
+    assert!(lines.next().unwrap().contains("\"version\":2"));
+    assert!(lines.all(|line| line.starts_with('[') && line.contains("\"o\"")));
+    assert!(cast.contains("line 1\\r\\nline 2\\r\\n"));
+}
+
+#[test]
+fn project_config_is_used_beneath_user_config() {
+    use std::fs;
+
+    use tempdir::TempDir;
+
+    let tmp_dir = TempDir::new("bat_test").expect("can create temporary directory");
+    let sub_dir = tmp_dir.path().join("src");
+    fs::create_dir(&sub_dir).expect("can create directory");
+    fs::write(tmp_dir.path().join(".bat.toml"), "tab-width = 2\n").expect("can write file");
+    fs::write(sub_dir.join("tabs.txt"), "\tx\n").expect("can write file");
+    let user_config = tmp_dir.path().join("config");
+    fs::write(&user_config, "").expect("can write file");
+
+    bat_with_config()
+        .env("BAT_CONFIG_PATH", &user_config)
+        .arg(sub_dir.join("tabs.txt"))
+        .arg("--style=plain")
+        .arg("--decorations=always")
+        .assert()
+        .success()
+        .stdout("  x\n");
+
+    fs::write(&user_config, "--tabs=3\n").expect("can write file");
+    bat_with_config()
+        .env("BAT_CONFIG_PATH", &user_config)
+        .arg(sub_dir.join("tabs.txt"))
+        .arg("--style=plain")
+        .arg("--decorations=always")
+        .assert()
+        .success()
+        .stdout("   x\n");
+}
+
 #[test]
 fn do_not_panic_regression_tests() {
     for filename in &[
@@ -670,3 +976,436 @@ fn do_not_panic_regression_tests() {
             .success();
     }
 }
+
+#[test]
+fn stack_trace_frames_show_their_source() {
+    bat()
+        .arg("--stack-trace")
+        .arg("--source-context=1")
+        .arg("--style=plain")
+        .arg("--decorations=always")
+        .arg("--terminal-width=40")
+        .write_stdin("Error: boom\n    at run (multiline.txt:2:1)\n    at missing.js:1:1\n")
+        .assert()
+        .success()
+        .stdout(
+            "Error: boom\n    at run (multiline.txt:2:1)\n    1 │ line 1\n  ▶ 2 │ line 2\n    3 │ line 3\n    at missing.js:1:1\n",
+        );
+}
+
+#[test]
+fn rendered_diffs_separate_files_and_hunks() {
+    bat()
+        .arg("--render-diffs")
+        .arg("--language=diff")
+        .arg("--style=plain")
+        .arg("--decorations=always")
+        .arg("--terminal-width=10")
+        .write_stdin(
+            "diff --git a/a.rs b/a.rs\n--- a/a.rs\n+++ b/a.rs\n@@ -1 +1 @@\n-a\n+b\n@@ -5 +5 @@\n-c\n+d\ndiff --git a/b.rs b/b.rs\n",
+        )
+        .assert()
+        .success()
+        .stdout(
+            "diff --git a/a.rs b/a.rs\n--- a/a.rs\n+++ b/a.rs\n@@ -1 +1 @@\n-a\n+b\n╌╌╌╌╌╌╌╌╌╌\n@@ -5 +5 @@\n-c\n+d\n──────────\ndiff --git a/b.rs b/b.rs\n",
+        );
+}
+
+#[test]
+fn rendered_commits_are_separated() {
+    bat()
+        .arg("--render-commits")
+        .arg("--style=plain")
+        .arg("--decorations=always")
+        .arg("--terminal-width=10")
+        .write_stdin("commit 0123456\nAuthor: A\n\n    One\n\ncommit 789abcd\nAuthor: B\n\n    Two\n")
+        .assert()
+        .success()
+        .stdout(
+            "commit 0123456\nAuthor: A\n\n    One\n\n──────────\ncommit 789abcd\nAuthor: B\n\n    Two\n",
+        );
+}
+
+#[test]
+fn templates_customize_header_footer_and_numbers() {
+    bat()
+        .arg("--style=header,numbers")
+        .arg("--decorations=always")
+        .arg("--header-template={name} • {size}")
+        .arg("--footer-template={lines} lines")
+        .arg("--number-template={number}:")
+        .arg("multiline.txt")
+        .assert()
+        .success()
+        .stdout(
+            "      multiline.txt • 28 B\n   1: line 1\n   2: line 2\n   3: line 3\n   4: line 4\n      4 lines\n",
+        );
+}
+
+#[test]
+fn invalid_templates_are_rejected() {
+    bat()
+        .arg("--header-template={lines}")
+        .arg("multiline.txt")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn color_vision_marks_log_levels() {
+    bat()
+        .arg("--json-lines=flattened")
+        .arg("--color-vision=deuteranopia")
+        .arg("--style=plain")
+        .arg("--decorations=always")
+        .write_stdin("{\"level\":\"error\",\"msg\":\"a\"}\n{\"level\":\"info\",\"msg\":\"b\"}\n")
+        .assert()
+        .success()
+        .stdout("level=✖ error msg=a\nlevel=● info msg=b\n");
+}
+
+#[test]
+fn highlight_patterns_are_styled_by_name() {
+    bat()
+        .arg("--color=always")
+        .arg("--theme=ansi-dark")
+        .arg("--language=txt")
+        .arg("--style=plain")
+        .arg("--decorations=always")
+        .arg("--highlight-pattern=error:ERROR")
+        .arg("--highlight-pattern=todo:\\w+ ERROR")
+        .arg("--pattern-style=todo=underline")
+        .write_stdin("an ERROR here\n")
+        .assert()
+        .success()
+        .stdout(
+            "\x1b[4;38;5;7man \x1b[0m\x1b[1;48;5;1;38;5;7mERROR\x1b[0m\x1b[38;5;7m here\x1b[0m\n",
+        );
+
+    bat()
+        .arg("--highlight-pattern=unknown:ERROR")
+        .arg("multiline.txt")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn card_puts_output_into_a_box() {
+    bat()
+        .arg("--card=14")
+        .arg("--card-border=ascii")
+        .arg("--card-align=center")
+        .arg("--terminal-width=20")
+        .arg("--style=numbers")
+        .arg("--decorations=always")
+        .arg("multiline.txt")
+        .assert()
+        .success()
+        .stdout(
+            "   +------------+
+   |    1 line  |
+   |      1     |
+   |    2 line  |
+   |      2     |
+   |    3 line  |
+   |      3     |
+   |    4 line  |
+   |      4     |
+   +------------+
+",
+        );
+}
+
+#[test]
+fn split_view_prints_ranges_side_by_side() {
+    bat()
+        .arg("--split")
+        .arg("1:2")
+        .arg("4")
+        .arg("--terminal-width=33")
+        .arg("--style=numbers")
+        .arg("--decorations=always")
+        .arg("multiline.txt")
+        .assert()
+        .success()
+        .stdout("   1 line 1     │    4 line 4\n   2 line 2     │ \n");
+}
+
+#[test]
+fn highlight_search_uses_distinct_colors() {
+    bat()
+        .arg("--color=always")
+        .arg("--theme=ansi-dark")
+        .arg("--language=txt")
+        .arg("--style=plain")
+        .arg("--decorations=always")
+        .arg("--highlight-search=TODO")
+        .arg("--highlight-search=FIXME")
+        .write_stdin("TODO FIXME\n")
+        .assert()
+        .success()
+        .stdout(
+            "\x1b[48;5;3;38;5;0mTODO\x1b[0m\x1b[38;5;7m \x1b[0m\x1b[48;5;2;38;5;0mFIXME\x1b[0m\n",
+        );
+}
+
+#[test]
+fn file_versions_from_git_history() {
+    use std::fs;
+    use std::path::Path;
+
+    use git2::{Repository, Signature};
+    use tempdir::TempDir;
+
+    let tmp_dir = TempDir::new("bat_test").expect("can create temporary directory");
+    let repo = Repository::init(tmp_dir.path()).expect("can create repository");
+    let signature = Signature::now("bat test runner", "bat@test.runner").unwrap();
+    for (content, message) in &[("a = 1\n", "First"), ("a = 2\n", "Second")] {
+        fs::write(tmp_dir.path().join("config.toml"), content).unwrap();
+        fs::write(tmp_dir.path().join("other.txt"), message).unwrap();
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new("config.toml")).unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let parent = repo.head().ok().and_then(|head| head.peel_to_commit().ok());
+        repo.commit(
+            Some("HEAD"),
+            &signature,
+            &signature,
+            message,
+            &tree,
+            &parent.iter().collect::<Vec<_>>(),
+        )
+        .unwrap();
+    }
+    fs::write(tmp_dir.path().join("config.toml"), "a = 3\n").unwrap();
+
+    let output = bat()
+        .current_dir(tmp_dir.path())
+        .arg("--history=5")
+        .arg("--header-template={path} {revision}")
+        .arg("--style=header")
+        .arg("--decorations=always")
+        .arg("config.toml")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let output = String::from_utf8(output).unwrap();
+    let lines: Vec<_> = output.lines().collect();
+    assert_eq!(lines.len(), 4);
+    assert!(lines[0].starts_with("config.toml ") && lines[0].ends_with(" First"));
+    assert_eq!(lines[1], "a = 1");
+    assert!(lines[2].starts_with("config.toml ") && lines[2].ends_with(" Second"));
+    assert_eq!(lines[3], "a = 2");
+
+    bat()
+        .current_dir(tmp_dir.path())
+        .arg("--revision=HEAD~1")
+        .arg("config.toml")
+        .assert()
+        .success()
+        .stdout("a = 1\n");
+
+    bat()
+        .current_dir(tmp_dir.path())
+        .arg("--revision=HEAD")
+        .arg("other.txt")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn diff_shows_changed_hunks() {
+    use std::fs;
+    use std::path::Path;
+
+    use git2::{Repository, Signature};
+    use tempdir::TempDir;
+
+    let tmp_dir = TempDir::new("bat_test").expect("can create temporary directory");
+    let repo = Repository::init(tmp_dir.path()).expect("can create repository");
+    let signature = Signature::now("bat test runner", "bat@test.runner").unwrap();
+    let lines: Vec<String> = (1..=10).map(|n| n.to_string()).collect();
+    fs::write(tmp_dir.path().join("numbers.txt"), lines.join("\n") + "\n").unwrap();
+    let mut index = repo.index().unwrap();
+    index.add_path(Path::new("numbers.txt")).unwrap();
+    index.write().unwrap();
+    let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+    repo.commit(Some("HEAD"), &signature, &signature, "Numbers", &tree, &[])
+        .unwrap();
+
+    let mut changed = lines.clone();
+    changed[0] = "one".into();
+    changed[8] = "nine".into();
+    fs::write(
+        tmp_dir.path().join("numbers.txt"),
+        changed.join("\n") + "\n",
+    )
+    .unwrap();
+
+    let output = bat()
+        .current_dir(tmp_dir.path())
+        .arg("--diff")
+        .arg("--diff-context=2")
+        .arg("--style=snip")
+        .arg("--decorations=always")
+        .arg("numbers.txt")
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let output = String::from_utf8(output).unwrap();
+    let lines: Vec<_> = output.lines().collect();
+    assert_eq!(&lines[..3], &["one", "2", "3"]);
+    assert!(lines[3].contains("8<"));
+    assert_eq!(&lines[4..], &["7", "8", "nine", "10"]);
+}
+
+#[test]
+fn repository_index() {
+    use std::fs;
+
+    use tempdir::TempDir;
+
+    let tmp_dir = TempDir::new("bat_test").expect("can create temporary directory");
+    fs::create_dir(tmp_dir.path().join("src")).unwrap();
+    fs::write(tmp_dir.path().join("notes.txt"), "notes\n").unwrap();
+    fs::write(tmp_dir.path().join("src/main.rs"), "fn main() {}\n").unwrap();
+    fs::write(tmp_dir.path().join(".hidden"), "secret\n").unwrap();
+
+    bat()
+        .current_dir(tmp_dir.path())
+        .arg("--repo=.")
+        .arg("--color=never")
+        .arg("--decorations=always")
+        .arg("--style=plain")
+        .assert()
+        .success()
+        .stdout(concat!(
+            "./\n",
+            "├── notes.txt     6 B  Plain Text\n",
+            "└── src/\n",
+            "    └── main.rs  13 B  Rust\n",
+            "notes\n",
+            "fn main() {}\n",
+        ));
+
+    bat()
+        .current_dir(tmp_dir.path())
+        .arg("--repo=.")
+        .arg("--color=never")
+        .arg("--decorations=always")
+        .arg("--style=header")
+        .arg("src/main.rs")
+        .assert()
+        .success()
+        .stdout(concat!(
+            "File: Index of .\n",
+            "./\n",
+            "├── notes.txt     6 B  Plain Text\n",
+            "└── src/\n",
+            "    └── main.rs  13 B  Rust\n",
+            "File: src/main.rs\n",
+            "fn main() {}\n",
+        ));
+}
+
+#[test]
+fn language_from_git_attributes() {
+    use std::fs;
+
+    use git2::Repository;
+    use tempdir::TempDir;
+
+    let tmp_dir = TempDir::new("bat_test").expect("can create temporary directory");
+    Repository::init(tmp_dir.path()).expect("can create repository");
+    fs::write(
+        tmp_dir.path().join(".gitattributes"),
+        "*.tpl linguist-language=Rust\n",
+    )
+    .unwrap();
+    fs::write(tmp_dir.path().join("lib.tpl"), "fn main() {}\n").unwrap();
+
+    let index = |args: &[&str]| {
+        bat()
+            .current_dir(tmp_dir.path())
+            .arg("--repo=.")
+            .arg("--color=never")
+            .arg("--decorations=always")
+            .arg("--style=plain")
+            .args(args)
+            .assert()
+            .success()
+    };
+    index(&[]).stdout("./\n└── lib.tpl  13 B  Plain Text\nfn main() {}\n");
+    index(&["--git-attributes"]).stdout("./\n└── lib.tpl  13 B  Rust\nfn main() {}\n");
+    index(&["--git-attributes", "--map-syntax=*.tpl:C"])
+        .stdout("./\n└── lib.tpl  13 B  C\nfn main() {}\n");
+}
+
+#[test]
+fn squeeze_blank_lines() {
+    bat()
+        .arg("--squeeze-blank")
+        .write_stdin("a\n\n\n\nb\n\nc\n")
+        .assert()
+        .success()
+        .stdout("a\n\nb\n\nc\n");
+
+    bat()
+        .arg("--squeeze-blank")
+        .arg("--squeeze-limit=2")
+        .arg("--color=never")
+        .arg("--decorations=always")
+        .arg("--style=numbers")
+        .write_stdin("a\n\n\n\nb\n")
+        .assert()
+        .success()
+        .stdout("   1 a\n   2 \n   3 \n   5 b\n");
+}
+
+#[test]
+fn linkify_urls() {
+    bat()
+        .arg("--linkify")
+        .arg("--color=never")
+        .arg("--decorations=always")
+        .arg("--style=plain")
+        .write_stdin("see https://example.com/docs.\n")
+        .assert()
+        .success()
+        .stdout(
+            "see \x1b]8;;https://example.com/docs\x1b\\https://example.com/docs\x1b]8;;\x1b\\.\n",
+        );
+
+    bat()
+        .arg("--linkify")
+        .arg("--color=never")
+        .arg("--decorations=always")
+        .arg("--style=numbers")
+        .arg("--wrap=character")
+        .arg("--terminal-width=20")
+        .write_stdin("x https://example.com/a/b/c\n")
+        .assert()
+        .success()
+        .stdout(
+            "   1 x \x1b]8;;https://example.com/a/b/c\x1b\\https://examp\x1b]8;;\x1b\\\n     \x1b]8;;https://example.com/a/b/c\x1b\\le.com/a/b/c\x1b]8;;\x1b\\\n",
+        );
+}
+
+#[test]
+fn show_binary_as_hexdump() {
+    bat()
+        .arg("--show-binary")
+        .arg("--style=numbers")
+        .arg("--decorations=always")
+        .write_stdin(&b"\x00\x01binary content\n"[..])
+        .assert()
+        .success()
+        .stdout(
+            "00000000 00 01 62 69 6e 61 72 79  20 63 6f 6e 74 65 6e 74  |..binary content|\n\
+             00000010 0a                                                |.|\n",
+        );
+}